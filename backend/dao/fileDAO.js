@@ -1,6 +1,14 @@
 import db from '../db/fileDB.js';
 import p from "path";
 import fs from "fs";
+import { mtimeOf } from "../utils/mtime.js";
+
+// fs.utimes takes the times as a float, so the disk keeps a requested mtime only to a fraction of a
+// microsecond: a time read from disk this close to the stored one is that same time, and the stored
+// seconds and nanoseconds (the ones the client asked for) are kept
+const SAME_MTIME_NS = 1000;
+const sameMtime = (stored, disk) =>
+    `ABS((${stored}.mtime - ${disk}.mtime) * 1000000000 + (${stored}.mtime_ns - ${disk}.mtime_ns)) < ${SAME_MTIME_NS}`;
 
 export default function FileDAO() {
     this.getFilesByDirectory = (parent) => {
@@ -23,12 +31,12 @@ export default function FileDAO() {
         });
     };
 
-    this.createDirectory = async ({ path, parent, name, is_dir, size, mtime, permissions, nlink }) => {
+    this.createDirectory = async ({ path, parent, name, is_dir, kind, size, mtime, mtime_ns, crtime, crtime_ns, permissions, nlink }) => {
         const parentPath = p.dirname(path);
         const parent_id = await this.getIdByPath(parentPath);
-        const query = 'INSERT INTO files(path, parent_id,parent, name, is_dir, size, mtime, permissions, nlink, version) VALUES (?,?, ?, ?, ?, ?, ?, ?, ?, 1)'
+        const query = 'INSERT INTO files(path, parent_id,parent, name, is_dir, kind, size, mtime, mtime_ns, crtime, crtime_ns, permissions, nlink, version) VALUES (?,?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1)'
         return new Promise((resolve, reject) => {
-            db.run(query, [path, parent_id, parent, name, is_dir, size, mtime, permissions, nlink], function (err) {
+            db.run(query, [path, parent_id, parent, name, is_dir, kind ?? (is_dir ? 'dir' : 'file'), size, mtime, mtime_ns ?? 0, crtime ?? null, crtime_ns ?? 0, permissions, nlink], function (err) {
                 if (err) return reject(err);
                 resolve({ id: this.lastID });
             });
@@ -69,29 +77,44 @@ export default function FileDAO() {
         });
     };
 
-    this.updateFile = async ({ path, name, parent, is_dir, size, mtime, permissions, nlink }) => {
+    this.updateFile = async ({ path, name, parent, is_dir, kind, symlink_target, size, mtime, mtime_ns, crtime, crtime_ns, permissions, nlink }) => {
         const parentPath = p.dirname(path);
         const parent_id = await this.getIdByPath(parentPath);
 
         const q = `
-        INSERT INTO files(path, parent_id, parent, name, is_dir, size, mtime, permissions, nlink, version)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 1)
+        INSERT INTO files(path, parent_id, parent, name, is_dir, kind, symlink_target, size, mtime, mtime_ns, crtime, crtime_ns, permissions, nlink, version)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1)
         ON CONFLICT(path) DO UPDATE SET
+            kind=excluded.kind,
+            symlink_target=excluded.symlink_target,
             size=excluded.size,
-            mtime=excluded.mtime,
+            mtime=CASE WHEN ${sameMtime('files', 'excluded')} THEN files.mtime ELSE excluded.mtime END,
+            mtime_ns=CASE WHEN ${sameMtime('files', 'excluded')} THEN files.mtime_ns ELSE excluded.mtime_ns END,
+            crtime=COALESCE(excluded.crtime, files.crtime),
+            crtime_ns=CASE WHEN excluded.crtime IS NULL THEN files.crtime_ns ELSE excluded.crtime_ns END,
             permissions=COALESCE(excluded.permissions, files.permissions),
             nlink=COALESCE(excluded.nlink, files.nlink),
             version=files.version + 1
     `;
 
         return new Promise((resolve, reject) => {
-            db.run(q, [path, parent_id, parent, name, is_dir, size, mtime, permissions ?? null, nlink ?? 1], function (err) {
+            db.run(q, [path, parent_id, parent, name, is_dir, kind ?? (is_dir ? 'dir' : 'file'), symlink_target ?? null, size, mtime, mtime_ns ?? 0, crtime ?? null, crtime_ns ?? 0, permissions ?? null, nlink ?? 1], function (err) {
                 if (err) return reject(err);
                 resolve({ success: this.changes >= 0 });
             });
         });
     };
 
+    this.updateTimes = (path, mtime, mtime_ns) => {
+        const q = 'UPDATE files SET mtime=?, mtime_ns=?, version=version+1 WHERE path=?';
+        return new Promise((resolve, reject) => {
+            db.run(q, [mtime, mtime_ns, path], function (err) {
+                if (err) return reject(err);
+                resolve({ success: this.changes > 0 });
+            });
+        });
+    };
+
     this.rename = async (oldPath, newPath) => {
         const newParentPath = p.dirname(newPath);
         const newParentId = await this.getIdByPath(newParentPath);
@@ -118,15 +141,21 @@ export default function FileDAO() {
             const stats = await fs.promises.stat(absPath);
             const query = `
                 UPDATE files 
-                SET size = ?, nlink = ?, mtime = ?, version = version + 1 
+                SET size = ?, nlink = ?,
+                    mtime = CASE WHEN ${sameMtime('files', 'disk')} THEN files.mtime ELSE disk.mtime END,
+                    mtime_ns = CASE WHEN ${sameMtime('files', 'disk')} THEN files.mtime_ns ELSE disk.mtime_ns END,
+                    version = version + 1 
+                FROM (SELECT ? AS mtime, ? AS mtime_ns) AS disk
                 WHERE path = ?
             `;
+            const { mtime, mtime_ns } = mtimeOf(stats);
 
             return new Promise((resolve, reject) => {
                 db.run(query, [
                     stats.size,
                     stats.nlink,
-                    Math.floor(stats.mtimeMs / 1000),
+                    mtime,
+                    mtime_ns,
                     relPath
                 ], function (err) {
                     if (err) reject(err);
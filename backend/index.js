@@ -5,6 +5,7 @@ import listRoutes from './routes/listRoutes.js';
 import filesRoutes from './routes/filesRoutes.js';
 import mkdirRoutes from './routes/mkdirRoutes.js';
 import statsRoutes from './routes/statsRoutes.js';
+import trashRoutes from './routes/trashRoutes.js';
 import os from 'os';
 import fs from 'fs/promises';
 import db from './db/fileDB.js';
@@ -13,8 +14,19 @@ import http from 'http';
 import chokidar from 'chokidar';
 import FileDAO from './dao/fileDAO.js';
 import path from 'path';
+import { mtimeOf } from './utils/mtime.js';
+import { kindOf } from './utils/kind.js';
 
 export const ROOT_DIR = path.join(process.cwd(), "storage");
+function addColumn(dbConnection, definition) {
+  return new Promise((resolve, reject) => {
+    dbConnection.run(`ALTER TABLE files ADD COLUMN ${definition}`, (err) => {
+      if (err && !/duplicate column/i.test(err.message)) return reject(err);
+      resolve();
+    });
+  });
+}
+
 async function bootstrap(rootDir, dbConnection) {
   try {
     await fs.mkdir(rootDir, { recursive: true });
@@ -30,8 +42,13 @@ async function bootstrap(rootDir, dbConnection) {
             parent TEXT,
             name TEXT,
             is_dir BOOLEAN,
+            kind TEXT,
+            symlink_target TEXT,
             size INTEGER,
             mtime INTEGER,
+            mtime_ns INTEGER DEFAULT 0,
+            crtime INTEGER,
+            crtime_ns INTEGER DEFAULT 0,
             permissions TEXT,
             nlink INTEGER,
             version INTEGER DEFAULT 1,
@@ -47,6 +64,17 @@ async function bootstrap(rootDir, dbConnection) {
     });
   });
 
+  // Databases created before these columns existed
+  await addColumn(dbConnection, "mtime_ns INTEGER DEFAULT 0");
+  await addColumn(dbConnection, "kind TEXT");
+  await addColumn(dbConnection, "crtime INTEGER");
+  await addColumn(dbConnection, "crtime_ns INTEGER DEFAULT 0");
+  await addColumn(dbConnection, "symlink_target TEXT");
+
+  // The root row mirrors the storage folder, so the mount shows its real permissions
+  const rootStats = await fs.stat(rootDir);
+  const rootPermissions = (rootStats.mode & 0o7777).toString(8);
+
   return new Promise((resolve, reject) => {
     dbConnection.get("SELECT * FROM files WHERE path = '.'", (err, row) => {
       if (err) return reject(err);
@@ -58,13 +86,16 @@ async function bootstrap(rootDir, dbConnection) {
         `);
 
         const now = Math.floor(Date.now() / 1000);
-        stmt.run('.', null, null, 'root', 1, 0, now, '755', 2, 1, (err) => {
+        stmt.run('.', null, null, 'root', 1, 0, now, rootPermissions, 2, 1, (err) => {
           if (err) reject(err);
           else resolve();
         });
         stmt.finalize();
       } else {
-        resolve();
+        dbConnection.run("UPDATE files SET permissions = ? WHERE path = '.'", [rootPermissions], (err) => {
+          if (err) reject(err);
+          else resolve();
+        });
       }
     });
   });
@@ -72,20 +103,27 @@ async function bootstrap(rootDir, dbConnection) {
 
 await bootstrap(ROOT_DIR, db);
 
+// Target of the link at absPath, null for anything else
+export async function symlinkTargetOf(absPath, stats) {
+  return stats.isSymbolicLink() ? fs.readlink(absPath) : null;
+}
+
 async function buildMetadataPayload(absPath) {
-  const stats = await fs.stat(absPath);
+  const stats = await fs.lstat(absPath);
   const relPath = clean(absPath);
   const name = path.basename(absPath);
   const parent = path.dirname(relPath);
-  const permissions = (stats.mode & 0o777).toString(8);
+  const permissions = (stats.mode & 0o7777).toString(8);
 
   return {
     relPath,
     name,
     parent,
     is_dir: stats.isDirectory(),
+    kind: kindOf(stats),
+    symlink_target: await symlinkTargetOf(absPath, stats),
     size: stats.size,
-    mtime: Math.floor(stats.mtimeMs / 1000),
+    ...mtimeOf(stats),
     permissions,
     nlink: stats.nlink,
   };
@@ -97,6 +135,8 @@ const watcher = chokidar.watch(ROOT_DIR, {
   persistent: true,
   ignoreInitial: true,
   depth: 10,
+  // Links are reported as links, their targets may be outside the storage folder
+  followSymlinks: false,
   alwaysStat: true,
   awaitWriteFinish: {
     stabilityThreshold: 200,
@@ -107,7 +147,7 @@ const watcher = chokidar.watch(ROOT_DIR, {
 async function handleFileUpdate(pathFile, stats) {
   try {
     if (!stats) {
-      stats = await fs.stat(pathFile);
+      stats = await fs.lstat(pathFile);
     }
     
     const relPath = clean(pathFile);
@@ -120,9 +160,11 @@ async function handleFileUpdate(pathFile, stats) {
       name,
       parent: parentPath,
       is_dir: stats.isDirectory(),
+      kind: kindOf(stats),
+      symlink_target: await symlinkTargetOf(pathFile, stats),
       size: stats.size,
-      mtime: Math.floor(stats.mtimeMs / 1000),
-      permissions: (stats.mode & 0o777).toString(8),
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
       nlink: stats.nlink
     };
     
@@ -398,11 +440,20 @@ app.get('/health', (req, res) => {
   });
 });
 
+// No version history is kept: refuse snapshot reads instead of serving live data
+app.use((req, res, next) => {
+  if (req.query.snapshot !== undefined) {
+    return res.status(501).json({ error: "Snapshots are not supported by this backend" });
+  }
+  next();
+});
+
 // API
 app.use('/list', listRoutes);
 app.use('/files', filesRoutes);
 app.use('/mkdir', mkdirRoutes);
 app.use('/stats', statsRoutes);
+app.use('/trash', trashRoutes);
 
 // Add the socket.io server
 const httpServer = http.createServer(app);
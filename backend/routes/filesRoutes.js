@@ -2,7 +2,11 @@ import express from "express";
 import fs from "fs";
 import path from "path";
 import FileDAO from "../dao/fileDAO.js";
-import { ROOT_DIR, backendChanges } from '../index.js';
+import { ROOT_DIR, backendChanges, symlinkTargetOf } from '../index.js';
+import { mtimeOf } from '../utils/mtime.js';
+import { kindOf } from '../utils/kind.js';
+import { normalizeRelPath } from '../utils/relPath.js';
+import { renameOver } from '../utils/renameOver.js';
 
 const router = express.Router();
 const f = new FileDAO();
@@ -21,16 +25,40 @@ function parseRange(rangeHeader, fileSize) {
   return [start, end];
 }
 
-router.get("/", async (req, res) => {
+// Links are resolved by the clients. Following one out of the storage folder here would hand out
+// (or overwrite) files of the host, so reads and writes through such a link are refused
+async function escapesRoot(absPath) {
+  const real = await fs.promises.realpath(absPath).catch(() => null);
+  if (real === null) return false;
+  const root = await fs.promises.realpath(ROOT_DIR);
+  return real !== root && !real.startsWith(root + path.sep);
+}
+
+// HEAD /files?relPath=... existence probe, no body and no listing
+router.head("/", async (req, res) => {
   try {
-    let relPath = req.query.relPath;
-    if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
+    const relPath = normalizeRelPath(req.query.relPath);
+    const stats = await fs.promises.lstat(path.join(ROOT_DIR, relPath)).catch(() => null);
+    if (!stats) {
+      return res.status(404).end();
     }
+    res.setHeader("Content-Length", stats.isDirectory() ? 0 : stats.size);
+    res.status(200).end();
+  } catch (err) {
+    res.status(500).end();
+  }
+});
+
+router.get("/", async (req, res) => {
+  try {
+    let relPath = normalizeRelPath(req.query.relPath);
     const filePath = path.join(ROOT_DIR, relPath);
     if (!fs.existsSync(filePath)) {
       return res.status(404).json({ error: "File not found" });
     }
+    if (await escapesRoot(filePath)) {
+      return res.status(403).json({ error: "The link leads out of the storage folder" });
+    }
 
     backendChanges.add(filePath);
 
@@ -43,6 +71,10 @@ router.get("/", async (req, res) => {
 
     if (range) {
       const [start, end] = parseRange(range, fileSize);
+      if (start >= fileSize || start > end) {
+        res.writeHead(416, { "Content-Range": `bytes */${fileSize}` });
+        return res.end();
+      }
       const chunkSize = end - start + 1;
 
       res.writeHead(206, {
@@ -73,10 +105,7 @@ router.put("/", async (req, res) => {
   let fd;
 
   try {
-    let relPath = req.query.relPath;
-    if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
-    }
+    let relPath = normalizeRelPath(req.query.relPath);
     const offset = parseInt(req.query.offset ?? "0", 10);
 
     const filePathAbs = path.join(ROOT_DIR, relPath);
@@ -90,6 +119,9 @@ router.put("/", async (req, res) => {
         error: "Parent directory not found. Create the directory first."
       });
     }
+    if (await escapesRoot(filePathAbs) || await escapesRoot(parentPathAbs)) {
+      return res.status(403).json({ error: "The link leads out of the storage folder" });
+    }
     const flag = (offset === 0) ? "w+" : "r+";
     
     try {
@@ -121,9 +153,10 @@ router.put("/", async (req, res) => {
       name,
       parent: parentPath,
       is_dir: false,
+      kind: kindOf(stats),
       size: stats.size,
-      mtime: Math.floor(stats.mtimeMs / 1000),
-      permissions: (stats.mode & 0o777).toString(8),
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
       nlink: stats.nlink,
     });
     await f.syncMetadataFromDisk(parentPath);
@@ -141,17 +174,58 @@ router.put("/", async (req, res) => {
   }
 });
 
-// DELETE /files/path
-router.delete("/", async (req, res) => {
+// POST /files/create?relPath=... creates an empty file only if the name is free
+router.post("/create", async (req, res) => {
   try {
-    let relPath = req.query.relPath;
-    if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
+    const relPath = normalizeRelPath(req.query.relPath);
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    const parentPath = path.dirname(relPath);
+
+    if (!fs.existsSync(path.dirname(filePathAbs))) {
+      return res.status(400).json({
+        error: "Parent directory not found. Create the directory first."
+      });
     }
+    backendChanges.add(filePathAbs);
+    // "wx" fails with EEXIST, so of two concurrent creates only one wins
+    try {
+      const fd = await fs.promises.open(filePathAbs, "wx");
+      await fd.close();
+    } catch (err) {
+      if (err.code === "EEXIST") {
+        return res.status(409).json({ error: "File already exists" });
+      }
+      throw err;
+    }
+
+    const stats = await fs.promises.stat(filePathAbs);
+    await f.updateFile({
+      path: relPath,
+      name: path.basename(filePathAbs),
+      parent: parentPath,
+      is_dir: false,
+      kind: kindOf(stats),
+      size: stats.size,
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
+      nlink: stats.nlink,
+    });
+    await f.syncMetadataFromDisk(parentPath);
+    res.status(201).json({ message: "File created" });
+  } catch (err) {
+    console.error(err);
+    res.status(500).json({ error: "Error creating file" });
+  }
+});
+
+// DELETE /files?relPath=...
+router.delete("/", async (req, res) => {
+  try {
+    let relPath = normalizeRelPath(req.query.relPath);
     const filePathAbs = path.join(ROOT_DIR, relPath);
     backendChanges.add(filePathAbs);
 
-    const stats = await fs.promises.stat(filePathAbs).catch(() => null);
+    const stats = await fs.promises.lstat(filePathAbs).catch(() => null);
     if (!stats) {
       return res.status(404).json({ error: "File or directory not found" });
     }
@@ -174,10 +248,7 @@ router.delete("/", async (req, res) => {
 // PATCH /files/chmod?relPath=...&perm=755
 router.patch("/chmod", async (req, res) => {
   try {
-    let relPath = req.query.relPath;
-    if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
-    }
+    let relPath = normalizeRelPath(req.query.relPath);
     const perm = req.query.perm;
     const filePathAbs = path.join(ROOT_DIR, relPath);
     backendChanges.add(filePathAbs);
@@ -189,13 +260,96 @@ router.patch("/chmod", async (req, res) => {
   }
 });
 
+// POST /files/symlink?relPath=...&target=... creates a symlink; the target is stored as given and
+// resolved by the clients, never by the backend
+router.post("/symlink", async (req, res) => {
+  try {
+    const relPath = normalizeRelPath(req.query.relPath);
+    const target = req.query.target;
+    if (!relPath || relPath === '.' || typeof target !== 'string' || target === '' || target.includes('\0')) {
+      return res.status(400).json({ error: "Missing relPath or target" });
+    }
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    if (!fs.existsSync(path.dirname(filePathAbs))) {
+      return res.status(404).json({ error: "Parent directory not found" });
+    }
+    backendChanges.add(filePathAbs);
+    await fs.promises.symlink(target, filePathAbs);
+    const stats = await fs.promises.lstat(filePathAbs);
+    await f.updateFile({
+      path: relPath,
+      name: path.basename(filePathAbs),
+      parent: path.dirname(relPath),
+      is_dir: false,
+      kind: kindOf(stats),
+      symlink_target: target,
+      size: stats.size,
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
+      nlink: stats.nlink,
+    });
+    await f.syncMetadataFromDisk(path.dirname(relPath));
+    res.status(201).json({ ok: true });
+  } catch (err) {
+    if (err.code === 'EEXIST') {
+      return res.status(409).json({ error: "File already exists" });
+    }
+    console.error(err);
+    res.status(500).json({ error: "symlink failed" });
+  }
+});
+
+// GET /files/readlink?relPath=... target of a symlink, 400 when the entry is not one
+router.get("/readlink", async (req, res) => {
+  try {
+    const relPath = normalizeRelPath(req.query.relPath);
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    const stats = await fs.promises.lstat(filePathAbs);
+    if (!stats.isSymbolicLink()) {
+      return res.status(400).json({ error: "Not a symlink" });
+    }
+    res.status(200).json({ target: await symlinkTargetOf(filePathAbs, stats) });
+  } catch (err) {
+    if (err.code === 'ENOENT') {
+      return res.status(404).json({ error: "File not found" });
+    }
+    res.status(500).json({ error: "readlink failed" });
+  }
+});
+
+// PATCH /files/chown?relPath=...&uid=1000&gid=1000, a missing id is left as it is
+router.patch("/chown", async (req, res) => {
+  try {
+    const relPath = normalizeRelPath(req.query.relPath);
+    // -1 tells chown(2) to keep the current id
+    const parseId = (v) => (v === undefined ? -1 : Number(v));
+    const uid = parseId(req.query.uid);
+    const gid = parseId(req.query.gid);
+    if (![uid, gid].every((id) => Number.isInteger(id) && id >= -1 && id <= 0xffffffff)) {
+      return res.status(400).json({ error: "Invalid uid or gid" });
+    }
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    backendChanges.add(filePathAbs);
+    await fs.promises.lchown(filePathAbs, uid, gid);
+    // chown clears the setuid and setgid bits of a file
+    const stats = await fs.promises.lstat(filePathAbs);
+    await f.updatePermissions(relPath, (stats.mode & 0o7777).toString(8));
+    res.status(200).json({ ok: true });
+  } catch (err) {
+    if (err.code === 'ENOENT') {
+      return res.status(404).json({ error: "File not found" });
+    }
+    if (err.code === 'EPERM' || err.code === 'EACCES') {
+      return res.status(403).json({ error: "The backend may not change the owner" });
+    }
+    res.status(500).json({ error: "chown failed" });
+  }
+});
+
 // PATCH /files/truncate?relPath=...&size=123
 router.patch("/truncate", async (req, res) => {
   try {
-    let relPath = req.query.relPath;
-    if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
-    }
+    let relPath = normalizeRelPath(req.query.relPath);
     const size = parseInt(req.query.size, 10);
     const filePathAbs = path.join(ROOT_DIR, relPath);
     backendChanges.add(filePathAbs);
@@ -206,9 +360,10 @@ router.patch("/truncate", async (req, res) => {
       name: path.basename(filePathAbs),
       parent: path.dirname(relPath),
       is_dir: false,
+      kind: kindOf(stats),
       size: stats.size,
-      mtime: Math.floor(stats.mtimeMs / 1000),
-      permissions: (stats.mode & 0o777).toString(8),
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
       nlink: stats.nlink,
     });
     res.status(200).json({ ok: true });
@@ -218,17 +373,160 @@ router.patch("/truncate", async (req, res) => {
 });
 
 
-// PATCH /files/rename?oldRelPath=...&newRelPath=... 
-router.patch("/rename", async (req, res) => {
+// PATCH /files/utimes?relPath=...&atime=...&atimeNs=...&mtime=...&mtimeNs=...
+router.patch("/utimes", async (req, res) => {
+  try {
+    let relPath = normalizeRelPath(req.query.relPath);
+    const atime = parseInt(req.query.atime, 10);
+    const atimeNs = parseInt(req.query.atimeNs ?? "0", 10);
+    const mtime = parseInt(req.query.mtime, 10);
+    const mtimeNs = parseInt(req.query.mtimeNs ?? "0", 10);
+    const validNs = (ns) => Number.isInteger(ns) && ns >= 0 && ns <= 999999999;
+    if (!Number.isInteger(atime) || !Number.isInteger(mtime) || !validNs(atimeNs) || !validNs(mtimeNs)) {
+      return res.status(400).json({ error: "Invalid times" });
+    }
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    backendChanges.add(filePathAbs);
+    await fs.promises.utimes(filePathAbs, atime + atimeNs / 1e9, mtime + mtimeNs / 1e9);
+    // The disk may round the nanoseconds, the database keeps the requested value
+    await f.updateTimes(relPath, mtime, mtimeNs);
+    res.status(200).json({ ok: true });
+  } catch (err) {
+    if (err.code === 'ENOENT') {
+      return res.status(404).json({ error: "File not found" });
+    }
+    res.status(500).json({ error: "utimes failed" });
+  }
+});
+
+// POST /files/touch?relPath=... creates the file empty if absent, then sets atime and mtime to now
+router.post("/touch", async (req, res) => {
+  try {
+    const relPath = normalizeRelPath(req.query.relPath);
+    const filePathAbs = path.join(ROOT_DIR, relPath);
+    const parentPath = path.dirname(relPath);
+    if (!fs.existsSync(path.dirname(filePathAbs))) {
+      return res.status(400).json({
+        error: "Parent directory not found. Create the directory first."
+      });
+    }
+    backendChanges.add(filePathAbs);
+    // "a" creates the file without truncating an existing one
+    const fd = await fs.promises.open(filePathAbs, "a");
+    await fd.close();
+    const now = new Date();
+    await fs.promises.utimes(filePathAbs, now, now);
+
+    const stats = await fs.promises.stat(filePathAbs);
+    await f.updateFile({
+      path: relPath,
+      name: path.basename(filePathAbs),
+      parent: parentPath,
+      is_dir: false,
+      kind: kindOf(stats),
+      size: stats.size,
+      ...mtimeOf(stats),
+      permissions: (stats.mode & 0o7777).toString(8),
+      nlink: stats.nlink,
+    });
+    await f.syncMetadataFromDisk(parentPath);
+    res.status(200).json({ ok: true });
+  } catch (err) {
+    if (err.code === 'EISDIR') {
+      return res.status(400).json({ error: "Path is a directory" });
+    }
+    console.error(err);
+    res.status(500).json({ error: "touch failed" });
+  }
+});
+
+// Records a copied entry in the DB, and everything below it for a directory: the listing must show
+// the copy as soon as the request answers, without waiting for the watcher
+async function indexTree(relPath) {
+  const absPath = path.join(ROOT_DIR, relPath);
+  const stats = await fs.promises.lstat(absPath);
+  await f.updateFile({
+    path: relPath,
+    name: path.basename(absPath),
+    parent: path.dirname(relPath),
+    is_dir: stats.isDirectory(),
+    kind: kindOf(stats),
+    symlink_target: await symlinkTargetOf(absPath, stats),
+    size: stats.size,
+    ...mtimeOf(stats),
+    permissions: (stats.mode & 0o7777).toString(8),
+    nlink: stats.nlink,
+  });
+  if (stats.isDirectory()) {
+    for (const name of await fs.promises.readdir(absPath)) {
+      await indexTree(relPath + '/' + name);
+    }
+  }
+}
+
+// POST /files/copy?srcRelPath=...&dstRelPath=...[&recursive=true][&overwrite=true] copies a file, or
+// with recursive a directory and all its content, without the data going through the client.
+// overwrite lets a file replace the content of an existing file, as copy_file_range does
+router.post("/copy", async (req, res) => {
   try {
-    let oldRelPath = req.query.oldRelPath;
-    if (oldRelPath.startsWith('././')) {
-      oldRelPath = oldRelPath.slice(2);
+    const srcRelPath = normalizeRelPath(req.query.srcRelPath);
+    const dstRelPath = normalizeRelPath(req.query.dstRelPath);
+    if (!srcRelPath || !dstRelPath || dstRelPath === '.') {
+      return res.status(400).json({ error: "Missing srcRelPath or dstRelPath" });
+    }
+    const srcAbsPath = path.join(ROOT_DIR, srcRelPath);
+    const dstAbsPath = path.join(ROOT_DIR, dstRelPath);
+    const source = await fs.promises.stat(srcAbsPath);
+    if (source.isDirectory() && req.query.recursive !== 'true') {
+      return res.status(400).json({ error: "Source is a directory, recursive=true is required" });
+    }
+    if (dstAbsPath === srcAbsPath || dstAbsPath.startsWith(srcAbsPath + path.sep)) {
+      return res.status(400).json({ error: "Cannot copy a directory into itself" });
+    }
+    if (!fs.existsSync(path.dirname(dstAbsPath))) {
+      return res.status(404).json({ error: "Destination parent directory does not exist" });
+    }
+    if (fs.existsSync(dstAbsPath)) {
+      const target = await fs.promises.stat(dstAbsPath);
+      if (req.query.overwrite !== 'true' || source.isDirectory() || target.isDirectory()) {
+        return res.status(409).json({ error: "Destination already exists" });
+      }
+      backendChanges.add(dstAbsPath);
+      // Only the content is copied, the destination keeps its mode
+      await fs.promises.copyFile(srcAbsPath, dstAbsPath);
+      await fs.promises.chmod(dstAbsPath, target.mode & 0o7777);
+      await indexTree(dstRelPath);
+      return res.status(200).json({ ok: true });
+    }
+    await fs.promises.cp(srcAbsPath, dstAbsPath, {
+      recursive: true,
+      preserveTimestamps: true,
+      errorOnExist: true,
+      force: false,
+    });
+    await indexTree(dstRelPath);
+    await f.syncMetadataFromDisk(path.dirname(dstRelPath));
+    res.status(200).json({ ok: true });
+  } catch (err) {
+    if (err.code === 'ENOENT') {
+      return res.status(404).json({ error: "File not found for copy" });
+    }
+    if (err.code === 'EEXIST' || err.code === 'ERR_FS_CP_EEXIST') {
+      return res.status(409).json({ error: "Destination already exists" });
     }
-    let newRelPath = req.query.newRelPath;
-     if (newRelPath.startsWith('././')) {
-      newRelPath = newRelPath.slice(2);
+    if (err.code === 'ENOSPC') {
+      return res.status(507).json({ error: "No space left on the backend" });
     }
+    console.error(err);
+    res.status(500).json({ error: "copy failed" });
+  }
+});
+
+// PATCH /files/rename?oldRelPath=...&newRelPath=... 
+router.patch("/rename", async (req, res) => {
+  try {
+    let oldRelPath = normalizeRelPath(req.query.oldRelPath);
+    let newRelPath = normalizeRelPath(req.query.newRelPath);
     if (!oldRelPath || !newRelPath) {
       return res.status(400).json({ error: "Missing oldRelPath or newRelPath" });
     }
@@ -239,11 +537,24 @@ router.patch("/rename", async (req, res) => {
     backendChanges.add(newAbsPath);
 
     const isRestore = oldRelPath.startsWith("/.Trash-");
+    // The source must exist before the destination is touched, otherwise a failed rename loses the target
+    await fs.promises.lstat(oldAbsPath);
+    let replacesExisting = false;
+    let replacesDir = false;
     try {
-      await fs.promises.stat(newAbsPath);
-      await fs.promises.unlink(newAbsPath);
-      await f.deleteFile(newRelPath);
-
+      const target = await fs.promises.lstat(newAbsPath);
+      replacesExisting = true;
+      // POSIX rename: a directory replaces only an empty directory
+      if (target.isDirectory()) {
+        const source = await fs.promises.lstat(oldAbsPath);
+        if (!source.isDirectory()) {
+          return res.status(400).json({ error: "Cannot replace a directory with a file" });
+        }
+        if ((await fs.promises.readdir(newAbsPath)).length > 0) {
+          return res.status(409).json({ error: "Target directory not empty" });
+        }
+        replacesDir = true;
+      }
     } catch (err) {
       if (err.code !== 'ENOENT') {
         throw err;
@@ -260,7 +571,11 @@ router.patch("/rename", async (req, res) => {
         return res.status(404).json({ error: "New parent directory does not exist" });
       }
     }
-    await fs.promises.rename(oldAbsPath, newAbsPath);
+    // The old content is gone only once the new one is in place
+    await renameOver(oldAbsPath, newAbsPath, replacesDir);
+    if (replacesExisting) {
+      await f.deleteFile(newRelPath);
+    }
     await f.rename(oldRelPath, newRelPath);
 
     res.status(200).json({ ok: true });
@@ -269,6 +584,16 @@ router.patch("/rename", async (req, res) => {
     if (err.code === 'ENOENT') {
       return res.status(404).json({ error: "File not found for rename" });
     }
+    if (err.code === 'ENOTEMPTY' || err.code === 'EEXIST') {
+      return res.status(409).json({ error: "Target directory not empty" });
+    }
+    if (err.code === 'ENOTDIR' || err.code === 'EISDIR') {
+      return res.status(400).json({ error: "Cannot replace a file with a directory or vice versa" });
+    }
+    // Source and target on different volumes: the client answers EXDEV and the caller copies instead
+    if (err.code === 'EXDEV') {
+      return res.status(422).json({ error: "Cannot rename across devices", code: "EXDEV" });
+    }
     res.status(500).json({ error: "rename failed" });
   }
 });
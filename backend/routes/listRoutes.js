@@ -1,20 +1,15 @@
 import express from 'express';
 import FileDAO from '../dao/fileDAO.js';
 import { ROOT_DIR } from '../index.js';
+import { normalizeRelPath } from '../utils/relPath.js';
 
 const router = express.Router();
 const file = new FileDAO();
 
-// GET /list/path
+// GET /list?relPath=...
 router.get('/', async (req, res) => {
     try {
-        let dirname = req.query.relPath;
-        if (dirname === '' || dirname === './storage') {
-            dirname = '.';
-        }
-        if (dirname.startsWith('././')) {
-            dirname = dirname.slice(2);
-        }
+        const dirname = normalizeRelPath(req.query.relPath) ?? '.';
         const files = await file.getFilesByDirectory(dirname);
         res.json(files);
         return;
@@ -26,13 +21,7 @@ router.get('/', async (req, res) => {
 
 router.get("/updatedMetadata", async (req, res) => {
     try {
-        let path = req.query.relPath;
-        if (!path || path=="" || path== "./storage") {
-            path = ".";
-        }
-        if (path.startsWith('././')) {
-            path = path.slice(2);
-        }
+        const path = normalizeRelPath(req.query.relPath) ?? '.';
         const f = await file.getFileByPath(path);
         if (!f) {
             return res.status(404).json({ error: "Not found" });
@@ -3,18 +3,18 @@ import fs from 'fs';
 import path from 'path';
 import FileDAO from '../dao/fileDAO.js';
 import { ROOT_DIR, backendChanges } from '../index.js';
+import { mtimeOf } from '../utils/mtime.js';
+import { kindOf } from '../utils/kind.js';
+import { normalizeRelPath } from '../utils/relPath.js';
 
 
 const f = new FileDAO();
 const router = express.Router();
 
-// POST /mkdir/path
+// POST /mkdir?relPath=...
 router.post("/", async (req, res) => {
   try {
-    let relPath = req.query.relPath;
-     if (relPath.startsWith('././')) {
-      relPath = relPath.slice(2);
-    }
+    let relPath = normalizeRelPath(req.query.relPath);
     const dirPath = path.join(ROOT_DIR, relPath);
     const parentPathAbs = path.dirname(dirPath);
     const parentDirName = path.dirname(relPath);
@@ -33,15 +33,16 @@ router.post("/", async (req, res) => {
 
     await fs.promises.mkdir(dirPath);
     const stats = await fs.promises.stat(dirPath);
-    const permissions = (stats.mode & 0o777).toString(8);
+    const permissions = (stats.mode & 0o7777).toString(8);
     
     await f.createDirectory({
       path: relPath,
       parent: parentDirName,
       name: name,
       is_dir: true,
+      kind: kindOf(stats),
       size: stats.size,
-      mtime: Math.floor(stats.mtimeMs / 1000),
+      ...mtimeOf(stats),
       permissions,
       nlink: stats.nlink,
     });
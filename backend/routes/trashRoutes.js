@@ -0,0 +1,177 @@
+import express from 'express';
+import fs from 'fs';
+import path from 'path';
+import FileDAO from '../dao/fileDAO.js';
+import { ROOT_DIR, backendChanges } from '../index.js';
+import { mtimeOf } from '../utils/mtime.js';
+import { kindOf } from '../utils/kind.js';
+import { normalizeRelPath } from '../utils/relPath.js';
+
+// Soft-deleted entries live outside the storage root, so they are neither listed nor watched:
+// each one is a folder <id>/ holding the entry itself and info.json with where it came from
+export const TRASH_DIR = path.join(process.cwd(), "trash");
+
+const f = new FileDAO();
+const router = express.Router();
+
+// An id is a single path segment, anything else could escape the trash folder
+function trashItemDir(id) {
+  if (!id || typeof id !== 'string' || path.basename(id) !== id || id === '.' || id === '..') {
+    return null;
+  }
+  return path.join(TRASH_DIR, id);
+}
+
+async function moveEntry(from, to) {
+  try {
+    await fs.promises.rename(from, to);
+  } catch (err) {
+    // The trash folder may be on another volume than the storage root
+    if (err.code !== 'EXDEV') throw err;
+    await fs.promises.cp(from, to, { recursive: true, preserveTimestamps: true });
+    await fs.promises.rm(from, { recursive: true, force: true });
+  }
+}
+
+// Puts the metadata of a restored entry, and of everything below it, back in the database
+async function indexTree(absPath, relPath) {
+  const stats = await fs.promises.lstat(absPath);
+  await f.updateFile({
+    path: relPath,
+    name: path.basename(relPath),
+    parent: path.dirname(relPath),
+    is_dir: stats.isDirectory(),
+    kind: kindOf(stats),
+    size: stats.size,
+    ...mtimeOf(stats),
+    permissions: (stats.mode & 0o7777).toString(8),
+    nlink: stats.nlink,
+  });
+  if (stats.isDirectory()) {
+    for (const child of await fs.promises.readdir(absPath)) {
+      await indexTree(path.join(absPath, child), `${relPath}/${child}`);
+    }
+  }
+}
+
+// GET /trash, the soft-deleted entries, oldest first
+router.get("/", async (req, res) => {
+  try {
+    await fs.promises.mkdir(TRASH_DIR, { recursive: true });
+    const items = [];
+    for (const id of await fs.promises.readdir(TRASH_DIR)) {
+      try {
+        const itemDir = path.join(TRASH_DIR, id);
+        const info = JSON.parse(await fs.promises.readFile(path.join(itemDir, "info.json"), "utf8"));
+        const stats = await fs.promises.lstat(path.join(itemDir, "entry"));
+        items.push({
+          id,
+          relPath: info.relPath,
+          name: path.basename(info.relPath),
+          is_dir: stats.isDirectory(),
+          size: stats.isDirectory() ? 0 : stats.size,
+          deletedAt: info.deletedAt,
+        });
+      } catch (err) {
+        // A half-written item (crash while trashing) is skipped, not reported as an error
+      }
+    }
+    items.sort((a, b) => a.deletedAt - b.deletedAt);
+    res.json(items);
+  } catch (err) {
+    res.status(500).json({ error: "Internal server error" });
+  }
+});
+
+// POST /trash?relPath=..., moves the entry to the trash instead of deleting it
+router.post("/", async (req, res) => {
+  try {
+    const relPath = normalizeRelPath(req.query.relPath);
+    if (!relPath) {
+      return res.status(400).json({ error: "Missing relPath" });
+    }
+    if (relPath === '.') {
+      return res.status(400).json({ error: "The root cannot be deleted" });
+    }
+    const absPath = path.join(ROOT_DIR, relPath);
+    if (!(await fs.promises.lstat(absPath).catch(() => null))) {
+      return res.status(404).json({ error: "File or directory not found" });
+    }
+
+    const base = `${Date.now()}-${path.basename(relPath)}`;
+    let id = base;
+    for (let n = 2; fs.existsSync(path.join(TRASH_DIR, id)); n++) {
+      id = `${base}-${n}`;
+    }
+    const itemDir = path.join(TRASH_DIR, id);
+    await fs.promises.mkdir(itemDir, { recursive: true });
+    await fs.promises.writeFile(
+      path.join(itemDir, "info.json"),
+      JSON.stringify({ relPath, deletedAt: Math.floor(Date.now() / 1000) })
+    );
+    backendChanges.add(absPath);
+    await moveEntry(absPath, path.join(itemDir, "entry"));
+
+    await f.deleteFile(relPath);
+    await f.syncMetadataFromDisk(path.dirname(relPath));
+    res.status(200).json({ id });
+  } catch (err) {
+    res.status(500).json({ error: "Internal server error" });
+  }
+});
+
+// POST /trash/restore?id=...&relPath=..., puts the entry back where it was deleted, or at relPath
+router.post("/restore", async (req, res) => {
+  try {
+    const itemDir = trashItemDir(req.query.id);
+    if (!itemDir) {
+      return res.status(400).json({ error: "Invalid id" });
+    }
+    const info = JSON.parse(
+      await fs.promises.readFile(path.join(itemDir, "info.json"), "utf8").catch(() => "null")
+    );
+    if (!info) {
+      return res.status(404).json({ error: "Not in the trash" });
+    }
+    const relPath = req.query.relPath !== undefined ? normalizeRelPath(req.query.relPath) : info.relPath;
+    if (!relPath || relPath === '.') {
+      return res.status(400).json({ error: "Invalid relPath" });
+    }
+    const absPath = path.join(ROOT_DIR, relPath);
+    if (!fs.existsSync(path.dirname(absPath))) {
+      return res.status(404).json({ error: "Parent directory not found" });
+    }
+    if (fs.existsSync(absPath)) {
+      return res.status(409).json({ error: "An entry already exists at the restore path" });
+    }
+
+    backendChanges.add(absPath);
+    await moveEntry(path.join(itemDir, "entry"), absPath);
+    await fs.promises.rm(itemDir, { recursive: true, force: true });
+
+    await indexTree(absPath, relPath);
+    await f.syncMetadataFromDisk(path.dirname(relPath));
+    res.status(200).json({ relPath });
+  } catch (err) {
+    res.status(500).json({ error: "Internal server error" });
+  }
+});
+
+// DELETE /trash?id=..., removes an entry from the trash for good
+router.delete("/", async (req, res) => {
+  try {
+    const itemDir = trashItemDir(req.query.id);
+    if (!itemDir) {
+      return res.status(400).json({ error: "Invalid id" });
+    }
+    if (!fs.existsSync(itemDir)) {
+      return res.status(404).json({ error: "Not in the trash" });
+    }
+    await fs.promises.rm(itemDir, { recursive: true, force: true });
+    res.status(200).json({ message: "Deletion completed" });
+  } catch (err) {
+    res.status(500).json({ error: "Internal server error" });
+  }
+});
+
+export default router;
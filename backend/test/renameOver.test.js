@@ -0,0 +1,81 @@
+import { test } from 'node:test';
+import assert from 'node:assert/strict';
+import fs from 'fs';
+import os from 'os';
+import path from 'path';
+import { renameOver } from '../utils/renameOver.js';
+
+// A scratch directory removed when test `t` ends
+function tempRoot(t) {
+  const root = fs.mkdtempSync(path.join(os.tmpdir(), 'rename-over-'));
+  t.after(() => fs.rmSync(root, { recursive: true, force: true }));
+  return root;
+}
+
+// fs.promises whose rename throws `code` for the first `failures` calls, like a Windows host
+// renaming over a directory
+function refusingRename(code, failures) {
+  let calls = 0;
+  return {
+    ...fs.promises,
+    rename: async (from, to) => {
+      calls += 1;
+      if (calls <= failures) {
+        throw Object.assign(new Error(`rename refused: ${code}`), { code });
+      }
+      return fs.promises.rename(from, to);
+    },
+  };
+}
+
+test('a file replaces the existing target', async (t) => {
+  const root = tempRoot(t);
+  fs.writeFileSync(path.join(root, 'a.txt'), 'new');
+  fs.writeFileSync(path.join(root, 'b.txt'), 'old');
+  await renameOver(path.join(root, 'a.txt'), path.join(root, 'b.txt'), false);
+  assert.equal(fs.readFileSync(path.join(root, 'b.txt'), 'utf8'), 'new');
+  assert.ok(!fs.existsSync(path.join(root, 'a.txt')));
+});
+
+test('a refused directory replace removes the empty target and retries', async (t) => {
+  const root = tempRoot(t);
+  fs.mkdirSync(path.join(root, 'src'));
+  fs.writeFileSync(path.join(root, 'src', 'f.txt'), 'data');
+  fs.mkdirSync(path.join(root, 'dst'));
+  await renameOver(path.join(root, 'src'), path.join(root, 'dst'), true, refusingRename('EPERM', 1));
+  assert.equal(fs.readFileSync(path.join(root, 'dst', 'f.txt'), 'utf8'), 'data');
+  assert.ok(!fs.existsSync(path.join(root, 'src')));
+});
+
+test('a retry that fails too puts the target back and leaves the source', async (t) => {
+  const root = tempRoot(t);
+  fs.mkdirSync(path.join(root, 'src'));
+  fs.writeFileSync(path.join(root, 'src', 'f.txt'), 'data');
+  fs.mkdirSync(path.join(root, 'dst'));
+  await assert.rejects(
+    renameOver(path.join(root, 'src'), path.join(root, 'dst'), true, refusingRename('EACCES', 2)),
+    { code: 'EACCES' },
+  );
+  assert.ok(fs.statSync(path.join(root, 'dst')).isDirectory());
+  assert.equal(fs.readFileSync(path.join(root, 'src', 'f.txt'), 'utf8'), 'data');
+});
+
+test('other failures never touch the target', async (t) => {
+  const root = tempRoot(t);
+  fs.mkdirSync(path.join(root, 'src'));
+  fs.mkdirSync(path.join(root, 'dst'));
+  fs.writeFileSync(path.join(root, 'b.txt'), 'old');
+  // Not a directory replace: the refusal is the answer
+  await assert.rejects(
+    renameOver(path.join(root, 'src'), path.join(root, 'b.txt'), false, refusingRename('EPERM', 1)),
+    { code: 'EPERM' },
+  );
+  assert.equal(fs.readFileSync(path.join(root, 'b.txt'), 'utf8'), 'old');
+  // A directory replace failing for another reason is not retried
+  await assert.rejects(
+    renameOver(path.join(root, 'src'), path.join(root, 'dst'), true, refusingRename('EXDEV', 1)),
+    { code: 'EXDEV' },
+  );
+  assert.ok(fs.existsSync(path.join(root, 'dst')));
+  assert.ok(fs.existsSync(path.join(root, 'src')));
+});
@@ -0,0 +1,11 @@
+// Type of an entry as sent to clients in the `kind` field
+export function kindOf(stats) {
+  if (stats.isDirectory()) return 'dir';
+  // Only an lstat result can tell a link
+  if (stats.isSymbolicLink()) return 'symlink';
+  if (stats.isFIFO()) return 'fifo';
+  if (stats.isSocket()) return 'socket';
+  if (stats.isCharacterDevice()) return 'char';
+  if (stats.isBlockDevice()) return 'block';
+  return 'file';
+}
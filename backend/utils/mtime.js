@@ -0,0 +1,25 @@
+// Splits the modification time of a stat result into whole seconds and nanoseconds, with the birth time
+// alongside: crtime is null where the filesystem does not record it (birthtime left at the epoch)
+export function mtimeOf(stats) {
+  return { ...splitTime(stats.mtimeNs, stats.mtimeMs, 'mtime'), ...birthOf(stats) };
+}
+
+function birthOf(stats) {
+  const bigint = typeof stats.birthtimeNs === 'bigint';
+  if (bigint ? stats.birthtimeNs === 0n : !stats.birthtimeMs) {
+    return { crtime: null, crtime_ns: 0 };
+  }
+  return splitTime(stats.birthtimeNs, stats.birthtimeMs, 'crtime');
+}
+
+function splitTime(ns, ms, field) {
+  if (typeof ns === 'bigint') {
+    return {
+      [field]: Number(ns / 1000000000n),
+      [`${field}_ns`]: Number(ns % 1000000000n),
+    };
+  }
+  const secs = Math.floor(ms / 1000);
+  const nanos = Math.min(Math.round((ms - secs * 1000) * 1e6), 999999999);
+  return { [field]: secs, [`${field}_ns`]: nanos };
+}
@@ -0,0 +1,11 @@
+// Canonical form of a relPath query parameter, shared with the client:
+// "." for the root, "./a/b" for everything else. Returns null when missing
+export function normalizeRelPath(raw) {
+  if (raw === undefined || raw === null) return null;
+  // No filesystem accepts a NUL in a name (fs calls would throw): the path is refused like a missing one
+  if (String(raw).includes('\0')) return null;
+  // "./storage" is the legacy root token of the first clients
+  if (raw === './storage') return '.';
+  const parts = String(raw).split(/[\\/]+/).filter((c) => c !== '' && c !== '.');
+  return parts.length === 0 ? '.' : './' + parts.join('/');
+}
@@ -0,0 +1,22 @@
+import fs from 'fs';
+
+// Renames oldAbsPath to newAbsPath, replacing an existing target. fs.rename replaces a file or an
+// empty directory atomically, but Windows hosts refuse to rename over a directory, even an empty
+// one: when `replacesDir` is set the empty target is removed only after a first attempt failed, and
+// put back if the second one fails too, so a failed rename never loses the destination
+export async function renameOver(oldAbsPath, newAbsPath, replacesDir, fsp = fs.promises) {
+  try {
+    await fsp.rename(oldAbsPath, newAbsPath);
+  } catch (err) {
+    if (!replacesDir || !['EPERM', 'EEXIST', 'ENOTEMPTY', 'EACCES'].includes(err.code)) {
+      throw err;
+    }
+    await fsp.rmdir(newAbsPath);
+    try {
+      await fsp.rename(oldAbsPath, newAbsPath);
+    } catch (retryErr) {
+      await fsp.mkdir(newAbsPath).catch(() => {});
+      throw retryErr;
+    }
+  }
+}
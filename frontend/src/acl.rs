@@ -0,0 +1,166 @@
+/// xattr holding the access ACL of an entry
+pub const XATTR_ACCESS: &str = "system.posix_acl_access";
+/// xattr holding the default ACL a directory passes on to new entries
+pub const XATTR_DEFAULT: &str = "system.posix_acl_default";
+
+// Binary layout of the ACL xattrs (linux/posix_acl_xattr.h): a version header, then one
+// (tag: u16, perm: u16, id: u32) record per entry, all little-endian
+const XATTR_VERSION: u32 = 2;
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+/// Kind of ACL an xattr name refers to, as the backend calls it
+pub fn kind_of(xattr: &str) -> Option<&'static str> {
+    match xattr {
+        XATTR_ACCESS => Some("access"),
+        XATTR_DEFAULT => Some("default"),
+        _ => None,
+    }
+}
+
+// Function that converts the text form the backend uses ("user::rw-,user:1000:r--,group::r--,
+// mask::r--,other::r--", numeric ids, commas or newlines between entries) to the xattr value the
+// kernel expects. None when the text is not a valid ACL
+pub fn to_xattr(text: &str) -> Option<Vec<u8>> {
+    let mut entries = Vec::new();
+    for entry in text.split([',', '\n']).map(str::trim) {
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        let mut fields = entry.splitn(3, ':');
+        let (tag, qualifier, perm) = (fields.next()?, fields.next()?, fields.next()?);
+        let named = !qualifier.is_empty();
+        let tag = match (tag, named) {
+            ("user" | "u", false) => ACL_USER_OBJ,
+            ("user" | "u", true) => ACL_USER,
+            ("group" | "g", false) => ACL_GROUP_OBJ,
+            ("group" | "g", true) => ACL_GROUP,
+            ("mask" | "m", false) => ACL_MASK,
+            ("other" | "o", false) => ACL_OTHER,
+            _ => return None,
+        };
+        let id = if named { qualifier.parse().ok()? } else { ACL_UNDEFINED_ID };
+        entries.push((tag, parse_perm(perm)?, id));
+    }
+    if entries.is_empty() {
+        return None;
+    }
+    // The kernel refuses entries out of order
+    entries.sort_by_key(|&(tag, _, id)| (tag, id));
+    let mut value = XATTR_VERSION.to_le_bytes().to_vec();
+    for (tag, perm, id) in entries {
+        value.extend_from_slice(&tag.to_le_bytes());
+        value.extend_from_slice(&perm.to_le_bytes());
+        value.extend_from_slice(&id.to_le_bytes());
+    }
+    Some(value)
+}
+
+// Function that converts an xattr value written by setfacl to the backend's text form. None when
+// the value is not a version 2 ACL
+pub fn from_xattr(value: &[u8]) -> Option<String> {
+    let (header, records) = value.split_at_checked(4)?;
+    if u32::from_le_bytes(header.try_into().ok()?) != XATTR_VERSION || records.len() % 8 != 0 {
+        return None;
+    }
+    let mut entries = Vec::new();
+    for record in records.chunks_exact(8) {
+        let tag = u16::from_le_bytes([record[0], record[1]]);
+        let perm = u16::from_le_bytes([record[2], record[3]]);
+        let id = u32::from_le_bytes([record[4], record[5], record[6], record[7]]);
+        let (name, qualifier) = match tag {
+            ACL_USER_OBJ => ("user", String::new()),
+            ACL_USER => ("user", id.to_string()),
+            ACL_GROUP_OBJ => ("group", String::new()),
+            ACL_GROUP => ("group", id.to_string()),
+            ACL_MASK => ("mask", String::new()),
+            ACL_OTHER => ("other", String::new()),
+            _ => return None,
+        };
+        entries.push(format!("{}:{}:{}", name, qualifier, perm_string(perm)));
+    }
+    Some(entries.join(","))
+}
+
+// Function that reads "rwx"-style permissions, "-" standing for a missing one
+fn parse_perm(perm: &str) -> Option<u16> {
+    let mut bits = 0;
+    for c in perm.chars() {
+        bits |= match c {
+            'r' => 4,
+            'w' => 2,
+            'x' => 1,
+            '-' => 0,
+            _ => return None,
+        };
+    }
+    Some(bits)
+}
+
+fn perm_string(perm: u16) -> String {
+    [(4, 'r'), (2, 'w'), (1, 'x')]
+        .iter()
+        .map(|&(bit, c)| if perm & bit != 0 { c } else { '-' })
+        .collect()
+}
+
+// Function that applies the sticky bit of a directory: only root, the owner of the directory or
+// the owner of the entry may remove it
+pub fn sticky_denies(uid: u32, dir_perm: u16, dir_uid: u32, owner: u32) -> bool {
+    dir_perm & 0o1000 != 0 && uid != 0 && uid != dir_uid && uid != owner
+}
+
+// Function that gives the mode bits of a file overwritten on Windows (CREATE_ALWAYS,
+// TRUNCATE_EXISTING) asking for the READONLY attribute or not: the attributes replace the current
+// ones with `replace`, are added to them otherwise. READONLY is the only one with a mode equivalent,
+// the write bits; a file made writable again gets the owner's back
+pub fn overwrite_perm(perm: u16, readonly: bool, replace: bool) -> u16 {
+    if readonly || (!replace && perm & 0o222 == 0) {
+        perm & !0o222
+    } else if perm & 0o222 == 0 {
+        perm | 0o200
+    } else {
+        perm
+    }
+}
+
+// Function that translates the unix permission bits into the SDDL Windows shows: owner, group and
+// others become one ACE each, with `owner` as the SID of the owner. In a sticky directory only its
+// owner gets FILE_DELETE_CHILD, the others may remove just the entries they own
+pub fn sddl_from_mode(perm: u16, is_dir: bool, owner: &str) -> String {
+    let sticky = is_dir && perm & 0o1000 != 0;
+    let rights = |bits: u16, is_owner: bool| {
+        let delete_child = !sticky || is_owner;
+        if bits == 0o7 && delete_child {
+            return "FA".to_string();
+        }
+        let mut r = String::new();
+        if bits & 0o4 != 0 {
+            r.push_str("FR");
+        }
+        if bits & 0o2 != 0 {
+            r.push_str("FW");
+            if is_dir && delete_child {
+                // FILE_DELETE_CHILD: on unix removing an entry only needs write on the directory
+                r.push_str("DT");
+            }
+        }
+        if bits & 0o1 != 0 {
+            r.push_str("FX");
+        }
+        r
+    };
+    let mut sddl = format!("O:{}G:BUD:P", owner);
+    for (shift, sid) in [(6, owner), (3, "BU"), (0, "WD")] {
+        let r = rights((perm >> shift) & 0o7, shift == 6);
+        if !r.is_empty() {
+            sddl.push_str(&format!("(A;;{};;;{})", r, sid));
+        }
+    }
+    sddl
+}
@@ -0,0 +1,58 @@
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use std::net::{IpAddr, SocketAddr};
+
+/// Port of the backend when the user gives only an IP or a host name
+pub const DEFAULT_BACKEND_PORT: u16 = 3001;
+
+// Function that tells whether the input is meant as an IP address rather than a host name
+fn looks_like_ip(input: &str) -> bool {
+    input.chars().all(|c| c.is_ascii_digit() || c == '.')
+        || (input.contains(':') && input.chars().all(|c| c.is_ascii_hexdigit() || c == ':'))
+}
+
+// Function that turns what the user typed (IP, host, host:port or http(s) URL) into the backend base URL
+pub fn backend_url(input: &str) -> Result<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(anyhow!("Indirizzo del backend mancante: inserisci un IP o un URL"));
+    }
+
+    if input.contains("://") {
+        let url = Url::parse(input)
+            .map_err(|e| anyhow!("URL del backend non valido: {} ({})", input, e))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(anyhow!(
+                "URL del backend non valido: {} (atteso http(s)://host[:porta])",
+                input
+            ));
+        }
+        return Ok(url.as_str().trim_end_matches('/').to_string());
+    }
+
+    if let Ok(addr) = input.parse::<SocketAddr>() {
+        return Ok(format!("http://{}", addr));
+    }
+
+    if looks_like_ip(input) {
+        let ip = input
+            .parse::<IpAddr>()
+            .map_err(|_| anyhow!("Formato IP non valido: {}", input))?;
+        return Ok(match ip {
+            IpAddr::V4(v4) => format!("http://{}:{}", v4, DEFAULT_BACKEND_PORT),
+            IpAddr::V6(v6) => format!("http://[{}]:{}", v6, DEFAULT_BACKEND_PORT),
+        });
+    }
+
+    // Host name, with or without port
+    let with_port = if input.contains(':') {
+        format!("http://{}", input)
+    } else {
+        format!("http://{}:{}", input, DEFAULT_BACKEND_PORT)
+    };
+    Url::parse(&with_port)
+        .ok()
+        .filter(|url| url.host_str().is_some() && url.path() == "/")
+        .map(|_| with_port)
+        .ok_or_else(|| anyhow!("Indirizzo del backend non valido: {}", input))
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io;
+
+/// Error returned by every FileApi call, so callers can match on what went wrong
+/// instead of parsing messages
+#[derive(Debug)]
+pub enum ApiError {
+    /// The backend answered with a non-success status; `op` names the failed call
+    Http { op: &'static str, status: u16, body: String },
+    /// The request never got a complete answer: connection refused, timeout, body cut short
+    Network(reqwest::Error),
+    /// The answer arrived but could not be understood (bad JSON, unexpected encoding)
+    Decode(String),
+    /// Local failure, e.g. reading the file being uploaded or a write refused by --snapshot
+    Io(io::Error),
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+impl ApiError {
+    // Function that turns a failed response into an Http error, keeping the body the backend sent
+    pub async fn from_response(op: &'static str, resp: reqwest::Response) -> Self {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        ApiError::Http { op, status, body }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::Http { status, .. } => Some(*status),
+            ApiError::Network(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Machine-readable `code` the backend sends next to `error` in some answers, e.g. "EXDEV"
+    pub fn backend_code(&self) -> Option<String> {
+        match self {
+            ApiError::Http { body, .. } => serde_json::from_str::<serde_json::Value>(body)
+                .ok()?
+                .get("code")?
+                .as_str()
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    // Function that tells whether the backend refused a rename because it crosses volumes
+    pub fn is_cross_device(&self) -> bool {
+        self.backend_code().as_deref() == Some("EXDEV")
+    }
+
+    // Function that tells whether a rename answered success without having moved the entry, see
+    // FileApi::rename: what the caller cached about both paths may be wrong
+    pub fn is_incomplete_rename(&self) -> bool {
+        self.backend_code().as_deref() == Some("EINCOMPLETE")
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            ApiError::Io(e) => e.kind() == io::ErrorKind::NotFound,
+            _ => self.status() == Some(404),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Http { op, status, body } if body.is_empty() => {
+                write!(f, "{} failed: {}", op, status)
+            }
+            ApiError::Http { op, status, body } => write!(f, "{} failed: {} - {}", op, status, body),
+            ApiError::Network(e) => write!(f, "backend request failed: {}", e),
+            ApiError::Decode(msg) => write!(f, "invalid backend answer: {}", msg),
+            ApiError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Network(e) => Some(e),
+            ApiError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ApiError::Decode(e.to_string())
+        } else {
+            ApiError::Network(e)
+        }
+    }
+}
+
+impl From<io::Error> for ApiError {
+    fn from(e: io::Error) -> Self {
+        ApiError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Decode(e.to_string())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::api_error::ApiResult;
+use crate::file_api::{EntryKind, FileApi};
+use crate::path::{canonical_rel, NAME_MAX};
+use crate::walk::{Checkpoint, Walk, WalkEnd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// Outcome of --audit on one backend
+pub struct Audit {
+    pub end: WalkEnd,
+    /// Totals of the whole tree once done, of the part walked so far when interrupted
+    pub totals: Checkpoint,
+}
+
+// Function that returns the checkpoint file of a backend inside `dir`, or inside the remote-fs folder
+// of the system temp dir: one per backend, so several can be audited in turn
+pub fn checkpoint_path(dir: Option<&Path>, url: &str) -> PathBuf {
+    let dir = dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| std::env::temp_dir().join("remote-fs"));
+    let name: String = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("audit-{}.json", name))
+}
+
+// Function that tells why the client could not show an entry, if it cannot
+fn problem_of(name: &str, rel: &str, path_max: usize) -> Option<String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') || name.contains('\0') {
+        return Some(format!("nome non valido {:?}", name));
+    }
+    if name.len() > NAME_MAX {
+        return Some(format!("nome di {} byte, oltre il limite di {}", name.len(), NAME_MAX));
+    }
+    if rel.len() > path_max {
+        return Some(format!("percorso di {} byte, oltre --max-path-len ({})", rel.len(), path_max));
+    }
+    None
+}
+
+// Function that walks the backend tree, printing the directories that cannot be listed and the entries
+// the client cannot show, and counting directories, files and bytes. Raising `stop` interrupts it
+// within a moment, and the next audit of the same backend resumes from the checkpoint
+pub fn audit_backend(
+    api: &FileApi,
+    rt: &Runtime,
+    checkpoint: &Path,
+    stop: Arc<AtomicBool>,
+    path_max: usize,
+) -> ApiResult<Audit> {
+    let mut walk = Walk::new(api, checkpoint, stop);
+    if let Some(cursor) = &walk.state.cursor {
+        println!("  Ripresa della verifica interrotta dopo {}", cursor);
+    }
+    let end = rt.block_on(walk.run(|rel, listing, totals| {
+        totals.dirs += 1;
+        let entries = match listing {
+            Ok(entries) => entries,
+            Err(e) => {
+                totals.problems += 1;
+                println!("  [ERRORE]   {}: {}", rel, e);
+                return;
+            }
+        };
+        for de in entries {
+            let child = canonical_rel(&Path::new(rel).join(&de.name));
+            if let Some(problem) = problem_of(&de.name, &child, path_max) {
+                totals.problems += 1;
+                println!("  [PROBLEMA] {}/{}: {}", rel, de.name, problem);
+            }
+            if de.entry_kind() != EntryKind::Dir {
+                totals.files += 1;
+                totals.bytes += de.size.max(0) as u64;
+            }
+        }
+    }))?;
+    Ok(Audit {
+        end,
+        totals: walk.state,
+    })
+}
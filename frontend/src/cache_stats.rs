@@ -0,0 +1,107 @@
+use crate::lru::LruMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Hit/miss counters of the metadata cache for a single directory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Misses caused by an expired entry (as opposed to a never cached one)
+    pub refetches: u64,
+    /// Sum of the ages, in milliseconds, at which expired entries were fetched again
+    pub refetch_age_ms: u64,
+}
+
+impl CacheStats {
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+
+    pub fn avg_refetch_age_ms(&self) -> u64 {
+        self.refetch_age_ms.checked_div(self.refetches).unwrap_or(0)
+    }
+}
+
+/// Per-directory cache counters, used to tell whether `cache_ttl` is too short or too long. Only the
+/// `capacity` directories used most recently keep theirs, like the caches they measure
+#[derive(Debug)]
+pub struct CacheMetrics {
+    by_dir: Mutex<LruMap<PathBuf, CacheStats>>,
+}
+
+impl CacheMetrics {
+    pub fn new(capacity: usize) -> Self {
+        CacheMetrics {
+            by_dir: Mutex::new(LruMap::new(capacity)),
+        }
+    }
+
+    // Function that applies `update` to the counters of `dir`, starting them if they are not kept
+    fn update(&self, dir: &Path, update: impl FnOnce(&mut CacheStats)) {
+        let mut by_dir = self.by_dir.lock().unwrap();
+        let mut stats = by_dir.peek(dir).copied().unwrap_or_default();
+        update(&mut stats);
+        by_dir.insert(dir.to_path_buf(), stats);
+    }
+
+    pub fn record_hit(&self, dir: &Path) {
+        self.update(dir, |stats| stats.hits += 1);
+    }
+
+    // Function that records a miss; `cached_at` is the timestamp of the expired entry, if any
+    pub fn record_miss(&self, dir: &Path, cached_at: Option<SystemTime>) {
+        self.update(dir, |stats| {
+            stats.misses += 1;
+            if let Some(ts) = cached_at {
+                let age = SystemTime::now().duration_since(ts).unwrap_or_default();
+                stats.refetches += 1;
+                stats.refetch_age_ms += age.as_millis() as u64;
+            }
+        });
+    }
+
+    pub fn get(&self, dir: &Path) -> CacheStats {
+        self.by_dir
+            .lock()
+            .unwrap()
+            .peek(dir)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_dir.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Function that renders one line per directory, sorted by path
+    pub fn report(&self, line_end: &str) -> String {
+        let by_dir = self.by_dir.lock().unwrap();
+        let mut dirs: Vec<_> = by_dir.iter().collect();
+        dirs.sort_by(|a, b| a.0.cmp(b.0));
+        let mut out = String::new();
+        for (dir, stats) in dirs {
+            let _ = write!(
+                out,
+                "cache[{}]: hits={} misses={} hit_ratio={:.2} avg_refetch_age_ms={}{}",
+                dir.display(),
+                stats.hits,
+                stats.misses,
+                stats.hit_ratio(),
+                stats.avg_refetch_age_ms(),
+                line_end
+            );
+        }
+        out
+    }
+}
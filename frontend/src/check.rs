@@ -0,0 +1,187 @@
+use crate::file_api::{split_time, FileApi};
+use rust_socketio::{ClientBuilder, Event};
+use std::fmt::Display;
+use std::sync::mpsc;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+
+// Content written and read back by the probes
+const PROBE_DATA: &[u8] = b"remote-fs check";
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one endpoint probed by --check
+pub struct Probe {
+    pub endpoint: &'static str,
+    /// `None` when the probe was skipped because an earlier one it depends on failed
+    pub result: Option<Result<(), String>>,
+}
+
+#[derive(Default)]
+pub struct Report {
+    pub probes: Vec<Probe>,
+}
+
+impl Report {
+    // Function that records the outcome of a probe and hands back its value when it passed
+    fn record<T, E: Display>(&mut self, endpoint: &'static str, res: Result<T, E>) -> Option<T> {
+        let (result, value) = match res {
+            Ok(v) => (Ok(()), Some(v)),
+            Err(e) => (Err(e.to_string()), None),
+        };
+        self.probes.push(Probe { endpoint, result: Some(result) });
+        value
+    }
+
+    fn skip(&mut self, endpoints: &[&'static str]) {
+        for endpoint in endpoints {
+            self.probes.push(Probe { endpoint, result: None });
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.probes.iter().all(|p| matches!(p.result, Some(Ok(()))))
+    }
+
+    pub fn print(&self, url: &str) {
+        println!("Verifica del backend {}:", url);
+        for p in &self.probes {
+            match &p.result {
+                Some(Ok(())) => println!("  [OK]      {}", p.endpoint),
+                Some(Err(e)) => println!("  [ERRORE]  {}: {}", p.endpoint, e),
+                None => println!("  [SALTATO] {}", p.endpoint),
+            }
+        }
+    }
+}
+
+// Function that compares what the backend answered with what the probe expects
+fn expect<T: PartialEq + std::fmt::Debug>(got: T, want: T) -> Result<(), String> {
+    if got == want {
+        Ok(())
+    } else {
+        Err(format!("atteso {:?}, ricevuto {:?}", want, got))
+    }
+}
+
+// Function that probes every endpoint FileApi uses, inside a scratch directory removed at the end
+pub fn check_backend(api: &FileApi, rt: &Runtime) -> Report {
+    let mut report = Report::default();
+    let dir = format!("./.remotefs-check-{}", std::process::id());
+    let file = format!("{}/probe", dir);
+    let renamed = format!("{}/probe-renamed", dir);
+    let created = format!("{}/created", dir);
+
+    report.record("GET /health", rt.block_on(api.health()));
+    // Optional: without /stats the mount shows an unknown capacity, see FileApi::capacity
+    report.record("GET /stats", rt.block_on(api.capacity()));
+    report.record("Socket.IO", probe_socket(api.base_url(), api.handshake_headers()));
+
+    if report.record("POST /mkdir", rt.block_on(api.mkdir(&dir))).is_none() {
+        report.skip(&[
+            "GET /list",
+            "PUT /files",
+            "GET /files",
+            "HEAD /files",
+            "GET /list/updatedMetadata",
+            "PATCH /files/chmod",
+            "PATCH /files/truncate",
+            "PATCH /files/utimes",
+            "POST /files/create",
+            "POST /files/touch",
+            "PATCH /files/rename",
+            "DELETE /files",
+        ]);
+        return report;
+    }
+
+    let listed = rt.block_on(api.ls(&dir)).map_err(|e| e.to_string());
+    report.record("GET /list", listed.and_then(|v| expect(v.len(), 0)));
+
+    if report.record("PUT /files", rt.block_on(api.write_bytes(&file, PROBE_DATA))).is_some() {
+        let read = rt
+            .block_on(api.read_range(&file, 0, PROBE_DATA.len() as u64 - 1))
+            .map_err(|e| e.to_string());
+        report.record("GET /files", read.and_then(|data| expect(data.as_slice(), PROBE_DATA)));
+
+        let exists = rt.block_on(api.exists(&file)).map_err(|e| e.to_string());
+        report.record("HEAD /files", exists.and_then(|found| expect(found, true)));
+
+        let meta = rt.block_on(api.get_update_metadata(&file)).map_err(|e| e.to_string());
+        report.record(
+            "GET /list/updatedMetadata",
+            meta.and_then(|de| expect(de.size, PROBE_DATA.len() as i64)),
+        );
+
+        let chmod = rt
+            .block_on(api.chmod(&file, 0o640))
+            .and_then(|_| rt.block_on(api.mode(&file)))
+            .map_err(|e| e.to_string());
+        report.record("PATCH /files/chmod", chmod.and_then(|mode| expect(mode & 0o777, 0o640)));
+
+        let truncate = rt
+            .block_on(api.truncate(&file, 4))
+            .and_then(|_| rt.block_on(api.get_update_metadata(&file)))
+            .map_err(|e| e.to_string());
+        report.record("PATCH /files/truncate", truncate.and_then(|de| expect(de.size, 4)));
+
+        let when = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let utimes = rt
+            .block_on(api.utimes(&file, when, when))
+            .and_then(|_| rt.block_on(api.get_update_metadata(&file)))
+            .map_err(|e| e.to_string());
+        report.record(
+            "PATCH /files/utimes",
+            utimes.and_then(|de| expect(de.mtime, split_time(when).0 as i64)),
+        );
+
+        report.record("POST /files/create", rt.block_on(api.create_exclusive(&created)));
+        report.record("POST /files/touch", rt.block_on(api.touch(&created)));
+
+        let rename = rt
+            .block_on(api.rename(&file, &renamed))
+            .and_then(|_| rt.block_on(api.exists(&renamed)))
+            .map_err(|e| e.to_string());
+        report.record("PATCH /files/rename", rename.and_then(|found| expect(found, true)));
+    } else {
+        report.skip(&[
+            "GET /files",
+            "HEAD /files",
+            "GET /list/updatedMetadata",
+            "PATCH /files/chmod",
+            "PATCH /files/truncate",
+            "PATCH /files/utimes",
+            "POST /files/create",
+            "POST /files/touch",
+            "PATCH /files/rename",
+        ]);
+    }
+
+    // Removing the scratch directory also probes DELETE, the backend deletes recursively
+    let deleted = rt
+        .block_on(api.delete(&dir))
+        .and_then(|_| rt.block_on(api.exists(&dir)))
+        .map_err(|e| e.to_string());
+    report.record("DELETE /files", deleted.and_then(|found| expect(found, false)));
+    report
+}
+
+// Function that opens and closes a Socket.IO connection, as the mount does for change notifications
+fn probe_socket(base_url: &str, headers: Vec<(String, String)>) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut builder = ClientBuilder::new(format!("{}/socket.io/", base_url.trim_end_matches('/')));
+    for (name, value) in headers {
+        builder = builder.opening_header(name, value);
+    }
+    let client = builder
+        .reconnect(false)
+        .on(Event::Connect, move |_, _| {
+            let _ = tx.send(());
+        })
+        .connect()
+        .map_err(|e| e.to_string())?;
+    let connected = rx.recv_timeout(SOCKET_TIMEOUT).map_err(|_| {
+        format!("nessun evento connect entro {} secondi", SOCKET_TIMEOUT.as_secs())
+    });
+    let _ = client.disconnect();
+    connected
+}
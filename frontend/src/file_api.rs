@@ -1,25 +1,319 @@
-use anyhow::{anyhow, Result};
-use reqwest::{Body, Client};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, COOKIE, SET_COOKIE};
+use reqwest::redirect::Policy;
+use reqwest::{Body, Client, RequestBuilder, Response, StatusCode, Url};
 use serde::Deserialize;
-use std::time::{Duration};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::fs;
 use tokio::io::AsyncReadExt;
-use urlencoding::encode;
+
+use crate::api_error::{ApiError, ApiResult};
+use crate::overlay::{backend_error, Overlay};
+use crate::path::{canonical_rel, child_of, parent_and_name};
+use crate::throttle::RateLimiter;
 #[derive(Clone)]
 pub struct FileApi {
     base_url: String,
     client: Client,
+    /// Backend version/timestamp every read is pinned to, writes are refused while set
+    snapshot: Option<String>,
+    /// Timeout of listing, stat and mutation calls, which should answer quickly
+    metadata_timeout: Duration,
+    /// Timeout of each read or upload request, large transfers may legitimately take longer
+    data_timeout: Duration,
+    /// --max-upload-rate / --max-download-rate, `None` means unthrottled
+    upload_limit: Option<Arc<RateLimiter>>,
+    download_limit: Option<Arc<RateLimiter>>,
+    /// --fixed-mtime: the mtime shown for every entry, whatever the backend reports
+    fixed_mtime: Option<SystemTime>,
+    /// --dir-size-from-backend: directories keep the size the backend reports instead of 0
+    dir_size_from_backend: bool,
+    /// --max-redirects: redirects followed by each request before it fails
+    max_redirects: usize,
+    /// --user/--password: credentials of the session login, sent again when the backend answers 401
+    credentials: Option<(String, String)>,
+    /// --header: sent with every request to the backend
+    headers: HeaderMap,
+    /// Cookies set by the backend (name -> value), sent back with every request; shared by the clones
+    cookies: Arc<Mutex<HashMap<String, String>>>,
+    /// Set once /stats answered 404/405/501, so it is not asked again
+    stats_unsupported: Arc<AtomicBool>,
+    /// Set once /files/acl turned out to be missing, so it is not asked again
+    acl_unsupported: Arc<AtomicBool>,
+    /// Set once /files/copy turned out to be missing, copies are then made through this client
+    copy_unsupported: Arc<AtomicBool>,
+    /// --overlay: local directory taking every write, the backend is only read
+    overlay: Option<Arc<Overlay>>,
+}
+
+pub const DEFAULT_METADATA_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_DATA_TIMEOUT: Duration = Duration::from_secs(300);
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+// Function that builds the client shared by the requests: 307/308 keep method and body and are
+// followed here, 301/302 would turn a PUT/PATCH/POST/DELETE into a bodiless GET and are left to `send`,
+// as are redirects to another origin, which must not carry the --header ones
+fn redirect_client(max_redirects: usize) -> Client {
+    let policy = Policy::custom(move |attempt| {
+        let cross_origin = attempt
+            .previous()
+            .last()
+            .is_some_and(|from| !same_origin(from, attempt.url()));
+        if attempt.previous().len() > max_redirects {
+            attempt.error("too many redirects")
+        } else if cross_origin
+            || matches!(attempt.status(), StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND)
+        {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    });
+    Client::builder().redirect(policy).build().unwrap_or_default()
+}
+
+// Function that tells whether two URLs share scheme, host and port
+fn same_origin(a: &Url, b: &Url) -> bool {
+    a.scheme() == b.scheme()
+        && a.host_str() == b.host_str()
+        && a.port_or_known_default() == b.port_or_known_default()
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(from = "RawDirectoryEntry")]
 pub struct DirectoryEntry {
     pub name: String,
+    /// Canonical relPath the backend keeps the entry under, when it sends one. It can differ from
+    /// the path the client asked for (case, Unicode normalization, resolved links)
+    pub path: Option<String>,
+    /// Logical size, what reads return; 0 when the backend could not stat the entry, see `partial`
     pub size: i64,
+    /// Bytes the entry takes on a backend storing it compressed, None when it is the size itself
+    pub stored_size: Option<u64>,
+    /// Epoch when the backend could not stat the entry, see `partial`
     pub mtime: i64,
     pub permissions: String,
     pub is_dir: i64,
     pub version: i64,
     pub nlink: i64,
+    /// Nanoseconds within `mtime`; older backends do not send it
+    pub mtime_ns: i64,
+    /// File type reported by the backend ("file", "dir", "symlink", "fifo", "socket", "char", "block")
+    pub kind: Option<String>,
+    /// Target of a symlink as stored on the backend, when the listing carries it
+    pub symlink_target: Option<String>,
+    /// The backend sent a null size or mtime, the values above are placeholders
+    pub partial: bool,
+    /// Birth time, None when the backend filesystem does not record it
+    pub crtime: Option<i64>,
+    pub crtime_ns: i64,
+}
+
+/// Entry as sent by the backend, size and mtime are null when it could not stat the file
+#[derive(Deserialize)]
+struct RawDirectoryEntry {
+    name: String,
+    #[serde(default)]
+    path: Option<String>,
+    size: Option<i64>,
+    /// Sent, with stored_size, by backends storing files compressed; size is then the stored one
+    #[serde(default)]
+    logical_size: Option<i64>,
+    #[serde(default)]
+    stored_size: Option<i64>,
+    mtime: Option<i64>,
+    permissions: String,
+    is_dir: i64,
+    version: i64,
+    nlink: i64,
+    #[serde(default)]
+    mtime_ns: i64,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    symlink_target: Option<String>,
+    #[serde(default)]
+    crtime: Option<i64>,
+    #[serde(default)]
+    crtime_ns: i64,
+}
+
+impl From<RawDirectoryEntry> for DirectoryEntry {
+    fn from(raw: RawDirectoryEntry) -> Self {
+        DirectoryEntry {
+            partial: raw.logical_size.or(raw.size).is_none() || raw.mtime.is_none(),
+            // The backend's path wins over the name when both are sent, so the entry is keyed the
+            // way the backend spells it in every listing and notification
+            name: raw
+                .path
+                .as_deref()
+                .map(|p| parent_and_name(p).1)
+                .filter(|n| !n.is_empty())
+                .unwrap_or(raw.name),
+            path: raw.path.as_deref().map(|p| canonical_rel(Path::new(p))),
+            size: raw.logical_size.or(raw.size).unwrap_or(0),
+            stored_size: raw
+                .stored_size
+                .or(raw.logical_size.and(raw.size))
+                .map(|s| s.max(0) as u64),
+            mtime: raw.mtime.unwrap_or(0),
+            permissions: raw.permissions,
+            is_dir: raw.is_dir,
+            version: raw.version,
+            nlink: raw.nlink,
+            mtime_ns: raw.mtime.map_or(0, |_| raw.mtime_ns),
+            kind: raw.kind,
+            symlink_target: raw.symlink_target,
+            crtime: raw.crtime,
+            crtime_ns: raw.crtime.map_or(0, |_| raw.crtime_ns),
+        }
+    }
+}
+
+impl DirectoryEntry {
+    pub fn mtime_time(&self) -> SystemTime {
+        system_time(self.mtime, self.mtime_ns)
+    }
+
+    /// Birth time of the entry, the mtime when the backend does not know it
+    pub fn crtime_time(&self) -> SystemTime {
+        self.crtime
+            .map_or_else(|| self.mtime_time(), |secs| system_time(secs, self.crtime_ns))
+    }
+
+    /// 512-byte blocks the entry takes on the backend: the stored size when it is compressed
+    pub fn blocks(&self) -> u64 {
+        self.stored_size
+            .unwrap_or(self.size.max(0) as u64)
+            .div_ceil(512)
+    }
+
+    pub fn entry_kind(&self) -> EntryKind {
+        EntryKind::parse(self.kind.as_deref(), self.is_dir == 1)
+    }
+
+    /// Canonical relPath of the directory holding the entry, as the backend spells it
+    pub fn parent_rel(&self) -> Option<String> {
+        self.path.as_deref().map(|p| parent_and_name(p).0)
+    }
+}
+
+/// Type of a backend entry, beyond the plain file/directory split
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+}
+
+impl EntryKind {
+    // Function that reads the kind sent by the backend, falling back on is_dir when it is missing or unknown
+    pub fn parse(kind: Option<&str>, is_dir: bool) -> EntryKind {
+        match kind {
+            Some("dir") => EntryKind::Dir,
+            Some("file") => EntryKind::File,
+            Some("symlink") => EntryKind::Symlink,
+            Some("fifo") => EntryKind::Fifo,
+            Some("socket") => EntryKind::Socket,
+            Some("char") => EntryKind::CharDevice,
+            Some("block") => EntryKind::BlockDevice,
+            _ if is_dir => EntryKind::Dir,
+            _ => EntryKind::File,
+        }
+    }
+}
+
+/// Builds a time from whole seconds and nanoseconds since the epoch; a value past what SystemTime
+/// can hold (a corrupt mtime) gives the epoch instead of panicking
+pub fn system_time(secs: i64, nanos: i64) -> SystemTime {
+    UNIX_EPOCH
+        .checked_add(Duration::new(secs.max(0) as u64, nanos.clamp(0, 999_999_999) as u32))
+        .unwrap_or(UNIX_EPOCH)
+}
+
+/// Splits a time into whole seconds and nanoseconds since the epoch
+pub fn split_time(t: SystemTime) -> (u64, u32) {
+    let d = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+    (d.as_secs(), d.subsec_nanos())
+}
+
+// Function that warns about the entries the backend could not stat, shown with size 0 and epoch mtime
+fn warn_partial(path: &str, entries: &[DirectoryEntry]) {
+    let names: Vec<&str> = entries
+        .iter()
+        .filter(|de| de.partial)
+        .map(|de| de.name.as_str())
+        .collect();
+    eprintln!(
+        "[WARN] Il backend non conosce dimensione o data di modifica di {} voci in {}: {}. Mostrate con dimensione 0 e data 1970.",
+        names.len(),
+        path,
+        names.join(", ")
+    );
+}
+
+// Function that keeps one entry per case-insensitive name, the one sorting first by bytes, so the
+// entry shown does not depend on the order the backend lists them in; the others are warned about
+// and returned
+fn drop_case_collisions(path: &str, entries: &mut Vec<DirectoryEntry>) -> Vec<String> {
+    let mut kept: HashMap<String, String> = HashMap::new();
+    for de in entries.iter() {
+        kept.entry(de.name.to_lowercase())
+            .and_modify(|name| {
+                if de.name < *name {
+                    *name = de.name.clone();
+                }
+            })
+            .or_insert_with(|| de.name.clone());
+    }
+    if kept.len() == entries.len() {
+        return Vec::new();
+    }
+
+    let mut hidden = Vec::new();
+    entries.retain(|de| {
+        let winner = &kept[&de.name.to_lowercase()];
+        if *winner != de.name {
+            eprintln!(
+                "[WARN] In {} '{}' e '{}' differiscono solo per maiuscole/minuscole: su questo sistema e' visibile solo '{}'.",
+                path, winner, de.name, winner
+            );
+            hidden.push(de.name.clone());
+        }
+        *winner == de.name
+    });
+    hidden
+}
+
+/// POSIX ACLs of an entry, as returned by GET /files/acl, in the text form of acl::to_xattr;
+/// null when the entry has none
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AclResponse {
+    pub access: Option<String>,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// Entry of the backend trash, as listed by GET /trash
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrashEntry {
+    /// Name of the entry in the trash, used to restore or purge it
+    pub id: String,
+    /// Canonical path the entry was deleted from
+    #[serde(rename = "relPath")]
+    pub rel_path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Seconds since the epoch
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: i64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -38,141 +332,804 @@ pub struct StatsResponse {
     pub ffree: u64,
 }
 
+impl StatsResponse {
+    // Function that returns the total and free bytes of the volume. The products saturate instead of
+    // wrapping to a tiny capacity, and free never exceeds total even when the backend says so
+    pub fn volume_bytes(&self) -> (u64, u64) {
+        let total = self.blocks.saturating_mul(self.bsize);
+        let free = self.bfree.saturating_mul(self.bsize).min(total);
+        (total, free)
+    }
+}
+
 impl FileApi {
     pub fn new(base_url: &str) -> Self {
         FileApi {
             base_url: base_url.trim_end_matches('/').to_string(),
-            client: Client::new(),
+            client: redirect_client(DEFAULT_MAX_REDIRECTS),
+            snapshot: None,
+            metadata_timeout: DEFAULT_METADATA_TIMEOUT,
+            data_timeout: DEFAULT_DATA_TIMEOUT,
+            upload_limit: None,
+            download_limit: None,
+            fixed_mtime: None,
+            dir_size_from_backend: false,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            credentials: None,
+            headers: HeaderMap::new(),
+            cookies: Arc::new(Mutex::new(HashMap::new())),
+            stats_unsupported: Arc::new(AtomicBool::new(false)),
+            acl_unsupported: Arc::new(AtomicBool::new(false)),
+            copy_unsupported: Arc::new(AtomicBool::new(false)),
+            overlay: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, user: Option<String>, password: Option<String>) -> Self {
+        self.credentials = user.zip(password);
+        self
+    }
+
+    // Function that sets the --header pairs, already validated by the option parser
+    pub fn with_headers(mut self, headers: &[(String, String)]) -> Self {
+        for (name, value) in headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                self.headers.append(name, value);
+            }
+        }
+        self
+    }
+
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Self {
+        self.client = redirect_client(max_redirects);
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn with_timeouts(mut self, metadata: Duration, data: Duration) -> Self {
+        self.metadata_timeout = metadata;
+        self.data_timeout = data;
+        self
+    }
+
+    pub fn with_rate_limits(mut self, upload: Option<u64>, download: Option<u64>) -> Self {
+        self.upload_limit = upload.map(|rate| Arc::new(RateLimiter::new(rate)));
+        self.download_limit = download.map(|rate| Arc::new(RateLimiter::new(rate)));
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot: Option<String>) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+
+    pub fn with_fixed_mtime(mut self, fixed_mtime: Option<SystemTime>) -> Self {
+        self.fixed_mtime = fixed_mtime;
+        self
+    }
+
+    pub fn fixed_mtime(&self) -> Option<SystemTime> {
+        self.fixed_mtime
+    }
+
+    pub fn with_dir_size_from_backend(mut self, dir_size_from_backend: bool) -> Self {
+        self.dir_size_from_backend = dir_size_from_backend;
+        self
+    }
+
+    pub fn dir_size_from_backend(&self) -> bool {
+        self.dir_size_from_backend
+    }
+
+    // Function that applies the presentation options to an entry: the mtime becomes --fixed-mtime
+    // when set, and a directory shows size 0 unless --dir-size-from-backend
+    fn present(&self, de: &mut DirectoryEntry) {
+        if let Some(fixed) = self.fixed_mtime {
+            let (secs, nanos) = split_time(fixed);
+            de.mtime = secs as i64;
+            de.mtime_ns = nanos as i64;
+        }
+        if de.entry_kind() == EntryKind::Dir && !self.dir_size_from_backend {
+            de.size = 0;
+            de.stored_size = None;
+        }
+    }
+
+    pub fn with_overlay(mut self, overlay: Option<Overlay>) -> Self {
+        self.overlay = overlay.map(Arc::new);
+        self
+    }
+
+    pub fn overlay(&self) -> Option<Arc<Overlay>> {
+        self.overlay.clone()
+    }
+
+    // Function that finds where an --overlay mount serves `rel` from: Some(overlay) when the overlay
+    // has it, a 404 when the overlay deleted it, None when it is read from the backend
+    fn upper_of(&self, op: &'static str, rel: &str) -> ApiResult<Option<&Overlay>> {
+        let Some(ov) = self.overlay.as_deref() else {
+            return Ok(None);
+        };
+        if ov.has_upper(rel) {
+            Ok(Some(ov))
+        } else if ov.hides(rel) {
+            Err(backend_error(op, 404, serde_json::json!({ "error": "File not found" })))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Function that tells whether `rel` is in the view of an --overlay mount
+    async fn overlay_exists(&self, ov: &Overlay, rel: &str) -> ApiResult<bool> {
+        Ok(ov.has_upper(rel) || (!ov.hides(rel) && self.backend_exists(rel).await?))
+    }
+
+    // Function that copies a backend entry into the overlay before it is modified; nothing to do
+    // when the overlay already has it
+    async fn copy_up(&self, ov: &Overlay, rel: &str) -> ApiResult<()> {
+        if ov.has_upper(rel) {
+            return Ok(());
+        }
+        let de = self.get_update_metadata(rel).await?;
+        if de.entry_kind() == EntryKind::Dir {
+            ov.mkdir(rel)?;
+        } else if de.entry_kind() == EntryKind::Symlink {
+            let target = self.readlink(rel).await?;
+            return Ok(ov.symlink(&target, rel)?);
+        } else {
+            let data = self.read_all(rel, de.size.max(0) as u64).await?;
+            ov.write(rel, &mut data.as_slice())?;
+        }
+        if let Ok(mode) = u32::from_str_radix(&de.permissions, 8) {
+            ov.chmod(rel, mode)?;
+        }
+        ov.set_times(rel, de.mtime_time(), de.mtime_time())?;
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> Option<&str> {
+        self.snapshot.as_deref()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Every relPath sent to the backend goes through the shared canonical form. The query encoder
+    // percent-encodes tabs, newlines and other control characters; a NUL cannot be part of a name
+    // on any backend filesystem and is refused before the request
+    fn rel(path: &str) -> ApiResult<String> {
+        if path.contains('\0') {
+            return Err(ApiError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{:?} contains a NUL character", path),
+            )));
+        }
+        Ok(canonical_rel(Path::new(path)))
+    }
+
+    // Query of the read/list requests: the path plus the snapshot selector, if any
+    fn read_query(&self, path: &str) -> ApiResult<Vec<(&'static str, String)>> {
+        let mut query = vec![("relPath", Self::rel(path)?)];
+        if let Some(s) = &self.snapshot {
+            query.push(("snapshot", s.clone()));
+        }
+        Ok(query)
+    }
+
+    // Function that records the cookies of a response; an empty value or Max-Age=0 drops the cookie
+    fn store_cookies(&self, resp: &Response) {
+        let mut cookies = self.cookies.lock().unwrap();
+        for header in resp.headers().get_all(SET_COOKIE) {
+            let Ok(header) = header.to_str() else {
+                continue;
+            };
+            let (pair, attrs) = header.split_once(';').unwrap_or((header, ""));
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let (name, value) = (name.trim(), value.trim());
+            let expired = attrs
+                .split(';')
+                .any(|attr| attr.trim().eq_ignore_ascii_case("max-age=0"));
+            if expired || value.is_empty() {
+                cookies.remove(name);
+            } else {
+                cookies.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    /// Headers of the websocket handshake: the --header ones and the session cookie, as the HTTP
+    /// requests carry them
+    pub fn handshake_headers(&self) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        headers.extend(self.cookie_header().map(|cookie| ("Cookie".to_string(), cookie)));
+        headers
+    }
+
+    // Cookie header carrying the session, `None` until the backend sets a cookie
+    fn cookie_header(&self) -> Option<String> {
+        let cookies = self.cookies.lock().unwrap();
+        if cookies.is_empty() {
+            return None;
+        }
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    // POST /login, opens the cookie session used by the following requests
+    pub async fn login(&self, user: &str, password: &str) -> ApiResult<()> {
+        let url = format!("{}/login", self.base_url);
+        let req = self
+            .client
+            .post(&url)
+            .timeout(self.metadata_timeout)
+            .headers(self.headers.clone())
+            .json(&serde_json::json!({ "username": user, "password": password }))
+            .build()?;
+        // Not through `send`: a 401 here must not trigger another login
+        let resp = self.client.execute(req).await?;
+        self.store_cookies(&resp);
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("login", resp).await)
+        }
+    }
+
+    // Function that sends a request, repeating it (method, body, headers) at the target of a 301/302,
+    // or of any redirect to another origin, which the client does not follow, at most
+    // --max-redirects times. With
+    // --user/--password a 401 means the session expired: the client logs in again and repeats the
+    // request once
+    async fn send(&self, req: RequestBuilder) -> ApiResult<Response> {
+        let mut req = req.build()?;
+        let base = Url::parse(&self.base_url).ok();
+        let mut hops = 0;
+        let mut relogged = false;
+        loop {
+            // The session cookies and the --header ones are for the backend only, not for the host
+            // of a redirect: each hop starts without them
+            for name in self.headers.keys() {
+                req.headers_mut().remove(name);
+            }
+            req.headers_mut().remove(COOKIE);
+            if base.as_ref().is_some_and(|base| same_origin(base, req.url())) {
+                for (name, value) in &self.headers {
+                    req.headers_mut().append(name, value.clone());
+                }
+                if let Some(cookie) = self.cookie_header().and_then(|c| HeaderValue::from_str(&c).ok()) {
+                    req.headers_mut().insert(COOKIE, cookie);
+                }
+            }
+            let retry = req.try_clone();
+            let resp = self.client.execute(req).await?;
+            self.store_cookies(&resp);
+            if resp.status() == StatusCode::UNAUTHORIZED && !relogged {
+                // A streamed body cannot be sent twice, its 401 is returned as is
+                if let (Some((user, password)), Some(next)) = (&self.credentials, retry) {
+                    self.login(user, password).await?;
+                    relogged = true;
+                    req = next;
+                    continue;
+                }
+                return Ok(resp);
+            }
+            if !matches!(
+                resp.status(),
+                StatusCode::MOVED_PERMANENTLY
+                    | StatusCode::FOUND
+                    | StatusCode::TEMPORARY_REDIRECT
+                    | StatusCode::PERMANENT_REDIRECT
+            ) {
+                return Ok(resp);
+            }
+            let target = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|location| resp.url().join(location).ok());
+            match (retry, target) {
+                (Some(next), Some(target)) if hops < self.max_redirects => {
+                    req = next;
+                    *req.url_mut() = target;
+                    hops += 1;
+                }
+                _ => return Err(ApiError::from_response("redirect", resp).await),
+            }
+        }
+    }
+
+    // Function that waits for the download budget of a chunk just received
+    async fn throttle_download(&self, bytes: usize) {
+        if let Some(limit) = &self.download_limit {
+            limit.consume(bytes).await;
+        }
+    }
+
+    // Function that waits for the upload budget of a chunk about to be sent
+    async fn throttle_upload(&self, bytes: usize) {
+        if let Some(limit) = &self.upload_limit {
+            limit.consume(bytes).await;
+        }
+    }
+
+    // Function that returns the size of each PUT: 1 MB, or about a second of transfer when throttled
+    fn upload_chunk_size(&self) -> usize {
+        const CHUNK_SIZE: usize = 1024 * 1024; // 1MB
+        self.upload_limit
+            .as_ref()
+            .map_or(CHUNK_SIZE, |limit| limit.chunk_size(CHUNK_SIZE))
+    }
+
+    // Function that refuses a read served with a Content-Encoding: the bytes would not be the
+    // file content, and a Range over them cannot be mapped back to file offsets
+    fn ensure_identity(res: &reqwest::Response, rel: &str) -> ApiResult<()> {
+        match res
+            .headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(enc) if !enc.trim().eq_ignore_ascii_case("identity") => Err(ApiError::Decode(
+                format!("{} was served {}-encoded, only identity reads are supported", rel, enc),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn ensure_writable(&self) -> ApiResult<()> {
+        match &self.snapshot {
+            Some(s) => Err(ApiError::Io(std::io::Error::new(
+                std::io::ErrorKind::ReadOnlyFilesystem,
+                format!("Read-only snapshot view ({}), writes are not allowed", s),
+            ))),
+            None => Ok(()),
         }
     }
 
     // STATS /stats
-    pub async fn statfs(&self) -> Result<StatsResponse> {
+    pub async fn statfs(&self) -> ApiResult<StatsResponse> {
         let url = format!("{}/stats", self.base_url);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.send(self.client.get(&url).timeout(self.metadata_timeout)).await?;
 
-        let status = resp.status();
-        if status.is_success() {
+        if resp.status().is_success() {
             let stats = resp.json::<StatsResponse>().await?;
             Ok(stats)
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow!("statfs failed: {} - {}", status, text))
+            Err(ApiError::from_response("statfs", resp).await)
+        }
+    }
+
+    /// Capacity of the backend for statfs and the volume info, `None` when the backend has no
+    /// /stats; that answer is remembered for the life of the mount
+    pub async fn capacity(&self) -> ApiResult<Option<StatsResponse>> {
+        if self.stats_unsupported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        match self.statfs().await {
+            Ok(stats) => Ok(Some(stats)),
+            Err(e) if matches!(e.status(), Some(404 | 405 | 501)) => {
+                if !self.stats_unsupported.swap(true, Ordering::Relaxed) {
+                    eprintln!("[WARN] Il backend non implementa /stats: spazio totale e libero mostrati come 0");
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Function that tells whether a failed answer means the backend lacks the endpoint: the route is
+    // missing (a 404 without the JSON error of a missing entry) or not implemented
+    fn route_missing(resp: &Response) -> bool {
+        let json = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("json"));
+        matches!(resp.status().as_u16(), 405 | 501) || (resp.status() == 404 && !json)
+    }
+
+    // Function that tells whether a failed /files/copy answer means the backend cannot copy
+    fn copy_missing(&self, resp: &Response) -> bool {
+        let missing = Self::route_missing(resp);
+        if missing && !self.copy_unsupported.swap(true, Ordering::Relaxed) {
+            eprintln!("[WARN] Il backend non implementa /files/copy: le copie passano dal client");
+        }
+        missing
+    }
+
+    // Function that tells whether a failed /files/acl answer means the backend has no ACLs
+    fn acl_missing(&self, resp: &Response) -> bool {
+        let missing = Self::route_missing(resp);
+        if missing && !self.acl_unsupported.swap(true, Ordering::Relaxed) {
+            eprintln!("[WARN] Il backend non implementa /files/acl: le ACL non sono disponibili");
+        }
+        missing
+    }
+
+    /// ACLs of an entry, `None` when the backend has no ACL support; that answer is remembered
+    /// for the life of the mount
+    pub async fn acl(&self, rel_path: &str) -> ApiResult<Option<AclResponse>> {
+        // Entries of the overlay have no ACLs
+        let shadowed = self.overlay.as_deref().is_some_and(|ov| ov.shadows(rel_path));
+        if shadowed || self.acl_unsupported.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let req = self
+            .client
+            .get(format!("{}/files/acl", self.base_url))
+            .query(&[("relPath", Self::rel(rel_path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(Some(resp.json::<AclResponse>().await?))
+        } else if self.acl_missing(&resp) {
+            Ok(None)
+        } else {
+            Err(ApiError::from_response("acl", resp).await)
+        }
+    }
+
+    /// Replaces the `kind` ("access" or "default") ACL of an entry, `None` removes it. Returns
+    /// false when the backend has no ACL support
+    pub async fn set_acl(&self, rel_path: &str, kind: &str, acl: Option<&str>) -> ApiResult<bool> {
+        self.ensure_writable()?;
+        if self.overlay.is_some() || self.acl_unsupported.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let req = self
+            .client
+            .put(format!("{}/files/acl", self.base_url))
+            .query(&[("relPath", Self::rel(rel_path)?.as_str()), ("kind", kind)])
+            .json(&serde_json::json!({ "acl": acl }))
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(true)
+        } else if self.acl_missing(&resp) {
+            Ok(false)
+        } else {
+            Err(ApiError::from_response("set_acl", resp).await)
         }
     }
 
     // CHMOD /files/chmod
-    pub async fn chmod(&self, rel_path: &str, mode: u32) -> anyhow::Result<()> {
+    pub async fn chmod(&self, rel_path: &str, mode: u32) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            self.copy_up(ov, rel_path).await?;
+            return Ok(ov.chmod(rel_path, mode)?);
+        }
         let url = format!("{}/files/chmod", self.base_url);
-        let perm = format!("{:o}", mode & 0o777);
-        let resp = self
+        let perm = format!("{:o}", mode & 0o7777);
+        let req = self
             .client
             .patch(&url)
-            .query(&[("relPath", rel_path), ("perm", perm.as_str())])
-            .send()
-            .await?;
-        let status = resp.status();
-        if status.is_success() {
+            .query(&[("relPath", Self::rel(rel_path)?.as_str()), ("perm", perm.as_str())])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
             Ok(())
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow::anyhow!("chmod failed: {} - {}", status, text))
+            Err(ApiError::from_response("chmod", resp).await)
         }
     }
 
+    /// PATCH /files/chown: a `None` id is left as it is
+    pub async fn chown(&self, rel_path: &str, uid: Option<u32>, gid: Option<u32>) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            self.copy_up(ov, rel_path).await?;
+            return Ok(ov.chown(rel_path, uid, gid)?);
+        }
+        let url = format!("{}/files/chown", self.base_url);
+        let mut query = vec![("relPath", Self::rel(rel_path)?)];
+        query.extend(uid.map(|uid| ("uid", uid.to_string())));
+        query.extend(gid.map(|gid| ("gid", gid.to_string())));
+        let req = self
+            .client
+            .patch(&url)
+            .query(&query)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("chown", resp).await)
+        }
+    }
+
+    /// POST /files/symlink: creates `link_rel` pointing to `target`, which is stored as given
+    pub async fn symlink(&self, target: &str, link_rel: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            if self.overlay_exists(ov, link_rel).await? {
+                return Err(backend_error("symlink", 409, serde_json::json!({ "error": "File already exists" })));
+            }
+            return Ok(ov.symlink(target, link_rel)?);
+        }
+        let url = format!("{}/files/symlink", self.base_url);
+        let req = self
+            .client
+            .post(&url)
+            .query(&[("relPath", Self::rel(link_rel)?.as_str()), ("target", target)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("symlink", resp).await)
+        }
+    }
+
+    /// GET /files/readlink: target of the symlink at `rel`
+    pub async fn readlink(&self, rel: &str) -> ApiResult<String> {
+        if let Some(ov) = self.upper_of("readlink", rel)? {
+            return Ok(ov.readlink(rel)?);
+        }
+        #[derive(Deserialize)]
+        struct Link {
+            target: String,
+        }
+        let url = format!("{}/files/readlink", self.base_url);
+        let req = self
+            .client
+            .get(&url)
+            .query(&self.read_query(rel)?)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("readlink", resp).await);
+        }
+        let text = resp.text().await?;
+        serde_json::from_str::<Link>(&text)
+            .map(|link| link.target)
+            .map_err(|e| ApiError::Decode(format!("JSON structure mismatch: {}. Body: {}", e, text)))
+    }
+
     // TRUNCATE /files/truncate
-    pub async fn truncate(&self, rel_path: &str, size: u64) -> anyhow::Result<()> {
+    pub async fn truncate(&self, rel_path: &str, size: u64) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            self.copy_up(ov, rel_path).await?;
+            return Ok(ov.set_len(rel_path, size)?);
+        }
         let url = format!("{}/files/truncate", self.base_url);
-        let resp = self
+        let req = self
+            .client
+            .patch(&url)
+            .query(&[("relPath", Self::rel(rel_path)?.as_str()), ("size", &size.to_string())])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("truncate", resp).await)
+        }
+    }
+
+
+    // UTIMES /files/utimes
+    pub async fn utimes(&self, rel_path: &str, atime: SystemTime, mtime: SystemTime) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            self.copy_up(ov, rel_path).await?;
+            return Ok(ov.set_times(rel_path, atime, mtime)?);
+        }
+        let url = format!("{}/files/utimes", self.base_url);
+        let (a_secs, a_nanos) = split_time(atime);
+        let (m_secs, m_nanos) = split_time(mtime);
+        let req = self
             .client
             .patch(&url)
-            .query(&[("relPath", rel_path), ("size", &size.to_string())])
-            .send()
-            .await?;
-        let status = resp.status();
-        if status.is_success() {
+            .query(&[
+                ("relPath", Self::rel(rel_path)?),
+                ("atime", a_secs.to_string()),
+                ("atimeNs", a_nanos.to_string()),
+                ("mtime", m_secs.to_string()),
+                ("mtimeNs", m_nanos.to_string()),
+            ])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("utimes", resp).await)
+        }
+    }
+
+    // TOUCH /files/touch: creates the file empty if absent and sets its times to now, in one request
+    pub async fn touch(&self, rel_path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            if !self.overlay_exists(ov, rel_path).await? {
+                return Ok(ov.write(rel_path, &mut std::io::empty())?);
+            }
+            self.copy_up(ov, rel_path).await?;
+            let now = SystemTime::now();
+            return Ok(ov.set_times(rel_path, now, now)?);
+        }
+        let req = self
+            .client
+            .post(format!("{}/files/touch", self.base_url))
+            .query(&[("relPath", Self::rel(rel_path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
             Ok(())
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow::anyhow!("truncate failed: {} - {}", status, text))
+            Err(ApiError::from_response("touch", resp).await)
         }
     }
 
+    /// HEAD /files?relPath=..., tells whether the entry exists without listing its parent
+    pub async fn exists(&self, rel_path: &str) -> ApiResult<bool> {
+        match self.overlay.as_deref() {
+            Some(ov) => self.overlay_exists(ov, rel_path).await,
+            None => self.backend_exists(rel_path).await,
+        }
+    }
+
+    async fn backend_exists(&self, rel_path: &str) -> ApiResult<bool> {
+        let req = self
+            .client
+            .head(format!("{}/files", self.base_url))
+            .query(&self.read_query(rel_path)?)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        match resp.status() {
+            s if s.is_success() => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            _ => Err(ApiError::from_response("exists", resp).await),
+        }
+    }
 
     /// GET /files?relPath=...
-    pub async fn read_range(&self, rel: &str, start: u64, end: u64) -> anyhow::Result<Vec<u8>> {
-        let encoded = encode(rel);
-        let url = format!("{}/files?relPath={}", self.base_url, encoded);
+    pub async fn read_range(&self, rel: &str, start: u64, end: u64) -> ApiResult<Vec<u8>> {
+        if let Some(ov) = self.upper_of("read_range", rel)? {
+            return Ok(ov.read_at(rel, start, (end - start + 1) as usize)?);
+        }
+        let url = format!("{}/files", self.base_url);
 
         let range_header = format!("bytes={}-{}", start, end);
 
-        let res = self
+        let req = self
             .client
             .get(&url)
+            .query(&self.read_query(rel)?)
             .header("Range", range_header)
-            .send()
-            .await?
-            .error_for_status()?;
+            // Offsets refer to the file's bytes, not to a Content-Encoding of them
+            .header("Accept-Encoding", "identity")
+            .timeout(self.data_timeout);
+        let mut res = self.send(req).await?;
+        // The file is shorter than the cached size said: nothing left past `start`
+        if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(Vec::new());
+        }
+        if !res.status().is_success() {
+            return Err(ApiError::from_response("read_range", res).await);
+        }
+        Self::ensure_identity(&res, rel)?;
 
-        Ok(res.bytes().await?.to_vec())
+        // A server ignoring the Range header answers 200 with the whole file, and a chunked answer
+        // has no Content-Length to trust: the body is streamed and cut to the requested range
+        let mut skip = if res.status() == StatusCode::PARTIAL_CONTENT {
+            0
+        } else {
+            start as usize
+        };
+        let want = (end - start + 1) as usize;
+        let mut out = Vec::with_capacity(res.content_length().map_or(0, |n| (n as usize).min(want)));
+        while out.len() < want {
+            let Some(chunk) = res.chunk().await? else {
+                break;
+            };
+            self.throttle_download(chunk.len()).await;
+            let skipped = skip.min(chunk.len());
+            skip -= skipped;
+            let chunk = &chunk[skipped..];
+            out.extend_from_slice(&chunk[..chunk.len().min(want - out.len())]);
+        }
+        Ok(out)
+    }
+
+    /// GET /files with a Range header, copying the body straight into `buf`; returns the bytes written
+    pub async fn read_into(&self, rel: &str, offset: u64, buf: &mut [u8]) -> ApiResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(ov) = self.upper_of("read_into", rel)? {
+            let data = ov.read_at(rel, offset, buf.len())?;
+            buf[..data.len()].copy_from_slice(&data);
+            return Ok(data.len());
+        }
+        let url = format!("{}/files", self.base_url);
+        let range_header = format!("bytes={}-{}", offset, offset + buf.len() as u64 - 1);
+
+        let req = self
+            .client
+            .get(&url)
+            .query(&self.read_query(rel)?)
+            .header("Range", range_header)
+            // Offsets refer to the file's bytes, not to a Content-Encoding of them
+            .header("Accept-Encoding", "identity")
+            .timeout(self.data_timeout);
+        let mut res = self.send(req).await?;
+        if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(0);
+        }
+        if !res.status().is_success() {
+            return Err(ApiError::from_response("read_into", res).await);
+        }
+        Self::ensure_identity(&res, rel)?;
+
+        // A server ignoring the Range header answers 200 with the whole file
+        let mut skip = if res.status() == StatusCode::PARTIAL_CONTENT {
+            0
+        } else {
+            offset as usize
+        };
+        let mut filled = 0;
+        while filled < buf.len() {
+            let Some(chunk) = res.chunk().await? else {
+                break;
+            };
+            self.throttle_download(chunk.len()).await;
+            let skipped = skip.min(chunk.len());
+            skip -= skipped;
+            let chunk = &chunk[skipped..];
+            let n = chunk.len().min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&chunk[..n]);
+            filled += n;
+        }
+        Ok(filled)
     }
 
     /// PUT /files?relPath=...
-    pub async fn write_file(&self, rel_path: &str, local_path: &str) -> Result<()> {
+    pub async fn write_file(&self, rel_path: &str, local_path: &Path) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            return Ok(ov.write(rel_path, &mut std::fs::File::open(local_path)?)?);
+        }
         let url = format!("{}/files", self.base_url);
+        let rel_path = Self::rel(rel_path)?;
+        let rel_path = rel_path.as_str();
 
         let mut file = fs::File::open(local_path).await?;
         let metadata = file.metadata().await?;
 
         if metadata.len() == 0 {
-            let resp = self
-                .client
-                .put(&url)
-                .query(&[("relPath", rel_path), ("offset", "0")])
-                .body(Body::from(vec![]))
-                .send()
-                .await?;
-
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let text = resp.text().await.unwrap_or_default();
-                return Err(anyhow!(
-                    "write_file failed for empty file: {} - {}",
-                    status,
-                    text
-                ));
-            }
-
-            return Ok(());
+            return self.write_bytes(rel_path, &[]).await;
         }
         let mut offset: u64 = 0;
 
-        const CHUNK_SIZE: usize = 1024 * 1024; // 1MB
-        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut buffer = vec![0u8; self.upload_chunk_size()];
 
         loop {
             let n = file.read(&mut buffer).await?;
             if n == 0 {
                 break;
             }
+            self.throttle_upload(n).await;
 
-            let resp = self
+            let req = self
                 .client
                 .put(&url)
                 .query(&[("relPath", rel_path), ("offset", &offset.to_string())])
                 .body(Body::from(buffer[..n].to_vec()))
-                .send()
-                .await?;
+                .timeout(self.data_timeout);
+            let resp = self.send(req).await?;
 
             if !resp.status().is_success() {
-                let status = resp.status();
-                let text = resp.text().await.unwrap_or_default();
-                return Err(anyhow!(
-                    "write_file failed at offset {}: {} - {}",
-                    offset,
-                    status,
-                    text
-                ));
+                return Err(ApiError::from_response("write_file", resp).await);
             }
 
             offset += n as u64;
@@ -181,146 +1138,587 @@ impl FileApi {
         Ok(())
     }
 
+    /// PUT /files from memory: replaces the whole file with `data`, no local tempfile needed
+    pub async fn write_bytes(&self, rel_path: &str, data: &[u8]) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            return Ok(ov.write(rel_path, &mut &data[..])?);
+        }
+        let url = format!("{}/files", self.base_url);
+        let rel_path = Self::rel(rel_path)?;
+
+        let chunk_size = self.upload_chunk_size();
+        let mut offset = 0;
+        // An empty buffer still sends one request at offset 0, which truncates the file
+        loop {
+            let end = (offset + chunk_size).min(data.len());
+            self.throttle_upload(end - offset).await;
+            let req = self
+                .client
+                .put(&url)
+                .query(&[("relPath", rel_path.as_str()), ("offset", &offset.to_string())])
+                .body(Body::from(data[offset..end].to_vec()))
+                .timeout(self.data_timeout);
+            let resp = self.send(req).await?;
+
+            if !resp.status().is_success() {
+                return Err(ApiError::from_response("write_bytes", resp).await);
+            }
+
+            offset = end;
+            if offset >= data.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// PUT /files?relPath=...&offset=... of `data` alone: the backend keeps the bytes around it, but
+    /// at offset 0 it starts the file over, so that range must reach the end of the file. Ok(false)
+    /// under --overlay, where the caller uploads the whole file instead
+    pub async fn write_range(&self, rel_path: &str, offset: u64, data: &[u8]) -> ApiResult<bool> {
+        self.ensure_writable()?;
+        if self.overlay.is_some() {
+            return Ok(false);
+        }
+        let url = format!("{}/files", self.base_url);
+        let rel_path = Self::rel(rel_path)?;
+
+        let chunk_size = self.upload_chunk_size();
+        let mut sent = 0;
+        loop {
+            let end = (sent + chunk_size).min(data.len());
+            self.throttle_upload(end - sent).await;
+            let at = offset + sent as u64;
+            let req = self
+                .client
+                .put(&url)
+                .query(&[("relPath", rel_path.as_str()), ("offset", &at.to_string())])
+                .body(Body::from(data[sent..end].to_vec()))
+                .timeout(self.data_timeout);
+            let resp = self.send(req).await?;
+
+            if !resp.status().is_success() {
+                return Err(ApiError::from_response("write_range", resp).await);
+            }
+
+            sent = end;
+            if sent >= data.len() {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// POST /files/create: creates an empty file, failing with an Http 409 when the name is taken
+    pub async fn create_exclusive(&self, rel_path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            if self.overlay_exists(ov, rel_path).await? {
+                return Err(backend_error("create", 409, serde_json::json!({ "error": "File already exists" })));
+            }
+            return Ok(ov.write(rel_path, &mut std::io::empty())?);
+        }
+        let req = self
+            .client
+            .post(format!("{}/files/create", self.base_url))
+            .query(&[("relPath", Self::rel(rel_path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("create", resp).await)
+        }
+    }
+
     /// DELETE /files?relPath=...
-    pub async fn delete(&self, rel_path: &str) -> Result<()> {
+    pub async fn delete(&self, rel_path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            let in_backend = !ov.hides(rel_path) && self.backend_exists(rel_path).await?;
+            if !in_backend && !ov.has_upper(rel_path) {
+                return Err(backend_error("delete", 404, serde_json::json!({ "error": "File or directory not found" })));
+            }
+            return Ok(ov.remove(rel_path, in_backend)?);
+        }
         let url = format!("{}/files", self.base_url);
 
-        let resp = self
+        let req = self
             .client
             .delete(&url)
-            .query(&[("relPath", rel_path)])
-            .send()
-            .await?;
+            .query(&[("relPath", Self::rel(rel_path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("delete", resp).await)
+        }
+    }
+
+    /// POST /trash?relPath=..., soft-delete: the entry is moved to the backend trash
+    pub async fn trash(&self, rel_path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        // The backend trash never sees the overlay: a delete is the closest
+        if self.overlay.is_some() {
+            return self.delete(rel_path).await;
+        }
+        let req = self
+            .client
+            .post(format!("{}/trash", self.base_url))
+            .query(&[("relPath", Self::rel(rel_path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("trash", resp).await)
+        }
+    }
+
+    /// GET /trash
+    pub async fn trash_list(&self) -> ApiResult<Vec<TrashEntry>> {
+        let req = self
+            .client
+            .get(format!("{}/trash", self.base_url))
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<TrashEntry>>().await?)
+        } else {
+            Err(ApiError::from_response("trash_list", resp).await)
+        }
+    }
 
-        let status = resp.status();
+    // Function that tells whether the backend has the trash endpoints; an unreachable backend counts
+    // as supporting them, deletes fail anyway until it answers
+    pub async fn trash_supported(&self) -> bool {
+        if self.overlay.is_some() {
+            return false;
+        }
+        match self.trash_list().await {
+            Ok(_) => true,
+            Err(e) => !matches!(e.status(), Some(404 | 405 | 501)),
+        }
+    }
+
+    /// POST /trash/restore?id=..., back to where it was deleted from or to `rel_path`
+    pub async fn trash_restore(&self, id: &str, rel_path: Option<&str>) -> ApiResult<()> {
+        self.ensure_writable()?;
+        let mut query = vec![("id", id.to_string())];
+        if let Some(rel) = rel_path {
+            query.push(("relPath", Self::rel(rel)?));
+        }
+        let req = self
+            .client
+            .post(format!("{}/trash/restore", self.base_url))
+            .query(&query)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
         if resp.status().is_success() {
             Ok(())
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow!("delete failed: {} - {}", status, text))
+            Err(ApiError::from_response("trash_restore", resp).await)
         }
     }
 
-    pub async fn get_update_metadata(&self, rel_path: &str) -> Result<DirectoryEntry> {
+    /// DELETE /trash?id=..., removes the entry from the trash for good
+    pub async fn trash_purge(&self, id: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        let req = self
+            .client
+            .delete(format!("{}/trash", self.base_url))
+            .query(&[("id", id)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(ApiError::from_response("trash_purge", resp).await)
+        }
+    }
+
+    pub async fn get_update_metadata(&self, rel_path: &str) -> ApiResult<DirectoryEntry> {
+        if let Some(ov) = self.upper_of("get_update_metadata", rel_path)? {
+            let mut de = ov.entry(rel_path)?;
+            self.present(&mut de);
+            return Ok(de);
+        }
         let url = format!("{}/list/updatedMetadata", self.base_url);
-        let resp = self
+        let req = self
             .client
             .get(&url)
-            .query(&[("relPath", rel_path)])
-            .send()
-            .await?;
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        if status.is_success() {
-            match serde_json::from_str::<DirectoryEntry>(&text) {
-                Ok(v) => Ok(v),
-                Err(e) => Err(anyhow!("JSON structure mismatch: {}. Body: {}", e, text)),
+            .query(&self.read_query(rel_path)?)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("get_update_metadata", resp).await);
+        }
+        let text = resp.text().await?;
+        match serde_json::from_str::<DirectoryEntry>(&text) {
+            Ok(mut v) => {
+                self.present(&mut v);
+                if v.partial {
+                    warn_partial(rel_path, std::slice::from_ref(&v));
+                }
+                Ok(v)
             }
-        } else {
-            Err(anyhow!(
-                "Get updated metadata failed: {} - {}",
-                status,
-                text
-            ))
+            Err(e) => Err(ApiError::Decode(format!("JSON structure mismatch: {}. Body: {}", e, text))),
         }
     }
 
+    /// Permission bits of a single entry, read from GET /list/updatedMetadata
+    pub async fn mode(&self, rel_path: &str) -> ApiResult<u32> {
+        let de = self.get_update_metadata(rel_path).await?;
+        u32::from_str_radix(&de.permissions, 8)
+            .map_err(|e| ApiError::Decode(format!("Invalid permissions '{}': {}", de.permissions, e)))
+    }
+
     // MKDIR /mkdir
-    pub async fn mkdir(&self, path: &str) -> Result<()> {
-        let resp = self
+    pub async fn mkdir(&self, path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            if self.overlay_exists(ov, path).await? {
+                return Err(backend_error("mkdir", 409, serde_json::json!({ "error": "Directory already exist" })));
+            }
+            return Ok(ov.mkdir(path)?);
+        }
+        let req = self
             .client
             .post(format!("{}/mkdir", self.base_url))
-            .query(&[("relPath", path)])
-            .send()
-            .await?;
+            .query(&[("relPath", Self::rel(path)?)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
 
-        let status = resp.status();
         if resp.status().is_success() {
             Ok(())
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow!("mkdir failed: {} - {}", status, text))
+            Err(ApiError::from_response("mkdir", resp).await)
         }
     }
 
     // LS /list
-    pub async fn ls(&self, path: &str) -> Result<Vec<DirectoryEntry>> {
-        let resp = self
+    pub async fn ls(&self, path: &str) -> ApiResult<Vec<DirectoryEntry>> {
+        let Some(ov) = self.overlay.as_deref() else {
+            return self.backend_ls(path).await;
+        };
+        if ov.hides(path) && !ov.has_upper(path) {
+            return Err(backend_error("ls", 404, serde_json::json!({ "error": "Directory not found" })));
+        }
+        // A directory only the overlay has, or one hiding the backend's, lists the overlay alone
+        let lower = if ov.hides(path) || ov.is_opaque(path) {
+            Vec::new()
+        } else {
+            match self.backend_ls(path).await {
+                Ok(v) => v,
+                Err(e) if e.is_not_found() && ov.has_upper(path) => Vec::new(),
+                Err(e) => return Err(e),
+            }
+        };
+        let mut v = ov.merge(path, lower);
+        v.iter_mut().for_each(|de| self.present(de));
+        Ok(v)
+    }
+
+    async fn backend_ls(&self, path: &str) -> ApiResult<Vec<DirectoryEntry>> {
+        let req = self
             .client
             .get(format!("{}/list", self.base_url))
-            .query(&[("relPath", path)])
-            .send()
-            .await?;
+            .query(&self.read_query(path)?)
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
 
-        let status = resp.status();
         if resp.status().is_success() {
-            let v = resp.json::<Vec<DirectoryEntry>>().await?;
+            let mut v = resp.json::<Vec<DirectoryEntry>>().await?;
+            v.iter_mut().for_each(|de| self.present(de));
+            if v.iter().any(|de| de.partial) {
+                warn_partial(path, &v);
+            }
+            // Windows and macOS resolve names case-insensitively: two names differing only in case
+            // would both answer to the same lookup
+            if cfg!(any(target_os = "windows", target_os = "macos")) {
+                drop_case_collisions(path, &mut v);
+            }
             Ok(v)
         } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow!("ls failed: {} - {}", status, text))
+            Err(ApiError::from_response("ls", resp).await)
+        }
+    }
+
+    /// POST /files/copy: copies `src_rel` to `dst_rel` on the backend, a directory with all its
+    /// content. A backend without the endpoint gets the tree walked and copied through this client
+    pub async fn copy_recursive(&self, src_rel: &str, dst_rel: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if self.overlay.is_none() && !self.copy_unsupported.load(Ordering::Relaxed) {
+            let req = self
+                .client
+                .post(format!("{}/files/copy", self.base_url))
+                .query(&[
+                    ("srcRelPath", Self::rel(src_rel)?.as_str()),
+                    ("dstRelPath", Self::rel(dst_rel)?.as_str()),
+                    ("recursive", "true"),
+                ])
+                .timeout(self.data_timeout);
+            let resp = self.send(req).await?;
+            if resp.status().is_success() {
+                return Ok(());
+            }
+            if !self.copy_missing(&resp) {
+                return Err(ApiError::from_response("copy_recursive", resp).await);
+            }
+        }
+        self.copy_walk(src_rel, dst_rel).await
+    }
+
+    /// POST /files/copy with overwrite: the content of the file `src_rel` replaces the one of
+    /// `dst_rel` on the backend. Ok(false) when the backend has no such endpoint, or the mount is an
+    /// --overlay: the caller then copies the data itself
+    pub async fn copy(&self, src_rel: &str, dst_rel: &str) -> ApiResult<bool> {
+        self.ensure_writable()?;
+        if self.overlay.is_some() || self.copy_unsupported.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let req = self
+            .client
+            .post(format!("{}/files/copy", self.base_url))
+            .query(&[
+                ("srcRelPath", Self::rel(src_rel)?.as_str()),
+                ("dstRelPath", Self::rel(dst_rel)?.as_str()),
+                ("overwrite", "true"),
+            ])
+            .timeout(self.data_timeout);
+        let resp = self.send(req).await?;
+        if resp.status().is_success() {
+            return Ok(true);
+        }
+        if self.copy_missing(&resp) {
+            return Ok(false);
+        }
+        Err(ApiError::from_response("copy", resp).await)
+    }
+
+    // Function that copies a tree by reading it through this client, for backends without
+    // /files/copy. Each file is held in memory while it is copied
+    async fn copy_walk(&self, src_rel: &str, dst_rel: &str) -> ApiResult<()> {
+        let (src, dst) = (Self::rel(src_rel)?, Self::rel(dst_rel)?);
+        if dst == src || dst.starts_with(&format!("{}/", src)) {
+            return Err(ApiError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("cannot copy {} into itself ({})", src, dst),
+            )));
+        }
+        if self.exists(&dst).await? {
+            return Err(backend_error("copy_recursive", 409, serde_json::json!({ "error": "Destination already exists" })));
+        }
+        let root = self.get_update_metadata(&src).await?;
+        let mut pending = vec![(src, dst, root)];
+        // Directories get their mode last, a read-only one would refuse its own content
+        let mut modes = Vec::new();
+        while let Some((from, to, de)) = pending.pop() {
+            let mode = u32::from_str_radix(&de.permissions, 8).ok();
+            if de.entry_kind() == EntryKind::Dir {
+                self.mkdir(&to).await?;
+                for child in self.ls(&from).await? {
+                    let from_child = child_of(&from, &child.name).to_string_lossy().into_owned();
+                    let to_child = child_of(&to, &child.name).to_string_lossy().into_owned();
+                    pending.push((from_child, to_child, child));
+                }
+                modes.extend(mode.map(|m| (to, m)));
+            } else {
+                let data = self.read_all(&from, de.size.max(0) as u64).await?;
+                self.write_bytes(&to, &data).await?;
+                if let Some(m) = mode {
+                    self.chmod(&to, m).await?;
+                }
+            }
         }
+        for (dir, mode) in modes.into_iter().rev() {
+            self.chmod(&dir, mode).await?;
+        }
+        Ok(())
     }
 
     // RENAME /files/rename
-    pub async fn rename(&self, old_rel_path: &str, new_rel_path: &str) -> Result<()> {
+    pub async fn rename(&self, old_rel_path: &str, new_rel_path: &str) -> ApiResult<()> {
+        self.ensure_writable()?;
+        if let Some(ov) = self.overlay.as_deref() {
+            let de = self.get_update_metadata(old_rel_path).await?;
+            let in_backend = !ov.hides(old_rel_path) && self.backend_exists(old_rel_path).await?;
+            // Copying up a whole backend tree is left to the caller, as overlayfs does
+            if in_backend && de.entry_kind() == EntryKind::Dir {
+                return Err(backend_error(
+                    "rename",
+                    422,
+                    serde_json::json!({ "error": "Cannot rename a backend directory in the overlay", "code": "EXDEV" }),
+                ));
+            }
+            self.copy_up(ov, old_rel_path).await?;
+            return Ok(ov.rename(old_rel_path, new_rel_path, in_backend)?);
+        }
         let url = format!("{}/files/rename", self.base_url);
-        let resp = self
+        let (old_rel, new_rel) = (Self::rel(old_rel_path)?, Self::rel(new_rel_path)?);
+        let req = self
             .client
             .patch(&url)
-            .query(&[("oldRelPath", old_rel_path), ("newRelPath", new_rel_path)])
-            .send()
-            .await?;
-        let status = resp.status();
-        if status.is_success() {
-            Ok(())
-        } else {
-            let text = resp.text().await.unwrap_or_default();
-            Err(anyhow!("rename failed: {} - {}", status, text))
+            .query(&[("oldRelPath", &old_rel), ("newRelPath", &new_rel)])
+            .timeout(self.metadata_timeout);
+        let resp = self.send(req).await?;
+        if !resp.status().is_success() {
+            return Err(ApiError::from_response("rename", resp).await);
+        }
+        self.verify_rename(&old_rel, &new_rel).await
+    }
+
+    // Function that checks a rename the backend reported as done: not every backend moves entries
+    // atomically, and one that failed halfway may leave the entry in both places or in neither
+    async fn verify_rename(&self, old_rel: &str, new_rel: &str) -> ApiResult<()> {
+        if old_rel == new_rel {
+            return Ok(());
+        }
+        let moved = self.backend_exists(new_rel).await?;
+        // On a case-insensitive backend a rename changing only the case still finds the old name
+        let left = !old_rel.eq_ignore_ascii_case(new_rel) && self.backend_exists(old_rel).await?;
+        if moved && !left {
+            return Ok(());
         }
+        let state = match (moved, left) {
+            (true, _) => "the source is still there",
+            (false, true) => "the destination is missing",
+            (false, false) => "both the source and the destination are missing",
+        };
+        Err(backend_error(
+            "rename",
+            500,
+            serde_json::json!({
+                "error": format!("Rename of {} to {} reported done but {}", old_rel, new_rel, state),
+                "code": "EINCOMPLETE",
+            }),
+        ))
     }
 
-    pub async fn read_all(&self, rel_path: &str, total_size: u64) -> anyhow::Result<Vec<u8>> {
+    pub async fn read_all(&self, rel_path: &str, total_size: u64) -> ApiResult<Vec<u8>> {
         const CHUNK_SIZE: u64 = 64 * 1024;
         let mut result = Vec::with_capacity(total_size as usize);
         let mut offset = 0;
 
-        while offset < total_size {
-            let end = (offset + CHUNK_SIZE - 1).min(total_size - 1);
+        // Asked even when `total_size` is 0: an empty file answers 416 (Ok, no bytes) and a missing
+        // one 404, so a file deleted meanwhile is not taken for an empty one
+        loop {
+            let end = (offset + CHUNK_SIZE - 1).min(total_size.max(1) - 1);
             let chunk = self.read_range(rel_path, offset, end).await?;
             if chunk.is_empty() {
                 break;
             }
             result.extend_from_slice(&chunk);
             offset += chunk.len() as u64;
+            if offset >= total_size {
+                break;
+            }
         }
 
         Ok(result)
     }
 
-    pub async fn health(ip: &str) -> anyhow::Result<(), anyhow::Error> {
-        let url = format!("http://{}:3001/health", ip);
+    pub async fn health(&self) -> ApiResult<()> {
+        let url = format!("{}/health", self.base_url);
         let client = reqwest::Client::builder()
             .connect_timeout(Duration::from_secs(1))
             .build()?;
 
-        let resp = client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|_| anyhow::anyhow!("Backend unreachable at IP address {}", ip))?;
+        let resp = client.get(&url).headers(self.headers.clone()).send().await?;
 
         if resp.status().is_success() {
             let body: serde_json::Value = resp.json().await?;
             if body["service"] == "project-api-2025" {
                 Ok(())
             } else {
-                Err(anyhow!(
-                    "Correct IP address, wrong service 'project-api-2025'",
+                Err(ApiError::Decode(
+                    "Correct IP address, wrong service 'project-api-2025'".to_string(),
                 ))
             }
         } else {
-            Err(anyhow!("Backend answered with error: {}", resp.status()))
+            Err(ApiError::from_response("health", resp).await)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(bsize: u64, blocks: u64, bfree: u64) -> StatsResponse {
+        StatsResponse {
+            bsize,
+            blocks,
+            bfree,
+            bavail: bfree,
+            files: 0,
+            ffree: 0,
+        }
+    }
+
+    #[test]
+    fn volume_bytes_multiplies_blocks_by_size() {
+        assert_eq!(stats(4096, 250_000_000, 1000).volume_bytes(), (1_024_000_000_000, 4_096_000));
+    }
+
+    #[test]
+    fn volume_bytes_saturates_instead_of_wrapping() {
+        let bsize = 4096;
+        let near = u64::MAX / bsize;
+        // Still fits
+        assert_eq!(stats(bsize, near, near).volume_bytes(), (near * bsize, near * bsize));
+        // One block more would wrap around to a few kilobytes
+        let (total, free) = stats(bsize, near + 1, near + 1).volume_bytes();
+        assert_eq!(total, u64::MAX);
+        assert_eq!(free, u64::MAX);
+        let (total, _) = stats(bsize, u64::MAX, 0).volume_bytes();
+        assert_eq!(total, u64::MAX);
+    }
+
+    #[test]
+    fn volume_bytes_keeps_free_within_total() {
+        assert_eq!(stats(512, 10, 20).volume_bytes(), (5120, 5120));
+        assert_eq!(stats(4096, near_max_blocks(), u64::MAX).volume_bytes().1, near_max_blocks() * 4096);
+    }
+
+    fn near_max_blocks() -> u64 {
+        u64::MAX / 4096 - 1
+    }
+
+    fn listing(names: &[&str]) -> Vec<DirectoryEntry> {
+        names
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::json!({
+                    "name": name, "size": 1, "mtime": 0, "permissions": "644",
+                    "is_dir": 0, "version": 1, "nlink": 1,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn case_collisions_keep_the_same_entry_whatever_the_listing_order() {
+        let names = |entries: &[DirectoryEntry]| entries.iter().map(|de| de.name.clone()).collect::<Vec<_>>();
+
+        let mut entries = listing(&["file.txt", "other.txt", "File.txt", "FILE.TXT"]);
+        let hidden = drop_case_collisions("./docs", &mut entries);
+        assert_eq!(names(&entries), ["other.txt", "FILE.TXT"]);
+        assert_eq!(hidden, ["file.txt", "File.txt"]);
+
+        let mut entries = listing(&["FILE.TXT", "File.txt", "other.txt", "file.txt"]);
+        let hidden = drop_case_collisions("./docs", &mut entries);
+        assert_eq!(names(&entries), ["FILE.TXT", "other.txt"]);
+        assert_eq!(hidden, ["File.txt", "file.txt"]);
+    }
+
+    #[test]
+    fn a_listing_without_case_collisions_is_left_alone() {
+        let mut entries = listing(&["a.txt", "b.txt", "a.TXT.bak"]);
+        assert!(drop_case_collisions("./docs", &mut entries).is_empty());
+        assert_eq!(entries.len(), 3);
+    }
+}
@@ -0,0 +1,46 @@
+use rust_socketio::Payload;
+use serde_json::Value;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::overlay::Overlay;
+
+// Function that extracts the event object of an fs_change frame. The backend sends a JSON object;
+// a JSON-encoded string or a binary frame holding JSON are accepted too, anything else is dropped
+pub fn decode(payload: &Payload) -> Option<Value> {
+    let value = match payload {
+        Payload::Text(values) => match values.first()? {
+            Value::String(text) => serde_json::from_str(text).ok()?,
+            value => value.clone(),
+        },
+        Payload::Binary(bytes) => serde_json::from_slice(bytes).ok()?,
+        _ => return None,
+    };
+    value.is_object().then_some(value)
+}
+
+// Function that hands one fs_change frame to `handle`. A frame that cannot be decoded is skipped,
+// and a handler panicking on an unexpected event is caught: either way the listener keeps running
+// and only that event is lost
+pub fn dispatch(payload: &Payload, handle: impl FnOnce(&Value)) {
+    let Some(event) = decode(payload) else {
+        if cfg!(debug_assertions) {
+            eprintln!("[FS_EVENT] Malformed fs_change frame ignored: {:?}", payload);
+        }
+        return;
+    };
+    if catch_unwind(AssertUnwindSafe(|| handle(&event))).is_err() {
+        eprintln!("[WARN] Evento fs_change non gestito, ignorato: {}", event);
+    }
+}
+
+// Function that tells whether an event is about entries an --overlay mount serves itself: the
+// backend change does not show through, and applying it would put the backend metadata in cache
+pub fn shadowed(event: &Value, overlay: Option<&Overlay>) -> bool {
+    let Some(ov) = overlay else {
+        return false;
+    };
+    ["relPath", "oldPath", "newPath"]
+        .iter()
+        .filter_map(|key| event[*key].as_str())
+        .any(|rel| ov.shadows(rel))
+}
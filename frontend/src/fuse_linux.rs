@@ -1,56 +1,147 @@
-use crate::file_api::{DirectoryEntry, FileApi};
+use crate::api_error::{ApiError, ApiResult};
+use crate::cache_stats::CacheMetrics;
+use crate::file_api::{system_time, DirectoryEntry, EntryKind, FileApi, TrashEntry};
+use crate::invalidation::{spawn_coalescer, spawn_debouncer, Inval, InvalSender};
+use crate::lru::LruMap;
+use crate::options::{
+    CommitStrategy, ConflictPolicy, MountOptions, UnlinkPolicy, COMMIT_INTERVAL, DEFAULT_FS_NAME,
+};
+use crate::overlay::Overlay;
+use crate::path::{canonical_rel, child_of, mount_path, too_long, DEFAULT_PATH_MAX};
+use crate::recovery::Recovered;
+use crate::workers::WorkerPool;
+use crate::MountSpec;
 use anyhow::Result;
 use fuser016::{
-    spawn_mount2, FileAttr, FileType, Filesystem, MountOption, Notifier, ReplyAttr, ReplyCreate,
-    ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, Request,
+    consts::{FOPEN_DIRECT_IO, FUSE_ATOMIC_O_TRUNC, FUSE_DO_READDIRPLUS}, spawn_mount2, BackgroundSession, FileAttr, FileType, Filesystem, KernelConfig, MountOption, Notifier, ReplyAttr, ReplyCreate,
+    ReplyData, ReplyDirectory, ReplyDirectoryPlus, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, ReplyXattr, Request,
     TimeOrNow,
 };
 use libc::{EIO, ENOENT, ENOTDIR, ENOTEMPTY};
-use rust_socketio::{ClientBuilder, Payload};
+use rust_socketio::{client::Client, ClientBuilder, Event};
 use serde_json::Value;
 use signal_hook::consts::signal::{SIGINT, SIGTERM};
 use signal_hook::iterator::Signals;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::future::Future;
 use std::thread;
 use std::{
-    collections::HashMap,
-    ffi::OsStr,
-    fs::{File, OpenOptions},
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fs::File,
     io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
-    sync::{mpsc::channel, Arc, Mutex},
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    sync::{
+        mpsc::{channel, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::runtime::Runtime;
 
 const TTL: Duration = Duration::from_millis(2000);
-
-#[derive(Debug, Clone, Copy)]
-struct HttpStatus(pub u16);
-impl std::fmt::Display for HttpStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "http status {}", self.0)
-    }
-}
-
-impl std::error::Error for HttpStatus {}
+// How often mount_all looks for sessions the kernel closed under it
+const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// INIT flag asking the kernel to hand the POSIX ACL xattrs to the filesystem (--acl); fuser only
+// exports it with the abi-7-26 feature
+const FUSE_POSIX_ACL: u64 = 1 << 20;
+
+// Virtual control directory exposed at the mount root, never forwarded to the backend
+const CONTROL_DIR: &str = ".remotefs";
+const CONTROL_REFRESH: &str = "refresh";
+const CONTROL_DIR_INO: u64 = u64::MAX - 1;
+const CONTROL_REFRESH_INO: u64 = u64::MAX - 2;
+const CONTROL_STATUS: &str = "status";
+const CONTROL_STATUS_INO: u64 = u64::MAX - 3;
+// Writing a source and a destination line copies the tree on the backend, see copy_from_control
+const CONTROL_COPY: &str = "copy";
+const CONTROL_COPY_INO: u64 = u64::MAX - 6;
+// Files of --recovery-dir, read-only; they take the inodes below RECOVERED_INO_BASE in order of discovery
+const CONTROL_RECOVERED: &str = "recovered";
+const CONTROL_RECOVERED_INO: u64 = u64::MAX - 4;
+const RECOVERED_INO_BASE: u64 = u64::MAX - 1024;
+// Backend trash (--trash): moving an entry out restores it, removing it purges it for good
+const CONTROL_TRASH: &str = "trash";
+const CONTROL_TRASH_INO: u64 = u64::MAX - 5;
+const TRASH_INO_BASE: u64 = u64::MAX - (1 << 32);
+// Name given on the backend to a file unlinked while still open, followed by its inode in hex
+const UNLINKED_PREFIX: &str = ".remotefs_unlinked_";
 
 pub(crate) struct TempWrite {
+    ino: u64,
+    // Path the handle was opened at, where --conflict-policy sends the data if the file goes away
+    path: PathBuf,
     tem_path: PathBuf,
     size: u64,
     dirty: bool,
+    // Held around the read of `size`, the tempfile write and the update of `size`, so writes and
+    // truncations on one handle never interleave, while other handles go on in parallel
+    io: Arc<Mutex<()>>,
+    // Commits of the handle the backend refused in a row, and when the next one may be tried
+    failures: usize,
+    retry_at: Option<Instant>,
+    // Given up after --commit-attempts failures: the data is in the recovery directory and the
+    // backend is not asked again
+    abandoned: bool,
+}
+
+// Handle open on an inode; it goes stale when the file is deleted or moved remotely
+pub(crate) struct OpenHandle {
+    ino: u64,
+    stale: bool,
+    // Opened with O_DIRECT
+    direct: bool,
+    // Last re-stat of the file for --coherency-interval
+    checked: Instant,
 }
 
+type DirCacheEntry = (Vec<DirectoryEntry>, SystemTime);
+
 #[derive(Clone)]
 pub(crate) struct FsState {
     pub ino_by_path: Arc<Mutex<HashMap<PathBuf, u64>>>,
     pub path_by_ino: Arc<Mutex<HashMap<u64, PathBuf>>>,
-    pub attr_cache: Arc<Mutex<HashMap<PathBuf, FileAttr>>>,
-    pub dir_cache: Arc<Mutex<HashMap<PathBuf, (Vec<DirectoryEntry>, SystemTime)>>>,
+    // Both capped at --max-cache-entries, the least recently used entries go first
+    pub attr_cache: Arc<Mutex<LruMap<PathBuf, FileAttr>>>,
+    pub dir_cache: Arc<Mutex<LruMap<PathBuf, DirCacheEntry>>>,
+    // Names looked up and not found, keyed on (parent, name), with the time they stop being trusted
+    pub negative: Arc<Mutex<HashMap<(PathBuf, OsString), Instant>>>,
+    // --negative-ttl, also handed to the kernel with the negative entries
+    pub negative_ttl: Duration,
     pub writes: Arc<Mutex<HashMap<u64, TempWrite>>>,
     pub next_ino: Arc<Mutex<u64>>,
     pub cache_ttl: Duration,
     pub next_fh: Arc<AtomicU64>,
+    pub ws_client: Arc<Mutex<Option<Client>>>,
+    pub ws_connected: Arc<AtomicBool>,
+    // Last filesystem operation, and whether the websocket was closed for inactivity (--ws-idle-timeout)
+    pub last_activity: Arc<Mutex<Instant>>,
+    pub ws_idle: Arc<AtomicBool>,
+    pub ws_failures: Arc<AtomicU64>,
+    pub ws_last_error: Arc<Mutex<Option<String>>>,
+    // Handles given up after --commit-attempts failed commits, reported by the status files
+    pub abandoned_commits: Arc<AtomicU64>,
+    pub cache_metrics: Arc<CacheMetrics>,
+    pub open_handles: Arc<Mutex<HashMap<u64, OpenHandle>>>,
+    // References the kernel holds on each inode, as counted by lookup/create/mkdir replies
+    pub lookups: Arc<Mutex<HashMap<u64, u64>>>,
+    // Held while the interval committer uploads, so release never drops a tempfile being sent
+    pub commit_lock: Arc<Mutex<()>>,
+    // Bumped by every full cache clear: results fetched under an older generation are not cached
+    pub cache_generation: Arc<AtomicU64>,
+    // Inodes unlinked while open, deleted on the backend when their last handle is released
+    pub unlinked: Arc<Mutex<HashSet<u64>>>,
+    // One lock per path being created, so two creates of the same name do not both commit
+    pub create_locks: Arc<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>>,
+    // --fixed-mtime, also applied to the metadata pushed by the websocket
+    pub fixed_mtime: Option<SystemTime>,
+    pub dir_size_from_backend: bool,
+    pub overlay: Option<Arc<Overlay>>,
+    // atime/mtime set on a file with unsent writes: the upload resets the backend mtime, so they
+    // are sent again once the data is there (full fsync or release)
+    pub pending_times: Arc<Mutex<HashMap<u64, (SystemTime, SystemTime)>>>,
+    // --stale-tolerance, how long the kernel may keep serving a file changed on the backend
+    pub stale_tolerance: Option<Duration>,
 }
 
 struct RemoteFs {
@@ -58,41 +149,96 @@ struct RemoteFs {
     api: FileApi,
     rt: Arc<Runtime>,
     notifier: Arc<Mutex<Option<Notifier>>>,
+    opts: MountOptions,
+    // Reused by every backend read, so reads do not allocate a buffer each
+    read_buf: Vec<u8>,
+    // --fuse-threads: workers answering the backend reads, None reads on the session thread
+    readers: Option<WorkerPool>,
+    // Where the tempfiles of open handles live, see temp_dir::choose
+    temp_dir: PathBuf,
+    // Names of the recovered files looked up so far, the inode of each is RECOVERED_INO_BASE - index
+    recovered_names: Vec<String>,
+    // Same for the ids of the trash entries, below TRASH_INO_BASE
+    trash_names: Vec<String>,
+    // Trash as last listed by the backend
+    trash_listing: Vec<TrashEntry>,
+}
+
+// Function that maps a failed backend call to the errno returned to the kernel
+fn errno_from_api(err: &ApiError) -> i32 {
+    use libc::{EACCES, EEXIST, EINVAL, EIO, ENOENT, ENOSPC, EXDEV};
+    match err {
+        ApiError::Http { .. } if err.is_cross_device() => EXDEV,
+        ApiError::Http { status, .. } => match *status {
+            409 => EEXIST,       // Already exist
+            404 => ENOENT,       // Not found
+            401 | 403 => EACCES, // Permissions/Authorization
+            400 => EINVAL,       // Invalid arguments
+            507 | 413 => ENOSPC, // Too large
+            _ => EIO,            // Server error
+        },
+        ApiError::Io(ioe) => errno_from_io(ioe),
+        ApiError::Network(_) | ApiError::Decode(_) => EIO,
+    }
+}
+
+fn errno_from_io(ioe: &std::io::Error) -> i32 {
+    use libc::{EACCES, EEXIST, EINVAL, EIO, ENOENT, ENOSPC, EROFS};
+    match ioe.kind() {
+        std::io::ErrorKind::PermissionDenied => EACCES,
+        std::io::ErrorKind::NotFound => ENOENT,
+        std::io::ErrorKind::AlreadyExists => EEXIST,
+        std::io::ErrorKind::InvalidInput => EINVAL,
+        std::io::ErrorKind::StorageFull => ENOSPC,
+        std::io::ErrorKind::ReadOnlyFilesystem => EROFS,
+        std::io::ErrorKind::WriteZero
+        | std::io::ErrorKind::UnexpectedEof
+        | std::io::ErrorKind::TimedOut
+        | std::io::ErrorKind::BrokenPipe
+        | std::io::ErrorKind::ConnectionReset => EIO,
+        _ => EIO,
+    }
 }
 
 fn errno_from_anyhow(err: &anyhow::Error) -> i32 {
-    use libc::{EACCES, EEXIST, EINVAL, EIO, ENOENT, ENOSPC};
     for cause in err.chain() {
-        if let Some(ioe) = cause.downcast_ref::<std::io::Error>() {
-            return match ioe.kind() {
-                std::io::ErrorKind::PermissionDenied => EACCES,
-                std::io::ErrorKind::NotFound => ENOENT,
-                std::io::ErrorKind::AlreadyExists => EEXIST,
-                std::io::ErrorKind::InvalidInput => EINVAL,
-                std::io::ErrorKind::WriteZero
-                | std::io::ErrorKind::UnexpectedEof
-                | std::io::ErrorKind::TimedOut
-                | std::io::ErrorKind::BrokenPipe
-                | std::io::ErrorKind::ConnectionReset => EIO,
-                _ => EIO,
-            };
+        if let Some(api_err) = cause.downcast_ref::<ApiError>() {
+            return errno_from_api(api_err);
         }
-        if let Some(HttpStatus(code)) = cause.downcast_ref::<HttpStatus>() {
-            return match *code {
-                409 => EEXIST,       // Already exist
-                404 => ENOENT,       // Not found
-                401 | 403 => EACCES, // Permissions/Authorization
-                400 => EINVAL,       // Invalid arguments
-                507 | 413 => ENOSPC, // Too large
-                _ => EIO,            // Server error
-            };
+        if let Some(ioe) = cause.downcast_ref::<std::io::Error>() {
+            return errno_from_io(ioe);
         }
     }
     EIO
 }
 
+// Function that answers an xattr read: the length alone when the caller passes a zero size, ERANGE
+// when its buffer is too small
+fn reply_xattr(reply: ReplyXattr, size: u32, value: &[u8]) {
+    if size == 0 {
+        reply.size(value.len() as u32);
+    } else if value.len() > size as usize {
+        reply.error(libc::ERANGE);
+    } else {
+        reply.data(value);
+    }
+}
+
+// Function that maps the kind reported by the backend to the type shown by FUSE
+fn file_type_of(kind: EntryKind) -> FileType {
+    match kind {
+        EntryKind::File => FileType::RegularFile,
+        EntryKind::Dir => FileType::Directory,
+        EntryKind::Symlink => FileType::Symlink,
+        EntryKind::Fifo => FileType::NamedPipe,
+        EntryKind::Socket => FileType::Socket,
+        EntryKind::CharDevice => FileType::CharDevice,
+        EntryKind::BlockDevice => FileType::BlockDevice,
+    }
+}
+
 // Function that extract the file's metadata from the payload
-fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64, i64, u16, i64)> {
+fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, FileType, u64, SystemTime, u16, i64)> {
     if cfg!(debug_assertions) {
         println!(
             "[UPDATE_METADATA_FROM_PAYLOAD] Payload received: {:?}",
@@ -110,53 +256,91 @@ fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64,
                 .map(|s| s.to_string())
         })?;
 
-    let is_dir = payload["is_dir"].as_bool().unwrap_or(false);
-    let size = payload["size"].as_u64().unwrap_or(0);
-    let mtime = payload["mtime"].as_i64().unwrap_or(0);
+    let kind = file_type_of(EntryKind::parse(
+        payload["kind"].as_str(),
+        payload["is_dir"].as_bool().unwrap_or(false),
+    ));
+    // Backends storing files compressed send the logical size apart
+    let size = payload["logical_size"]
+        .as_u64()
+        .or(payload["size"].as_u64())
+        .unwrap_or(0);
+    let mtime = system_time(
+        payload["mtime"].as_i64().unwrap_or(0),
+        payload["mtime_ns"].as_i64().unwrap_or(0),
+    );
 
     let perm_str = payload["permissions"].as_str().unwrap_or("644");
     let perm = u16::from_str_radix(perm_str, 8).unwrap_or(0o644);
     let nlink = payload["nlink"].as_i64().unwrap_or(1);
-    let abs = Path::new("/").join(rel);
+    let abs = mount_path(Path::new(rel));
     if cfg!(debug_assertions) {
-        println!("[UPDATE_METADATA_FROM_PAYLOAD] Values extracted: (abs: {:?}, name: {}, is_dir: {}, size: {}, mtime: {}, perm: {}, nlink: {})", abs, name, is_dir, size, mtime, perm, nlink);
+        println!("[UPDATE_METADATA_FROM_PAYLOAD] Values extracted: (abs: {:?}, name: {}, kind: {:?}, size: {}, mtime: {:?}, perm: {}, nlink: {})", abs, name, kind, size, mtime, perm, nlink);
     }
-    Some((abs, name, is_dir, size, mtime, perm, nlink))
+    Some((abs, name, kind, size, mtime, perm, nlink))
 }
 
 // Function that start the websocket listener, initialize the websocket connection and listen the messages
-pub fn start_websocket_listener(api_url: &str, notifier: Arc<Notifier>, fs_state: Arc<FsState>) {
+pub fn start_websocket_listener(
+    api_url: &str,
+    headers: Vec<(String, String)>,
+    notifier: Arc<Notifier>,
+    fs_state: Arc<FsState>,
+) {
     let ws_url = format!("{}/socket.io/", api_url.trim_end_matches('/'));
     if cfg!(debug_assertions) {
         println!("[START_WBSOCKET_LISTENER] WebSocket URL: {}", ws_url);
     }
     tokio::spawn(async move {
-        let notifier_cloned = notifier.clone();
+        // Bursts of events are folded before reaching the kernel
+        let inval = spawn_coalescer(move |op| match op {
+            Inval::Entry(parent, name) => {
+                let _ = notifier.inval_entry(parent, &name);
+            }
+            Inval::Inode(ino) => {
+                let _ = notifier.inval_inode(ino, 0, 0);
+            }
+        });
+        // Changes to file contents wait --stale-tolerance, so a hot file is refreshed once per window
+        let updates = match fs_state.stale_tolerance {
+            Some(tolerance) => spawn_debouncer(tolerance, inval.clone()),
+            None => inval.clone(),
+        };
         let fs_state_cloned = fs_state.clone();
         let ws_url = ws_url.clone();
         tokio::task::spawn_blocking(move || {
-            let client = ClientBuilder::new(ws_url)
-                .on("connect", |_, _| {})
-                .on("fs_change", move |payload, _| match payload {
-                    Payload::Text(values) => {
-                        if values.len() < 1 {
-                            return;
-                        }
-                        let json_payload = &values[0];
-                        handle_fs_change(json_payload, &notifier_cloned, &fs_state_cloned);
+            let connected_on = fs_state.ws_connected.clone();
+            let connected_off = fs_state.ws_connected.clone();
+            let resync_state = fs_state.clone();
+            let ever_connected = AtomicBool::new(false);
+            let mut builder = ClientBuilder::new(ws_url.clone());
+            // The handshake presents the --header ones and the session cookie, as the HTTP requests do
+            for (name, value) in headers {
+                builder = builder.opening_header(name, value);
+            }
+            let client = builder
+                .on(Event::Connect, move |_, _| {
+                    connected_on.store(true, Ordering::Relaxed);
+                    // Events sent while disconnected are lost: drop everything cached before the gap
+                    if ever_connected.swap(true, Ordering::Relaxed) {
+                        resync_state.clear_all_cache();
                     }
-                    _other => {}
+                })
+                .on("close", move |_, _| connected_off.store(false, Ordering::Relaxed))
+                .on("fs_change", move |payload, _| {
+                    crate::fs_event::dispatch(&payload, |event| {
+                        handle_fs_change(event, &inval, &updates, &fs_state_cloned)
+                    })
                 })
                 .on("error", |_err, _| {})
                 .connect();
 
-            if let Err(err) = client {
-                if cfg!(debug_assertions) {
-                    eprintln!(
-                        "[START_WBSOCKET_LISTENER] WebSocket connection error: {:?}",
-                        err
-                    );
+            match client {
+                Ok(client) => {
+                    *fs_state.ws_last_error.lock().unwrap() = None;
+                    *fs_state.ws_client.lock().unwrap() = Some(client);
                 }
+                Err(err) => fs_state.record_ws_failure(&ws_url, &format!("{:?}", err)),
             }
         });
     });
@@ -177,69 +361,71 @@ fn resolve_parent<'a>(path: &'a Path, st: &FsState) -> Option<(u64, &'a std::ffi
 }
 
 /// Dispatches a filesystem-change WebSocket event to the correct handler based on its operation type.
-fn handle_fs_change(payload: &serde_json::Value, notifier: &Notifier, fs_state: &FsState) {
+fn handle_fs_change(payload: &serde_json::Value, inval: &InvalSender, updates: &InvalSender, fs_state: &FsState) {
     if cfg!(debug_assertions) {
         println!("[HANDLE_FS_CHANGE] Payload received: {:?}", payload);
     }
+    if crate::fs_event::shadowed(payload, fs_state.overlay.as_deref()) {
+        return;
+    }
     let op = payload["op"].as_str().unwrap_or("");
     match op {
         "add" | "addDir" => {
             if cfg!(debug_assertions) {
                 println!("[HANDLE_FS_CHANGE] Handling 'create' event");
             }
-            handle_created(payload, notifier, fs_state);
+            handle_created(payload, inval, fs_state);
         }
 
         "write" | "change" => {
             if cfg!(debug_assertions) {
                 println!("[HANDLE_FS_CHANGE] Handling 'update' event");
             }
-            handle_updated(payload, notifier, fs_state);
+            handle_updated(payload, updates, fs_state);
         }
 
         "unlink" | "unlinkDir" => {
             if cfg!(debug_assertions) {
                 println!("[HANDLE_FS_CHANGE] Handling 'delete' event");
             }
-            handle_deleted_event(payload, notifier, fs_state);
+            handle_deleted_event(payload, inval, fs_state);
         }
 
         "rename" | "renameDir" => {
             if cfg!(debug_assertions) {
                 println!("[HANDLE_FS_CHANGE] Handling 'rename' event");
             }
-            handle_renamed_event(payload, notifier, fs_state);
+            handle_renamed_event(payload, inval, fs_state);
         }
 
-        _ => {
-            return;
-        }
+        _ => {}
     }
 }
 
 /// Handles a "create" event by inserting the new file/dir metadata and invalidating relevant caches
-fn handle_created(payload: &Value, notifier: &Notifier, st: &FsState) {
+fn handle_created(payload: &Value, inval: &InvalSender, st: &FsState) {
     if cfg!(debug_assertions) {
         println!("[HANDLE_CREATED] Payload received: {:?}", payload);
     }
-    let Some((abs, name, is_dir, size, mtime, perm, nlink)) = metadata_from_payload(payload) else {
+    let Some((abs, name, kind, size, mtime, perm, nlink)) = metadata_from_payload(payload) else {
         if cfg!(debug_assertions) {
             eprintln!("[HANDLE_CREATED] Failed to extract metadata from payload");
         }
         return;
     };
+    st.forget_negative(&abs);
     if let Some(_existing_ino) = st.ino_of(&abs) {
         if cfg!(debug_assertions) {
             println!("[HANDLE_CREATED] Path already exists in cache: {:?}", abs);
         }
-        update_cache_from_metadata(st, &abs, &name, is_dir, size, mtime, perm, nlink);
+        update_cache_from_metadata(st, &abs, &name, kind, size, mtime, perm, nlink);
         return;
     }
-    let ino = update_cache_from_metadata(st, &abs, &name, is_dir, size, mtime, perm, nlink);
+    let ino = update_cache_from_metadata(st, &abs, &name, kind, size, mtime, perm, nlink);
     let parent = abs.parent().unwrap_or(Path::new("/"));
     if let Some(parent_ino) = st.ino_of(parent) {
         st.remove_dir_cache(parent);
-        let _ = notifier.inval_entry(parent_ino, OsStr::new(&name));
+        inval.entry(parent_ino, OsStr::new(&name));
         if cfg!(debug_assertions) {
             println!(
                 "[HANDLE_CREATED] Invalidated entry for parent ino: {}, name: {:?}",
@@ -253,21 +439,21 @@ fn handle_created(payload: &Value, notifier: &Notifier, st: &FsState) {
             abs, ino
         );
     }
-    let _ = notifier.inval_inode(ino, 0, 0);
+    inval.inode(ino);
 }
 /// Handles a delete event by resolving the absolute path and delegating removal logic
-fn handle_deleted_event(payload: &Value, notifier: &Notifier, st: &FsState) {
+fn handle_deleted_event(payload: &Value, inval: &InvalSender, st: &FsState) {
     if let Some(rel) = payload["relPath"].as_str() {
         if cfg!(debug_assertions) {
             println!("[HANDLE_DELETED] relPath: {}", rel);
         }
-        let abs = Path::new("/").join(rel);
-        handle_deleted_path(&abs, notifier, st);
+        let abs = mount_path(Path::new(rel));
+        handle_deleted_path(&abs, inval, st);
     }
 }
 
 /// Removes all cached state for a deleted path and notifies FUSE of invalidated entries
-fn handle_deleted_path(abs: &Path, notifier: &Notifier, st: &FsState) {
+fn handle_deleted_path(abs: &Path, inval: &InvalSender, st: &FsState) {
     if let Some((parent_ino, name)) = resolve_parent(abs, st) {
         if cfg!(debug_assertions) {
             println!(
@@ -275,10 +461,15 @@ fn handle_deleted_path(abs: &Path, notifier: &Notifier, st: &FsState) {
                 parent_ino, name
             );
         }
-        let _ = notifier.inval_entry(parent_ino, name);
-        let _ = notifier.inval_inode(parent_ino, 0, 0);
+        inval.entry(parent_ino, name);
+        inval.inode(parent_ino);
     }
 
+    if let Some(ino) = st.ino_of(abs) {
+        st.mark_handles_stale(ino);
+        // Otherwise the kernel keeps serving the open handles from its page cache, never asking read
+        inval.inode(ino);
+    }
     st.remove_path(abs);
     st.remove_attr(abs);
 
@@ -294,7 +485,7 @@ fn handle_deleted_path(abs: &Path, notifier: &Notifier, st: &FsState) {
 }
 
 /// Handles a rename event by updating inode-path mappings and invalidating affected caches
-fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
+fn handle_renamed_event(payload: &Value, inval: &InvalSender, st: &FsState) {
     let Some(old_rel) = payload["oldPath"].as_str() else {
         if cfg!(debug_assertions) {
             eprintln!("[HANDLE_RENAMED_EVENT] Missing oldPath in payload");
@@ -308,8 +499,9 @@ fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
         return;
     };
 
-    let old_abs = Path::new("/").join(old_rel);
-    let new_abs = Path::new("/").join(new_rel);
+    let old_abs = mount_path(Path::new(old_rel));
+    let new_abs = mount_path(Path::new(new_rel));
+    st.forget_negative(&new_abs);
 
     if let Some((old_parent_ino, old_name)) = resolve_parent(&old_abs, st) {
         if cfg!(debug_assertions) {
@@ -318,10 +510,13 @@ fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
                 old_parent_ino, old_name
             );
         }
-        let _ = notifier.inval_entry(old_parent_ino, old_name);
-        let _ = notifier.inval_inode(old_parent_ino, 0, 0);
+        inval.entry(old_parent_ino, old_name);
+        inval.inode(old_parent_ino);
     }
 
+    if let Some(replaced_ino) = st.ino_of(&new_abs) {
+        st.mark_handles_stale(replaced_ino);
+    }
     let ino = if let Some(ino) = st.ino_of(&old_abs) {
         if cfg!(debug_assertions) {
             println!(
@@ -329,6 +524,7 @@ fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
                 old_abs, ino
             );
         }
+        st.mark_handles_stale(ino);
         st.remove_path(&old_abs);
         st.insert_path_mapping(&new_abs, ino);
         ino
@@ -343,7 +539,7 @@ fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
             .unwrap_or_else(|| st.allocate_ino(&new_abs))
     };
 
-    let Some((_abs_meta, name, is_dir, size, mtime, perm, nlink)) = metadata_from_payload(payload)
+    let Some((_abs_meta, name, kind, size, mtime, perm, nlink)) = metadata_from_payload(payload)
     else {
         if cfg!(debug_assertions) {
             eprintln!("[HANDLE_RENAMED_EVENT] Failed to extract metadata from payload");
@@ -353,34 +549,36 @@ fn handle_renamed_event(payload: &Value, notifier: &Notifier, st: &FsState) {
         return;
     };
     let final_abs = &new_abs;
-    let _ = update_cache_from_metadata(st, final_abs, &name, is_dir, size, mtime, perm, nlink);
-    if let Some((new_parent_ino, _)) = resolve_parent(&new_abs, st) {
+    let _ = update_cache_from_metadata(st, final_abs, &name, kind, size, mtime, perm, nlink);
+    if let Some((new_parent_ino, new_name)) = resolve_parent(&new_abs, st) {
         if cfg!(debug_assertions) {
             println!(
                 "[HANDLE_RENAMED_EVENT] Invalidating new entry for parent ino: {}, name: {:?}",
                 new_parent_ino, name
             );
         }
-        let _ = notifier.inval_inode(new_parent_ino, 0, 0);
+        // The kernel may hold the new name as a negative entry
+        inval.entry(new_parent_ino, new_name);
+        inval.inode(new_parent_ino);
     }
 
-    let _ = notifier.inval_inode(ino, 0, 0);
+    inval.inode(ino);
 }
 
 /// Handles a file update event by refreshing attributes and invalidating the inode in FUSE
-fn handle_updated(payload: &Value, notifier: &Notifier, st: &FsState) {
-    let Some((abs, name, is_dir, size, mtime, perm, nlink)) = metadata_from_payload(payload) else {
+fn handle_updated(payload: &Value, inval: &InvalSender, st: &FsState) {
+    let Some((abs, name, kind, size, mtime, perm, nlink)) = metadata_from_payload(payload) else {
         if cfg!(debug_assertions) {
             eprintln!("[HANDLE_UPDATED] Failed to extract metadata from payload");
         }
         return;
     };
 
-    let ino = update_cache_from_metadata(st, &abs, &name, is_dir, size, mtime, perm, nlink);
+    let ino = update_cache_from_metadata(st, &abs, &name, kind, size, mtime, perm, nlink);
 
-    let _ = notifier.inval_inode(ino, 0, 0);
+    inval.inode(ino);
     if let Some((parent_ino, _)) = resolve_parent(&abs, st) {
-        let _ = notifier.inval_entry(parent_ino, OsStr::new(&name));
+        inval.entry(parent_ino, OsStr::new(&name));
         
         if cfg!(debug_assertions) {
             println!(
@@ -392,22 +590,21 @@ fn handle_updated(payload: &Value, notifier: &Notifier, st: &FsState) {
 }
 
 /// Updates metadata caches based on remote API info and returns the inode associated with the path
+#[allow(clippy::too_many_arguments)]
 pub fn update_cache_from_metadata(
     st: &FsState,
     abs: &Path,
     name: &str,
-    is_dir: bool,
+    kind: FileType,
     size: u64,
-    mtime: i64,
+    mtime: SystemTime,
     perm: u16,
     nlink: i64,
 ) -> u64 {
-    let kind = if is_dir {
-        FileType::Directory
-    } else {
-        FileType::RegularFile
-    };
     let parent = abs.parent().unwrap_or(Path::new("/"));
+    let mtime = st.fixed_mtime.unwrap_or(mtime);
+    // Notifications carry the backend's size, listings already went through FileApi::present
+    let size = if kind == FileType::Directory && !st.dir_size_from_backend { 0 } else { size };
     let ino = match st.ino_of(abs) {
         Some(i) => i,
         None => {
@@ -421,7 +618,7 @@ pub fn update_cache_from_metadata(
         }
     };
 
-    let blocks = if size == 0 { 0 } else { (size + 511) / 512 };
+    let blocks = if size == 0 { 0 } else { size.div_ceil(512) };
     let uid = (unsafe { libc::getuid() }) as u32;
     let gid = (unsafe { libc::getgid() }) as u32;
     if st.get_attr(parent).is_none() {
@@ -430,10 +627,10 @@ pub fn update_cache_from_metadata(
             size,
             blocks,
             blksize: 512,
-            atime: UNIX_EPOCH + Duration::from_secs(mtime as u64),
-            mtime: UNIX_EPOCH + Duration::from_secs(mtime as u64),
-            ctime: UNIX_EPOCH + Duration::from_secs(mtime as u64),
-            crtime: UNIX_EPOCH + Duration::from_secs(mtime as u64),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
             kind,
             perm,
             nlink: nlink as u32,
@@ -460,7 +657,13 @@ pub fn update_cache_from_metadata(
 }
 
 impl FsState {
-    fn new(_api: FileApi, _rt: Arc<Runtime>) -> Self {
+    fn new(
+        api: FileApi,
+        _rt: Arc<Runtime>,
+        stale_tolerance: Option<Duration>,
+        max_cache_entries: usize,
+        negative_ttl: Duration,
+    ) -> Self {
         let mut ino_by_path = HashMap::new();
         let mut path_by_ino = HashMap::new();
         ino_by_path.insert(PathBuf::from("/"), 1);
@@ -468,12 +671,33 @@ impl FsState {
         Self {
             ino_by_path: Arc::new(Mutex::new(ino_by_path)),
             path_by_ino: Arc::new(Mutex::new(path_by_ino)),
-            attr_cache: Arc::new(Mutex::new(HashMap::new())),
-            dir_cache: Arc::new(Mutex::new(HashMap::new())),
+            attr_cache: Arc::new(Mutex::new(LruMap::new(max_cache_entries))),
+            dir_cache: Arc::new(Mutex::new(LruMap::new(max_cache_entries))),
+            negative: Arc::new(Mutex::new(HashMap::new())),
+            negative_ttl,
             writes: Arc::new(Mutex::new(HashMap::new())),
             next_ino: Arc::new(Mutex::new(2)),
             cache_ttl: TTL,
             next_fh: Arc::new(AtomicU64::new(1)),
+            ws_client: Arc::new(Mutex::new(None)),
+            ws_connected: Arc::new(AtomicBool::new(false)),
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            ws_idle: Arc::new(AtomicBool::new(false)),
+            ws_failures: Arc::new(AtomicU64::new(0)),
+            ws_last_error: Arc::new(Mutex::new(None)),
+            abandoned_commits: Arc::new(AtomicU64::new(0)),
+            cache_metrics: Arc::new(CacheMetrics::new(max_cache_entries)),
+            open_handles: Arc::new(Mutex::new(HashMap::new())),
+            lookups: Arc::new(Mutex::new(HashMap::new())),
+            commit_lock: Arc::new(Mutex::new(())),
+            cache_generation: Arc::new(AtomicU64::new(0)),
+            unlinked: Arc::new(Mutex::new(HashSet::new())),
+            create_locks: Arc::new(Mutex::new(HashMap::new())),
+            fixed_mtime: api.fixed_mtime(),
+            dir_size_from_backend: api.dir_size_from_backend(),
+            overlay: api.overlay(),
+            pending_times: Arc::new(Mutex::new(HashMap::new())),
+            stale_tolerance,
         }
     }
 
@@ -501,14 +725,35 @@ impl FsState {
         path_by_ino.insert(ino, child);
     }
 
-    pub fn insert_write_tempfile(&self, fh: u64, temp_path: PathBuf, dirty: bool) {
+    // Records a failed websocket connection: from now on consistency relies on the cache TTL only
+    pub fn record_ws_failure(&self, url: &str, err: &str) {
+        self.ws_connected.store(false, Ordering::Relaxed);
+        self.ws_failures.fetch_add(1, Ordering::Relaxed);
+        *self.ws_last_error.lock().unwrap() = Some(err.to_string());
+        eprintln!(
+            "[WARN] Connessione WebSocket a {} fallita: {}. Aggiornamenti in tempo reale non attivi, la cache scade solo col TTL.",
+            url, err
+        );
+    }
+
+    pub fn open_writes(&self) -> usize {
+        self.writes.lock().unwrap().len()
+    }
+
+    pub fn insert_write_tempfile(&self, fh: u64, ino: u64, path: PathBuf, temp_path: PathBuf, dirty: bool) {
         let mut writes = self.writes.lock().unwrap();
         writes.insert(
             fh,
             TempWrite {
+                ino,
+                path,
                 tem_path: temp_path,
                 size: 0,
                 dirty,
+                io: Arc::new(Mutex::new(())),
+                failures: 0,
+                retry_at: None,
+                abandoned: false,
             },
         );
     }
@@ -522,11 +767,264 @@ impl FsState {
         writes.get_mut(&fh).map(f)
     }
 
+    // Function that returns the lock serializing the tempfile I/O of a handle
+    fn write_lock(&self, fh: u64) -> Option<Arc<Mutex<()>>> {
+        self.writes.lock().unwrap().get(&fh).map(|tw| tw.io.clone())
+    }
+
+    // Function that resizes the tempfile of every handle open for writing on `ino`: they all hold the
+    // same file, and the kernel truncates for O_TRUNC without naming a handle. Returns whether `fh`
+    // is one of them
+    fn truncate_writes_of(&self, ino: u64, fh: Option<u64>, size: u64) -> bool {
+        let handles: Vec<u64> = self
+            .writes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, tw)| tw.ino == ino)
+            .map(|(fh, _)| *fh)
+            .collect();
+        for handle in &handles {
+            let io = self.write_lock(*handle);
+            let _serial = io.as_ref().map(|l| l.lock().unwrap());
+            self.with_write_mut(*handle, |tw| {
+                tw.size = size;
+                // The tempfile no longer matches the backend, even if nothing was written to it
+                tw.dirty = true;
+                if let Ok(f) = std::fs::OpenOptions::new().write(true).open(&tw.tem_path) {
+                    let _ = f.set_len(size);
+                }
+            });
+        }
+        fh.is_some_and(|fh| handles.contains(&fh))
+    }
+
     // Rimuove e restituisce (solo in release)
     pub fn take_write(&self, fh: u64) -> Option<TempWrite> {
         self.writes.lock().unwrap().remove(&fh)
     }
 
+    // Tempfiles of an inode not committed yet, as (fh, path)
+    pub fn dirty_writes_of(&self, ino: u64) -> Vec<(u64, PathBuf)> {
+        self.writes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, tw)| tw.ino == ino && tw.dirty)
+            .map(|(fh, tw)| (*fh, tw.tem_path.clone()))
+            .collect()
+    }
+
+    // Tempfile da committare, come (fh, inode, percorso); vengono segnati come puliti prima dell'upload.
+    // With `due_only` the ones still waiting out their backoff are left for a later round
+    pub fn take_dirty_writes(&self, due_only: bool) -> Vec<(u64, u64, PathBuf)> {
+        let now = Instant::now();
+        let mut writes = self.writes.lock().unwrap();
+        writes
+            .iter_mut()
+            .filter(|(_, tw)| tw.dirty && !tw.abandoned)
+            .filter(|(_, tw)| !due_only || tw.retry_at.is_none_or(|at| at <= now))
+            .map(|(fh, tw)| {
+                tw.dirty = false;
+                (*fh, tw.ino, tw.tem_path.clone())
+            })
+            .collect()
+    }
+
+    // Function that records a commit of `fh` the backend refused; true when it was the last attempt
+    // allowed, the handle is then given up and its data goes to the recovery directory
+    pub fn commit_failed(&self, fh: u64, opts: &MountOptions) -> bool {
+        self.with_write_mut(fh, |tw| {
+            tw.dirty = true;
+            tw.failures += 1;
+            tw.retry_at = Some(Instant::now() + opts.commit_retry_delay(tw.failures));
+            if tw.abandoned || tw.failures < opts.commit_attempts() {
+                return false;
+            }
+            tw.abandoned = true;
+            true
+        })
+        .unwrap_or(false)
+    }
+
+    pub fn commit_succeeded(&self, fh: u64) {
+        self.with_write_mut(fh, |tw| {
+            tw.failures = 0;
+            tw.retry_at = None;
+        });
+    }
+
+    pub fn is_abandoned(&self, fh: u64) -> bool {
+        self.with_write_mut(fh, |tw| tw.abandoned).unwrap_or(false)
+    }
+
+    // Drops the pending writes of a replaced inode, so they do not overwrite the new content
+    pub fn discard_writes_of(&self, ino: u64) {
+        let mut writes = self.writes.lock().unwrap();
+        for tw in writes.values_mut().filter(|tw| tw.ino == ino) {
+            tw.dirty = false;
+        }
+        self.pending_times.lock().unwrap().remove(&ino);
+    }
+
+    // ---- OPEN HANDLES ----
+
+    pub fn register_handle(&self, fh: u64, ino: u64) {
+        self.open_handles
+            .lock()
+            .unwrap()
+            .insert(
+                fh,
+                OpenHandle {
+                    ino,
+                    stale: false,
+                    direct: false,
+                    checked: Instant::now(),
+                },
+            );
+    }
+
+    // Removes the handle and returns true if it had gone stale
+    pub fn unregister_handle(&self, fh: u64) -> bool {
+        self.open_handles
+            .lock()
+            .unwrap()
+            .remove(&fh)
+            .is_some_and(|h| h.stale)
+    }
+
+    pub fn mark_handles_stale(&self, ino: u64) {
+        let mut handles = self.open_handles.lock().unwrap();
+        for handle in handles.values_mut().filter(|h| h.ino == ino) {
+            handle.stale = true;
+        }
+    }
+
+    pub fn has_open_handles(&self, ino: u64) -> bool {
+        self.open_handles
+            .lock()
+            .unwrap()
+            .values()
+            .any(|h| h.ino == ino)
+    }
+
+    pub fn create_lock(&self, path: &Path) -> Arc<Mutex<()>> {
+        self.create_locks
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default()
+            .clone()
+    }
+
+    // Drops the lock of `path` once no other create holds or waits on it
+    pub fn release_create_lock(&self, path: &Path) {
+        let mut locks = self.create_locks.lock().unwrap();
+        if locks.get(path).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(path);
+        }
+    }
+
+    pub fn is_unlinked(&self, ino: u64) -> bool {
+        self.unlinked.lock().unwrap().contains(&ino)
+    }
+
+    // O_DIRECT handles bypass the caches, see open
+    pub fn mark_handle_direct(&self, fh: u64) {
+        if let Some(handle) = self.open_handles.lock().unwrap().get_mut(&fh) {
+            handle.direct = true;
+        }
+    }
+
+    pub fn is_handle_direct(&self, fh: u64) -> bool {
+        self.open_handles
+            .lock()
+            .unwrap()
+            .get(&fh)
+            .is_some_and(|h| h.direct)
+    }
+
+    // Function that tells whether the file open as `fh` was last re-stat'ed `interval` ago or more,
+    // starting a new interval when it was
+    pub fn coherency_due(&self, fh: u64, interval: Duration) -> bool {
+        let mut handles = self.open_handles.lock().unwrap();
+        let Some(handle) = handles.get_mut(&fh) else {
+            return false;
+        };
+        if handle.checked.elapsed() < interval {
+            return false;
+        }
+        handle.checked = Instant::now();
+        true
+    }
+
+    pub fn is_handle_stale(&self, fh: u64) -> bool {
+        self.open_handles
+            .lock()
+            .unwrap()
+            .get(&fh)
+            .is_some_and(|h| h.stale)
+    }
+
+    // ---- LOOKUP COUNT ----
+
+    pub fn add_lookup(&self, ino: u64) {
+        *self.lookups.lock().unwrap().entry(ino).or_insert(0) += 1;
+    }
+
+    // Drops `nlookup` kernel references; once none is left and no handle is open the inode is evicted
+    pub fn forget(&self, ino: u64, nlookup: u64) {
+        if ino == 1 {
+            return;
+        }
+        {
+            let mut lookups = self.lookups.lock().unwrap();
+            let left = lookups.get(&ino).copied().unwrap_or(0).saturating_sub(nlookup);
+            if left > 0 {
+                lookups.insert(ino, left);
+                return;
+            }
+            lookups.remove(&ino);
+        }
+        if self.inode_in_use(ino) {
+            return;
+        }
+        let Some(path) = self.path_by_ino.lock().unwrap().remove(&ino) else {
+            return;
+        };
+        let mut ino_by_path = self.ino_by_path.lock().unwrap();
+        // After a rename the path may already belong to another inode
+        if ino_by_path.get(&path) == Some(&ino) {
+            ino_by_path.remove(&path);
+            drop(ino_by_path);
+            self.remove_attr(&path);
+            self.remove_dir_cache(&path);
+        }
+    }
+
+    // Function that tells whether the kernel, an open handle or a pending write still refers to `ino`
+    fn inode_in_use(&self, ino: u64) -> bool {
+        self.lookups.lock().unwrap().contains_key(&ino)
+            || self.open_handles.lock().unwrap().values().any(|h| h.ino == ino)
+            || self.writes.lock().unwrap().values().any(|tw| tw.ino == ino)
+    }
+
+    // Function that forgets the inodes of the attributes evicted from the cache when nothing refers
+    // to them any more: the next lookup of the path allocates a new one
+    fn drop_evicted(&self, evicted: Vec<(PathBuf, FileAttr)>) {
+        for (path, attr) in evicted {
+            if attr.ino == 1 || self.inode_in_use(attr.ino) {
+                continue;
+            }
+            let mut ino_by_path = self.ino_by_path.lock().unwrap();
+            // After a rename the path may already belong to another inode
+            if ino_by_path.get(&path) == Some(&attr.ino) {
+                ino_by_path.remove(&path);
+                self.path_by_ino.lock().unwrap().remove(&attr.ino);
+            }
+        }
+    }
+
     // ---- PATH ↔ INODE ----
 
     pub fn ino_of(&self, path: &Path) -> Option<u64> {
@@ -559,10 +1057,33 @@ impl FsState {
             if cfg!(debug_assertions) {
                 eprintln!("[REMOVE_PATH] Path not found in cache: {:?}", path);
             }
-            return;
         }
     }
 
+    // Function that moves what is cached under `from`, and below it, to `to`: an entry reached under
+    // two spellings ends up as a single one. An inode already known under `to` keeps the path, the
+    // one known under `from` resolves to it as well
+    pub fn merge_path(&self, from: &Path, to: &Path) {
+        let mut by_path = self.ino_by_path.lock().unwrap();
+        let mut by_ino = self.path_by_ino.lock().unwrap();
+        let moved: Vec<(PathBuf, u64)> = by_path
+            .iter()
+            .filter(|(p, _)| p.starts_with(from))
+            .map(|(p, ino)| (p.clone(), *ino))
+            .collect();
+        for (old, ino) in moved {
+            let new = to.join(old.strip_prefix(from).unwrap_or(Path::new("")));
+            by_path.remove(&old);
+            by_path.entry(new.clone()).or_insert(ino);
+            by_ino.insert(ino, new);
+        }
+        drop(by_ino);
+        drop(by_path);
+        // Refilled under `to` by the listing that found the mismatch
+        self.attr_cache.lock().unwrap().retain(|p, _| !p.starts_with(from));
+        self.dir_cache.lock().unwrap().retain(|p, _| !p.starts_with(from));
+    }
+
     pub fn insert_path_mapping(&self, path: &Path, ino: u64) {
         self.ino_by_path
             .lock()
@@ -582,10 +1103,12 @@ impl FsState {
     }
 
     pub fn set_attr(&self, path: &Path, attr: FileAttr) {
-        self.attr_cache
+        let evicted = self
+            .attr_cache
             .lock()
             .unwrap()
             .insert(path.to_path_buf(), attr);
+        self.drop_evicted(evicted);
     }
 
     pub fn remove_attr(&self, path: &Path) {
@@ -598,25 +1121,94 @@ impl FsState {
         self.dir_cache.lock().unwrap().get(path).cloned()
     }
 
-    pub fn set_dir_cache(&self, path: &Path, data: (Vec<DirectoryEntry>, SystemTime)) {
-        self.dir_cache
+    pub fn remove_dir_cache(&self, path: &Path) {
+        self.dir_cache.lock().unwrap().remove(path);
+    }
+
+    // ---- NEGATIVE CACHE ----
+
+    // Function that tells whether `name` was found missing in `parent` less than --negative-ttl ago
+    pub fn is_negative(&self, parent: &Path, name: &OsStr) -> bool {
+        let mut negative = self.negative.lock().unwrap();
+        let key = (parent.to_path_buf(), name.to_os_string());
+        match negative.get(&key) {
+            Some(until) if *until > Instant::now() => true,
+            Some(_) => {
+                negative.remove(&key);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_negative(&self, parent: &Path, name: &OsStr) {
+        if self.negative_ttl.is_zero() {
+            return;
+        }
+        let mut negative = self.negative.lock().unwrap();
+        let now = Instant::now();
+        negative.retain(|_, until| *until > now);
+        negative.insert((parent.to_path_buf(), name.to_os_string()), now + self.negative_ttl);
+    }
+
+    // Function that forgets a missing name once something is created under it
+    pub fn forget_negative(&self, path: &Path) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        self.negative
             .lock()
             .unwrap()
-            .insert(path.to_path_buf(), data);
+            .remove(&(parent.to_path_buf(), name.to_os_string()));
     }
 
-    pub fn remove_dir_cache(&self, path: &Path) {
-        self.dir_cache.lock().unwrap().remove(path);
+    // ---- CACHE GENERATION ----
+
+    pub fn generation(&self) -> u64 {
+        self.cache_generation.load(Ordering::Acquire)
+    }
+
+    // Function that caches attributes fetched under `generation`, unless the cache was cleared meanwhile
+    pub fn set_attr_if_current(&self, path: &Path, attr: FileAttr, generation: u64) -> bool {
+        let mut cache = self.attr_cache.lock().unwrap();
+        if self.generation() != generation {
+            return false;
+        }
+        let evicted = cache.insert(path.to_path_buf(), attr);
+        drop(cache);
+        self.drop_evicted(evicted);
+        true
+    }
+
+    // Function that caches a listing fetched under `generation`, unless the cache was cleared meanwhile
+    pub fn set_dir_cache_if_current(
+        &self,
+        path: &Path,
+        data: (Vec<DirectoryEntry>, SystemTime),
+        generation: u64,
+    ) -> bool {
+        let mut cache = self.dir_cache.lock().unwrap();
+        if self.generation() != generation {
+            return false;
+        }
+        cache.insert(path.to_path_buf(), data);
+        true
     }
 
     // ---- CLEAR CACHE ----
 
+    // The generation is bumped with both caches locked, so no stale result lands after the clear
     pub fn clear_all_cache(&self) {
-        self.attr_cache.lock().unwrap().clear();
-        self.dir_cache.lock().unwrap().clear();
+        let mut attrs = self.attr_cache.lock().unwrap();
+        let mut dirs = self.dir_cache.lock().unwrap();
+        self.cache_generation.fetch_add(1, Ordering::AcqRel);
+        attrs.clear();
+        dirs.clear();
+        self.negative.lock().unwrap().clear();
     }
 
-    pub fn cleanup_all_tempfiles(&self) {
+    // Unsent data is moved to `recovery_dir` (--recovery-dir) instead of being deleted
+    pub fn cleanup_all_tempfiles(&self, recovery_dir: Option<&Path>) {
         let writes = match self.writes.lock() {
             Ok(w) => w,
             Err(_) => {
@@ -628,6 +1220,11 @@ impl FsState {
         };
 
         for (_ino, tw) in writes.iter() {
+            if let (true, Some(dir)) = (tw.dirty, recovery_dir) {
+                if crate::recovery::save(dir, &canonical_rel(&tw.path), &tw.tem_path).is_ok() {
+                    continue;
+                }
+            }
             if tw.tem_path.exists() {
                 match std::fs::remove_file(&tw.tem_path) {
                     Ok(_) => {
@@ -658,9 +1255,22 @@ impl FsState {
     }
 }
 
+// Function that gives a stable inode to a name of a synthetic directory: the names seen so far
+// take the inodes below `base`, in order of discovery
+fn name_ino(names: &mut Vec<String>, base: u64, name: &str) -> u64 {
+    let index = match names.iter().position(|n| n == name) {
+        Some(i) => i,
+        None => {
+            names.push(name.to_string());
+            names.len() - 1
+        }
+    };
+    base - index as u64
+}
+
 impl RemoteFs {
     fn get_temporary_path(&self, ino: u64) -> PathBuf {
-        let mut tmp_path = std::env::temp_dir();
+        let mut tmp_path = self.temp_dir.clone();
         tmp_path.push(format!("tempfile_{}", ino));
         tmp_path
     }
@@ -671,13 +1281,33 @@ impl RemoteFs {
         self.state.clear_all_cache();
     }
 
-    // Function that verify if the cache is still valid
-    pub fn is_cache_valid(&self, timestamp: SystemTime) -> bool {
-        SystemTime::now().duration_since(timestamp).unwrap() < self.state.cache_ttl
+    // Function that verify if the cache of `path` is still valid
+    pub fn is_cache_valid(&self, path: &Path, timestamp: SystemTime) -> bool {
+        SystemTime::now().duration_since(timestamp).unwrap() < self.ttl_for(path)
+    }
+
+    // Function that returns the TTL of a path: the --cache-rule matching it, or the global one
+    fn ttl_for(&self, path: &Path) -> Duration {
+        self.opts
+            .cache_ttl_for(&canonical_rel(path))
+            .unwrap_or(self.state.cache_ttl)
+    }
+
+    // Function that answers a lookup of a missing name with a negative entry (inode 0), so the kernel
+    // answers ENOENT itself for --negative-ttl; names created later are invalidated explicitly
+    fn reply_missing(&self, reply: ReplyEntry) {
+        let ttl = self.state.negative_ttl;
+        if ttl.is_zero() {
+            reply.error(ENOENT);
+            return;
+        }
+        let mut attr = Self::control_attr(CONTROL_STATUS_INO);
+        attr.ino = 0;
+        reply.entry(&ttl, &attr, 0);
     }
 
     pub fn get_attr_cache(&self, path: &Path) -> Option<FileAttr> {
-        self.state.get_attr(&path)
+        self.state.get_attr(path)
     }
 
     // Function that allow to free the cache
@@ -688,8 +1318,9 @@ impl RemoteFs {
                 if cfg!(debug_assertions) {
                     println!("[CLEAR_CACHE] Clearing cache for path: {:?}", p);
                 }
-                self.state.remove_attr(&p);
-                self.state.remove_dir_cache(&p);
+                self.state.remove_attr(p);
+                self.state.remove_dir_cache(p);
+                self.state.forget_negative(p);
             }
             None => {
                 if cfg!(debug_assertions) {
@@ -702,13 +1333,22 @@ impl RemoteFs {
 
     // Function that update the cache
     pub fn update_cache(&self, dir: &Path) -> anyhow::Result<()> {
-        let rel_db = Self::rel_for_db(dir);
-        let rel_fs = Self::rel_for_fs(dir);
-
-        let list = self.rt.block_on(self.api.ls(&rel_db))?;
-        self.state
-            .set_dir_cache(&dir.to_path_buf(), (list.clone(), SystemTime::now()));
-        let dir_meta = self.rt.block_on(self.api.get_update_metadata(&rel_db))?;
+        let rel_db = canonical_rel(dir);
+
+        let generation = self.state.generation();
+        let list = self.block_on(self.api.ls(&rel_db))?;
+        let canonical = self.adopt_backend_path(dir, &list);
+        let (dir, rel_db) = (canonical.as_path(), canonical_rel(&canonical));
+        if !self
+            .state
+            .set_dir_cache_if_current(dir, (list.clone(), SystemTime::now()), generation)
+        {
+            if cfg!(debug_assertions) {
+                println!("[UPDATE_CACHE] Cache cleared while listing {:?}, result dropped", dir);
+            }
+            return Ok(());
+        }
+        let dir_meta = self.block_on(self.api.get_update_metadata(&rel_db))?;
 
         let mut dir_attr = if let Some(attr) = self.get_attr_cache(dir) {
             attr
@@ -717,49 +1357,36 @@ impl RemoteFs {
                 dir,
                 FileType::Directory,
                 dir_meta.size as u64,
-                Some(dir_meta.mtime),
-                0o755,
+                Some(dir_meta.mtime_time()),
+                Self::parse_perm(&dir_meta.permissions),
                 dir_meta.nlink as u32,
             )
         };
 
+        // The root has no parent listing to learn its mode from, this is where it comes from
+        dir_attr.perm = Self::parse_perm(&dir_meta.permissions);
         dir_attr.nlink = dir_meta.nlink as u32;
         dir_attr.size = dir_meta.size as u64;
-        dir_attr.mtime = UNIX_EPOCH + Duration::from_secs(dir_meta.mtime as u64);
-
-        self.state.set_attr(dir, dir_attr);
-
-        if let Some(n) = self.notifier.lock().unwrap().as_ref() {
-            let _ = n.inval_inode(dir_attr.ino, 0, 0);
-        }
+        dir_attr.mtime = dir_meta.mtime_time();
+        dir_attr.crtime = dir_meta.crtime_time();
 
         for child_de in &list {
-            let mut child_path = PathBuf::from("/");
-            if !rel_fs.is_empty() {
-                child_path.push(&rel_fs);
-            }
-            child_path.push(&child_de.name);
-
-            let ty = if Self::is_dir(&child_de) {
-                FileType::Directory
-            } else {
-                FileType::RegularFile
-            };
-            let attr = self.file_attr(
-                &child_path,
-                ty,
-                child_de.size as u64,
-                Some(child_de.mtime),
-                Self::parse_perm(&child_de.permissions),
-                child_de.nlink as u32,
-            );
+            let child_path = mount_path(&child_of(&rel_db, &child_de.name));
+            let attr = self.entry_attr(&child_path, child_de);
 
-            self.state.set_attr(&child_path, attr);
+            self.state.set_attr_if_current(&child_path, attr, generation);
 
             if let Some(n) = self.notifier.lock().unwrap().as_ref() {
                 let _ = n.inval_inode(attr.ino, 0, 0);
             }
         }
+
+        // Cached after the children, so a listing larger than --max-cache-entries does not evict it
+        self.state.set_attr_if_current(dir, dir_attr, generation);
+
+        if let Some(n) = self.notifier.lock().unwrap().as_ref() {
+            let _ = n.inval_inode(dir_attr.ino, 0, 0);
+        }
         Ok(())
     }
 
@@ -768,18 +1395,26 @@ impl RemoteFs {
         self.state.set_attr(&path, attr);
     }
 
-    // Function that insert the folder state
-    pub fn insert_dir_cache(&self, path: PathBuf, data: (Vec<DirectoryEntry>, SystemTime)) {
-        self.state.set_dir_cache(&path, data);
-    }
-
     // Function that create a new instance of RemoteFs
-    fn new(api: FileApi, rt: Arc<Runtime>) -> Self {
+    fn new(api: FileApi, rt: Arc<Runtime>, opts: MountOptions) -> Self {
         Self {
-            state: Arc::new(FsState::new(api.clone(), rt.clone())),
+            state: Arc::new(FsState::new(
+                api.clone(),
+                rt.clone(),
+                opts.stale_tolerance,
+                opts.max_cache_entries(),
+                opts.negative_ttl(),
+            )),
             api,
             rt,
             notifier: Arc::new(Mutex::new(None)),
+            temp_dir: crate::temp_dir::choose(opts.temp_dir.as_deref()),
+            opts,
+            read_buf: Vec::new(),
+            readers: None,
+            recovered_names: Vec::new(),
+            trash_names: Vec::new(),
+            trash_listing: Vec::new(),
         }
     }
 
@@ -793,30 +1428,24 @@ impl RemoteFs {
     }
 
     // Function that obtain the path from the inode
+    // Every operation on an inode resolves it here, which makes it the place activity is noted
     fn path_of(&self, ino: u64) -> Option<PathBuf> {
+        self.note_activity();
         self.state.path_of(ino)
     }
 
-    /// Extract relative path for db
-    fn rel_for_db(path: &Path) -> String {
-        let s = path.to_string_lossy();
-
-        if s == "/" {
-            return "".to_string();
-        } else {
-            let trimmed = s.trim_start_matches("/");
-            format!("./{}", trimmed)
+    // Function that records filesystem activity for --ws-idle-timeout; the first operation after the
+    // websocket was closed for inactivity reconnects it, dropping the cache that went unnotified
+    fn note_activity(&self) {
+        if self.opts.ws_idle_timeout.is_none() {
+            return;
         }
-    }
-
-    /// Extract relative path for fs (PathBuf)
-
-    fn rel_for_fs(path: &Path) -> String {
-        let s = path.to_string_lossy();
-        if s == "/" {
-            "".to_string()
-        } else {
-            s.trim_start_matches('/').to_string()
+        *self.state.last_activity.lock().unwrap() = Instant::now();
+        if self.state.ws_idle.swap(false, Ordering::Relaxed) {
+            if cfg!(debug_assertions) {
+                println!("[WS_IDLE] Activity after an idle period, reconnecting the websocket");
+            }
+            self.refresh_mount();
         }
     }
 
@@ -826,20 +1455,18 @@ impl RemoteFs {
         path: &Path,
         ty: FileType,
         size: u64,
-        mtime: Option<i64>,
+        mtime: Option<SystemTime>,
         perm: u16,
         nlink: u32,
     ) -> FileAttr {
         let now = SystemTime::now();
-        let mtime_st = mtime
-            .and_then(|sec| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(sec as u64)))
-            .unwrap_or(now);
+        let mtime_st = mtime.or(self.api.fixed_mtime()).unwrap_or(now);
         let uid = (unsafe { libc::getuid() }) as u32;
         let gid = (unsafe { libc::getgid() }) as u32;
         FileAttr {
             ino: self.alloc_ino(path),
             size,
-            blocks: (size + 511) / 512,
+            blocks: size.div_ceil(512),
             atime: mtime_st,
             mtime: mtime_st,
             ctime: mtime_st,
@@ -855,87 +1482,203 @@ impl RemoteFs {
         }
     }
 
-    // Function that transform the permissions in octal format
-    fn parse_perm(permissions: &str) -> u16 {
-        u16::from_str_radix(&permissions, 8).unwrap_or(0)
-    }
-
-    // Function that analyze the permissions and verify if we are working with a directory
-    fn is_dir(de: &DirectoryEntry) -> bool {
-        if de.is_dir == 1 {
-            return true;
-        }
-        false
-    }
-
-    // Function that define the directory entries
-    pub fn dir_entries(&self, dir: &Path) -> Result<Vec<(PathBuf, DirectoryEntry)>> {
-        let rel_db = Self::rel_for_db(dir);
-        let rel_fs = Self::rel_for_fs(dir);
-
-        if let Some((entries, ts)) = self.state.get_dir_cache(&dir) {
-            if cfg!(debug_assertions) {
-                println!("[DIR_ENTRIES] Using cached entries for dir: {:?}", dir);
+    // Function that returns the attributes of the root as the backend reports them, caching them;
+    // 0o755 and the current time stand in when the backend cannot be asked
+    fn root_attr(&self) -> FileAttr {
+        let root = Path::new("/");
+        match self.block_on(self.api.get_update_metadata(".")) {
+            Ok(de) => {
+                let mut attr = self.file_attr(
+                    root,
+                    FileType::Directory,
+                    de.size as u64,
+                    Some(de.mtime_time()),
+                    Self::parse_perm(&de.permissions),
+                    de.nlink as u32,
+                );
+                attr.crtime = de.crtime_time();
+                self.state.set_attr(root, attr);
+                attr
             }
-            if self.is_cache_valid(ts) {
+            Err(e) => {
                 if cfg!(debug_assertions) {
-                    println!(
-                        "[DIR_ENTRIES] Cache is valid for dir: {:?}, returning cached entries",
-                        dir
-                    );
-                }
-                let mut out = Vec::with_capacity(entries.len());
-                for de in entries {
-                    let mut child = PathBuf::from("/");
-                    if !rel_fs.is_empty() {
-                        child.push(&rel_fs);
-                    }
-                    child.push(&de.name);
-                    out.push((child, de));
+                    eprintln!("[ROOT_ATTR] Could not read the root attributes: {}", e);
                 }
-                let _ = self.update_cache(dir);
-                return Ok(out);
+                self.file_attr(root, FileType::Directory, 0, None, 0o755, 2)
             }
         }
+    }
 
-        let list = self.rt.block_on(self.api.ls(&rel_db))?;
-
-        self.insert_dir_cache(dir.to_path_buf(), (list.clone(), SystemTime::now()));
+    // Function that applies the POSIX rules of a rename over an existing entry: a directory
+    // replaces only an empty directory, and files and directories never replace each other
+    fn check_rename_target(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+        no_replace: bool,
+    ) -> Result<(), i32> {
+        let entry_of = |path: &Path| -> Option<DirectoryEntry> {
+            let name = path.file_name()?.to_string_lossy();
+            self.dir_entries(path.parent()?)
+                .ok()?
+                .into_iter()
+                .map(|(_, de)| de)
+                .find(|de| de.name == name)
+        };
+        if old_path == new_path {
+            return Ok(());
+        }
+        let target = match entry_of(new_path) {
+            Some(de) => de,
+            None => return Ok(()),
+        };
+        if no_replace {
+            return Err(libc::EEXIST);
+        }
+        // A file created on the mount reaches the backend only when released, its attr is cached
+        let source_is_dir = match self.state.get_attr(old_path) {
+            Some(attr) => attr.kind == FileType::Directory,
+            None => entry_of(old_path).ok_or(ENOENT)?.entry_kind() == EntryKind::Dir,
+        };
+        match (source_is_dir, target.entry_kind() == EntryKind::Dir) {
+            (true, false) => Err(ENOTDIR),
+            (false, true) => Err(libc::EISDIR),
+            (true, true) => match self.dir_entries(new_path) {
+                Ok(children) if children.is_empty() => Ok(()),
+                Ok(_) => Err(ENOTEMPTY),
+                Err(e) => Err(errno_from_anyhow(&e)),
+            },
+            (false, false) => Ok(()),
+        }
+    }
 
-        let mut out = Vec::with_capacity(list.len());
+    // Function that applies the sticky bit of the parent directory to the removal of `path`
+    fn sticky_denies(&self, uid: u32, parent_path: &Path, path: &Path) -> bool {
+        if self.opts.backend_auth {
+            return false;
+        }
+        let Some(dir) = self.state.get_attr(parent_path) else {
+            return false;
+        };
+        let owner = self
+            .state
+            .get_attr(path)
+            .map_or(unsafe { libc::getuid() }, |attr| attr.uid);
+        crate::acl::sticky_denies(uid, dir.perm, dir.uid, owner)
+    }
 
-        for de in &list {
-            let mut child = PathBuf::from("/");
-            if !rel_fs.is_empty() {
+    // Function that keeps a file unlinked while open readable through its handles: it is renamed
+    // to a hidden name on the backend and deleted when the last handle is released
+    fn hide_unlinked(&self, ino: u64, parent_path: &Path, path: &Path) -> Result<()> {
+        let hidden = parent_path.join(format!("{}{:x}", UNLINKED_PREFIX, ino));
+        self.block_on(self.api.rename(&canonical_rel(path), &canonical_rel(&hidden)))?;
+        let attr = self.state.get_attr(path);
+        self.clear_cache(Some(path));
+        self.state.remove_path(path);
+        self.state.insert_path_mapping(&hidden, ino);
+        self.state.unlinked.lock().unwrap().insert(ino);
+        let _ = self.update_cache(parent_path);
+        if let Some(mut attr) = attr {
+            attr.nlink = 0;
+            self.state.set_attr(&hidden, attr);
+        }
+        Ok(())
+    }
+
+    // Function that deletes on the backend a file unlinked while open, once its last handle is gone
+    fn delete_unlinked(&self, ino: u64) {
+        self.state.unlinked.lock().unwrap().remove(&ino);
+        let Some(hidden) = self.path_of(ino) else {
+            return;
+        };
+        if let Err(e) = self.delete_remote(&canonical_rel(&hidden)) {
+            if cfg!(debug_assertions) {
+                eprintln!("[RELEASE] Failed to delete unlinked file {:?}: {}", hidden, e);
+            }
+        }
+        self.clear_cache(Some(&hidden));
+        self.state.remove_path(&hidden);
+        if let Some(parent) = hidden.parent() {
+            self.state.remove_dir_cache(parent);
+        }
+    }
+
+    // Function that reconciles the directory the kernel asked for with the spelling the backend lists its
+    // entries under (case, Unicode normalization, resolved links): what is cached under the requested
+    // form moves to the backend's one, the form notifications use too
+    fn adopt_backend_path(&self, requested: &Path, list: &[DirectoryEntry]) -> PathBuf {
+        let Some(parent) = list.iter().find_map(DirectoryEntry::parent_rel) else {
+            return requested.to_path_buf();
+        };
+        let canonical = mount_path(Path::new(&parent));
+        if canonical == requested || requested == Path::new("/") {
+            return requested.to_path_buf();
+        }
+        if cfg!(debug_assertions) {
+            println!("[DIR_ENTRIES] Backend lists {:?} as {:?}, merging the cache", requested, canonical);
+        }
+        self.state.merge_path(requested, &canonical);
+        canonical
+    }
+
+    // Function that transform the permissions in octal format
+    fn parse_perm(permissions: &str) -> u16 {
+        u16::from_str_radix(permissions, 8).unwrap_or(0)
+    }
+
+    // Function that define the directory entries
+    pub fn dir_entries(&self, dir: &Path) -> Result<Vec<(PathBuf, DirectoryEntry)>> {
+        let rel_db = canonical_rel(dir);
+
+        if let Some((entries, ts)) = self.state.get_dir_cache(dir) {
+            if cfg!(debug_assertions) {
+                println!("[DIR_ENTRIES] Using cached entries for dir: {:?}", dir);
+            }
+            if self.is_cache_valid(dir, ts) {
                 if cfg!(debug_assertions) {
                     println!(
-                        "[DIR_ENTRIES] Building child path for dir: {:?} with rel_fs: {}",
-                        dir, rel_fs
+                        "[DIR_ENTRIES] Cache is valid for dir: {:?}, returning cached entries",
+                        dir
                     );
                 }
-                child.push(&rel_fs);
+                self.state.cache_metrics.record_hit(dir);
+                let mut out = Vec::with_capacity(entries.len());
+                for de in entries {
+                    let child = mount_path(&child_of(&rel_db, &de.name));
+                    out.push((child, de));
+                }
+                return Ok(out);
             }
-            child.push(&de.name);
+            self.state.cache_metrics.record_miss(dir, Some(ts));
+        } else {
+            self.state.cache_metrics.record_miss(dir, None);
+        }
 
-            let is_dir = Self::is_dir(&de);
-            let ty = if is_dir {
-                FileType::Directory
-            } else {
-                FileType::RegularFile
-            };
-            let perm = Self::parse_perm(&de.permissions);
-            let size = de.size as u64;
+        let generation = self.state.generation();
+        let list = self.block_on(self.api.ls(&rel_db))?;
+        let canonical = self.adopt_backend_path(dir, &list);
+        let (dir, rel_db) = (canonical.as_path(), canonical_rel(&canonical));
+
+        // A listing that started before a full clear is returned but not cached, it may predate the resync
+        if !self
+            .state
+            .set_dir_cache_if_current(dir, (list.clone(), SystemTime::now()), generation)
+            && cfg!(debug_assertions)
+        {
+            println!("[DIR_ENTRIES] Cache cleared while listing {:?}, result not cached", dir);
+        }
 
-            let child_nlink = de.nlink as u32;
-            let attr = self.file_attr(&child, ty, size, Some(de.mtime), perm, child_nlink);
-            self.insert_attr_cache(child.clone(), attr);
+        let mut out = Vec::with_capacity(list.len());
+
+        for de in &list {
+            let child = mount_path(&child_of(&rel_db, &de.name));
+            let attr = self.entry_attr(&child, de);
+            self.state.set_attr_if_current(&child, attr, generation);
 
             out.push((child, de.clone()));
         }
-        let rel_db_parent = Self::rel_for_db(dir);
-        let de = self
-            .rt
-            .block_on(self.api.get_update_metadata(&rel_db_parent))?;
+        let rel_db_parent = canonical_rel(dir);
+        let de = self.block_on(self.api.get_update_metadata(&rel_db_parent))?;
 
         if let Some(mut parent_attr) = self.state.get_attr(dir) {
             if cfg!(debug_assertions) {
@@ -944,10 +1687,12 @@ impl RemoteFs {
                     dir
                 );
             }
+            parent_attr.perm = Self::parse_perm(&de.permissions);
             parent_attr.nlink = de.nlink as u32;
             parent_attr.size = de.size as u64;
-            parent_attr.mtime = UNIX_EPOCH + Duration::from_secs(de.mtime as u64);
-            self.state.set_attr(dir, parent_attr);
+            parent_attr.mtime = de.mtime_time();
+            parent_attr.crtime = de.crtime_time();
+            self.state.set_attr_if_current(dir, parent_attr, generation);
         } else {
             if cfg!(debug_assertions) {
                 println!(
@@ -955,39 +1700,693 @@ impl RemoteFs {
                     dir
                 );
             }
-            let attr = self.file_attr(
+            let mut attr = self.file_attr(
                 dir,
                 FileType::Directory,
                 de.size as u64,
-                Some(de.mtime),
-                0o755,
+                Some(de.mtime_time()),
+                Self::parse_perm(&de.permissions),
                 de.nlink as u32,
             );
-            self.state.set_attr(dir, attr);
+            attr.crtime = de.crtime_time();
+            self.state.set_attr_if_current(dir, attr, generation);
         }
 
         Ok(out)
     }
+
+    // Function that builds the attributes of a listed entry from what the backend returned
+    fn entry_attr(&self, child: &Path, de: &DirectoryEntry) -> FileAttr {
+        let ty = file_type_of(de.entry_kind());
+        let perm = Self::parse_perm(&de.permissions);
+        let mut attr = self.file_attr(child, ty, de.size as u64, Some(de.mtime_time()), perm, de.nlink as u32);
+        // statx reports crtime as the birth time, keep the backend's instead of the mtime
+        attr.crtime = de.crtime_time();
+        attr.blocks = de.blocks();
+        attr
+    }
+
+    // Function that looks `path` up in the listing of `parent`. A listing cached before the name was
+    // created on the backend would hide it until it expires, so a name missing from a cached one is
+    // looked up again in a fresh listing; the negative entry then spares the lookups that follow
+    fn lookup_listed(&self, parent: &Path, path: &Path) -> Result<Option<FileAttr>> {
+        let was_cached = self
+            .state
+            .get_dir_cache(parent)
+            .is_some_and(|(_, ts)| self.is_cache_valid(parent, ts));
+        let attr = self.listed_attr(path, &self.dir_entries(parent)?);
+        if attr.is_some() || !was_cached {
+            return Ok(attr);
+        }
+        self.update_cache(parent)?;
+        Ok(self.listed_attr(path, &self.dir_entries(parent)?))
+    }
+
+    // Function that returns the attributes of `path` once its parent was listed: from the cache, or
+    // from `entries` when the listing was larger than --max-cache-entries and they were evicted
+    fn listed_attr(&self, path: &Path, entries: &[(PathBuf, DirectoryEntry)]) -> Option<FileAttr> {
+        if let Some(attr) = self.state.get_attr(path) {
+            return Some(attr);
+        }
+        let name = path.file_name()?;
+        let (child, de) = entries.iter().find(|(child, _)| child.file_name() == Some(name))?;
+        let attr = self.entry_attr(child, de);
+        self.state.set_attr(child, attr);
+        Some(attr)
+    }
+
+    // Function that lists a directory of .remotefs, "." and ".." first
+    fn control_entries(&mut self, ino: u64) -> Result<Vec<(u64, FileType, String)>, libc::c_int> {
+        let mut entries = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (if ino == CONTROL_DIR_INO { 1 } else { CONTROL_DIR_INO }, FileType::Directory, "..".to_string()),
+        ];
+        if ino == CONTROL_DIR_INO {
+            entries.push((CONTROL_REFRESH_INO, FileType::RegularFile, CONTROL_REFRESH.to_string()));
+            entries.push((CONTROL_COPY_INO, FileType::RegularFile, CONTROL_COPY.to_string()));
+            entries.push((CONTROL_STATUS_INO, FileType::RegularFile, CONTROL_STATUS.to_string()));
+            if self.opts.recovery_dir.is_some() {
+                entries.push((CONTROL_RECOVERED_INO, FileType::Directory, CONTROL_RECOVERED.to_string()));
+            }
+            if self.opts.trash {
+                entries.push((CONTROL_TRASH_INO, FileType::Directory, CONTROL_TRASH.to_string()));
+            }
+        } else if ino == CONTROL_TRASH_INO {
+            self.refresh_trash()?;
+            for e in self.trash_listing.clone() {
+                let ty = if e.is_dir { FileType::Directory } else { FileType::RegularFile };
+                entries.push((self.trash_ino(&e.id), ty, e.id));
+            }
+        } else if let Some(dir) = self.opts.recovery_dir.clone() {
+            for r in crate::recovery::list(&dir) {
+                entries.push((self.recovered_ino(&r.name), FileType::RegularFile, r.name));
+            }
+        }
+        Ok(entries)
+    }
+
+    // Function that tells if an entry belongs to the virtual control directory
+    fn is_control_entry(parent: u64, name: &OsStr) -> bool {
+        parent == CONTROL_DIR_INO
+            || parent == CONTROL_RECOVERED_INO
+            || parent == CONTROL_TRASH_INO
+            || (parent == 1 && name == CONTROL_DIR)
+    }
+
+    // Function that builds the attributes of the virtual control entries
+    fn control_attr(ino: u64) -> FileAttr {
+        let (kind, perm, nlink) = if ino == CONTROL_DIR_INO || ino == CONTROL_RECOVERED_INO || ino == CONTROL_TRASH_INO {
+            (FileType::Directory, 0o755, 2)
+        } else if ino == CONTROL_STATUS_INO {
+            (FileType::RegularFile, 0o444, 1)
+        } else {
+            (FileType::RegularFile, 0o644, 1)
+        };
+        let now = SystemTime::now();
+        FileAttr {
+            ino,
+            size: 0,
+            blocks: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+            crtime: now,
+            kind,
+            perm,
+            nlink,
+            uid: (unsafe { libc::getuid() }) as u32,
+            gid: (unsafe { libc::getgid() }) as u32,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+
+    // Function that returns the attributes of an entry under .remotefs, None for backend inodes
+    fn synthetic_attr(&self, ino: u64) -> Option<FileAttr> {
+        match ino {
+            CONTROL_DIR_INO | CONTROL_REFRESH_INO | CONTROL_STATUS_INO | CONTROL_COPY_INO | CONTROL_RECOVERED_INO
+            | CONTROL_TRASH_INO => Some(Self::control_attr(ino)),
+            _ => self
+                .recovered_file(ino)
+                .map(|r| Self::recovered_attr(ino, &r))
+                .or_else(|| self.trash_entry(ino).map(|e| Self::trash_attr(ino, e))),
+        }
+    }
+
+    // Function that finds the recovered file behind an inode of .remotefs/recovered
+    fn recovered_file(&self, ino: u64) -> Option<Recovered> {
+        let dir = self.opts.recovery_dir.as_deref()?;
+        let index = RECOVERED_INO_BASE.checked_sub(ino)? as usize;
+        crate::recovery::stat(dir, self.recovered_names.get(index)?)
+    }
+
+    fn recovered_ino(&mut self, name: &str) -> u64 {
+        name_ino(&mut self.recovered_names, RECOVERED_INO_BASE, name)
+    }
+
+    fn recovered_attr(ino: u64, r: &Recovered) -> FileAttr {
+        let mut attr = Self::control_attr(CONTROL_STATUS_INO);
+        attr.ino = ino;
+        attr.size = r.size;
+        attr.blocks = r.size.div_ceil(512);
+        attr.mtime = r.mtime;
+        attr.ctime = r.mtime;
+        attr
+    }
+
+    // Function that reads the backend trash again, for .remotefs/trash
+    fn refresh_trash(&mut self) -> Result<(), i32> {
+        self.trash_listing = self.block_on(self.api.trash_list())
+            .map_err(|e| errno_from_api(&e))?;
+        Ok(())
+    }
+
+    fn trash_entry(&self, ino: u64) -> Option<&TrashEntry> {
+        let index = TRASH_INO_BASE.checked_sub(ino)? as usize;
+        let id = self.trash_names.get(index)?;
+        self.trash_listing.iter().find(|e| e.id == *id)
+    }
+
+    fn trash_ino(&mut self, id: &str) -> u64 {
+        name_ino(&mut self.trash_names, TRASH_INO_BASE, id)
+    }
+
+    fn trash_attr(ino: u64, e: &TrashEntry) -> FileAttr {
+        let mut attr = Self::control_attr(CONTROL_STATUS_INO);
+        if e.is_dir {
+            attr.kind = FileType::Directory;
+            attr.perm = 0o555;
+            attr.nlink = 2;
+        }
+        attr.ino = ino;
+        attr.size = e.size;
+        attr.blocks = e.size.div_ceil(512);
+        attr.mtime = system_time(e.deleted_at, 0);
+        attr.ctime = attr.mtime;
+        attr
+    }
+
+    // Function that restores a trashed entry to where it is moved out of .remotefs/trash
+    fn restore_trashed(&mut self, name: &OsStr, newparent: u64, newname: &OsStr) -> Result<(), i32> {
+        let parent_path = self.path_of(newparent).ok_or(ENOENT)?;
+        let target = parent_path.join(newname);
+        if self.path_too_long(&target) {
+            return Err(libc::ENAMETOOLONG);
+        }
+        self.block_on(
+                self.api
+                    .trash_restore(&name.to_string_lossy(), Some(&canonical_rel(&target))),
+            )
+            .map_err(|e| errno_from_api(&e))?;
+        self.trash_listing.retain(|e| *e.id != *name.to_string_lossy());
+        self.clear_cache(Some(&target));
+        let _ = self.update_cache(&parent_path);
+        Ok(())
+    }
+
+    fn purge_trashed(&mut self, name: &OsStr) -> Result<(), i32> {
+        self.block_on(self.api.trash_purge(&name.to_string_lossy()))
+            .map_err(|e| errno_from_api(&e))?;
+        self.trash_listing.retain(|e| *e.id != *name.to_string_lossy());
+        Ok(())
+    }
+
+    // Function that routes a delete through the backend trash when --trash is on
+    fn delete_remote(&self, rel: &str) -> Result<(), ApiError> {
+        if self.opts.trash {
+            self.block_on(self.api.trash(rel))
+        } else {
+            self.block_on(self.api.delete(rel))
+        }
+    }
+
+    // Function that creates `path` once the create lock of the name is held
+    fn create_file(
+        &mut self,
+        parent: u64,
+        path: &Path,
+        mode: u32,
+        umask: u32,
+        flags: i32,
+        reply: ReplyCreate,
+    ) {
+        if let Some(attr) = self.state.get_attr(path) {
+            // Another create of the same name got here first
+            if flags & libc::O_EXCL != 0 {
+                reply.error(libc::EEXIST);
+                return;
+            }
+            self.join_created(path, attr, reply);
+            return;
+        }
+        if flags & libc::O_EXCL != 0 {
+            // The backend creates the name atomically, so other clients see EEXIST too
+            if let Err(e) = self.block_on(self.api.create_exclusive(&canonical_rel(path))) {
+                if cfg!(debug_assertions) {
+                    eprintln!("[CREATE] Exclusive create of {:?} failed: {}", path, e);
+                }
+                reply.error(errno_from_api(&e));
+                return;
+            }
+        }
+        let fh = self.state.alloc_fh();
+
+        let mut tmp = self.temp_dir.clone();
+        tmp.push(format!("remote_fs_fh_{:x}.part", fh));
+
+        let _ = std::fs::remove_file(&tmp);
+        if std::fs::File::create(&tmp).is_err() {
+            if cfg!(debug_assertions) {
+                eprintln!(
+                    "[CREATE] Failed to create temporary file at path: {:?}",
+                    tmp
+                );
+            }
+            reply.error(libc::EIO);
+            return;
+        }
+
+        let final_mode = mode & !umask;
+        let attr = self.file_attr(
+            path,
+            FileType::RegularFile,
+            0,
+            None,
+            (final_mode & 0o777) as u16,
+            1,
+        );
+
+        self.state.insert_write_tempfile(fh, attr.ino, path.to_path_buf(), tmp, true);
+        self.state.register_handle(fh, attr.ino);
+
+        self.state.set_attr(path, attr);
+        if let Some(parent_path) = self.state.path_of(parent) {
+            if cfg!(debug_assertions) {
+                println!("[CREATE] Updating cache for: {:?}", parent_path);
+            }
+            let _ = self.update_cache(&parent_path);
+        }
+
+        self.state.add_lookup(attr.ino);
+        reply.created(&self.state.cache_ttl, &attr, 0, fh, 0);
+    }
+
+    // Function that opens for writing the file another create of the same name just made
+    fn join_created(&mut self, path: &Path, attr: FileAttr, reply: ReplyCreate) {
+        let fh = self.state.alloc_fh();
+        let mut tmp = self.temp_dir.clone();
+        tmp.push(format!("remote_fs_fh_{:x}.part", fh));
+        if std::fs::File::create(&tmp).is_err() {
+            if cfg!(debug_assertions) {
+                eprintln!("[CREATE] Failed to create temporary file at path: {:?}", tmp);
+            }
+            reply.error(libc::EIO);
+            return;
+        }
+        if cfg!(debug_assertions) {
+            println!("[CREATE] {:?} already created, joining ino: {}", path, attr.ino);
+        }
+        // Not dirty: only what this handle writes is committed over the first creator's content
+        self.state.insert_write_tempfile(fh, attr.ino, path.to_path_buf(), tmp, false);
+        self.state.register_handle(fh, attr.ino);
+        self.state.add_lookup(attr.ino);
+        reply.created(&self.state.cache_ttl, &attr, 0, fh, 0);
+    }
+
+    // Function that invalidates the kernel caches of `ino` from another thread: sent from a read of that
+    // same inode, the notification waits on the page the read keeps locked and never returns
+    fn inval_from_read(notifier: &Mutex<Option<Notifier>>, ino: u64) {
+        if let Some(n) = notifier.lock().unwrap().clone() {
+            thread::spawn(move || {
+                let _ = n.inval_inode(ino, 0, 0);
+            });
+        }
+    }
+
+    // Function that rereads the size of a file from the backend, adopting it when the file grew
+    // since it was cached, or whenever its size or mtime changed if `any_change`; the cached attr is
+    // returned unchanged otherwise
+    fn refresh_attr_size(&self, path: &Path, mut attr: FileAttr, any_change: bool) -> FileAttr {
+        match self.block_on(self.api.get_update_metadata(&canonical_rel(path))) {
+            Ok(de)
+                if de.size as u64 > attr.size
+                    || (any_change
+                        && (de.size as u64 != attr.size
+                            || self.state.fixed_mtime.unwrap_or(de.mtime_time()) != attr.mtime)) =>
+            {
+                if cfg!(debug_assertions) {
+                    println!(
+                        "[READ] {:?} changed on the backend from {} to {} bytes",
+                        path, attr.size, de.size
+                    );
+                }
+                attr.size = de.size as u64;
+                attr.blocks = de.blocks();
+                attr.mtime = self.state.fixed_mtime.unwrap_or(de.mtime_time());
+                self.state.set_attr(path, attr);
+                Self::inval_from_read(&self.notifier, attr.ino);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[READ] Could not refresh the size of {:?}: {}", path, e);
+                }
+            }
+        }
+        attr
+    }
+
+    // Function that tells whether the file open as `fh` is due a re-stat (--coherency-interval): a
+    // file held open for long, like one followed by tail -f, would keep the size it had when opened
+    fn coherency_due(&self, fh: u64) -> bool {
+        self.opts
+            .coherency_interval
+            .is_some_and(|interval| self.state.coherency_due(fh, interval))
+    }
+
+    // Function that returns the TTL of a getattr answer; through an open handle it is capped by
+    // --coherency-interval, so the kernel asks again and sees the file grow
+    fn getattr_ttl(&self, path: &Path, fh: Option<u64>) -> Duration {
+        match (self.opts.coherency_interval, fh) {
+            (Some(interval), Some(_)) => self.ttl_for(path).min(interval),
+            _ => self.ttl_for(path),
+        }
+    }
+
+    // Function that records the size learnt from a short read: the backend sent less than the cached
+    // size promised, so the file shrank there (a chunked answer has no length to check beforehand)
+    fn shrink_attr(&self, path: &Path, attr: FileAttr, size: u64) {
+        Self::record_shrink(&self.state, &self.notifier, path, attr, size);
+    }
+
+    // Same as shrink_attr, for the reads answered on the --fuse-threads workers
+    fn record_shrink(
+        state: &FsState,
+        notifier: &Mutex<Option<Notifier>>,
+        path: &Path,
+        mut attr: FileAttr,
+        size: u64,
+    ) {
+        if cfg!(debug_assertions) {
+            println!("[READ] {:?} shrank on the backend from {} to {} bytes", path, attr.size, size);
+        }
+        attr.size = size;
+        attr.blocks = size.div_ceil(512);
+        state.set_attr(path, attr);
+        Self::inval_from_read(notifier, attr.ino);
+    }
+
+    // Function that stores an ACL on the backend for setxattr/removexattr; `None` removes it
+    fn store_acl(&self, ino: u64, path: &Path, kind: &str, acl: Option<&str>) -> Result<(), i32> {
+        match self.block_on(self.api.set_acl(&canonical_rel(path), kind, acl)) {
+            Ok(true) => {
+                // The access ACL and the group bits of the mode move together, reread both
+                self.state.remove_attr(path);
+                if let Some(n) = self.notifier.lock().unwrap().as_ref() {
+                    let _ = n.inval_inode(ino, 0, 0);
+                }
+                Ok(())
+            }
+            Ok(false) => Err(libc::ENOTSUP),
+            Err(e) => Err(errno_from_api(&e)),
+        }
+    }
+
+    // Function that runs a backend call on the mount's runtime, failing instead once shutdown began
+    fn block_on<T: Send>(&self, fut: impl Future<Output = ApiResult<T>> + Send) -> ApiResult<T> {
+        crate::block_on(&self.rt, fut)
+    }
+
+    // Function that tells whether the mount is a read-only --snapshot view
+    fn read_only(&self) -> bool {
+        self.opts.snapshot.is_some()
+    }
+
+    // Function that sends a write of a handle right away (--commit-strategy writethrough). When the
+    // rest of the tempfile is already on the backend (`rest_sent`) only the range written goes; a
+    // range at offset 0 short of the end would start the backend file over, so that one and a
+    // handle with unsent data upload the whole tempfile
+    fn commit_write(&self, ino: u64, fh: u64, offset: u64, data: &[u8], rest_sent: bool) -> Result<()> {
+        let Some((tmp_path, size)) = self.state.with_write_mut(fh, |tw| (tw.tem_path.clone(), tw.size)) else {
+            return Ok(());
+        };
+        let path = self
+            .path_of(ino)
+            .ok_or_else(|| anyhow::anyhow!("Inode {} not found", ino))?;
+        let rel = canonical_rel(&path);
+        let ranged = rest_sent
+            && (offset > 0 || data.len() as u64 >= size)
+            && self.block_on(self.api.write_range(&rel, offset, data))?;
+        if !ranged {
+            self.block_on(self.api.write_file(&rel, &tmp_path))?;
+        }
+        self.state.with_write_mut(fh, |tw| tw.dirty = false);
+        Ok(())
+    }
+
+    // Function that tells whether the --max-open-handles cap has been reached
+    fn path_too_long(&self, path: &Path) -> bool {
+        too_long(path, self.opts.max_path_len.unwrap_or(DEFAULT_PATH_MAX))
+    }
+
+    // Function that sends the times set while the file had unsent writes, now that its data is on
+    // the backend; they stay pending while another handle still has data to upload
+    fn flush_pending_times(&self, ino: u64, path: &Path) -> Result<(), i32> {
+        let Some((atime, mtime)) = self.state.pending_times.lock().unwrap().get(&ino).copied() else {
+            return Ok(());
+        };
+        self.block_on(self.api.utimes(&canonical_rel(path), atime, mtime))
+            .map_err(|e| errno_from_api(&e))?;
+        if self.state.dirty_writes_of(ino).is_empty() {
+            self.state.pending_times.lock().unwrap().remove(&ino);
+        }
+        if let Some(mut attr) = self.state.get_attr(path) {
+            attr.atime = atime;
+            attr.mtime = mtime;
+            self.state.set_attr(path, attr);
+        }
+        Ok(())
+    }
+
+    // Function that applies --conflict-policy to the unsent data of a handle whose file was removed
+    // or replaced on the backend while it was open
+    fn resolve_conflict(&self, tw: &TempWrite) -> Result<(), i32> {
+        if !tw.dirty {
+            return Ok(());
+        }
+        let target = match self.opts.conflict_policy {
+            ConflictPolicy::Fail => {
+                eprintln!(
+                    "[WARN] {:?} e' stato modificato sul backend mentre era aperto: le modifiche locali sono state scartate",
+                    tw.path
+                );
+                return Err(libc::ESTALE);
+            }
+            ConflictPolicy::Overwrite => tw.path.clone(),
+            ConflictPolicy::Rename => self.conflict_path(&tw.path),
+        };
+        self.block_on(self.api.write_file(&canonical_rel(&target), &tw.tem_path))
+            .map_err(|e| errno_from_api(&e))?;
+        eprintln!(
+            "[WARN] {:?} e' stato modificato sul backend mentre era aperto: le modifiche locali sono state salvate in {:?}",
+            tw.path, target
+        );
+        self.state.remove_attr(&target);
+        if let Some(parent) = target.parent() {
+            self.state.remove_dir_cache(parent);
+        }
+        Ok(())
+    }
+
+    // Function that picks a free name next to `path` for --conflict-policy rename
+    fn conflict_path(&self, path: &Path) -> PathBuf {
+        let base = format!("{}.conflict", path.to_string_lossy());
+        (1..100)
+            .map(|n| if n == 1 { PathBuf::from(&base) } else { PathBuf::from(format!("{}-{}", base, n)) })
+            .find(|p| !matches!(self.block_on(self.api.exists(&canonical_rel(p))), Ok(true)))
+            .unwrap_or_else(|| PathBuf::from(base))
+    }
+
+    fn write_handles_exhausted(&self) -> bool {
+        self.opts
+            .max_open_handles
+            .is_some_and(|max| self.state.open_writes() >= max)
+    }
+
+    // Function that renders the mount status returned when reading the refresh control file
+    fn control_status(&self) -> String {
+        let connected = self.state.ws_connected.load(Ordering::Relaxed);
+        format!(
+            "backend_url: {}\nwebsocket: {}\nwebsocket_failures: {}\nwebsocket_error: {}\nsymlink_policy: {}\ncommit_strategy: {}\nabandoned_commits: {}\nattr_cache_entries: {}\ndir_cache_entries: {}\nopen_writes: {}\ninodes: {}\n",
+            self.api.base_url(),
+            if connected {
+                "connected"
+            } else if self.state.ws_idle.load(Ordering::Relaxed) {
+                "idle"
+            } else {
+                "disconnected"
+            },
+            self.state.ws_failures.load(Ordering::Relaxed),
+            self.state.ws_last_error.lock().unwrap().as_deref().unwrap_or("-"),
+            self.opts.symlink_policy.as_str(),
+            self.opts.commit_strategy.as_str(),
+            self.state.abandoned_commits.load(Ordering::Relaxed),
+            self.state.attr_cache.lock().unwrap().len(),
+            self.state.dir_cache.lock().unwrap().len(),
+            self.state.writes.lock().unwrap().len(),
+            self.state.path_by_ino.lock().unwrap().len(),
+        ) + &self.state.cache_metrics.report("\n")
+    }
+
+    // Function that renders the JSON returned by .remotefs/status, for scripts and monitoring
+    fn status_json(&self) -> String {
+        let connected = self.state.ws_connected.load(Ordering::Relaxed);
+        let last_error = self.state.ws_last_error.lock().unwrap().clone();
+        let (open_writes, pending_writes) = {
+            let writes = self.state.writes.lock().unwrap();
+            (writes.len(), writes.values().filter(|tw| tw.dirty).count())
+        };
+        let status = serde_json::json!({
+            "backend_url": self.api.base_url(),
+            // Without the websocket the cache only expires with the TTL
+            "state": if connected {
+                "connected"
+            } else if self.state.ws_idle.load(Ordering::Relaxed) {
+                "idle"
+            } else {
+                "degraded"
+            },
+            "websocket": {
+                "connected": connected,
+                "failures": self.state.ws_failures.load(Ordering::Relaxed),
+            },
+            "cache": {
+                "attr_entries": self.state.attr_cache.lock().unwrap().len(),
+                "dir_entries": self.state.dir_cache.lock().unwrap().len(),
+            },
+            "open_writes": open_writes,
+            "pending_writes": pending_writes,
+            "abandoned_commits": self.state.abandoned_commits.load(Ordering::Relaxed),
+            "inodes": self.state.path_by_ino.lock().unwrap().len(),
+            "last_error": last_error,
+        });
+        format!("{}\n", status)
+    }
+
+    // Function that runs a copy written to .remotefs/copy: a source line and a destination line,
+    // paths from the mount root. The backend copies the whole tree; the destination directory is
+    // listed again on the next access
+    fn copy_from_control(&self, data: &[u8]) -> Result<(), i32> {
+        let text = std::str::from_utf8(data).map_err(|_| libc::EINVAL)?;
+        let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+        let (Some(src), Some(dst), None) = (lines.next(), lines.next(), lines.next()) else {
+            return Err(libc::EINVAL);
+        };
+        let (src, dst) = (canonical_rel(Path::new(src)), canonical_rel(Path::new(dst)));
+        if cfg!(debug_assertions) {
+            println!("[COPY] Copying {} to {} through the control file", src, dst);
+        }
+        self.block_on(self.api.copy_recursive(&src, &dst))
+            .map_err(|e| errno_from_api(&e))?;
+        let dst_path = mount_path(Path::new(&dst));
+        self.state.forget_negative(&dst_path);
+        if let Some(parent) = dst_path.parent() {
+            self.clear_cache(Some(parent));
+            // A lookup of the destination before the copy left a negative entry in the kernel
+            let parent_ino = self.state.ino_of(parent);
+            if let (Some(parent_ino), Some(name), Some(n)) =
+                (parent_ino, dst_path.file_name(), self.notifier.lock().unwrap().as_ref())
+            {
+                let _ = n.inval_entry(parent_ino, name);
+            }
+        }
+        Ok(())
+    }
+
+    // Function that drops every cached entry and restarts the websocket listener
+    fn refresh_mount(&self) {
+        self.clear_cache(None);
+        if let Some(old) = self.state.ws_client.lock().unwrap().take() {
+            if cfg!(debug_assertions) {
+                println!("[REFRESH_MOUNT] Disconnecting current websocket client");
+            }
+            let _ = old.disconnect();
+        }
+        self.state.ws_connected.store(false, Ordering::Relaxed);
+
+        let Some(notifier) = self.notifier.lock().unwrap().clone() else {
+            if cfg!(debug_assertions) {
+                eprintln!("[REFRESH_MOUNT] Notifier not available, websocket not restarted");
+            }
+            return;
+        };
+        if self.read_only() {
+            // A snapshot never changes, there are no notifications to follow
+            return;
+        }
+        let _guard = self.rt.enter();
+        start_websocket_listener(
+            self.api.base_url(),
+            self.api.handshake_headers(),
+            Arc::new(notifier),
+            self.state.clone(),
+        );
+    }
 }
 
 impl Drop for RemoteFs {
     fn drop(&mut self) {
-        self.state.cleanup_all_tempfiles();
+        // Unmounted with handles still open (umount -f, an aborted connection): their writes are sent if
+        // the backend answers, what is left goes to --recovery-dir or is dropped below
+        let unsent = self.state.writes.lock().unwrap().values().any(|tw| tw.dirty);
+        if unsent && !self.read_only() && self.block_on(self.api.health()).is_ok() {
+            commit_dirty_writes(&self.state, &self.api, &self.rt, &self.opts, false);
+        }
+        // Unmounted: notifications have nowhere to go, the websocket is closed before the runtime stops
+        if let Some(client) = self.state.ws_client.lock().unwrap().take() {
+            let _ = client.disconnect();
+        }
+        self.state.ws_connected.store(false, Ordering::Relaxed);
+        self.state.cleanup_all_tempfiles(self.opts.recovery_dir.as_deref());
     }
 }
 
 impl Filesystem for RemoteFs {
+    // Function that applies --max-write when the kernel opens the session
+    fn init(&mut self, _req: &Request<'_>, config: &mut KernelConfig) -> Result<(), libc::c_int> {
+        if self.opts.acl && config.add_capabilities(FUSE_POSIX_ACL).is_err() {
+            eprintln!("[WARN] Il kernel non supporta le ACL POSIX su FUSE: --acl ignorato");
+            self.opts.acl = false;
+        }
+        // An older kernel keeps listing with plain readdir and a lookup per entry
+        let _ = config.add_capabilities(FUSE_DO_READDIRPLUS);
+        // O_TRUNC then reaches open, which skips downloading a file about to be emptied; without it
+        // the kernel opens first and truncates with a setattr afterwards
+        let _ = config.add_capabilities(FUSE_ATOMIC_O_TRUNC);
+        if let Some(max_write) = self.opts.max_write {
+            if let Err(nearest) = config.set_max_write(max_write) {
+                eprintln!(
+                    "[WARN] --max-write {} non accettato, uso {}",
+                    max_write, nearest
+                );
+                let _ = config.set_max_write(nearest);
+            }
+        }
+        Ok(())
+    }
+
     // Function that update the file's attributes
     fn setattr(
         &mut self,
         _req: &Request<'_>,
         ino: u64,
         mode: Option<u32>,
-        _uid: Option<u32>,
-        _gid: Option<u32>,
+        uid: Option<u32>,
+        gid: Option<u32>,
         size: Option<u64>,
-        _atime: Option<TimeOrNow>,
-        _mtime: Option<TimeOrNow>,
+        atime: Option<TimeOrNow>,
+        mtime: Option<TimeOrNow>,
         _ctime: Option<SystemTime>,
         fh: Option<u64>,
         _crtime: Option<SystemTime>,
@@ -996,22 +2395,25 @@ impl Filesystem for RemoteFs {
         _flags: Option<u32>,
         reply: ReplyAttr,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!(
                 "[SETATTR] Called with ino: {}, mode: {:?}, size: {:?}, fh: {:?}",
                 ino, mode, size, fh
             );
         }
+        if let Some(attr) = self.synthetic_attr(ino) {
+            reply.attr(&Duration::ZERO, &attr);
+            return;
+        }
         if ino == 1 {
             if cfg!(debug_assertions) {
                 println!("[SETATTR] Setting attributes for root inode");
             }
-            let uid = (unsafe { libc::getuid() }) as u32;
-            let gid = (unsafe { libc::getgid() }) as u32;
-
-            let mut attr = self.file_attr(Path::new("/"), FileType::Directory, 0, None, 0o755, 2);
-            attr.uid = uid;
-            attr.gid = gid;
+            let attr = self.root_attr();
             reply.attr(&self.state.cache_ttl, &attr);
             return;
         }
@@ -1022,14 +2424,14 @@ impl Filesystem for RemoteFs {
             reply.error(ENOENT);
             return;
         };
-        let rel_db = Self::rel_for_db(&path);
+        let rel_db = canonical_rel(&path);
 
         let mut attr = if let Some(a) = self.state.get_attr(&path) {
             a
         } else {
             let parent = path.parent().unwrap_or(Path::new("/"));
-            let _ = self.dir_entries(parent);
-            match self.state.get_attr(&path) {
+            let entries = self.dir_entries(parent).unwrap_or_default();
+            match self.listed_attr(&path, &entries) {
                 Some(a) => a,
                 None => {
                     if cfg!(debug_assertions) {
@@ -1043,8 +2445,20 @@ impl Filesystem for RemoteFs {
                 }
             }
         };
+        // The owner is only shown from the cache once the backend has taken it
+        if uid.is_some() || gid.is_some() {
+            if let Err(e) = self.block_on(self.api.chown(&rel_db, uid, gid)) {
+                if cfg!(debug_assertions) {
+                    eprintln!("[SETATTR] chown failed for path: {:?}, error: {:?}", path, e);
+                }
+                reply.error(errno_from_api(&e));
+                return;
+            }
+            attr.uid = uid.unwrap_or(attr.uid);
+            attr.gid = gid.unwrap_or(attr.gid);
+        }
         if let Some(m) = mode {
-            attr.perm = (m & 0o777) as u16;
+            attr.perm = (m & 0o7777) as u16;
         } else {
             if cfg!(debug_assertions) {
                 println!("[SETATTR] Mode not provided, keeping existing permissions");
@@ -1052,8 +2466,6 @@ impl Filesystem for RemoteFs {
         }
 
         if let Some(new_size) = size {
-            let mut is_local_write = false;
-
             if let Some(fh_val) = fh {
                 if cfg!(debug_assertions) {
                     println!(
@@ -1061,30 +2473,24 @@ impl Filesystem for RemoteFs {
                         fh_val, path
                     );
                 }
-                if let Some(effective_size) = self.state.with_write_mut(fh_val, |tw| {
-                    tw.size = new_size;
-                    if let Ok(f) = std::fs::OpenOptions::new().write(true).open(&tw.tem_path) {
-                        let _ = f.set_len(new_size);
-                    }
-                    tw.size
-                }) {
-                    if cfg!(debug_assertions) {
-                        println!(
-                            "[SETATTR] Updating size from local write tempfile for fh: {}, new size: {}",
-                            fh_val, effective_size
-                        );
-                    }
-                    attr.size = effective_size;
-                    attr.blocks = (effective_size + 511) / 512;
-                    is_local_write = true;
+            }
+            let is_local_write = self.state.truncate_writes_of(ino, fh, new_size);
+            if is_local_write {
+                if cfg!(debug_assertions) {
+                    println!(
+                        "[SETATTR] Updating size from local write tempfiles for ino: {}, new size: {}",
+                        ino, new_size
+                    );
                 }
+                attr.size = new_size;
+                attr.blocks = new_size.div_ceil(512);
             }
 
             if !is_local_write {
-                match self.rt.block_on(self.api.truncate(&rel_db, new_size)) {
+                match self.block_on(self.api.truncate(&rel_db, new_size)) {
                     Ok(_) => {
                         attr.size = new_size;
-                        attr.blocks = (new_size + 511) / 512;
+                        attr.blocks = new_size.div_ceil(512);
                     }
                     Err(e) => {
                         if cfg!(debug_assertions) {
@@ -1093,7 +2499,7 @@ impl Filesystem for RemoteFs {
                                 path, e
                             );
                         }
-                        let errno = errno_from_anyhow(&e);
+                        let errno = errno_from_api(&e);
 
                         if errno == libc::ENOENT || errno == libc::EIO || errno == libc::ENOSPC {
                             if cfg!(debug_assertions) {
@@ -1103,7 +2509,7 @@ impl Filesystem for RemoteFs {
                                 );
                             }
                             attr.size = new_size;
-                            attr.blocks = (new_size + 511) / 512;
+                            attr.blocks = new_size.div_ceil(512);
                         } else {
                             if cfg!(debug_assertions) {
                                 eprintln!(
@@ -1118,7 +2524,55 @@ impl Filesystem for RemoteFs {
                 }
             }
         }
-        self.insert_attr_cache(path.to_path_buf(), attr.clone());
+        if atime.is_some() || mtime.is_some() {
+            let now = SystemTime::now();
+            let resolve = |t: TimeOrNow| match t {
+                TimeOrNow::SpecificTime(st) => st,
+                TimeOrNow::Now => now,
+            };
+            let new_atime = atime.map(resolve).unwrap_or(attr.atime);
+            let new_mtime = mtime.map(resolve).unwrap_or(attr.mtime);
+            // `touch newfile` is create + utimes(now): send one touch instead of an empty upload on release
+            let unsent = self.state.dirty_writes_of(ino);
+            let touch_new = matches!((atime, mtime), (Some(TimeOrNow::Now), Some(TimeOrNow::Now)))
+                && attr.size == 0
+                && !unsent.is_empty()
+                && unsent
+                    .iter()
+                    .all(|(_, tmp)| std::fs::metadata(tmp).is_ok_and(|m| m.len() == 0));
+            let result = if touch_new {
+                self.block_on(self.api.touch(&rel_db))
+            } else {
+                self.block_on(self.api.utimes(&rel_db, new_atime, new_mtime))
+            };
+            if touch_new && result.is_ok() {
+                for (fh, _) in unsent {
+                    self.state.with_write_mut(fh, |tw| tw.dirty = false);
+                }
+            } else if !unsent.is_empty() {
+                self.state
+                    .pending_times
+                    .lock()
+                    .unwrap()
+                    .insert(ino, (new_atime, new_mtime));
+            }
+            if let Err(e) = result {
+                // A file created locally is not on the backend until its first commit
+                let is_local_write = fh.is_some_and(|f| self.state.with_write_mut(f, |_| ()).is_some())
+                    || !self.state.dirty_writes_of(ino).is_empty();
+                if !is_local_write {
+                    if cfg!(debug_assertions) {
+                        eprintln!("[SETATTR] utimes failed for path: {:?}, error: {:?}", path, e);
+                    }
+                    reply.error(errno_from_api(&e));
+                    return;
+                }
+            }
+            attr.atime = new_atime;
+            attr.mtime = new_mtime;
+            attr.ctime = now;
+        }
+        self.insert_attr_cache(path.to_path_buf(), attr);
         if cfg!(debug_assertions) {
             println!(
                 "[SETATTR] Updated attributes for path: {:?}, new attr: {:?}",
@@ -1128,12 +2582,20 @@ impl Filesystem for RemoteFs {
         reply.attr(&self.state.cache_ttl, &attr);
     }
 
+    // The kernel no longer references the inode `nlookup` times; batch_forget falls back to this
+    fn forget(&mut self, _req: &Request<'_>, ino: u64, nlookup: u64) {
+        if cfg!(debug_assertions) {
+            println!("[FORGET] Forget called for ino: {}, nlookup: {}", ino, nlookup);
+        }
+        self.state.forget(ino, nlookup);
+    }
+
     fn statfs(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
         if cfg!(debug_assertions) {
             println!("[STATFS] Statfs called");
         }
-        match self.rt.block_on(self.api.statfs()) {
-            Ok(stats) => {
+        match self.block_on(self.api.capacity()) {
+            Ok(Some(stats)) => {
                 if cfg!(debug_assertions) {
                     println!("[STATFS] Remote statfs succeeded: {:?}", stats);
                 }
@@ -1156,22 +2618,20 @@ impl Filesystem for RemoteFs {
                     namelen,
                     frsize,
                 );
-                return;
             }
-            Err(_e) => {
-                if cfg!(debug_assertions) {
-                    eprintln!("[STATFS] Remote statfs failed, using default values");
-                }
+            // A backend without /stats has an unknown capacity, reported like an unreachable one
+            Ok(None) => {
                 let bsize: u32 = 4096;
-                let blocks: u64 = 1_000_000;
-                let bfree: u64 = 1_000_000;
-                let bavail: u64 = 1_000_000;
-                let files: u64 = 1_000_000;
-                let ffree: u64 = 1_000_000;
                 let namelen: u32 = 255;
-                let frsize: u32 = bsize;
-                reply.statfs(blocks, bfree, bavail, files, ffree, bsize, namelen, frsize);
-                return;
+                reply.statfs(0, 0, 0, 0, 0, bsize, namelen, bsize);
+            }
+            Err(e) => {
+                // Without the backend the free space is unknown: report none rather than a
+                // made-up large value, so tools do not plan writes that are going to fail
+                eprintln!("[STATFS] Backend /stats unreachable, reporting no free space: {:#}", e);
+                let bsize: u32 = 4096;
+                let namelen: u32 = 255;
+                reply.statfs(0, 0, 0, 0, 0, bsize, namelen, bsize);
             }
         }
     }
@@ -1195,6 +2655,50 @@ impl Filesystem for RemoteFs {
                 parent, name
             );
         }
+        if Self::is_control_entry(parent, name) {
+            if parent == 1 {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_DIR_INO), 0);
+            } else if name == CONTROL_REFRESH {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_REFRESH_INO), 0);
+            } else if name == CONTROL_COPY {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_COPY_INO), 0);
+            } else if parent == CONTROL_RECOVERED_INO {
+                let found = self
+                    .opts
+                    .recovery_dir
+                    .as_deref()
+                    .and_then(|dir| crate::recovery::stat(dir, &name_str));
+                match found {
+                    Some(r) => {
+                        let ino = self.recovered_ino(&r.name);
+                        reply.entry(&Duration::ZERO, &Self::recovered_attr(ino, &r), 0);
+                    }
+                    None => reply.error(ENOENT),
+                }
+            } else if name == CONTROL_STATUS {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_STATUS_INO), 0);
+            } else if parent == CONTROL_TRASH_INO {
+                if let Err(errno) = self.refresh_trash() {
+                    reply.error(errno);
+                    return;
+                }
+                let found = self.trash_listing.iter().find(|e| *e.id == *name_str).cloned();
+                match found {
+                    Some(e) => {
+                        let ino = self.trash_ino(&e.id);
+                        reply.entry(&Duration::ZERO, &Self::trash_attr(ino, &e), 0);
+                    }
+                    None => reply.error(ENOENT),
+                }
+            } else if name == CONTROL_TRASH && self.opts.trash {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_TRASH_INO), 0);
+            } else if name == CONTROL_RECOVERED && self.opts.recovery_dir.is_some() {
+                reply.entry(&Duration::ZERO, &Self::control_attr(CONTROL_RECOVERED_INO), 0);
+            } else {
+                reply.error(ENOENT);
+            }
+            return;
+        }
         let Some(parent_path) = self.path_of(parent) else {
             if cfg!(debug_assertions) {
                 eprintln!("[LOOKUP] Parent inode not found: {}", parent);
@@ -1204,18 +2708,25 @@ impl Filesystem for RemoteFs {
         };
 
         let child_path = parent_path.join(name);
+        if self.state.is_negative(&parent_path, name) {
+            if cfg!(debug_assertions) {
+                println!("[LOOKUP] {:?} was not found recently, skipping the listing", child_path);
+            }
+            self.reply_missing(reply);
+            return;
+        }
 
-        match self.dir_entries(&parent_path) {
-            Ok(_) => {
-                if let Some(attr) = self.state.get_attr(&child_path) {
+        match self.lookup_listed(&parent_path, &child_path) {
+            Ok(found) => {
+                if let Some(attr) = found {
                     if cfg!(debug_assertions) {
                         println!(
                             "[LOOKUP] Found attributes for child path: {:?}, returning entry",
                             child_path
                         );
                     }
-                    reply.entry(&self.state.cache_ttl, &attr, 0);
-                    return;
+                    self.state.add_lookup(attr.ino);
+                    reply.entry(&self.ttl_for(&child_path), &attr, 0);
                 } else {
                     if cfg!(debug_assertions) {
                         eprintln!(
@@ -1223,8 +2734,8 @@ impl Filesystem for RemoteFs {
                             child_path
                         );
                     }
-                    reply.error(ENOENT);
-                    return;
+                    self.state.add_negative(&parent_path, name);
+                    self.reply_missing(reply);
                 }
             }
             Err(e) => {
@@ -1235,7 +2746,6 @@ impl Filesystem for RemoteFs {
                     );
                 }
                 reply.error(errno_from_anyhow(&e));
-                return;
             }
         }
     }
@@ -1255,6 +2765,22 @@ impl Filesystem for RemoteFs {
                 ino, offset
             );
         }
+        if ino == CONTROL_DIR_INO || ino == CONTROL_RECOVERED_INO || ino == CONTROL_TRASH_INO {
+            let entries = match self.control_entries(ino) {
+                Ok(entries) => entries,
+                Err(errno) => {
+                    reply.error(errno);
+                    return;
+                }
+            };
+            for (i, (e_ino, ty, name)) in entries.iter().enumerate().skip(offset.max(0) as usize) {
+                if reply.add(*e_ino, (i + 1) as i64, *ty, name) {
+                    break;
+                }
+            }
+            reply.ok();
+            return;
+        }
         let Some(dir) = self.path_of(ino) else {
             if cfg!(debug_assertions) {
                 eprintln!("[READDIR] Inode not found: {}", ino);
@@ -1275,14 +2801,17 @@ impl Filesystem for RemoteFs {
                 return;
             }
         };
-        if offset == 0 {
-            if !reply.add(ino, 1, FileType::Directory, ".") {
-                if cfg!(debug_assertions) {
-                    println!("[READDIR] Reply buffer full after adding '.' entry");
-                }
-                reply.ok();
-                return;
+        // Offset 1 is ".", 2 is "..", 3 + i the i-th entry: the kernel resumes after the last offset
+        // it got, which may be past the end of a listing that shrank since
+        let offset = offset.max(0);
+        if offset < 1 && !reply.add(ino, 1, FileType::Directory, ".") {
+            if cfg!(debug_assertions) {
+                println!("[READDIR] Reply buffer full after adding '.' entry");
             }
+            reply.ok();
+            return;
+        }
+        if offset < 2 {
             let parent_ino = if dir == Path::new("/") {
                 if cfg!(debug_assertions) {
                     println!("[READDIR] Directory is root, setting parent ino to 1");
@@ -1293,7 +2822,7 @@ impl Filesystem for RemoteFs {
                     println!("[READDIR] Getting parent ino for directory: {:?}", dir);
                 }
                 dir.parent()
-                    .and_then(|p| self.state.ino_of(&p))
+                    .and_then(|p| self.state.ino_of(p))
                     .unwrap_or(1)
             };
             if !reply.add(parent_ino, 2, FileType::Directory, "..") {
@@ -1304,29 +2833,23 @@ impl Filesystem for RemoteFs {
                 return;
             }
         }
-        let mut idx = if offset <= 2 {
-            if cfg!(debug_assertions) {
-                println!("[READDIR] Starting index at 0 since offset is {}", offset);
-            }
-            0
-        } else {
-            if cfg!(debug_assertions) {
-                println!(
-                    "[READDIR] Starting index at {} based on offset {}",
-                    (offset - 2) as usize,
-                    offset
-                );
-            }
-            (offset - 2) as usize
-        };
+        let mut idx = ((offset - 2).max(0) as usize).min(entries.len());
+        if cfg!(debug_assertions) {
+            println!(
+                "[READDIR] Starting index at {} of {} based on offset {}",
+                idx,
+                entries.len(),
+                offset
+            );
+        }
         while idx < entries.len() {
             let (child, de) = &entries[idx];
-            let is_dir = Self::is_dir(&de);
-            let ty = if is_dir {
-                FileType::Directory
-            } else {
-                FileType::RegularFile
-            };
+            // A file unlinked while open waits under a hidden name for its last close
+            if de.name.starts_with(UNLINKED_PREFIX) {
+                idx += 1;
+                continue;
+            }
+            let ty = file_type_of(de.entry_kind());
             let child_ino = self.alloc_ino(child);
             let this_off = 3 + (idx as i64);
             if !reply.add(child_ino, this_off, ty, child.file_name().unwrap()) {
@@ -1344,11 +2867,111 @@ impl Filesystem for RemoteFs {
         reply.ok();
     }
 
+    // Function that lists a directory with the attributes of every entry, so the kernel needs no
+    // lookup or getattr for them afterwards. Each entry handed out counts as a lookup, "." and ".."
+    // excepted: the kernel does not take a reference on those
+    fn readdirplus(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectoryPlus,
+    ) {
+        if cfg!(debug_assertions) {
+            println!(
+                "[READDIRPLUS] Readdirplus called for ino: {}, offset: {}",
+                ino, offset
+            );
+        }
+        if ino == CONTROL_DIR_INO || ino == CONTROL_RECOVERED_INO || ino == CONTROL_TRASH_INO {
+            let entries = match self.control_entries(ino) {
+                Ok(entries) => entries,
+                Err(errno) => {
+                    reply.error(errno);
+                    return;
+                }
+            };
+            for (i, (e_ino, _, name)) in entries.iter().enumerate().skip(offset.max(0) as usize) {
+                let attr = self.synthetic_attr(*e_ino).unwrap_or_else(|| Self::control_attr(*e_ino));
+                if reply.add(*e_ino, (i + 1) as i64, name, &Duration::ZERO, &attr, 0) {
+                    break;
+                }
+            }
+            reply.ok();
+            return;
+        }
+        let Some(dir) = self.path_of(ino) else {
+            reply.error(ENOTDIR);
+            return;
+        };
+        let entries = match self.dir_entries(&dir) {
+            Ok(v) => v,
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!(
+                        "[READDIRPLUS] Failed to read directory entries for path: {:?}, error: {:?}",
+                        dir, e
+                    );
+                }
+                reply.error(ENOENT);
+                return;
+            }
+        };
+        // Same offsets as readdir
+        let offset = offset.max(0);
+        // The kernel only takes the inode of "." and "..": a listing that raced a cache clear left
+        // no attributes cached, placeholder ones do
+        let dir_attr = self
+            .state
+            .get_attr(&dir)
+            .unwrap_or_else(|| self.file_attr(&dir, FileType::Directory, 0, None, 0o755, 2));
+        if offset < 1 && reply.add(ino, 1, ".", &self.ttl_for(&dir), &dir_attr, 0) {
+            reply.ok();
+            return;
+        }
+        if offset < 2 {
+            let parent = dir.parent().unwrap_or(Path::new("/"));
+            let parent_attr = self.state.get_attr(parent).unwrap_or(dir_attr);
+            if reply.add(parent_attr.ino, 2, "..", &self.ttl_for(parent), &parent_attr, 0) {
+                reply.ok();
+                return;
+            }
+        }
+        let start = ((offset - 2).max(0) as usize).min(entries.len());
+        for (idx, (child, de)) in entries.iter().enumerate().skip(start) {
+            if de.name.starts_with(UNLINKED_PREFIX) {
+                continue;
+            }
+            // A listing served from the cache may outlive the attributes of entries the kernel forgot
+            let attr = self
+                .state
+                .get_attr(child)
+                .unwrap_or_else(|| self.entry_attr(child, de));
+            let this_off = 3 + (idx as i64);
+            if reply.add(attr.ino, this_off, child.file_name().unwrap(), &self.ttl_for(child), &attr, 0) {
+                break;
+            }
+            self.state.add_lookup(attr.ino);
+            if cfg!(debug_assertions) {
+                println!(
+                    "[READDIRPLUS] Added entry: {:?} with ino: {} at offset: {}",
+                    child, attr.ino, this_off
+                );
+            }
+        }
+        reply.ok();
+    }
+
     // Retrieves metadata and file attributes for a given path
     fn getattr(&mut self, _req: &Request<'_>, ino: u64, fh: Option<u64>, reply: ReplyAttr) {
         if cfg!(debug_assertions) {
             println!("[GETATTR] Getattr called for ino: {}", ino);
         }
+        if let Some(attr) = self.synthetic_attr(ino) {
+            reply.attr(&Duration::ZERO, &attr);
+            return;
+        }
         let Some(path) = self.path_of(ino) else {
             if cfg!(debug_assertions) {
                 eprintln!("[GETATTR] Inode not found: {}", ino);
@@ -1357,40 +2980,51 @@ impl Filesystem for RemoteFs {
             return;
         };
         let mut forced_size: Option<u64> = None;
-        if let Some(fh_val) = fh {
+        {
             if cfg!(debug_assertions) {
                 println!(
-                    "[GETATTR] Checking for local write tempfile for fh: {} on path: {:?}",
-                    fh_val, path
+                    "[GETATTR] Checking for local write tempfile for fh: {:?} on path: {:?}",
+                    fh, path
                 );
             }
+            // fstat comes without a handle: a handle holding unsent writes on the inode has its size
             let writes = self.state.writes.lock().unwrap();
-            if let Some(tw) = writes.get(&fh_val) {
+            let tw = fh
+                .and_then(|fh_val| writes.get(&fh_val))
+                .or_else(|| writes.values().find(|tw| tw.ino == ino && tw.dirty));
+            if let Some(tw) = tw {
                 if cfg!(debug_assertions) {
                     println!(
-                        "[GETATTR] Found local write tempfile for fh: {}, size: {}",
-                        fh_val, tw.size
+                        "[GETATTR] Found local write tempfile for fh: {:?}, size: {}",
+                        fh, tw.size
                     );
                 }
                 forced_size = Some(tw.size);
             }
         }
 
-        let attr_opt = if let Some(a) = self.state.get_attr(&path) {
+        let direct = fh.is_some_and(|fh| self.state.is_handle_direct(fh));
+        // A zero-TTL --cache-rule never answers from the cache
+        let cacheable = !self.ttl_for(&path).is_zero();
+        let attr_opt = if let Some(a) = self.state.get_attr(&path).filter(|_| cacheable) {
             if cfg!(debug_assertions) {
                 println!("[GETATTR] Found attributes in cache for path: {:?}", path);
             }
+            // Misses are accounted by dir_entries on the fallback below
+            self.state
+                .cache_metrics
+                .record_hit(path.parent().unwrap_or(Path::new("/")));
             Some(a)
         } else {
             let parent = path.parent().unwrap_or(Path::new("/"));
-            if self.dir_entries(parent).is_ok() {
+            if let Ok(entries) = self.dir_entries(parent) {
                 if cfg!(debug_assertions) {
                     println!(
                         "[GETATTR] Fetched directory entries for parent: {:?}, checking cache again for path: {:?}",
                         parent, path
                     );
                 }
-                self.state.get_attr(&path)
+                self.listed_attr(&path, &entries)
             } else {
                 if cfg!(debug_assertions) {
                     eprintln!(
@@ -1401,6 +3035,8 @@ impl Filesystem for RemoteFs {
                 None
             }
         };
+        // The root is always there, with the defaults if the backend does not answer
+        let attr_opt = attr_opt.or_else(|| (ino == 1).then(|| self.root_attr()));
         if let Some(mut attr) = attr_opt {
             if cfg!(debug_assertions) {
                 println!(
@@ -1408,6 +3044,10 @@ impl Filesystem for RemoteFs {
                     path, attr
                 );
             }
+            // A zero TTL (--cache-rule) is not served from the parent's cached listing either
+            if direct || !cacheable || fh.is_some_and(|fh| self.coherency_due(fh)) {
+                attr = self.refresh_attr_size(&path, attr, true);
+            }
             if let Some(real_size) = forced_size {
                 if cfg!(debug_assertions) {
                     println!(
@@ -1415,15 +3055,147 @@ impl Filesystem for RemoteFs {
                         real_size, path
                     );
                 }
-                attr.size = real_size;
-                attr.blocks = (real_size + 511) / 512;
+                attr.size = real_size;
+                attr.blocks = real_size.div_ceil(512);
+            }
+            let ttl = if direct { Duration::ZERO } else { self.getattr_ttl(&path, fh) };
+            reply.attr(&ttl, &attr);
+        } else {
+            if cfg!(debug_assertions) {
+                eprintln!("[GETATTR] Attributes not found for path: {:?}", path);
+            }
+            reply.error(ENOENT);
+        }
+    }
+
+    // Returns an ACL xattr (--acl) in the binary form getfacl reads; every other name is absent
+    fn getxattr(&mut self, _req: &Request<'_>, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        if !self.opts.acl {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+        let Some(kind) = name.to_str().and_then(crate::acl::kind_of) else {
+            reply.error(libc::ENODATA);
+            return;
+        };
+        if self.synthetic_attr(ino).is_some() {
+            reply.error(libc::ENODATA);
+            return;
+        }
+        let Some(path) = self.path_of(ino) else {
+            reply.error(ENOENT);
+            return;
+        };
+        match self.block_on(self.api.acl(&canonical_rel(&path))) {
+            Ok(Some(acl)) => {
+                let text = if kind == "access" { acl.access } else { acl.default };
+                match text.as_deref().and_then(crate::acl::to_xattr) {
+                    Some(value) => reply_xattr(reply, size, &value),
+                    None => reply.error(libc::ENODATA),
+                }
             }
-            reply.attr(&self.state.cache_ttl, &attr);
-        } else {
-            if cfg!(debug_assertions) {
-                eprintln!("[GETATTR] Attributes not found for path: {:?}", path);
+            // Without ACLs on the backend the mode bits say it all
+            Ok(None) => reply.error(libc::ENODATA),
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[GETXATTR] ACL of {:?} not read: {}", path, e);
+                }
+                reply.error(errno_from_api(&e));
             }
+        }
+    }
+
+    // Lists the ACL xattrs an entry has (--acl)
+    fn listxattr(&mut self, _req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
+        if !self.opts.acl {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+        if self.synthetic_attr(ino).is_some() {
+            reply_xattr(reply, size, &[]);
+            return;
+        }
+        let Some(path) = self.path_of(ino) else {
             reply.error(ENOENT);
+            return;
+        };
+        match self.block_on(self.api.acl(&canonical_rel(&path))) {
+            Ok(acl) => {
+                let acl = acl.unwrap_or_default();
+                let mut names = Vec::new();
+                for (name, text) in [
+                    (crate::acl::XATTR_ACCESS, acl.access),
+                    (crate::acl::XATTR_DEFAULT, acl.default),
+                ] {
+                    if text.is_some() {
+                        names.extend_from_slice(name.as_bytes());
+                        names.push(0);
+                    }
+                }
+                reply_xattr(reply, size, &names);
+            }
+            Err(e) => reply.error(errno_from_api(&e)),
+        }
+    }
+
+    // Stores an ACL set with setfacl on the backend (--acl); other xattrs are not supported
+    fn setxattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        name: &OsStr,
+        value: &[u8],
+        _flags: i32,
+        _position: u32,
+        reply: ReplyEmpty,
+    ) {
+        if !self.opts.acl {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
+        let Some(kind) = name.to_str().and_then(crate::acl::kind_of) else {
+            reply.error(libc::ENOTSUP);
+            return;
+        };
+        let Some(acl) = crate::acl::from_xattr(value) else {
+            reply.error(libc::EINVAL);
+            return;
+        };
+        let Some(path) = self.path_of(ino).filter(|_| self.synthetic_attr(ino).is_none()) else {
+            reply.error(libc::EPERM);
+            return;
+        };
+        match self.store_acl(ino, &path, kind, Some(&acl)) {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    // Removes an ACL on the backend (setfacl -b / -k) (--acl)
+    fn removexattr(&mut self, _req: &Request<'_>, ino: u64, name: &OsStr, reply: ReplyEmpty) {
+        if !self.opts.acl {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
+        let Some(kind) = name.to_str().and_then(crate::acl::kind_of) else {
+            reply.error(libc::ENODATA);
+            return;
+        };
+        let Some(path) = self.path_of(ino).filter(|_| self.synthetic_attr(ino).is_none()) else {
+            reply.error(libc::EPERM);
+            return;
+        };
+        match self.store_acl(ino, &path, kind, None) {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno),
         }
     }
 
@@ -1434,9 +3206,38 @@ impl Filesystem for RemoteFs {
         }
         let fh = self.state.alloc_fh();
 
-        let wants_write = (flags & (libc::O_WRONLY | libc::O_RDWR)) != 0;
+        if ino == CONTROL_REFRESH_INO || ino == CONTROL_COPY_INO {
+            reply.opened(fh, FOPEN_DIRECT_IO);
+            return;
+        }
+        // Trashed entries are only listed, their content stays on the backend until restored
+        if self.trash_entry(ino).is_some() {
+            reply.error(libc::EACCES);
+            return;
+        }
+        if ino == CONTROL_STATUS_INO || self.recovered_file(ino).is_some() {
+            if (flags & libc::O_ACCMODE) != libc::O_RDONLY {
+                reply.error(libc::EACCES);
+            } else {
+                reply.opened(fh, FOPEN_DIRECT_IO);
+            }
+            return;
+        }
+
+        let wants_write = (flags & libc::O_ACCMODE) != libc::O_RDONLY;
 
         if wants_write {
+            if self.read_only() {
+                reply.error(libc::EROFS);
+                return;
+            }
+            if self.write_handles_exhausted() {
+                if cfg!(debug_assertions) {
+                    eprintln!("[OPEN] Too many handles open for writing, refusing ino: {}", ino);
+                }
+                reply.error(libc::EMFILE);
+                return;
+            }
             if cfg!(debug_assertions) {
                 println!(
                     "[OPEN] Open requested with write access for ino: {}, allocating tempfile",
@@ -1445,7 +3246,7 @@ impl Filesystem for RemoteFs {
             }
             let temp_path = self.get_temporary_path(fh);
 
-            if let Err(_) = File::create(&temp_path) {
+            if File::create(&temp_path).is_err() {
                 if cfg!(debug_assertions) {
                     eprintln!(
                         "[OPEN] Failed to create temporary file at path: {:?}",
@@ -1455,47 +3256,105 @@ impl Filesystem for RemoteFs {
                 reply.error(libc::EIO);
                 return;
             }
-            if let Some(path) = self.path_of(ino) {
+            let mut populated = 0;
+            // The tempfile matches the backend, as loaded or emptied like the truncate sent for
+            // O_TRUNC, unless another handle holds unsent writes: only then is it dirty from the start
+            let mut dirty = !self.state.dirty_writes_of(ino).is_empty();
+            // With FUSE_ATOMIC_O_TRUNC no setattr follows: the file is emptied here, as setattr would
+            if let Some(path) = self.path_of(ino).filter(|_| flags & libc::O_TRUNC != 0) {
+                if let Err(e) = self.block_on(self.api.truncate(&canonical_rel(&path), 0)) {
+                    let errno = errno_from_api(&e);
+                    if cfg!(debug_assertions) {
+                        eprintln!("[OPEN] Truncate failed for path: {:?}, errno: {}", path, errno);
+                    }
+                    // Same recoverable errors as setattr: the empty tempfile is uploaded on release
+                    if errno != libc::ENOENT && errno != libc::EIO && errno != libc::ENOSPC {
+                        let _ = std::fs::remove_file(&temp_path);
+                        reply.error(errno);
+                        return;
+                    }
+                    dirty = true;
+                }
+                self.state.truncate_writes_of(ino, None, 0);
+                if let Some(mut attr) = self.state.get_attr(&path) {
+                    attr.size = 0;
+                    attr.blocks = 0;
+                    self.insert_attr_cache(path, attr);
+                }
+            }
+            // Writes and reads on this handle go to the tempfile, so it must hold the whole file,
+            // unless O_TRUNC empties it anyway
+            if let Some(path) = self.path_of(ino).filter(|_| flags & libc::O_TRUNC == 0) {
                 if cfg!(debug_assertions) {
                     println!(
                         "[OPEN] Loading existing file data into tempfile for path: {:?}",
                         path
                     );
                 }
-                let Some(attr) = self.state.get_attr(&path) else {
+                // The kernel may still hold an inode whose attributes the cache evicted
+                let attr = self.state.get_attr(&path).or_else(|| {
+                    let parent = path.parent().unwrap_or(Path::new("/"));
+                    let entries = self.dir_entries(parent).unwrap_or_default();
+                    self.listed_attr(&path, &entries)
+                });
+                let Some(attr) = attr else {
                     if cfg!(debug_assertions) {
                         eprintln!("[OPEN] Attributes not found in cache for path: {:?}", path);
                     }
+                    let _ = std::fs::remove_file(&temp_path);
                     reply.error(ENOENT);
                     return;
                 };
-                let rel = Self::rel_for_db(&path);
-                if let Ok(bytes) = self.rt.block_on(self.api.read_all(&rel, attr.size)) {
+                // Fail early rather than half-filling the temp dir
+                if let Err(e) = crate::temp_dir::ensure_room(&self.temp_dir, attr.size) {
                     if cfg!(debug_assertions) {
-                        println!(
-                            "[OPEN] Writing {} bytes to tempfile at path: {:?}",
-                            bytes.len(),
-                            temp_path
-                        );
+                        eprintln!("[OPEN] No room to buffer {:?}: {}", path, e);
                     }
-                    if let Ok(mut f) = File::options().write(true).open(&temp_path) {
+                    let _ = std::fs::remove_file(&temp_path);
+                    reply.error(libc::ENOSPC);
+                    return;
+                }
+                let rel = canonical_rel(&path);
+                // Another handle may hold writes the backend has not seen yet, or a file it is still
+                // creating: this one starts from its tempfile
+                let loaded = match self.state.dirty_writes_of(ino).into_iter().next() {
+                    Some((_, pending)) => std::fs::read(pending).map_err(|e| errno_from_io(&e)),
+                    None => self
+                        .block_on(self.api.read_all(&rel, attr.size))
+                        .map_err(|e| errno_from_api(&e)),
+                };
+                let bytes = match loaded {
+                    Ok(bytes) => bytes,
+                    Err(errno) => {
+                        // A missing file is ENOENT, not an empty tempfile uploaded on close
                         if cfg!(debug_assertions) {
-                            println!(
-                                "[OPEN] Opened tempfile for writing at path: {:?}",
-                                temp_path
-                            );
-                        }
-                        if flags & libc::O_APPEND != 0 {
-                            if cfg!(debug_assertions) {
-                                println!(
-                                    "[OPEN] Seeking to end of tempfile at path: {:?} due to O_APPEND flag",
-                                    temp_path
-                                );
-                            }
-                            let _ = f.write_all(&bytes);
+                            eprintln!("[OPEN] Failed to load {:?} into the tempfile: errno {}", path, errno);
                         }
+                        let _ = std::fs::remove_file(&temp_path);
+                        reply.error(errno);
+                        return;
+                    }
+                };
+                if cfg!(debug_assertions) {
+                    println!(
+                        "[OPEN] Writing {} bytes to tempfile at path: {:?}",
+                        bytes.len(),
+                        temp_path
+                    );
+                }
+                if let Err(e) = File::options()
+                    .write(true)
+                    .open(&temp_path)
+                    .and_then(|mut f| f.write_all(&bytes))
+                {
+                    if cfg!(debug_assertions) {
+                        eprintln!("[OPEN] Failed to fill tempfile at path: {:?}: {}", temp_path, e);
                     }
+                    let _ = std::fs::remove_file(&temp_path);
+                    reply.error(errno_from_io(&e));
+                    return;
                 }
+                populated = bytes.len() as u64;
             }
             if cfg!(debug_assertions) {
                 println!(
@@ -1503,11 +3362,30 @@ impl Filesystem for RemoteFs {
                     fh, temp_path
                 );
             }
-            self.state.insert_write_tempfile(fh, temp_path, true);
+            let path = self.path_of(ino).unwrap_or_default();
+            self.state.insert_write_tempfile(fh, ino, path, temp_path, dirty);
+            // getattr on the handle reports tw.size, which starts from the loaded content
+            self.state.with_write_mut(fh, |tw| tw.size = populated);
         }
         if cfg!(debug_assertions) {
             println!("[OPEN] File opened with fh: {}", fh);
         }
+        self.state.register_handle(fh, ino);
+        // The attributes the kernel holds came from a lookup, with the full TTL: dropped here so its
+        // next size check is a getattr on this handle, answered with the --coherency-interval TTL
+        if self.opts.coherency_interval.is_some() {
+            Self::inval_from_read(&self.notifier, ino);
+        }
+        // O_DIRECT asks for no caching: the page cache is skipped with FOPEN_DIRECT_IO, and the
+        // handle reads sizes and data from the backend and commits every write
+        if flags & libc::O_DIRECT != 0 {
+            if cfg!(debug_assertions) {
+                println!("[OPEN] O_DIRECT requested, bypassing the caches for fh: {}", fh);
+            }
+            self.state.mark_handle_direct(fh);
+            reply.opened(fh, flags as u32 | FOPEN_DIRECT_IO);
+            return;
+        }
         reply.opened(fh, flags as u32);
     }
     // Reads data from a file starting at a specified offset
@@ -1528,6 +3406,41 @@ impl Filesystem for RemoteFs {
                 ino, fh, offset, size
             );
         }
+        if ino == CONTROL_COPY_INO {
+            reply.data(&[]);
+            return;
+        }
+        if ino == CONTROL_REFRESH_INO || ino == CONTROL_STATUS_INO {
+            let status = if ino == CONTROL_STATUS_INO {
+                self.status_json()
+            } else {
+                self.control_status()
+            }
+            .into_bytes();
+            let start = (offset.max(0) as usize).min(status.len());
+            let end = (start + size as usize).min(status.len());
+            reply.data(&status[start..end]);
+            return;
+        }
+        if let Some(r) = self.recovered_file(ino) {
+            let mut buf = vec![0u8; size as usize];
+            let read = File::open(&r.path).and_then(|mut f| {
+                f.seek(SeekFrom::Start(offset.max(0) as u64))?;
+                f.read(&mut buf)
+            });
+            match read {
+                Ok(n) => reply.data(&buf[..n]),
+                Err(e) => reply.error(errno_from_io(&e)),
+            }
+            return;
+        }
+        if self.state.is_handle_stale(fh) {
+            if cfg!(debug_assertions) {
+                eprintln!("[READ] Handle {} is stale, file changed on the backend", fh);
+            }
+            reply.error(libc::ESTALE);
+            return;
+        }
         let Some(path) = self.path_of(ino) else {
             if cfg!(debug_assertions) {
                 eprintln!("[READ] Inode not found: {}", ino);
@@ -1536,48 +3449,52 @@ impl Filesystem for RemoteFs {
             return;
         };
 
-        let rel_db = Self::rel_for_db(&path);
+        let rel_db = canonical_rel(&path);
         let mut tmp_result: Option<Vec<u8>> = None;
         let mut tmp_error: Option<i32> = None;
 
-        self.state.with_write_mut(fh, |tw| {
-            if let Ok(mut f) = File::open(&tw.tem_path) {
-                if cfg!(debug_assertions) {
-                    println!(
-                        "[READ] Reading from local write tempfile for fh: {}, path: {:?}",
-                        fh, tw.tem_path
-                    );
-                }
-                let mut buf = vec![0u8; size as usize];
-                if f.seek(SeekFrom::Start(offset.max(0) as u64)).is_ok() {
+        // An O_DIRECT handle reads the backend: its writes are committed as they happen
+        let direct = self.state.is_handle_direct(fh);
+        if !direct {
+            self.state.with_write_mut(fh, |tw| {
+                if let Ok(mut f) = File::open(&tw.tem_path) {
                     if cfg!(debug_assertions) {
                         println!(
-                            "[READ] Seeked to offset {} in tempfile for fh: {}",
-                            offset, fh
+                            "[READ] Reading from local write tempfile for fh: {}, path: {:?}",
+                            fh, tw.tem_path
                         );
                     }
-                    let n = f.read(&mut buf).unwrap_or(0);
-                    buf.truncate(n);
-                    tmp_result = Some(buf);
+                    let mut buf = vec![0u8; size as usize];
+                    if f.seek(SeekFrom::Start(offset.max(0) as u64)).is_ok() {
+                        if cfg!(debug_assertions) {
+                            println!(
+                                "[READ] Seeked to offset {} in tempfile for fh: {}",
+                                offset, fh
+                            );
+                        }
+                        let n = f.read(&mut buf).unwrap_or(0);
+                        buf.truncate(n);
+                        tmp_result = Some(buf);
+                    } else {
+                        if cfg!(debug_assertions) {
+                            eprintln!(
+                                "[READ] Failed to seek to offset {} in tempfile for fh: {}",
+                                offset, fh
+                            );
+                        }
+                        tmp_error = Some(EIO);
+                    }
                 } else {
                     if cfg!(debug_assertions) {
                         eprintln!(
-                            "[READ] Failed to seek to offset {} in tempfile for fh: {}",
-                            offset, fh
+                            "[READ] Failed to open tempfile for reading for fh: {}, path: {:?}",
+                            fh, tw.tem_path
                         );
                     }
                     tmp_error = Some(EIO);
                 }
-            } else {
-                if cfg!(debug_assertions) {
-                    eprintln!(
-                        "[READ] Failed to open tempfile for reading for fh: {}, path: {:?}",
-                        fh, tw.tem_path
-                    );
-                }
-                tmp_error = Some(EIO);
-            }
-        });
+            });
+        }
         if let Some(e) = tmp_error {
             if cfg!(debug_assertions) {
                 eprintln!(
@@ -1612,8 +3529,8 @@ impl Filesystem for RemoteFs {
                 );
             }
             let parent = path.parent().unwrap_or(Path::new("/"));
-            let _ = self.dir_entries(parent);
-            self.state.get_attr(&path)
+            let entries = self.dir_entries(parent).unwrap_or_default();
+            self.listed_attr(&path, &entries)
         };
 
         let Some(attr) = attr else {
@@ -1624,6 +3541,14 @@ impl Filesystem for RemoteFs {
             return;
         };
 
+        // The cached size may predate a write made on the backend: confirm it before cutting the read
+        let attr = if direct || self.coherency_due(fh) {
+            self.refresh_attr_size(&path, attr, true)
+        } else if offset.max(0) as u64 + size as u64 > attr.size {
+            self.refresh_attr_size(&path, attr, false)
+        } else {
+            attr
+        };
         if (offset as u64) >= attr.size {
             if cfg!(debug_assertions) {
                 println!(
@@ -1638,8 +3563,41 @@ impl Filesystem for RemoteFs {
         let start = offset.max(0) as u64;
         let end = (start + (size as u64) - 1).min(attr.size - 1);
 
-        match self.rt.block_on(self.api.read_range(&rel_db, start, end)) {
-            Ok(bytes) => reply.data(&bytes),
+        // With --fuse-threads the backend read goes to a worker and the session reads the next request
+        if let Some(readers) = &self.readers {
+            let (api, rt) = (self.api.clone(), self.rt.clone());
+            let (state, notifier) = (self.state.clone(), self.notifier.clone());
+            readers.run(move || {
+                let mut buf = vec![0u8; (end - start + 1) as usize];
+                match crate::block_on(&rt, api.read_into(&rel_db, start, &mut buf)) {
+                    Ok(n) => {
+                        if (n as u64) < end - start + 1 {
+                            Self::record_shrink(&state, &notifier, &path, attr, start + n as u64);
+                        }
+                        reply.data(&buf[..n])
+                    }
+                    Err(err) => {
+                        if cfg!(debug_assertions) {
+                            eprintln!(
+                                "[READ] Failed to read range {}-{} for path: {:?}, error: {:?}",
+                                start, end, path, err
+                            );
+                        }
+                        reply.error(errno_from_api(&err))
+                    }
+                }
+            });
+            return;
+        }
+        self.read_buf.resize((end - start + 1) as usize, 0);
+        match crate::block_on(&self.rt, self.api.read_into(&rel_db, start, &mut self.read_buf))
+        {
+            Ok(n) => {
+                if (n as u64) < end - start + 1 {
+                    self.shrink_attr(&path, attr, start + n as u64);
+                }
+                reply.data(&self.read_buf[..n])
+            }
             Err(err) => {
                 if cfg!(debug_assertions) {
                     eprintln!(
@@ -1647,7 +3605,7 @@ impl Filesystem for RemoteFs {
                         start, end, path, err
                     );
                 }
-                reply.error(errno_from_anyhow(&err))
+                reply.error(errno_from_api(&err))
             }
         }
     }
@@ -1656,7 +3614,7 @@ impl Filesystem for RemoteFs {
     fn write(
         &mut self,
         _req: &Request<'_>,
-        _ino: u64,
+        ino: u64,
         fh: u64,
         offset: i64,
         data: &[u8],
@@ -1665,6 +3623,10 @@ impl Filesystem for RemoteFs {
         _lock_owner: Option<u64>,
         reply: ReplyWrite,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!(
                 "[WRITE] Write called for fh: {}, offset: {}, data length: {}, data: {:?}",
@@ -1674,6 +3636,28 @@ impl Filesystem for RemoteFs {
                 data
             );
         }
+        if ino == CONTROL_REFRESH_INO {
+            if cfg!(debug_assertions) {
+                println!("[WRITE] Refresh requested through control file");
+            }
+            self.refresh_mount();
+            reply.written(data.len() as u32);
+            return;
+        }
+        if ino == CONTROL_COPY_INO {
+            match self.copy_from_control(data) {
+                Ok(()) => reply.written(data.len() as u32),
+                Err(errno) => reply.error(errno),
+            }
+            return;
+        }
+        if self.state.is_handle_stale(fh) {
+            if cfg!(debug_assertions) {
+                eprintln!("[WRITE] Handle {} is stale, file changed on the backend", fh);
+            }
+            reply.error(libc::ESTALE);
+            return;
+        }
         if offset < 0 {
             if cfg!(debug_assertions) {
                 eprintln!("[WRITE] Invalid negative offset: {}", offset);
@@ -1683,22 +3667,31 @@ impl Filesystem for RemoteFs {
         }
 
         let mut wrote = false;
+        // Nothing else of the handle was waiting for the backend before this write
+        let mut rest_sent = false;
+        let mut errno = libc::EIO;
 
-        self.state.with_write_mut(fh, |tw| {
+        let Some(io) = self.state.write_lock(fh) else {
+            // Only handles opened for writing get a tempfile
+            if cfg!(debug_assertions) {
+                eprintln!("[WRITE] Handle {} was not opened for writing", fh);
+            }
+            reply.error(libc::EBADF);
+            return;
+        };
+        // The writes map stays free during the disk I/O, only this handle waits
+        let serial = io.lock().unwrap();
+        let current = self.state.with_write_mut(fh, |tw| (tw.tem_path.clone(), tw.size));
+        if let Some((tem_path, size)) = current {
             if cfg!(debug_assertions) {
                 println!(
                     "[WRITE] Writing to tempfile for fh: {}, path: {:?}",
-                    fh, tw.tem_path
+                    fh, tem_path
                 );
             }
-            if let Ok(mut f) = OpenOptions::new().write(true).open(&tw.tem_path) {
-                if cfg!(debug_assertions) {
-                    println!(
-                        "[WRITE] Opened tempfile for writing at path: {:?}",
-                        tw.tem_path
-                    );
-                }
-                if f.seek(SeekFrom::Start(offset as u64)).is_ok() && f.write_all(data).is_ok() {
+            // A failed write leaves tw.size and the tempfile length as they were
+            match crate::temp_dir::write_at(&tem_path, size, offset as u64, data) {
+                Ok(size) => {
                     if cfg!(debug_assertions) {
                         println!(
                             "[WRITE] Wrote {} bytes at offset {} in tempfile for fh: {}",
@@ -1707,13 +3700,42 @@ impl Filesystem for RemoteFs {
                             fh
                         );
                     }
-                    let end = (offset as u64) + (data.len() as u64);
-                    tw.size = tw.size.max(end);
-                    tw.dirty = true;
+                    rest_sent = self
+                        .state
+                        .with_write_mut(fh, |tw| {
+                            tw.size = size;
+                            !std::mem::replace(&mut tw.dirty, true)
+                        })
+                        .unwrap_or(false);
                     wrote = true;
                 }
+                Err(e) => {
+                    if cfg!(debug_assertions) {
+                        eprintln!("[WRITE] Tempfile write failed for fh: {}, error: {}", fh, e);
+                    }
+                    if crate::temp_dir::is_disk_full(&e) {
+                        errno = libc::ENOSPC;
+                    }
+                }
             }
-        });
+        }
+        drop(serial);
+
+        // Writing after utimes moves the mtime again, as on a local filesystem
+        if wrote {
+            self.state.pending_times.lock().unwrap().remove(&ino);
+        }
+        let write_through = self.opts.commit_strategy == CommitStrategy::WriteThrough
+            || self.state.is_handle_direct(fh);
+        if wrote && write_through {
+            if let Err(e) = self.commit_write(ino, fh, offset as u64, data, rest_sent) {
+                if cfg!(debug_assertions) {
+                    eprintln!("[WRITE] Write-through commit failed for fh: {}, error: {:?}", fh, e);
+                }
+                reply.error(errno_from_anyhow(&e));
+                return;
+            }
+        }
 
         if wrote {
             if cfg!(debug_assertions) {
@@ -1728,8 +3750,84 @@ impl Filesystem for RemoteFs {
             if cfg!(debug_assertions) {
                 eprintln!("[WRITE] Failed to write data for fh: {}", fh);
             }
-            reply.error(libc::EIO);
+            reply.error(errno);
+        }
+    }
+
+    // Copies a whole file on the backend when `cp` asks for it: the data does not go through the
+    // client. Anything else gets EOPNOTSUPP, and the kernel copies with read+write on the handles
+    fn copy_file_range(
+        &mut self,
+        _req: &Request<'_>,
+        ino_in: u64,
+        _fh_in: u64,
+        offset_in: i64,
+        ino_out: u64,
+        fh_out: u64,
+        offset_out: i64,
+        len: u64,
+        flags: u32,
+        reply: ReplyWrite,
+    ) {
+        if cfg!(debug_assertions) {
+            println!(
+                "[COPY_FILE_RANGE] ino_in: {}, offset_in: {}, ino_out: {}, fh_out: {}, offset_out: {}, len: {}",
+                ino_in, offset_in, ino_out, fh_out, offset_out, len
+            );
+        }
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
+        let (Some(src), Some(dst)) = (self.path_of(ino_in), self.path_of(ino_out)) else {
+            reply.error(ENOENT);
+            return;
+        };
+        let size = self.state.get_attr(&src).map(|a| a.size);
+        // Only a copy of the whole file into a handle nothing was written to yet; unsent writes to
+        // the source are not on the backend, and the reply cannot count more than u32::MAX bytes
+        let pristine = self.state.with_write_mut(fh_out, |tw| tw.size == 0) == Some(true);
+        let whole = size.is_some_and(|s| s <= len && s <= u32::MAX as u64);
+        if flags != 0
+            || ino_in == ino_out
+            || offset_in != 0
+            || offset_out != 0
+            || !pristine
+            || !whole
+            || !self.state.dirty_writes_of(ino_in).is_empty()
+        {
+            reply.error(libc::EOPNOTSUPP);
+            return;
+        }
+        let size = size.unwrap_or(0);
+        let _committing = self.state.commit_lock.lock().unwrap();
+        match self.block_on(self.api.copy(&canonical_rel(&src), &canonical_rel(&dst))) {
+            Ok(true) => {}
+            Ok(false) => {
+                reply.error(libc::EOPNOTSUPP);
+                return;
+            }
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[COPY_FILE_RANGE] Copy of {:?} to {:?} failed: {:?}", src, dst, e);
+                }
+                reply.error(errno_from_api(&e));
+                return;
+            }
         }
+        // The backend holds the data now: the empty tempfile must not replace it on release
+        self.state.with_write_mut(fh_out, |tw| tw.dirty = false);
+        if let Some(mut attr) = self.state.get_attr(&dst) {
+            attr.size = size;
+            attr.blocks = size.div_ceil(512);
+            attr.mtime = SystemTime::now();
+            attr.ctime = attr.mtime;
+            self.state.set_attr(&dst, attr);
+        }
+        if let Some(parent) = dst.parent() {
+            self.state.remove_dir_cache(parent);
+        }
+        reply.written(size as u32);
     }
 
     // Ensures that any buffered file data is written to storage
@@ -1747,7 +3845,7 @@ impl Filesystem for RemoteFs {
         reply.ok();
     }
 
-    fn fsync(&mut self, _req: &Request<'_>, ino: u64, fh: u64, _datasync: bool, reply: ReplyEmpty) {
+    fn fsync(&mut self, _req: &Request<'_>, ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
         if cfg!(debug_assertions) {
             println!("[FSYNC] Fsync called for ino: {}, fh: {}", ino, fh);
         }
@@ -1767,10 +3865,13 @@ impl Filesystem for RemoteFs {
                         path, tmp_path
                     );
                 }
-                let rel = Self::rel_for_db(&path);
-                if let Err(e) = self
-                    .rt
-                    .block_on(self.api.write_file(&rel, &tmp_path.to_string_lossy()))
+                let rel = canonical_rel(&path);
+                // Given up after --commit-attempts failures, the backend is not asked again
+                if self.state.is_abandoned(fh) {
+                    reply.error(EIO);
+                    return;
+                }
+                if let Err(e) = self.block_on(self.api.write_file(&rel, &tmp_path))
                 {
                     if cfg!(debug_assertions) {
                         eprintln!(
@@ -1778,18 +3879,47 @@ impl Filesystem for RemoteFs {
                             path, e
                         );
                     }
-                    reply.error(errno_from_anyhow(&e));
+                    if self.state.commit_failed(fh, &self.opts) {
+                        abandon_commit(&self.state, &self.opts, &rel, &tmp_path);
+                    }
+                    reply.error(errno_from_api(&e));
                     return;
                 }
                 if cfg!(debug_assertions) {
                     println!("[FSYNC] File {:?} written successfully during fsync.", path);
                 }
+                self.state.commit_succeeded(fh);
                 self.state.with_write_mut(fh, |tw| tw.dirty = false);
+                // The tempfile stays for the writes still to come, the backend now has its size
+                if let Ok(meta) = std::fs::metadata(&tmp_path) {
+                    if let Some(mut attr) = self.state.get_attr(&path) {
+                        attr.size = meta.len();
+                        attr.blocks = meta.len().div_ceil(512);
+                        self.state.set_attr(&path, attr);
+                    }
+                }
+            }
+        }
+        // fdatasync only needs the content, fsync also the times set while the file was written
+        if !datasync {
+            if let Some(path) = self.path_of(ino) {
+                if let Err(errno) = self.flush_pending_times(ino, &path) {
+                    reply.error(errno);
+                    return;
+                }
             }
         }
         reply.ok();
     }
 
+    // Directories have no buffered data and their metadata is sent right away: nothing to flush
+    fn fsyncdir(&mut self, _req: &Request<'_>, ino: u64, _fh: u64, datasync: bool, reply: ReplyEmpty) {
+        if cfg!(debug_assertions) {
+            println!("[FSYNCDIR] Fsyncdir called for ino: {}, datasync: {}", ino, datasync);
+        }
+        reply.ok();
+    }
+
     // Closes a file and releases associated resources
     fn release(
         &mut self,
@@ -1804,6 +3934,19 @@ impl Filesystem for RemoteFs {
         if cfg!(debug_assertions) {
             println!("[RELEASE] Release called for ino: {}, fh: {}", ino, fh);
         }
+        let stale = self.state.unregister_handle(fh);
+        let _committing = self.state.commit_lock.lock().unwrap();
+        if self.state.is_unlinked(ino) {
+            // Nothing written to an unlinked file survives it
+            if let Some(tw) = self.state.take_write(fh) {
+                let _ = std::fs::remove_file(&tw.tem_path);
+            }
+            if !self.state.has_open_handles(ino) {
+                self.delete_unlinked(ino);
+            }
+            reply.ok();
+            return;
+        }
         let Some(tw) = self.state.take_write(fh) else {
             if cfg!(debug_assertions) {
                 eprintln!(
@@ -1814,6 +3957,16 @@ impl Filesystem for RemoteFs {
             reply.ok();
             return;
         };
+        if stale {
+            // The file was removed or replaced remotely: --conflict-policy decides where the data goes
+            let result = self.resolve_conflict(&tw);
+            let _ = std::fs::remove_file(&tw.tem_path);
+            match result {
+                Ok(()) => reply.ok(),
+                Err(errno) => reply.error(errno),
+            }
+            return;
+        }
 
         let path = match self.path_of(ino) {
             Some(p) => p,
@@ -1826,12 +3979,24 @@ impl Filesystem for RemoteFs {
             }
         };
 
-        let rel = Self::rel_for_db(&path);
+        let rel = canonical_rel(&path);
 
+        if tw.abandoned {
+            // The backend refused every attempt: the final data replaces the copy in the recovery directory
+            if cfg!(debug_assertions) {
+                eprintln!("[RELEASE] Commits of {:?} were given up, not sending it again", path);
+            }
+            let saved = self.opts.recovery_dir.as_deref().is_some_and(|dir| {
+                crate::recovery::save(dir, &rel, &tw.tem_path).is_ok()
+            });
+            if !saved {
+                let _ = std::fs::remove_file(&tw.tem_path);
+            }
+            reply.error(EIO);
+            return;
+        }
         if tw.dirty {
-            match self
-                .rt
-                .block_on(self.api.write_file(&rel, &tw.tem_path.to_string_lossy()))
+            match self.block_on(self.api.write_file(&rel, &tw.tem_path))
             {
                 Ok(_) => {
                     if cfg!(debug_assertions) {
@@ -1876,11 +4041,30 @@ impl Filesystem for RemoteFs {
                             path
                         );
                     }
-                    let _ = std::fs::remove_file(&tw.tem_path);
+                    match &self.opts.recovery_dir {
+                        Some(dir) => {
+                            if crate::recovery::save(dir, &rel, &tw.tem_path).is_err() {
+                                let _ = std::fs::remove_file(&tw.tem_path);
+                            }
+                        }
+                        None => {
+                            let _ = std::fs::remove_file(&tw.tem_path);
+                        }
+                    }
                     reply.error(libc::EIO);
                     return;
                 }
             }
+        } else {
+            // Already committed by write-through or by the interval committer
+            let _ = std::fs::remove_file(&tw.tem_path);
+        }
+        // The data is on the backend: times set in the meantime are not lost, but a failure here
+        // does not fail the close, the content is safe
+        if let Err(errno) = self.flush_pending_times(ino, &path) {
+            if cfg!(debug_assertions) {
+                eprintln!("[RELEASE] Failed to send pending times for {:?}: {}", path, errno);
+            }
         }
         reply.ok();
     }
@@ -1893,15 +4077,23 @@ impl Filesystem for RemoteFs {
         name: &OsStr,
         mode: u32,
         umask: u32,
-        _flags: i32,
+        flags: i32,
         reply: ReplyCreate,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!(
                 "[CREATE] Create called for parent ino: {}, name: {:?}, mode: {:o}, umask: {:o}",
                 parent, name, mode, umask
             );
         }
+        if Self::is_control_entry(parent, name) {
+            reply.error(libc::EPERM);
+            return;
+        }
         let parent_path = match self.path_of(parent) {
             Some(p) => p,
             None => {
@@ -1913,45 +4105,26 @@ impl Filesystem for RemoteFs {
             }
         };
 
-        let path = parent_path.join(name);
-        let fh = self.state.alloc_fh();
-
-        let mut tmp = std::env::temp_dir();
-        tmp.push(format!("remote_fs_fh_{:x}.part", fh));
-
-        let _ = std::fs::remove_file(&tmp);
-        if std::fs::File::create(&tmp).is_err() {
+        if self.write_handles_exhausted() {
             if cfg!(debug_assertions) {
-                eprintln!(
-                    "[CREATE] Failed to create temporary file at path: {:?}",
-                    tmp
-                );
+                eprintln!("[CREATE] Too many handles open for writing, refusing {:?}", name);
             }
-            reply.error(libc::EIO);
+            reply.error(libc::EMFILE);
             return;
         }
-
-        self.state.insert_write_tempfile(fh, tmp, true);
-
-        let final_mode = mode & !umask;
-        let attr = self.file_attr(
-            &path,
-            FileType::RegularFile,
-            0,
-            None,
-            (final_mode & 0o777) as u16,
-            1,
-        );
-
-        self.state.set_attr(&path, attr.clone());
-        if let Some(parent_path) = self.state.path_of(parent) {
-            if cfg!(debug_assertions) {
-                println!("[CREATE] Updating cache for: {:?}", parent_path);
-            }
-            let _ = self.update_cache(&parent_path);
+        let path = parent_path.join(name);
+        if self.path_too_long(&path) {
+            reply.error(libc::ENAMETOOLONG);
+            return;
         }
-
-        reply.created(&self.state.cache_ttl, &attr, 0, fh, 0);
+        // Concurrent creates of one name run one at a time, the later ones see the file made by the first
+        let create_lock = self.state.create_lock(&path);
+        let creating = create_lock.lock().unwrap();
+        self.state.forget_negative(&path);
+        self.create_file(parent, &path, mode, umask, flags, reply);
+        drop(creating);
+        drop(create_lock);
+        self.state.release_create_lock(&path);
     }
 
     // Changes the name or path of a file or directory
@@ -1962,9 +4135,13 @@ impl Filesystem for RemoteFs {
         name: &OsStr,
         newparent: u64,
         newname: &OsStr,
-        _flags: u32,
+        flags: u32,
         reply: ReplyEmpty,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!(
                 "[RENAME] Rename called from parent ino: {}, name: {:?} to new parent ino: {}, new name: {:?}",
@@ -1974,6 +4151,22 @@ impl Filesystem for RemoteFs {
                 newname
             );
         }
+        if parent == CONTROL_TRASH_INO && !Self::is_control_entry(newparent, newname) {
+            match self.restore_trashed(name, newparent, newname) {
+                Ok(()) => reply.ok(),
+                Err(errno) => reply.error(errno),
+            }
+            return;
+        }
+        if Self::is_control_entry(parent, name) || Self::is_control_entry(newparent, newname) {
+            reply.error(libc::EPERM);
+            return;
+        }
+        // The backend cannot swap two entries atomically
+        if flags & libc::RENAME_EXCHANGE != 0 {
+            reply.error(libc::EINVAL);
+            return;
+        }
         let old_parent = match self.path_of(parent) {
             Some(p) => p,
             None => {
@@ -1997,20 +4190,63 @@ impl Filesystem for RemoteFs {
 
         let old_path = old_parent.join(name);
         let new_path = new_parent.join(newname);
+        if self.path_too_long(&new_path) {
+            reply.error(libc::ENAMETOOLONG);
+            return;
+        }
+
+        let old_rel = canonical_rel(&old_path);
+        let new_rel = canonical_rel(&new_path);
 
-        let old_rel = Self::rel_for_db(&old_path);
-        let new_rel = Self::rel_for_db(&new_path);
+        if let Err(errno) = self.check_rename_target(&old_path, &new_path, flags & libc::RENAME_NOREPLACE != 0) {
+            if cfg!(debug_assertions) {
+                eprintln!(
+                    "[RENAME] {:?} cannot replace {:?}: {}",
+                    old_path, new_path, errno
+                );
+            }
+            reply.error(errno);
+            return;
+        }
 
         let target_ino_opt = self.state.ino_of(&new_path);
-        match self.rt.block_on(self.api.rename(&old_rel, &new_rel)) {
+
+        // Editors save by writing a temp file and renaming it over the target: commit whatever
+        // is still buffered for the source first, so the backend rename moves the new content
+        if let Some(src_ino) = self.state.ino_of(&old_path) {
+            for (fh, tmp_path) in self.state.dirty_writes_of(src_ino) {
+                if let Err(e) = self.block_on(self.api.write_file(&old_rel, &tmp_path))
+                {
+                    if cfg!(debug_assertions) {
+                        eprintln!(
+                            "[RENAME] Failed to commit pending write of {:?} before rename: {}",
+                            old_path, e
+                        );
+                    }
+                    reply.error(errno_from_api(&e));
+                    return;
+                }
+                self.state.with_write_mut(fh, |tw| tw.dirty = false);
+                // The cached size is the one from before the writes, the new name is looked up again
+                self.state.remove_attr(&old_path);
+            }
+        }
+
+        match self.block_on(self.api.rename(&old_rel, &new_rel)) {
             Ok(_) => {
                 if cfg!(debug_assertions) {
                     println!("[RENAME] Renaming from {:?} to {:?}", old_path, new_path);
                 }
+                self.state.forget_negative(&new_path);
                 if let Some(ino) = self.state.ino_of(&old_path) {
                     self.state.remove_path(&old_path);
-                    if let Some(_target_ino) = target_ino_opt {
+                    if let Some(target_ino) = target_ino_opt {
+                        if target_ino != ino {
+                            self.state.discard_writes_of(target_ino);
+                        }
                         self.state.remove_attr(&new_path);
+                        // A replaced empty directory must not keep its listing
+                        self.state.remove_dir_cache(&new_path);
                     } else {
                         if cfg!(debug_assertions) {
                             eprintln!("[RENAME] ino of {:?} not found", new_path);
@@ -2031,13 +4267,28 @@ impl Filesystem for RemoteFs {
                     self.state.remove_dir_cache(&new_parent);
                 }
                 reply.ok();
-                return;
             }
             Err(e) => {
                 if cfg!(debug_assertions) {
                     eprintln!("[RENAME] {}", e);
                 }
-                reply.error(errno_from_anyhow(&e))
+                // The backend moved the entry only partly, or failed midway (after removing the
+                // destination, say): nothing cached about either name can be trusted, both are looked
+                // up again. Only a 4xx is a refusal that left both untouched
+                if e.is_incomplete_rename() || e.status().is_none_or(|s| s >= 500) {
+                    self.state.forget_negative(&new_path);
+                    self.state.remove_attr(&old_path);
+                    self.state.remove_attr(&new_path);
+                    self.state.remove_dir_cache(&old_parent);
+                    self.state.remove_dir_cache(&new_parent);
+                    // The kernel asks again too; its entries cannot be dropped while it holds both parents
+                    if let Some(n) = self.notifier.lock().unwrap().as_ref() {
+                        for ino in [&old_path, &new_path].into_iter().filter_map(|p| self.state.ino_of(p)) {
+                            let _ = n.inval_inode(ino, 0, 0);
+                        }
+                    }
+                }
+                reply.error(errno_from_api(&e))
             }
         }
     }
@@ -2052,9 +4303,17 @@ impl Filesystem for RemoteFs {
         _umask: u32,
         reply: ReplyEntry,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!("[MKDIR] Called for parent_ino: {}", parent);
         }
+        if Self::is_control_entry(parent, name) {
+            reply.error(libc::EPERM);
+            return;
+        }
         let parent_path = match self.path_of(parent) {
             Some(p) => p,
             None => {
@@ -2070,11 +4329,16 @@ impl Filesystem for RemoteFs {
         } else {
             parent_path.join(name)
         };
+        if self.path_too_long(&path) {
+            reply.error(libc::ENAMETOOLONG);
+            return;
+        }
 
-        let rel = Self::rel_for_db(&path);
+        let rel = canonical_rel(&path);
 
-        match self.rt.block_on(self.api.mkdir(&rel)) {
+        match self.block_on(self.api.mkdir(&rel)) {
             Ok(_) => {
+                self.state.forget_negative(&path);
                 if let Err(_e) = self.update_cache(&parent_path) {
                     if cfg!(debug_assertions) {
                         eprintln!("[MKDIR] Update cache failed for {:?}", parent_path);
@@ -2086,24 +4350,129 @@ impl Filesystem for RemoteFs {
                     if cfg!(debug_assertions) {
                         println!("[MKDIR] Attr of {:?} fond", path);
                     }
+                    self.state.add_lookup(attr.ino);
                     reply.entry(&self.state.cache_ttl, &attr, 0);
                 } else {
                     if cfg!(debug_assertions) {
                         println!("[MKDIR] Attr of {:?} not fond", path);
                     }
                     let attr = self.file_attr(&path, FileType::Directory, 64, None, 0o755, 2);
-                    self.state.set_attr(&path, attr.clone());
+                    self.state.set_attr(&path, attr);
+                    self.state.add_lookup(attr.ino);
                     reply.entry(&self.state.cache_ttl, &attr, 0);
-                    return;
                 }
             }
             Err(e) => {
                 if cfg!(debug_assertions) {
                     eprintln!("[MKDIR] {}", e);
                 }
-                let errno = errno_from_anyhow(&e);
+                let errno = errno_from_api(&e);
                 reply.error(errno);
-                return;
+            }
+        }
+    }
+
+    // Function that creates a symlink; the target is stored on the backend as given
+    fn symlink(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        link_name: &OsStr,
+        target: &Path,
+        reply: ReplyEntry,
+    ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
+        if cfg!(debug_assertions) {
+            println!("[SYMLINK] Called for parent_ino: {}, name: {:?}, target: {:?}", parent, link_name, target);
+        }
+        if Self::is_control_entry(parent, link_name) {
+            reply.error(libc::EPERM);
+            return;
+        }
+        let Some(parent_path) = self.path_of(parent) else {
+            reply.error(ENOENT);
+            return;
+        };
+        let path = parent_path.join(link_name);
+        if self.path_too_long(&path) {
+            reply.error(libc::ENAMETOOLONG);
+            return;
+        }
+        // The backend keeps targets as text
+        let Some(target) = target.to_str() else {
+            reply.error(libc::EINVAL);
+            return;
+        };
+        // With --symlink-policy strict a link leading out of the mount is not created
+        if !self.opts.symlink_policy.allows(&parent_path, Path::new(target)) {
+            reply.error(libc::EPERM);
+            return;
+        }
+        let rel = canonical_rel(&path);
+        if let Err(e) = self.block_on(self.api.symlink(target, &rel)) {
+            if cfg!(debug_assertions) {
+                eprintln!("[SYMLINK] {}", e);
+            }
+            reply.error(errno_from_api(&e));
+            return;
+        }
+        self.state.forget_negative(&path);
+        if let Err(e) = self.update_cache(&parent_path) {
+            if cfg!(debug_assertions) {
+                eprintln!("[SYMLINK] Update cache failed for {:?}: {:?}", parent_path, e);
+            }
+        }
+        let attr = match self.state.get_attr(&path) {
+            Some(attr) => attr,
+            None => {
+                let attr = self.file_attr(&path, FileType::Symlink, target.len() as u64, None, 0o777, 1);
+                self.state.set_attr(&path, attr);
+                attr
+            }
+        };
+        self.state.add_lookup(attr.ino);
+        reply.entry(&self.ttl_for(&path), &attr, 0);
+    }
+
+    // Function that returns the target of a symlink, taken from the listing when it carries it
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        if cfg!(debug_assertions) {
+            println!("[READLINK] Called for ino: {}", ino);
+        }
+        let Some(path) = self.path_of(ino) else {
+            reply.error(ENOENT);
+            return;
+        };
+        let listed = path.parent().and_then(|dir| {
+            let (entries, ts) = self.state.get_dir_cache(dir)?;
+            if !self.is_cache_valid(dir, ts) {
+                return None;
+            }
+            let name = path.file_name()?.to_str()?;
+            entries.into_iter().find(|de| de.name == name)?.symlink_target
+        });
+        let target = match listed {
+            Some(target) => Ok(target),
+            None => self.block_on(self.api.readlink(&canonical_rel(&path))),
+        };
+        // With --symlink-policy strict a link created on the backend leading out of the mount is not followed
+        let link_dir = path.parent().unwrap_or(Path::new(""));
+        match target {
+            Ok(target) if !self.opts.symlink_policy.allows(link_dir, Path::new(&target)) => {
+                if cfg!(debug_assertions) {
+                    println!("[READLINK] {:?} -> {} leaves the mount, refused", path, target);
+                }
+                reply.error(libc::EPERM);
+            }
+            Ok(target) => reply.data(target.as_bytes()),
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[READLINK] {}", e);
+                }
+                reply.error(errno_from_api(&e));
             }
         }
     }
@@ -2111,14 +4480,29 @@ impl Filesystem for RemoteFs {
     // Deletes a file from the filesystem
     fn unlink(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &std::ffi::OsStr,
         reply: ReplyEmpty,
     ) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!("[UNLINK] Called for {:?}", name);
         }
+        if parent == CONTROL_TRASH_INO {
+            match self.purge_trashed(name) {
+                Ok(()) => reply.ok(),
+                Err(errno) => reply.error(errno),
+            }
+            return;
+        }
+        if Self::is_control_entry(parent, name) {
+            reply.error(libc::EPERM);
+            return;
+        }
         let Some(parent_path) = self.path_of(parent) else {
             if cfg!(debug_assertions) {
                 eprintln!("[UNLINK] Path of {:?} not fount", parent);
@@ -2131,9 +4515,38 @@ impl Filesystem for RemoteFs {
         } else {
             parent_path.join(name)
         };
+        if self.sticky_denies(req.uid(), &parent_path, &path) {
+            if cfg!(debug_assertions) {
+                eprintln!(
+                    "[UNLINK] {:?} is in a sticky directory and not owned by uid {}",
+                    path,
+                    req.uid()
+                );
+            }
+            reply.error(libc::EPERM);
+            return;
+        }
+
+        // POSIX: an open file stays readable through its handles until the last one is closed
+        if self.opts.unlink_policy == UnlinkPolicy::Deferred {
+            let open_ino = self
+                .state
+                .ino_of(&path)
+                .filter(|ino| self.state.has_open_handles(*ino));
+            if let Some(ino) = open_ino {
+                if cfg!(debug_assertions) {
+                    println!("[UNLINK] {:?} is still open, deleting it on last close", path);
+                }
+                match self.hide_unlinked(ino, &parent_path, &path) {
+                    Ok(()) => reply.ok(),
+                    Err(e) => reply.error(errno_from_anyhow(&e)),
+                }
+                return;
+            }
+        }
 
-        let rel = Self::rel_for_db(&path);
-        match self.rt.block_on(self.api.delete(&rel)) {
+        let rel = canonical_rel(&path);
+        match self.delete_remote(&rel) {
             Ok(_) => {
                 self.clear_cache(Some(&path));
                 let _ = self.update_cache(&parent_path);
@@ -2142,24 +4555,37 @@ impl Filesystem for RemoteFs {
                     println!("[UNLINK] Succesfully unlinked {:?} ", name);
                 }
                 reply.ok();
-                return;
             }
             Err(e) => {
                 if cfg!(debug_assertions) {
                     eprintln!("[UNLINK] {}", e);
                 }
-                let errno = errno_from_anyhow(&e);
+                let errno = errno_from_api(&e);
                 reply.error(errno);
-                return;
             }
         }
     }
 
     // Removes an empty directory from the filesystem
-    fn rmdir(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+    fn rmdir(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        if self.read_only() {
+            reply.error(libc::EROFS);
+            return;
+        }
         if cfg!(debug_assertions) {
             println!("[RMDIR] Called for {:?}", name);
         }
+        if parent == CONTROL_TRASH_INO {
+            match self.purge_trashed(name) {
+                Ok(()) => reply.ok(),
+                Err(errno) => reply.error(errno),
+            }
+            return;
+        }
+        if Self::is_control_entry(parent, name) {
+            reply.error(libc::EPERM);
+            return;
+        }
         let parent_path = match self.path_of(parent) {
             Some(p) => p,
             None => {
@@ -2175,6 +4601,17 @@ impl Filesystem for RemoteFs {
         } else {
             parent_path.join(name)
         };
+        if self.sticky_denies(req.uid(), &parent_path, &path) {
+            if cfg!(debug_assertions) {
+                eprintln!(
+                    "[RMDIR] {:?} is in a sticky directory and not owned by uid {}",
+                    path,
+                    req.uid()
+                );
+            }
+            reply.error(libc::EPERM);
+            return;
+        }
         let is_dir = if let Some(attr) = self.state.get_attr(&path) {
             matches!(attr.kind, FileType::Directory)
         } else {
@@ -2218,8 +4655,8 @@ impl Filesystem for RemoteFs {
             }
         }
 
-        let rel = Self::rel_for_db(&path);
-        match self.rt.block_on(self.api.delete(&rel)) {
+        let rel = canonical_rel(&path);
+        match self.delete_remote(&rel) {
             Ok(_) => {
                 if cfg!(debug_assertions) {
                     println!("[RMDIR] {:?} deleted on backend", rel);
@@ -2231,15 +4668,13 @@ impl Filesystem for RemoteFs {
                     println!("[RMDIR] Cache updated for {:?}", rel);
                 }
                 reply.ok();
-                return;
             }
             Err(e) => {
                 if cfg!(debug_assertions) {
                     eprintln!("[RMDIR] {}", e);
                 }
-                let errno = errno_from_anyhow(&e);
+                let errno = errno_from_api(&e);
                 reply.error(errno);
-                return;
             }
         }
     }
@@ -2257,20 +4692,164 @@ pub fn is_mountpoint_busy(path: &str) -> bool {
     }
 }
 
-pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<()> {
-    let rt = Arc::new(Runtime::new()?);
-    let remote_fs = RemoteFs::new(api, rt.clone());
+// Function that starts the thread closing the websocket once no operation reached the mount for
+// `timeout` (--ws-idle-timeout); note_activity reconnects it
+fn spawn_ws_idle_watcher(state: Arc<FsState>, timeout: Duration) {
+    let tick = (timeout / 4).clamp(Duration::from_millis(100), Duration::from_secs(5));
+    thread::spawn(move || loop {
+        thread::sleep(tick);
+        if state.last_activity.lock().unwrap().elapsed() < timeout {
+            continue;
+        }
+        let Some(client) = state.ws_client.lock().unwrap().take() else {
+            continue;
+        };
+        if cfg!(debug_assertions) {
+            println!("[WS_IDLE] No activity for {:?}, closing the websocket", timeout);
+        }
+        state.ws_idle.store(true, Ordering::Relaxed);
+        let _ = client.disconnect();
+        state.ws_connected.store(false, Ordering::Relaxed);
+    });
+}
+
+// Function that starts the thread uploading the dirty tempfiles every COMMIT_INTERVAL (--commit-strategy interval)
+fn spawn_interval_committer(state: Arc<FsState>, api: FileApi, rt: Arc<Runtime>, opts: MountOptions) {
+    thread::spawn(move || loop {
+        thread::sleep(COMMIT_INTERVAL);
+        if crate::shutting_down() {
+            break;
+        }
+        commit_dirty_writes(&state, &api, &rt, &opts, true);
+    });
+}
+
+// Function that uploads every tempfile with unsent writes; the failed ones stay dirty, for the next
+// attempt once their backoff is over (all of them when `due_only` is false) or for cleanup_all_tempfiles
+fn commit_dirty_writes(state: &FsState, api: &FileApi, rt: &Runtime, opts: &MountOptions, due_only: bool) {
+    let _committing = state.commit_lock.lock().unwrap();
+    for (fh, ino, tmp_path) in state.take_dirty_writes(due_only) {
+        if state.is_handle_stale(fh) {
+            continue;
+        }
+        let Some(path) = state.path_of(ino) else {
+            continue;
+        };
+        let rel = canonical_rel(&path);
+        if let Err(e) = crate::block_on(rt, api.write_file(&rel, &tmp_path)) {
+            if cfg!(debug_assertions) {
+                eprintln!("[COMMIT] Commit failed for path: {:?}, error: {:?}", path, e);
+            }
+            // Left dirty, retried after the backoff or at close
+            if state.commit_failed(fh, opts) {
+                abandon_commit(state, opts, &rel, &tmp_path);
+            }
+        } else {
+            state.commit_succeeded(fh);
+        }
+    }
+}
+
+// Function that stops sending a file the backend keeps refusing: the data is copied to the recovery
+// directory, the handle keeps writing to its tempfile and close saves the final version there
+fn abandon_commit(state: &FsState, opts: &MountOptions, rel: &str, tmp_path: &Path) {
+    state.abandoned_commits.fetch_add(1, Ordering::Relaxed);
+    eprintln!(
+        "[WARN] ********** {} rifiutato dal backend {} volte di seguito: l'invio e' sospeso **********",
+        rel,
+        opts.commit_attempts()
+    );
+    match opts.recovery_dir.as_deref().map(|dir| crate::recovery::keep(dir, rel, tmp_path)) {
+        Some(Ok(dest)) => eprintln!("[WARN] I dati di {} sono stati salvati in {:?}", rel, dest),
+        Some(Err(e)) => eprintln!("[WARN] Impossibile salvare i dati di {} in --recovery-dir: {}", rel, e),
+        None => eprintln!(
+            "[WARN] Senza --recovery-dir i dati di {} restano solo nel file temporaneo {:?} e vanno persi alla chiusura",
+            rel, tmp_path
+        ),
+    }
+}
+
+pub fn mount_fs(
+    mountpoint: &str,
+    api: FileApi,
+    url: String,
+    opts: MountOptions,
+) -> anyhow::Result<()> {
+    mount_all(vec![MountSpec {
+        mountpoint: mountpoint.to_string(),
+        api,
+        url,
+        opts,
+    }])
+}
+
+// Function that mounts one filesystem on the shared runtime and starts its websocket listener
+// Function that builds the options the filesystem is mounted with
+fn mount_options(opts: &MountOptions, read_only: bool) -> Vec<MountOption> {
+    let name = opts.fs_name.as_deref().unwrap_or(DEFAULT_FS_NAME);
+    let mut options = vec![MountOption::FSName(name.to_string())];
+    if let Some(fs_type) = &opts.fs_type {
+        options.push(MountOption::Subtype(fs_type.clone()));
+    }
+    // Without DefaultPermissions the kernel does not check the mode bits, every operation reaches
+    // the backend and its 401/403 answers become EACCES
+    if !opts.backend_auth {
+        options.push(MountOption::DefaultPermissions);
+    }
+    if read_only {
+        options.push(MountOption::RO);
+    }
+    if let Some(max_read) = opts.max_read {
+        options.push(MountOption::CUSTOM(format!("max_read={}", max_read)));
+    }
+    options
+}
+
+fn spawn_mount(spec: MountSpec, rt: &Arc<Runtime>) -> anyhow::Result<BackgroundSession> {
+    let MountSpec {
+        mountpoint,
+        api,
+        url,
+        mut opts,
+    } = spec;
+    if let Err(msg) = crate::mountpoint::verify(&mountpoint, opts.force) {
+        crate::write_status(&msg);
+        return Err(anyhow::anyhow!(msg));
+    }
+    let read_only = opts.snapshot.is_some();
+    if opts.trash && !read_only && !rt.block_on(api.trash_supported()) {
+        eprintln!("[WARN] Il backend non supporta il cestino: con --trash le cancellazioni restano definitive");
+        opts.trash = false;
+    }
+    let handshake = api.handshake_headers();
+    let mut remote_fs = RemoteFs::new(api, rt.clone(), opts);
+    if let Some(threads) = remote_fs.opts.fuse_threads {
+        remote_fs.readers = Some(WorkerPool::new(threads, "remote-fs-read")?);
+    }
     let notifier_ptr = remote_fs.notifier.clone();
     let fs_state = remote_fs.state.clone();
+    if remote_fs.opts.commit_strategy == CommitStrategy::Interval && !read_only {
+        spawn_interval_committer(fs_state.clone(), remote_fs.api.clone(), rt.clone(), remote_fs.opts.clone());
+    }
+    if let (Some(timeout), false) = (remote_fs.opts.ws_idle_timeout, read_only) {
+        spawn_ws_idle_watcher(fs_state.clone(), timeout);
+    }
+    if let (Some(dir), true) = (&remote_fs.opts.recovery_dir, remote_fs.opts.recovery_retry) {
+        crate::recovery::spawn_retry(dir.clone(), remote_fs.api.clone(), rt.clone());
+    }
 
     remote_fs.init_cache();
-    let mp = mountpoint.to_string();
-    let options = vec![
-        MountOption::FSName("remote_fs".to_string()),
-        MountOption::DefaultPermissions,
-    ];
+    let options = mount_options(&remote_fs.opts, read_only);
 
-    let bg_session = spawn_mount2(remote_fs, &mp, &options).expect("Failed to mount filesystem");
+    let bg_session = match spawn_mount2(remote_fs, &mountpoint, &options) {
+        Ok(session) => session,
+        Err(e) => {
+            let msg = crate::mount_error::describe(&e, &mountpoint);
+            // In daemon mode stderr is lost, the status file keeps the reason
+            crate::write_status(&msg);
+            return Err(anyhow::anyhow!(msg));
+        }
+    };
 
     let notifier_actual = bg_session.notifier();
     {
@@ -2278,15 +4857,55 @@ pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<(
         *lock = Some(notifier_actual.clone());
     }
 
-    {
-        let url_clone = url.clone();
+    if !read_only {
         let notifier_for_ws = Arc::new(notifier_actual);
         rt.spawn(async move {
-            start_websocket_listener(&url_clone, notifier_for_ws, fs_state);
+            start_websocket_listener(&url, handshake, notifier_for_ws, fs_state);
         });
     }
+    Ok(bg_session)
+}
+
+// Function that drops the sessions that ended without a signal of ours: the kernel closed them,
+// as fusermount -u or umount -l from outside do. Returns true when none is left
+fn reap_ended_sessions(sessions: &mut Vec<(String, BackgroundSession)>) -> bool {
+    let (ended, alive): (Vec<_>, Vec<_>) = std::mem::take(sessions)
+        .into_iter()
+        .partition(|(_, session)| session.guard.is_finished());
+    *sessions = alive;
+    for (mp, session) in ended {
+        // The filesystem was dropped with the session: pending writes are already committed or saved
+        session.join();
+        let msg = format!("Filesystem {} smontato dall'esterno", mp);
+        eprintln!("[STOP] {}", msg);
+        crate::write_status(&msg);
+    }
+    sessions.is_empty()
+}
+
+// Function that mounts every filesystem on one runtime and unmounts them all on SIGINT/SIGTERM
+pub fn mount_all(specs: Vec<MountSpec>) -> anyhow::Result<()> {
+    let rt = Arc::new(crate::io_runtime(crate::io_threads_of(&specs))?);
+    let mut sessions: Vec<(String, BackgroundSession)> = Vec::new();
+    for spec in specs {
+        let mp = spec.mountpoint.clone();
+        match spawn_mount(spec, &rt) {
+            Ok(session) => {
+                println!("[INFO] Filesystem montato su {}.", mp);
+                sessions.push((mp, session));
+            }
+            Err(e) => {
+                // A failed mount leaves none of the others behind
+                for (_, session) in sessions {
+                    session.join();
+                }
+                crate::shutdown_runtime(rt);
+                return Err(e);
+            }
+        }
+    }
 
-    let mut signals = Signals::new(&[SIGINT, SIGTERM])?;
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
     let (tx, rx) = channel();
 
     thread::spawn(move || {
@@ -2295,39 +4914,158 @@ pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<(
         }
     });
 
-    println!("[INFO] Filesystem montato su {}. In attesa di segnali...", mp);
+    println!("[INFO] {} filesystem montati. In attesa di segnali...", sessions.len());
 
-    while let Ok(_) = rx.recv() {
+    loop {
+        match rx.recv_timeout(SESSION_POLL_INTERVAL) {
+            Ok(()) => {}
+            Err(RecvTimeoutError::Timeout) => {
+                if reap_ended_sessions(&mut sessions) {
+                    crate::shutdown_runtime(rt);
+                    return Err(anyhow::anyhow!("Every filesystem was unmounted from outside the client"));
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
         let mut success = false;
         let max_attempts = 3;
 
         for i in 1..=max_attempts {
-            if !is_mountpoint_busy(&mp) {
+            let busy: Vec<&str> = sessions
+                .iter()
+                .map(|(mp, _)| mp.as_str())
+                .filter(|mp| is_mountpoint_busy(mp))
+                .collect();
+            if busy.is_empty() {
                 success = true;
                 break;
             }
 
-            let msg = format!("EBUSY: Mountpoint occupato. Tentativo di chiusura {}/{}...", i, max_attempts);
+            let msg = format!(
+                "EBUSY: Mountpoint occupato ({}). Tentativo di chiusura {}/{}...",
+                busy.join(", "),
+                i,
+                max_attempts
+            );
             eprintln!("[STOP] {}", msg);
             crate::write_status(&msg);
-            
+
             if i < max_attempts {
                 thread::sleep(Duration::from_secs(2));
             }
         }
 
         if success {
-            println!("[STOP] Mountpoint libero. Smontaggio in corso...");
+            println!("[STOP] Mountpoint liberi. Smontaggio in corso...");
             crate::clear_status();
-            let _ = bg_session.join();
-            println!("[STOP] Filesystem smontato con successo.");
-            return Ok(()); 
+            for (mp, session) in sessions.drain(..) {
+                session.join();
+                println!("[STOP] Filesystem smontato con successo: {}", mp);
+            }
+            // Joining the sessions waited for the operations in flight, nothing needs the runtime now
+            crate::shutdown_runtime(rt);
+            return Ok(());
         } else {
             let err_msg = "ERRORE: Impossibile smontare (Busy). Il demone resta attivo. Chiudi i file aperti.";
             eprintln!("[STOP] {}", err_msg);
             crate::write_status(err_msg);
         }
     }
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn mount_options_carry_the_configured_name_and_type() {
+        let opts = MountOptions {
+            fs_name: Some("backup-nas".to_string()),
+            fs_type: Some("remotefs".to_string()),
+            ..Default::default()
+        };
+        let options = mount_options(&opts, false);
+        assert!(options.contains(&MountOption::FSName("backup-nas".to_string())));
+        assert!(options.contains(&MountOption::Subtype("remotefs".to_string())));
+        assert!(!options.contains(&MountOption::FSName(DEFAULT_FS_NAME.to_string())));
+    }
+
+    #[test]
+    fn mount_options_default_to_the_generic_name_without_subtype() {
+        let options = mount_options(&MountOptions::default(), true);
+        assert!(options.contains(&MountOption::FSName(DEFAULT_FS_NAME.to_string())));
+        assert!(!options.iter().any(|o| matches!(o, MountOption::Subtype(_))));
+        assert!(options.contains(&MountOption::RO));
+    }
+
+    #[test]
+    fn backend_auth_leaves_the_permission_checks_to_the_backend() {
+        assert!(mount_options(&MountOptions::default(), false).contains(&MountOption::DefaultPermissions));
+        let opts = MountOptions { backend_auth: true, ..Default::default() };
+        assert!(!mount_options(&opts, false).contains(&MountOption::DefaultPermissions));
+
+        // A sticky directory of uid 1000 holding a file of uid 1001: uid 1002 may not remove it locally
+        let sticky = |opts: MountOptions| {
+            let rt = Arc::new(crate::io_runtime(Some(1)).unwrap());
+            let fs = RemoteFs::new(FileApi::new("http://127.0.0.1:9"), rt, opts);
+            let mut dir = fs.file_attr(Path::new("/tmp"), FileType::Directory, 0, None, 0o1777, 2);
+            dir.uid = 1000;
+            fs.state.set_attr(Path::new("/tmp"), dir);
+            let mut file = fs.file_attr(Path::new("/tmp/a"), FileType::RegularFile, 1, None, 0o644, 1);
+            file.uid = 1001;
+            fs.state.set_attr(Path::new("/tmp/a"), file);
+            fs.sticky_denies(1002, Path::new("/tmp"), Path::new("/tmp/a"))
+        };
+        assert!(sticky(MountOptions::default()));
+        assert!(!sticky(MountOptions { backend_auth: true, ..Default::default() }));
+    }
+
+    #[test]
+    fn merging_two_spellings_leaves_one_entry() {
+        let rt = Arc::new(crate::io_runtime(Some(1)).unwrap());
+        let fs = RemoteFs::new(FileApi::new("http://127.0.0.1:9"), rt, MountOptions::default());
+        let st = &fs.state;
+        st.insert_path_mapping(Path::new("/docs"), 5);
+        st.insert_path_mapping(Path::new("/docs/a.txt"), 6);
+        st.insert_path_mapping(Path::new("/Docs/b.txt"), 7);
+        let attr = fs.file_attr(Path::new("/docs/a.txt"), FileType::RegularFile, 1, None, 0o644, 1);
+        st.set_attr(Path::new("/docs/a.txt"), attr);
+
+        st.merge_path(Path::new("/docs"), Path::new("/Docs"));
+        assert_eq!(st.ino_of(Path::new("/Docs")), Some(5));
+        assert_eq!(st.ino_of(Path::new("/Docs/a.txt")), Some(6));
+        assert_eq!(st.ino_of(Path::new("/Docs/b.txt")), Some(7));
+        assert_eq!(st.path_of(6), Some(PathBuf::from("/Docs/a.txt")));
+        assert_eq!(st.ino_of(Path::new("/docs")), None);
+        assert_eq!(st.ino_of(Path::new("/docs/a.txt")), None);
+        assert!(st.get_attr(Path::new("/docs/a.txt")).is_none());
+
+        // An inode already known under the backend's spelling keeps it, the other one resolves there too
+        st.insert_path_mapping(Path::new("/docs/b.txt"), 8);
+        st.merge_path(Path::new("/docs"), Path::new("/Docs"));
+        assert_eq!(st.ino_of(Path::new("/Docs/b.txt")), Some(7));
+        assert_eq!(st.path_of(8), Some(PathBuf::from("/Docs/b.txt")));
+    }
+
+    #[test]
+    fn listed_attributes_carry_the_backend_birth_time() {
+        let rt = Arc::new(crate::io_runtime(Some(1)).unwrap());
+        let fs = RemoteFs::new(FileApi::new("http://127.0.0.1:9"), rt, MountOptions::default());
+        let entry = |extra: &str| -> DirectoryEntry {
+            serde_json::from_str(&format!(
+                r#"{{"name":"a","size":1,"mtime":2000,"permissions":"644","is_dir":0,"version":1,"nlink":1{}}}"#,
+                extra
+            ))
+            .unwrap()
+        };
+        let attr = fs.entry_attr(Path::new("/a"), &entry(r#","crtime":1000,"crtime_ns":7"#));
+        assert_eq!(attr.crtime, UNIX_EPOCH + Duration::new(1000, 7));
+        assert_eq!(attr.mtime, UNIX_EPOCH + Duration::from_secs(2000));
+        let attr = fs.entry_attr(Path::new("/b"), &entry(""));
+        assert_eq!(attr.crtime, attr.mtime);
+    }
+}
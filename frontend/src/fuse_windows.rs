@@ -1,27 +1,33 @@
 use ctrlc;
-use rust_socketio::{ClientBuilder, Payload};
+use rust_socketio::{client::Client, ClientBuilder, Event};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
-use std::io::{self, Seek, Write};
+use std::future::Future;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::slice;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{ffi::c_void, ptr};
 use tokio::runtime::Runtime;
 use widestring::{U16CStr, U16CString};
 use windows_sys::Win32::Foundation::{
-    LocalFree, ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_FILE_NOT_FOUND,
-    ERROR_INVALID_PARAMETER, HLOCAL,
+    CloseHandle, LocalFree, ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS, ERROR_FILENAME_EXCED_RANGE,
+    ERROR_FILE_NOT_FOUND, ERROR_INVALID_PARAMETER, ERROR_NOT_A_REPARSE_POINT, ERROR_NOT_SAME_DEVICE,
+    ERROR_TOO_MANY_OPEN_FILES, ERROR_WRITE_PROTECT, HLOCAL,
 };
-use windows_sys::Win32::Security::Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW;
+use windows_sys::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW,
+};
+use windows_sys::Win32::Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER};
+use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 use windows_sys::Win32::Storage::FileSystem::{
-    DELETE, FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_READONLY,
-    FILE_WRITE_DATA,
+    DELETE, FILE_APPEND_DATA, FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_NORMAL,
+    FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_REPARSE_POINT, FILE_WRITE_DATA,
 };
 use winfsp::filesystem::DirInfo;
 use winfsp::filesystem::{DirMarker, FileInfo, FileSecurity, FileSystemContext, OpenFileInfo};
@@ -45,13 +51,69 @@ enum NodeType {
     RegularFile,
 }
 
-use crate::file_api::{DirectoryEntry, FileApi};
+use crate::api_error::{ApiError, ApiResult};
+use crate::cache_stats::CacheMetrics;
+use crate::file_api::{split_time, system_time, DirectoryEntry, EntryKind, FileApi};
+use crate::lru::LruMap;
+use crate::options::{CommitStrategy, ConflictPolicy, MountOptions, WindowsSymlinks, COMMIT_INTERVAL};
+use crate::overlay::Overlay;
+use crate::MountSpec;
+use crate::path::{canonical_rel, mount_path, parent_and_name, too_long, DEFAULT_PATH_MAX};
+use crate::win_link;
 const TTL: Duration = Duration::from_secs(1);
 
+// Virtual control directory exposed at the mount root, never forwarded to the backend
+const CONTROL_DIR: &str = "./.remotefs";
+const CONTROL_REFRESH: &str = "./.remotefs/refresh";
+const CONTROL_STATUS: &str = "./.remotefs/status";
+const CONTROL_DIR_INO: u64 = u64::MAX - 1;
+const CONTROL_REFRESH_INO: u64 = u64::MAX - 2;
+const CONTROL_STATUS_INO: u64 = u64::MAX - 3;
+
+// SID of the user running the mount, owner of every file like getuid() on unix
+static OWNER_SID: OnceLock<String> = OnceLock::new();
+
+fn owner_sid() -> &'static str {
+    OWNER_SID.get_or_init(|| query_user_sid().unwrap_or_else(|| "BA".to_string()))
+}
+
+// Function that reads the user SID from the process token, in SDDL string form
+fn query_user_sid() -> Option<String> {
+    unsafe {
+        let mut token = ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return None;
+        }
+        let mut len: u32 = 0;
+        GetTokenInformation(token, TokenUser, ptr::null_mut(), 0, &mut len);
+        // u64 buffer so that TOKEN_USER is correctly aligned
+        let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+        let ok = GetTokenInformation(token, TokenUser, buf.as_mut_ptr().cast(), len, &mut len);
+        CloseHandle(token);
+        if ok == 0 {
+            return None;
+        }
+        let user = &*(buf.as_ptr() as *const TOKEN_USER);
+        let mut sid_str: *mut u16 = ptr::null_mut();
+        if ConvertSidToStringSidW(user.User.Sid, &mut sid_str) == 0 {
+            return None;
+        }
+        let sid = U16CStr::from_ptr_str(sid_str).to_string_lossy();
+        LocalFree(sid_str as HLOCAL);
+        Some(sid)
+    }
+}
+
 #[derive(Clone)]
 pub struct TempWrite {
+    // Percorso con cui il file e' stato aperto, dove --conflict-policy invia i dati se il file sparisce
+    pub rel: String,
     pub tem_path: PathBuf,
     pub _size: u64,
+    // Held around the writes and resizes of the tempfile, shared by the handles open on the inode
+    pub io: Arc<Mutex<()>>,
+    // Set once the tempfile differs from the backend: close uploads only then
+    pub dirty: Arc<AtomicBool>,
 }
 
 #[derive(Clone, Debug)]
@@ -80,10 +142,10 @@ pub struct FsState {
     pub ino_by_path: Arc<Mutex<HashMap<PathBuf, u64>>>,
     /// Mappa inode -> path
     pub path_by_ino: Arc<Mutex<HashMap<u64, PathBuf>>>,
-    /// Cache degli attributi dei file
-    pub attr_cache: Arc<Mutex<HashMap<PathBuf, FileAttr>>>,
-    /// Cache delle directory (contenuto + timestamp)
-    pub dir_cache: Arc<Mutex<HashMap<PathBuf, (Vec<DirectoryEntry>, SystemTime)>>>,
+    /// Cache degli attributi dei file, al massimo --max-cache-entries voci usate di recente
+    pub attr_cache: Arc<Mutex<LruMap<PathBuf, FileAttr>>>,
+    /// Cache delle directory (contenuto + timestamp), con lo stesso limite
+    pub dir_cache: Arc<Mutex<LruMap<PathBuf, (Vec<DirectoryEntry>, SystemTime)>>>,
     /// File aperti in scrittura con temp file
     pub writes: Arc<Mutex<HashMap<u64, TempWrite>>>,
     /// Prossimo inode da allocare
@@ -92,10 +154,42 @@ pub struct FsState {
     pub cache_ttl: Duration,
     /// Set di inode già cancellati (per evitare doppie cancellazioni)
     pub already_deleted: Arc<Mutex<HashSet<u64>>>,
+    /// Client WebSocket attivo (per la riconnessione)
+    pub ws_client: Arc<Mutex<Option<Client>>>,
+    /// Stato della connessione WebSocket
+    pub ws_connected: Arc<AtomicBool>,
+    /// Tentativi di connessione WebSocket falliti
+    pub ws_failures: Arc<AtomicU64>,
+    /// Ultimo errore di connessione WebSocket, riportato dal file di stato
+    pub ws_last_error: Arc<Mutex<Option<String>>>,
+    /// Contatori hit/miss della cache per directory
+    pub cache_metrics: Arc<CacheMetrics>,
+    /// Security descriptor per path, con permessi e tipo da cui sono stati generati
+    pub sd_cache: Arc<Mutex<HashMap<PathBuf, (u16, bool, Vec<u8>)>>>,
+    /// Inode scritti dopo l'ultimo commit periodico (--commit-strategy interval)
+    pub dirty_inos: Arc<Mutex<HashSet<u64>>>,
+    /// Commit periodici rifiutati di seguito per inode, con l'istante da cui si può riprovare
+    pub commit_failures: Arc<Mutex<HashMap<u64, (usize, Instant)>>>,
+    /// Inode abbandonati dopo --commit-attempts fallimenti: i dati sono nella cartella di recupero
+    pub abandoned_inos: Arc<Mutex<HashSet<u64>>>,
+    /// Numero di file abbandonati, riportato dal file di stato
+    pub abandoned_commits: Arc<AtomicU64>,
+    /// Tenuto durante il commit periodico, così close non rimuove un temp file in upload
+    pub commit_lock: Arc<Mutex<()>>,
+    /// Incrementata a ogni svuotamento completo: i risultati letti con una generazione precedente non entrano in cache
+    pub cache_generation: Arc<AtomicU64>,
+    /// Un lock per ogni percorso in creazione, così due create dello stesso nome non committano entrambe
+    pub create_locks: Arc<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>>,
+    /// --fixed-mtime, applicato anche ai metadati inviati dal websocket
+    pub fixed_mtime: Option<SystemTime>,
+    pub dir_size_from_backend: bool,
+    pub overlay: Option<Arc<Overlay>>,
 }
 
 impl FsState {
-    pub fn new() -> Self {
+    // Gli inode restano mappati anche quando i loro attributi escono dalla cache: WinFsp non dice
+    // quando il kernel smette di usarli
+    pub fn new(max_cache_entries: usize) -> Self {
         let mut ino_by_path = HashMap::new();
         let mut path_by_ino = HashMap::new();
         ino_by_path.insert(PathBuf::from("."), 1);
@@ -104,12 +198,45 @@ impl FsState {
         Self {
             ino_by_path: Arc::new(Mutex::new(ino_by_path)),
             path_by_ino: Arc::new(Mutex::new(path_by_ino)),
-            attr_cache: Arc::new(Mutex::new(HashMap::new())),
-            dir_cache: Arc::new(Mutex::new(HashMap::new())),
+            attr_cache: Arc::new(Mutex::new(LruMap::new(max_cache_entries))),
+            dir_cache: Arc::new(Mutex::new(LruMap::new(max_cache_entries))),
             writes: Arc::new(Mutex::new(HashMap::new())),
             next_ino: Arc::new(Mutex::new(2)),
             already_deleted: Arc::new(Mutex::new(HashSet::new())),
             cache_ttl: TTL,
+            ws_client: Arc::new(Mutex::new(None)),
+            ws_connected: Arc::new(AtomicBool::new(false)),
+            ws_failures: Arc::new(AtomicU64::new(0)),
+            ws_last_error: Arc::new(Mutex::new(None)),
+            cache_metrics: Arc::new(CacheMetrics::new(max_cache_entries)),
+            sd_cache: Arc::new(Mutex::new(HashMap::new())),
+            dirty_inos: Arc::new(Mutex::new(HashSet::new())),
+            commit_failures: Arc::new(Mutex::new(HashMap::new())),
+            abandoned_inos: Arc::new(Mutex::new(HashSet::new())),
+            abandoned_commits: Arc::new(AtomicU64::new(0)),
+            commit_lock: Arc::new(Mutex::new(())),
+            cache_generation: Arc::new(AtomicU64::new(0)),
+            create_locks: Arc::new(Mutex::new(HashMap::new())),
+            fixed_mtime: None,
+            dir_size_from_backend: false,
+            overlay: None,
+        }
+    }
+
+    pub fn create_lock(&self, path: &Path) -> Arc<Mutex<()>> {
+        self.create_locks
+            .lock()
+            .unwrap()
+            .entry(path.to_path_buf())
+            .or_default()
+            .clone()
+    }
+
+    // Rimuove il lock di `path` quando nessun'altra create lo tiene o lo aspetta
+    pub fn release_create_lock(&self, path: &Path) {
+        let mut locks = self.create_locks.lock().unwrap();
+        if locks.get(path).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(path);
         }
     }
 
@@ -179,22 +306,51 @@ impl FsState {
     }
 
     // ---- CACHE DIRECTORY ----
-    pub fn set_dir_cache(&self, path: &Path, data: (Vec<DirectoryEntry>, SystemTime)) {
-        self.dir_cache
-            .lock()
-            .unwrap()
-            .insert(path.to_path_buf(), data);
-    }
 
     pub fn remove_dir_cache(&self, path: &Path) {
         self.dir_cache.lock().unwrap().remove(path);
     }
 
+    // ---- CACHE GENERATION ----
+
+    pub fn generation(&self) -> u64 {
+        self.cache_generation.load(Ordering::Acquire)
+    }
+
+    // Salva gli attributi letti con `generation`, a meno che la cache sia stata svuotata nel frattempo
+    pub fn set_attr_if_current(&self, path: &Path, attr: FileAttr, generation: u64) -> bool {
+        let mut cache = self.attr_cache.lock().unwrap();
+        if self.generation() != generation {
+            return false;
+        }
+        cache.insert(path.to_path_buf(), attr);
+        true
+    }
+
+    // Salva un listing letto con `generation`, a meno che la cache sia stata svuotata nel frattempo
+    pub fn set_dir_cache_if_current(
+        &self,
+        path: &Path,
+        data: (Vec<DirectoryEntry>, SystemTime),
+        generation: u64,
+    ) -> bool {
+        let mut cache = self.dir_cache.lock().unwrap();
+        if self.generation() != generation {
+            return false;
+        }
+        cache.insert(path.to_path_buf(), data);
+        true
+    }
+
     // ---- CLEAR CACHE ----
 
+    // La generazione cambia con entrambe le cache bloccate, così nessun risultato vecchio arriva dopo lo svuotamento
     pub fn clear_all_cache(&self) {
-        self.attr_cache.lock().unwrap().clear();
-        self.dir_cache.lock().unwrap().clear();
+        let mut attrs = self.attr_cache.lock().unwrap();
+        let mut dirs = self.dir_cache.lock().unwrap();
+        self.cache_generation.fetch_add(1, Ordering::AcqRel);
+        attrs.clear();
+        dirs.clear();
     }
 
     // ---- DELETED TRACKING ----
@@ -202,6 +358,27 @@ impl FsState {
     pub fn mark_deleted(&self, ino: u64) {
         self.already_deleted.lock().unwrap().insert(ino);
     }
+
+    /// Vero se l'inode è stato cancellato o spostato da remoto: gli handle aperti su di esso sono stale
+    /// Registra una connessione WebSocket fallita: da qui in poi la coerenza dipende solo dal TTL della cache
+    pub fn record_ws_failure(&self, url: &str, err: &str) {
+        self.ws_connected.store(false, Ordering::Relaxed);
+        self.ws_failures.fetch_add(1, Ordering::Relaxed);
+        *self.ws_last_error.lock().unwrap() = Some(err.to_string());
+        eprintln!(
+            "[WARN] Connessione WebSocket a {} fallita: {}. Aggiornamenti in tempo reale non attivi, la cache scade solo col TTL.",
+            url, err
+        );
+    }
+
+    pub fn is_deleted(&self, ino: u64) -> bool {
+        self.already_deleted.lock().unwrap().contains(&ino)
+    }
+
+    /// Numero di file attualmente aperti in scrittura
+    pub fn open_writes(&self) -> usize {
+        self.writes.lock().unwrap().len()
+    }
 }
 
 struct RemoteFs {
@@ -211,16 +388,26 @@ struct RemoteFs {
     api: FileApi,
     /// Runtime Tokio per operazioni async
     rt: Arc<Runtime>,
+    /// Opzioni passate da riga di comando
+    opts: MountOptions,
+    /// Cartella dei temp file degli handle aperti, vedi temp_dir::choose
+    temp_dir: PathBuf,
 }
 
 const CREATE_DIRECTORY: u32 = 0x00000001;
 
 impl RemoteFs {
-    fn new(api: FileApi, rt: Arc<Runtime>) -> Self {
+    fn new(api: FileApi, rt: Arc<Runtime>, opts: MountOptions) -> Self {
+        let mut state = FsState::new(opts.max_cache_entries());
+        state.fixed_mtime = api.fixed_mtime();
+        state.dir_size_from_backend = api.dir_size_from_backend();
+        state.overlay = api.overlay();
         Self {
-            state: Arc::new(FsState::new()),
+            state: Arc::new(state),
             api,
             rt,
+            temp_dir: crate::temp_dir::choose(opts.temp_dir.as_deref()),
+            opts,
         }
     }
 
@@ -249,22 +436,26 @@ impl RemoteFs {
         self.state.set_attr(&path, attr);
     }
 
-    pub fn insert_dir_cache(&self, path: PathBuf, data: (Vec<DirectoryEntry>, SystemTime)) {
-        self.state.set_dir_cache(&path, data);
-    }
-
     pub fn update_cache(&self, dir: &Path) -> anyhow::Result<()> {
-        let rel = Self::rel_of(dir);
+        let rel = canonical_rel(dir);
         let parent_key = PathBuf::from(rel.clone());
 
-        let list = self.rt.block_on(self.api.ls(&rel))?;
+        let generation = self.state.generation();
+        let list = self.block_on(self.api.ls(&rel))?;
 
+        if !self
+            .state
+            .set_dir_cache_if_current(&parent_key, (list.clone(), SystemTime::now()), generation)
         {
-            let mut dircache = self.state.dir_cache.lock().unwrap();
-            dircache.insert(parent_key.clone(), (list.clone(), SystemTime::now()));
+            if cfg!(debug_assertions) {
+            println!("[UPDATE CACHE] cache svuotata durante il listing di '{}', risultato scartato", rel);}
+            return Ok(());
         }
-        let dir_meta = self.rt.block_on(self.api.get_update_metadata(&rel))?;
+        let dir_meta = self.block_on(self.api.get_update_metadata(&rel))?;
         let mut attrcache = self.state.attr_cache.lock().unwrap();
+        if self.state.generation() != generation {
+            return Ok(());
+        }
         for de in &list {
             let child = if rel == "." || rel.is_empty() {
                 PathBuf::from(format!("./{}", de.name))
@@ -273,7 +464,7 @@ impl RemoteFs {
                 PathBuf::from(format!("./{}/{}", r, de.name))
             };
 
-            if !attrcache.contains_key(&child) {
+            if attrcache.peek(&child).is_none() {
                 if cfg!(debug_assertions) {
                 println!("[UPDATE CACHE] aggiornamento attr cache miss");}
                 let isdir = Self::is_dir(&de);
@@ -289,7 +480,7 @@ impl RemoteFs {
                     dir_meta.size.max(0) as u64
                 };
                 let nlink = 1;
-                let attr = self.file_attr(&child, ty, size, Some(de.mtime), perm, nlink);
+                let attr = self.file_attr(&child, ty, size, Some(de.mtime_time()), perm, nlink);
                 if cfg!(debug_assertions) {
                 println!(
                     "[INSERT ATTR CACHE/Update] (path , attr) : ({:?}, {:?}) ",
@@ -302,6 +493,32 @@ impl RemoteFs {
         Ok(())
     }
 
+    // Function that returns the security descriptor of `rel`, generating it again only when its permissions change
+    fn security_descriptor(&self, rel: &str, perm: u16, is_dir: bool) -> Vec<u8> {
+        let key = PathBuf::from(rel);
+        if let Some((p, d, sd)) = self.state.sd_cache.lock().unwrap().get(&key) {
+            if *p == perm && *d == is_dir {
+                return sd.clone();
+            }
+        }
+        let sddl = crate::acl::sddl_from_mode(perm, is_dir, owner_sid());
+        if cfg!(debug_assertions) {
+            println!("[SECURITY] '{}' perm={:#o} -> {}", rel, perm, sddl);
+        }
+        let sd = Self::sd_from_sddl(&sddl).unwrap_or_else(|_| {
+            if cfg!(debug_assertions) {
+                eprintln!("[SECURITY] WARN: sd_from_sddl failed for '{}', using empty SD", sddl);
+            }
+            Vec::new()
+        });
+        self.state
+            .sd_cache
+            .lock()
+            .unwrap()
+            .insert(key, (perm, is_dir, sd.clone()));
+        sd
+    }
+
     fn sd_from_sddl(sddl: &str) -> anyhow::Result<Vec<u8>> {
         let sddl_u16 = U16CString::from_str(sddl)?;
         let mut sd_ptr: *mut c_void = ptr::null_mut();
@@ -325,53 +542,17 @@ impl RemoteFs {
         Ok(bytes)
     }
 
-    fn rel_of(path: &Path) -> String {
-        let mut s = path.to_string_lossy().replace('\\', "/");
-        if s.is_empty() || s == "/" {
-            return ".".to_string();
-        }
-        
-
-        if s.starts_with('.') {
-            while s.starts_with("././") {
-                s = format!("./{}", &s[4..]);
-            }
-            if s == "./" || s == "./." {
-                return ".".to_string();
-            }
-            if s.ends_with("/.") {
-                s.truncate(s.len() - 2);
-                if s.is_empty() || s == "./" {
-                    return ".".to_string();
-                }
-            }
-            return s;
-        }
-
-        if s.starts_with('/') {
-            s = s.trim_start_matches('/').to_string();
-        }
-
-        if s.is_empty() {
-            ".".to_string()
-        } else {
-            format!("./{}", s)
-        }
-    }
-
     fn file_attr(
         &self,
         path: &Path,
         ty: NodeType,
         size: u64,
-        mtime: Option<i64>,
+        mtime: Option<SystemTime>,
         perm: u16,
         nlink: u32,
     ) -> FileAttr {
         let now = SystemTime::now();
-        let mtime_st = mtime
-            .and_then(|sec| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(sec as u64)))
-            .unwrap_or(now);
+        let mtime_st = mtime.or(self.api.fixed_mtime()).unwrap_or(now);
         let uid = 0u32;
         let gid = 0u32;
 
@@ -398,15 +579,72 @@ impl RemoteFs {
         u16::from_str_radix(&permissions, 8).unwrap_or(0)
     }
 
+    // Windows non ha fifo, socket o device: queste voci vengono mostrate come file regolari
     fn is_dir(de: &DirectoryEntry) -> bool {
-        if de.is_dir == 1 {
-            return true;
+        de.entry_kind() == EntryKind::Dir
+    }
+
+    // Con --symlink-policy strict i link che portano fuori dal mount non vengono mostrati,
+    // altrimenti aprirli come file ne seguirebbe il target
+    fn link_escapes(&self, rel: &str, de: &DirectoryEntry) -> bool {
+        de.symlink_target.as_deref().is_some_and(|target| {
+            !self.opts.symlink_policy.allows(Path::new(rel), Path::new(target))
+        })
+    }
+
+    // Una voce come la vede Windows: i link che escono dal mount sono tolti, gli altri presentati
+    // secondo --windows-symlinks
+    fn presented(&self, rel: &str, de: DirectoryEntry) -> Option<DirectoryEntry> {
+        if self.link_escapes(rel, &de) {
+            return None;
+        }
+        win_link::present(self.opts.windows_symlinks, de)
+    }
+
+    // La voce di `rel` nel listing del genitore, dopo la presentazione dei link
+    fn listed_entry(&self, rel: &str) -> Option<DirectoryEntry> {
+        let (parent, name) = parent_and_name(rel);
+        self.dir_entries(Path::new(&parent))
+            .ok()?
+            .into_iter()
+            .map(|(_, de)| de)
+            .find(|de| de.name == name)
+    }
+
+    // Il target di `rel` quando e' un link presentato come reparse point, con --windows-symlinks reparse
+    fn reparse_target(&self, rel: &str) -> Option<String> {
+        if self.opts.windows_symlinks != WindowsSymlinks::Reparse {
+            return None;
+        }
+        let de = self.listed_entry(rel)?;
+        (de.entry_kind() == EntryKind::Symlink).then_some(de.symlink_target).flatten()
+    }
+
+    // Il contenuto di `rel` quando e' la scorciatoia .lnk di un link, che sul backend non esiste
+    fn shortcut_data(&self, rel: &str) -> Option<Vec<u8>> {
+        if self.opts.windows_symlinks != WindowsSymlinks::Shortcut {
+            return None;
+        }
+        let de = self.listed_entry(rel)?;
+        win_link::is_shortcut(&de).then(|| win_link::shortcut(de.symlink_target.as_deref().unwrap_or_default()))
+    }
+
+    // Copia in `buffer` il REPARSE_DATA_BUFFER del link a `target`; senza buffer ne restituisce solo la dimensione
+    fn copy_reparse_data(target: &str, buffer: Option<&mut [u8]>) -> WinFspResult<u64> {
+        let data = win_link::reparse_data(target);
+        if let Some(buffer) = buffer {
+            if buffer.len() < data.len() {
+                return Err(FspError::WIN32(
+                    windows_sys::Win32::Foundation::ERROR_INSUFFICIENT_BUFFER,
+                ));
+            }
+            buffer[..data.len()].copy_from_slice(&data);
         }
-        return false;
+        Ok(data.len() as u64)
     }
 
     fn dir_entries(&self, dir: &Path) -> WinFspResult<Vec<(PathBuf, DirectoryEntry)>> {
-        let rel = Self::rel_of(dir);
+        let rel = canonical_rel(dir);
         if cfg!(debug_assertions) {
         println!("[DEBUG] dir_entries(): chiamata backend -> rel='{}'", rel);}
         if let Some((entries, ts)) = self
@@ -420,10 +658,11 @@ impl RemoteFs {
             if SystemTime::now()
                 .duration_since(ts)
                 .unwrap_or(Duration::ZERO)
-                < self.state.cache_ttl
+                < self.ttl_for(&rel)
             {
+                self.state.cache_metrics.record_hit(Path::new(&rel));
                 let mut out = Vec::with_capacity(entries.len());
-                for de in entries {
+                for de in entries.into_iter().filter_map(|de| self.presented(&rel, de)) {
                     let child_str = if rel == "." || rel.is_empty() {
                         format!("./{}", de.name)
                     } else {
@@ -440,18 +679,23 @@ impl RemoteFs {
                             NodeType::RegularFile
                         };
                         let perm = Self::parse_perm(&de.permissions);
-                        let size = if is_dir { 0 } else { de.size.max(0) as u64 };
-                        let attr =
-                            self.file_attr(&child, ty, size, Some(de.mtime), perm, de.nlink as u32);
+                        let size = de.size.max(0) as u64;
+                        let mut attr =
+                            self.file_attr(&child, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
+                        attr.blocks = de.blocks();
                         self.insert_attr_cache(child.clone(), attr);
                     }
                     out.push((child, de));
                 }
                 return Ok(out);
             }
+            self.state.cache_metrics.record_miss(Path::new(&rel), Some(ts));
+        } else {
+            self.state.cache_metrics.record_miss(Path::new(&rel), None);
         }
 
-        let list_res = self.rt.block_on(self.api.ls(&rel));
+        let generation = self.state.generation();
+        let list_res = self.block_on(self.api.ls(&rel));
 
         match &list_res {
             Ok(list) => {
@@ -476,7 +720,14 @@ impl RemoteFs {
             FspError::from(io_err)
         })?;
 
-        self.insert_dir_cache(PathBuf::from(&rel), (list.clone(), SystemTime::now()));
+        // Un listing iniziato prima di uno svuotamento completo viene restituito ma non salvato
+        if !self
+            .state
+            .set_dir_cache_if_current(&PathBuf::from(&rel), (list.clone(), SystemTime::now()), generation)
+            && cfg!(debug_assertions)
+        {
+            println!("[DIR_ENTRIES] cache svuotata durante il listing di '{}', risultato non salvato", rel);
+        }
 
         let mut out = Vec::with_capacity(list.len());
         if cfg!(debug_assertions) {
@@ -485,7 +736,7 @@ impl RemoteFs {
             rel
         );}
 
-        for de in list {
+        for de in list.into_iter().filter_map(|de| self.presented(&rel, de)) {
             let child_str = if rel == "." || rel.is_empty() {
                 format!("./{}", de.name)
             } else {
@@ -501,16 +752,15 @@ impl RemoteFs {
                 NodeType::RegularFile
             };
             let perm = Self::parse_perm(&de.permissions);
-            let size = if is_dir { 0 } else { de.size.max(0) as u64 };
-            let attr = self.file_attr(&child, ty, size, Some(de.mtime), perm, de.nlink as u32);
-            self.insert_attr_cache(child.clone(), attr);
+            let size = de.size.max(0) as u64;
+            let mut attr = self.file_attr(&child, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
+            attr.blocks = de.blocks();
+            self.state.set_attr_if_current(&child, attr, generation);
             out.push((child, de))
         }
 
-        let rel_db_parent = Self::rel_of(dir);
-        let de = self
-            .rt
-            .block_on(self.api.get_update_metadata(&rel_db_parent))
+        let rel_db_parent = canonical_rel(dir);
+        let de = self.block_on(self.api.get_update_metadata(&rel_db_parent))
             .unwrap();
 
         if let Some(mut parent_attr) = self.state.get_attr(dir) {
@@ -522,8 +772,8 @@ impl RemoteFs {
             }
             parent_attr.nlink = de.nlink as u32;
             parent_attr.size = de.size as u64;
-            parent_attr.mtime = UNIX_EPOCH + Duration::from_secs(de.mtime as u64);
-            self.state.set_attr(dir, parent_attr);
+            parent_attr.mtime = de.mtime_time();
+            self.state.set_attr_if_current(dir, parent_attr, generation);
         } else {
             if cfg!(debug_assertions) {
                 println!(
@@ -535,11 +785,11 @@ impl RemoteFs {
                 dir,
                 NodeType::Directory,
                 de.size as u64,
-                Some(de.mtime),
+                Some(de.mtime_time()),
                 0o755,
                 de.nlink as u32,
             );
-            self.state.set_attr(dir, attr);
+            self.state.set_attr_if_current(dir, attr, generation);
         }
 
         Ok(out)
@@ -550,75 +800,15 @@ impl RemoteFs {
         if cfg!(debug_assertions) {
         println!("[DEBUG] path_from_u16 RAW input: '{}'", raw);}
 
-        let mut s = raw;
-
-        if s.contains('\\') {
-            s = s.replace('\\', "/");
-        }
-        while s.contains("//") {
-            s = s.replace("//", "/");
-        }
-
-        if s.is_empty() {
-            return "/".to_string();
-        }
-        if !s.starts_with('/') {
-            s = format!("/{}", s);
-        }
-        if s.len() > 1 && s.ends_with('/') {
-            s.truncate(s.len() - 1);
-        }
-
-        s
+        mount_path(Path::new(&raw)).to_string_lossy().to_string()
     }
 
     fn get_temporary_path(&self, ino: u64) -> PathBuf {
-        let mut p = std::env::temp_dir();
+        let mut p = self.temp_dir.clone();
         p.push(format!("remotefs_tmp_{}.bin", ino));
         p
     }
 
-    fn backend_entry_exists(&self, path: &str) -> bool {
-        let rel_path = Path::new(path);
-        let parent_rel = rel_path
-            .parent()
-            .map(|pp| pp.to_string_lossy().to_string())
-            .filter(|s| !s.is_empty())
-            .unwrap_or_else(|| ".".to_string());
-        let name = rel_path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_default();
-if cfg!(debug_assertions) {
-        println!(
-            "[DEBUG] backend_entry_exists: rel='{}' -> parent_rel='{}' name='{}'",
-            path, parent_rel, name
-        );}
-
-        match self.rt.block_on(self.api.ls(&parent_rel)) {
-            Ok(list) => {
-                let exists = list.iter().any(|de| de.name == name);
-                if cfg!(debug_assertions) {
-                println!(
-                    "[DEBUG] backend_entry_exists: parent='{}' found={} entries=[{}] exists={}",
-                    parent_rel,
-                    list.len(),
-                    list.iter()
-                        .map(|d| d.name.clone())
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    exists
-                );}
-                exists
-            }
-            Err(e) => {
-                if cfg!(debug_assertions) {
-                eprintln!("[DEBUG] backend_entry_exists: backend error: {}", e);}
-                false
-            }
-        }
-    }
-
     fn nt_time_from_system_time(t: SystemTime) -> u64 {
         const SECS_BETWEEN_EPOCHS: u64 = 11644473600;
         const HUNDRED_NS_PER_SEC: u64 = 10_000_000;
@@ -642,6 +832,24 @@ if cfg!(debug_assertions) {
             }
         }
         self.state.attr_cache.lock().unwrap().remove(&path_buf);
+        self.state.sd_cache.lock().unwrap().remove(&path_buf);
+    }
+
+    // Scarta attributi e listing toccati da un rename fallito, cosi' vengono riletti dal backend
+    fn drop_rename_caches(&self, src_rel: &str, dst_rel: &str, src_parent: &Path, dst_parent: &Path) {
+        self.state.remove_attr(Path::new(src_rel));
+        self.state.remove_attr(Path::new(dst_rel));
+        self.state.sd_cache.lock().unwrap().remove(Path::new(src_rel));
+        self.state.sd_cache.lock().unwrap().remove(Path::new(dst_rel));
+        self.state.remove_dir_cache(src_parent);
+        self.state.remove_dir_cache(dst_parent);
+    }
+
+    // Dice se il backend ha comunque spostato il sorgente nella destinazione
+    fn rename_landed(&self, src_rel: &str, dst_rel: &str) -> bool {
+        let src_exists = self.block_on(self.api.exists(src_rel));
+        let dst_exists = self.block_on(self.api.exists(dst_rel));
+        matches!((src_exists, dst_exists), (Ok(false), Ok(true)))
     }
 
     fn can_delete(&self, _file_context: &MyFileContext, rel: String) -> WinFspResult<()> {
@@ -671,7 +879,7 @@ if cfg!(debug_assertions) {
             "[CAN_DELETE] parent_rel='{}', name_only='{}'",
             parent_rel, name_only
         );}
-        let list = match self.rt.block_on(self.api.ls(&parent_rel)) {
+        let list = match self.block_on(self.api.ls(&parent_rel)) {
             Ok(v) => {
                 if cfg!(debug_assertions) {
                 println!(
@@ -722,7 +930,7 @@ if cfg!(debug_assertions) {
         if is_dir {
             if cfg!(debug_assertions) {
             println!("[CAN_DELETE] directory case -> check emptiness for RemoveDirectory");}
-            let children = match self.rt.block_on(self.api.ls(&rel)) {
+            let children = match self.block_on(self.api.ls(&rel)) {
                 Ok(v) => {
                     if cfg!(debug_assertions) {
                     println!(
@@ -764,18 +972,288 @@ if cfg!(debug_assertions) {
         Ok(())
     }
 
-    fn split_parent_name(rel: &str) -> (String, String) {
-        let p = Path::new(rel);
-        let parent = p
-            .parent()
-            .map(|x| x.to_string_lossy().to_string())
-            .filter(|s| !s.is_empty())
-            .unwrap_or_else(|| ".".to_string());
-        let name = p
-            .file_name()
-            .map(|x| x.to_string_lossy().to_string())
-            .unwrap_or_default();
-        (parent, name)
+    fn is_control_rel(rel: &str) -> bool {
+        rel.strip_prefix(CONTROL_DIR)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    fn control_file_info(ino: u64, fi: &mut FileInfo) {
+        let now = RemoteFs::nt_time_from_system_time(SystemTime::now());
+        fi.file_attributes = if ino == CONTROL_DIR_INO {
+            FILE_ATTRIBUTE_DIRECTORY
+        } else if ino == CONTROL_STATUS_INO {
+            FILE_ATTRIBUTE_READONLY
+        } else {
+            FILE_ATTRIBUTE_NORMAL
+        };
+        fi.file_size = 0;
+        fi.allocation_size = 0;
+        fi.creation_time = now;
+        fi.last_access_time = now;
+        fi.last_write_time = now;
+        fi.change_time = now;
+        fi.index_number = ino;
+    }
+
+    fn control_dir_entry(name: &str, permissions: &str) -> DirectoryEntry {
+        let (secs, nanos) = split_time(SystemTime::now());
+        DirectoryEntry {
+            name: name.to_string(),
+            path: None,
+            size: 0,
+            mtime: secs as i64,
+            mtime_ns: nanos as i64,
+            permissions: permissions.to_string(),
+            is_dir: 0,
+            version: 0,
+            nlink: 1,
+            kind: None,
+            symlink_target: None,
+            partial: false,
+            crtime: None,
+            crtime_ns: 0,
+            stored_size: None,
+        }
+    }
+
+    // Restituisce il TTL di un percorso canonico: quello della --cache-rule che lo riguarda, altrimenti quello globale
+    fn ttl_for(&self, rel: &str) -> Duration {
+        self.opts.cache_ttl_for(rel).unwrap_or(self.state.cache_ttl)
+    }
+
+    // Crea il file `path_str` tenendo il lock di creazione del nome, vedi create
+    fn create_file(
+        &self,
+        path_str: &str,
+        rel: &str,
+        parent_path: &Path,
+        nt_time: u64,
+        file_info: &mut OpenFileInfo,
+    ) -> WinFspResult<MyFileContext> {
+        let fi = file_info.as_mut();
+        if self.write_handles_exhausted() {
+            if cfg!(debug_assertions) {
+                eprintln!("[CREATE] Too many files open for writing, refusing '{}'", rel);
+            }
+            return Err(FspError::WIN32(ERROR_TOO_MANY_OPEN_FILES));
+        }
+
+        let ino = self.alloc_ino(Path::new(path_str));
+        if cfg!(debug_assertions) {
+        println!("[CREATE] file ino: {:?}", ino);}
+        // La creazione sul backend e' atomica: se il nome esiste gia' risponde 409
+        match self.block_on(self.api.create_exclusive(rel)) {
+            Ok(_) => {
+                let desired_mode: u32 = 0o644;
+                let temp_path = self.get_temporary_path(ino);
+                if let Err(e) = std::fs::File::create(&temp_path) {
+                    if cfg!(debug_assertions) {
+                    eprintln!("[CREATE] Errore creazione file temporaneo: {}", e);}
+                    return Err(FspError::WIN32(ERROR_INVALID_PARAMETER as u32));
+                }
+                let temp_write = TempWrite {
+                    rel: rel.to_string(),
+                    tem_path: temp_path,
+                    _size: 0,
+                    io: Arc::default(),
+                    // create_exclusive left the same empty file on the backend
+                    dirty: Arc::default(),
+                };
+                self.state
+                    .writes
+                    .lock()
+                    .unwrap()
+                    .insert(ino, temp_write.clone());
+
+                let file_context = MyFileContext {
+                    ino,
+                    temp_write: Some(temp_write),
+                    delete_on_close: AtomicBool::new(false),
+                    is_dir: false,
+                    access_mask: 0,
+                };
+                fi.file_attributes = FILE_ATTRIBUTE_NORMAL;
+                fi.file_size = 0;
+                fi.creation_time = nt_time;
+                fi.last_access_time = nt_time;
+                fi.last_write_time = nt_time;
+                fi.change_time = nt_time;
+
+                let attr = self.file_attr(
+                    Path::new(path_str),
+                    NodeType::RegularFile,
+                    0,
+                    None,
+                    desired_mode as u16,
+                    1,
+                );
+                self.insert_attr_cache(Path::new(rel).to_path_buf(), attr);
+                let _ = self.update_cache(parent_path);
+
+                Ok(file_context)
+            }
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                eprintln!("[CREATE] Errore creazione file sul backend: {}", e);}
+                if e.status() == Some(409) {
+                    Err(FspError::WIN32(ERROR_ALREADY_EXISTS))
+                } else {
+                    Err(FspError::WIN32(ERROR_INVALID_PARAMETER as u32))
+                }
+            }
+        }
+    }
+
+    // Rilegge dal backend la dimensione di un file e la adotta se il file e' cresciuto da quando
+    // e' in cache; altrimenti restituisce l'attr invariato
+    fn refresh_grown_attr(&self, path: &Path, mut attr: FileAttr) -> FileAttr {
+        match self.block_on(self.api.get_update_metadata(&canonical_rel(path))) {
+            Ok(de) if de.size.max(0) as u64 > attr.size => {
+                if cfg!(debug_assertions) {
+                    println!(
+                        "[READ] '{}' grew on the backend from {} to {} bytes",
+                        path.display(),
+                        attr.size,
+                        de.size
+                    );
+                }
+                attr.size = de.size as u64;
+                attr.blocks = de.blocks();
+                attr.mtime = de.mtime_time();
+                self.insert_attr_cache(path.to_path_buf(), attr.clone());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[READ] Could not refresh the size of '{}': {}", path.display(), e);
+                }
+            }
+        }
+        attr
+    }
+
+    // Function that applies --conflict-policy to the data of a file removed or replaced on the
+    // backend while it was open; close cannot fail, the outcome is only reported
+    fn resolve_conflict(&self, tw: &TempWrite) {
+        let target = match self.opts.conflict_policy {
+            ConflictPolicy::Fail => {
+                eprintln!(
+                    "[WARN] '{}' e' stato modificato sul backend mentre era aperto: le modifiche locali sono state scartate",
+                    tw.rel
+                );
+                return;
+            }
+            ConflictPolicy::Overwrite => tw.rel.clone(),
+            ConflictPolicy::Rename => self.conflict_rel(&tw.rel),
+        };
+        match self.block_on(self.api.write_file(&target, &tw.tem_path))
+        {
+            Ok(()) => {
+                eprintln!(
+                    "[WARN] '{}' e' stato modificato sul backend mentre era aperto: le modifiche locali sono state salvate in '{}'",
+                    tw.rel, target
+                );
+                self.evict_all_state_for(&target);
+                let (parent, _) = parent_and_name(&target);
+                let _ = self.update_cache(&PathBuf::from(parent));
+            }
+            Err(e) => eprintln!(
+                "[WARN] '{}' e' stato modificato sul backend mentre era aperto e il salvataggio in '{}' e' fallito: {}",
+                tw.rel, target, e
+            ),
+        }
+    }
+
+    // Function that picks a free name next to `rel` for --conflict-policy rename
+    fn conflict_rel(&self, rel: &str) -> String {
+        let base = format!("{}.conflict", rel);
+        (1..100)
+            .map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+            .find(|r| !matches!(self.block_on(self.api.exists(r)), Ok(true)))
+            .unwrap_or(base)
+    }
+
+    // Function that runs a backend call on the mount's runtime, failing instead once shutdown began
+    fn block_on<T: Send>(&self, fut: impl Future<Output = ApiResult<T>> + Send) -> ApiResult<T> {
+        crate::block_on(&self.rt, fut)
+    }
+
+    // Function that tells whether the mount is a read-only --snapshot view
+    fn read_only(&self) -> bool {
+        self.opts.snapshot.is_some()
+    }
+
+    // Function that tells whether a new entry exceeds the name length or --max-path-len
+    fn path_too_long(&self, rel: &str) -> bool {
+        too_long(Path::new(rel), self.opts.max_path_len.unwrap_or(DEFAULT_PATH_MAX))
+    }
+
+    // Function that tells whether the --max-open-handles cap has been reached
+    fn write_handles_exhausted(&self) -> bool {
+        self.opts
+            .max_open_handles
+            .is_some_and(|max| self.state.open_writes() >= max)
+    }
+
+    fn control_status(&self) -> String {
+        let connected = self.state.ws_connected.load(Ordering::Relaxed);
+        format!(
+            "backend_url: {}\r\nwebsocket: {}\r\nwebsocket_failures: {}\r\nwebsocket_error: {}\r\nsymlink_policy: {}\r\nwindows_symlinks: {}\r\ncommit_strategy: {}\r\nabandoned_commits: {}\r\nattr_cache_entries: {}\r\ndir_cache_entries: {}\r\nopen_writes: {}\r\ninodes: {}\r\n",
+            self.api.base_url(),
+            if connected { "connected" } else { "disconnected" },
+            self.state.ws_failures.load(Ordering::Relaxed),
+            self.state.ws_last_error.lock().unwrap().as_deref().unwrap_or("-"),
+            self.opts.symlink_policy.as_str(),
+            self.opts.windows_symlinks.as_str(),
+            self.opts.commit_strategy.as_str(),
+            self.state.abandoned_commits.load(Ordering::Relaxed),
+            self.state.attr_cache.lock().unwrap().len(),
+            self.state.dir_cache.lock().unwrap().len(),
+            self.state.writes.lock().unwrap().len(),
+            self.state.path_by_ino.lock().unwrap().len(),
+        ) + &self.state.cache_metrics.report("\r\n")
+    }
+
+    // Restituisce il JSON letto da .remotefs/status, pensato per script e monitoraggio
+    fn status_json(&self) -> String {
+        let connected = self.state.ws_connected.load(Ordering::Relaxed);
+        let last_error = self.state.ws_last_error.lock().unwrap().clone();
+        let status = serde_json::json!({
+            "backend_url": self.api.base_url(),
+            // Senza websocket la cache scade solo con il TTL
+            "state": if connected { "connected" } else { "degraded" },
+            "websocket": {
+                "connected": connected,
+                "failures": self.state.ws_failures.load(Ordering::Relaxed),
+            },
+            "cache": {
+                "attr_entries": self.state.attr_cache.lock().unwrap().len(),
+                "dir_entries": self.state.dir_cache.lock().unwrap().len(),
+            },
+            "open_writes": self.state.writes.lock().unwrap().len(),
+            "pending_writes": self.state.dirty_inos.lock().unwrap().len(),
+            "abandoned_commits": self.state.abandoned_commits.load(Ordering::Relaxed),
+            "inodes": self.state.path_by_ino.lock().unwrap().len(),
+            "last_error": last_error,
+        });
+        format!("{}\r\n", status)
+    }
+
+    fn refresh_mount(&self) {
+        self.state.clear_all_cache();
+        if let Some(old) = self.state.ws_client.lock().unwrap().take() {
+            if cfg!(debug_assertions) {
+                println!("[REFRESH_MOUNT] Disconnessione del client WebSocket corrente");
+            }
+            let _ = old.disconnect();
+        }
+        self.state.ws_connected.store(false, Ordering::Relaxed);
+        if self.read_only() {
+            // Uno snapshot non cambia, non ci sono notifiche da seguire
+            return;
+        }
+        let _guard = self.rt.enter();
+        start_websocket_listener(self.api.base_url(), self.api.handshake_headers(), self.state.clone());
     }
 }
 
@@ -808,7 +1286,7 @@ fn normalize_websocket_path(raw: &str) -> String {
     raw.to_string()
 }
 
-fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64, i64, u16, i64)> {
+fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64, SystemTime, u16, i64)> {
     let raw_rel = payload["relPath"].as_str()?;
     let rel = normalize_websocket_path(raw_rel);
     if cfg!(debug_assertions) {
@@ -827,8 +1305,15 @@ fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64,
         })?;
 
     let is_dir = payload["is_dir"].as_bool().unwrap_or(false);
-    let size = payload["size"].as_u64().unwrap_or(0);
-    let mtime = payload["mtime"].as_i64().unwrap_or(0);
+    // Backends storing files compressed send the logical size apart
+    let size = payload["logical_size"]
+        .as_u64()
+        .or(payload["size"].as_u64())
+        .unwrap_or(0);
+    let mtime = system_time(
+        payload["mtime"].as_i64().unwrap_or(0),
+        payload["mtime_ns"].as_i64().unwrap_or(0),
+    );
 
     let perm_str = payload["permissions"].as_str().unwrap_or("644");
     let perm = u16::from_str_radix(perm_str, 8).unwrap_or(0o644);
@@ -845,7 +1330,7 @@ fn metadata_from_payload(payload: &Value) -> Option<(PathBuf, String, bool, u64,
     Some((abs_path, name, is_dir, size, mtime, perm, nlink))
 }
 
-pub fn start_websocket_listener(api_url: &str, fs_state: Arc<FsState>) {
+pub fn start_websocket_listener(api_url: &str, headers: Vec<(String, String)>, fs_state: Arc<FsState>) {
     let ws_url = format!("{}/socket.io/", api_url.trim_end_matches('/'));
 
     tokio::spawn(async move {
@@ -853,41 +1338,53 @@ pub fn start_websocket_listener(api_url: &str, fs_state: Arc<FsState>) {
         let ws_url_clone = ws_url.clone();
 
         tokio::task::spawn_blocking(move || {
-            let client = ClientBuilder::new(ws_url_clone)
-                .on("connect", |_, _| {
+            let connected_on = fs_state.ws_connected.clone();
+            let connected_off = fs_state.ws_connected.clone();
+            let resync_state = fs_state.clone();
+            let ever_connected = AtomicBool::new(false);
+            let mut builder = ClientBuilder::new(ws_url_clone.clone());
+            // L'handshake presenta gli header di --header e il cookie di sessione, come le richieste HTTP
+            for (name, value) in headers {
+                builder = builder.opening_header(name, value);
+            }
+            let client = builder
+                .on(Event::Connect, move |_, _| {
                     if cfg!(debug_assertions) {
                     println!("[WebSocket] Connected!");}
-                })
-                .on("fs_change", move |payload, _| match payload {
-                    Payload::Text(values) => {
-                        if values.len() < 1 {
-                            if cfg!(debug_assertions) {
-                            eprintln!("[WebSocket] fs_change without data");}
-                            return;
-                        }
-                        let json_payload = &values[0];
-                        handle_fs_change(json_payload, &fs_state_cloned);
-                    }
-                    _ => {
-                        if cfg!(debug_assertions) {
-                        eprintln!("[WebSocket] Binary payload not supported");}
+                    connected_on.store(true, Ordering::Relaxed);
+                    // Gli eventi inviati mentre eravamo disconnessi sono persi: si svuota tutto ciò che era in cache
+                    if ever_connected.swap(true, Ordering::Relaxed) {
+                        resync_state.clear_all_cache();
                     }
                 })
+                .on("close", move |_, _| {
+                    connected_off.store(false, Ordering::Relaxed);
+                })
+                .on("fs_change", move |payload, _| {
+                    crate::fs_event::dispatch(&payload, |event| handle_fs_change(event, &fs_state_cloned))
+                })
                 .on("error", |err, _| {
                     if cfg!(debug_assertions) {
                     eprintln!("[WebSocket] Error: {:?}", err);}
                 })
                 .connect();
 
-            if let Err(err) = client {
-                if cfg!(debug_assertions) {
-                eprintln!("[WebSocket] Connection failed: {:?}", err);}
+            match client {
+                Ok(client) => {
+                    *fs_state.ws_last_error.lock().unwrap() = None;
+                    *fs_state.ws_client.lock().unwrap() = Some(client);
+                }
+                Err(err) => fs_state.record_ws_failure(&ws_url_clone, &format!("{:?}", err)),
             }
         });
     });
 }
 
 fn handle_fs_change(payload: &Value, fs_state: &FsState) {
+    // Le modifiche del backend alle voci dell'overlay non sono visibili
+    if crate::fs_event::shadowed(payload, fs_state.overlay.as_deref()) {
+        return;
+    }
     let op = payload["op"].as_str().unwrap_or("");
     if cfg!(debug_assertions) {
     println!("[WebSocket] Received fs_change: op={}", op);}
@@ -1009,13 +1506,16 @@ fn handle_renamed_event(payload: &Value, st: &FsState) {
     if cfg!(debug_assertions) {
     println!("[WebSocket] RENAME: {:?} -> {:?}", old_abs, new_abs);}
 
-    let _ino = if let Some(ino) = st.ino_of(&old_abs) {
+    // Handles opened on the moved or the replaced file become stale, the new path gets a fresh inode
+    if let Some(replaced) = st.ino_of(&new_abs) {
+        st.mark_deleted(replaced);
+        st.remove_path(&new_abs);
+    }
+    if let Some(ino) = st.ino_of(&old_abs) {
+        st.mark_deleted(ino);
         st.remove_path(&old_abs);
-        st.insert_path_mapping(&new_abs, ino);
-        ino
-    } else {
-        st.allocate_ino(&new_abs)
-    };
+    }
+    let _ino = st.allocate_ino(&new_abs);
 
     if let Some((_, name, is_dir, size, mtime, perm, nlink)) = metadata_from_payload(payload) {
         update_cache_from_metadata(st, &new_abs, &name, is_dir, size, mtime, perm, nlink);
@@ -1041,7 +1541,7 @@ pub fn update_cache_from_metadata(
     _name: &str,
     is_dir: bool,
     size: u64,
-    mtime: i64,
+    mtime: SystemTime,
     perm: u16,
     nlink: i64,
 ) -> u64 {
@@ -1058,8 +1558,10 @@ pub fn update_cache_from_metadata(
         None => st.allocate_ino(abs),
     };
 
+    // Le notifiche portano la dimensione del backend, le listing passano già da FileApi::present
+    let size = if is_dir && !st.dir_size_from_backend { 0 } else { size };
     let blocks = if size == 0 { 0 } else { (size + 511) / 512 };
-    let mtime_st = UNIX_EPOCH + Duration::from_secs(mtime.max(0) as u64);
+    let mtime_st = st.fixed_mtime.unwrap_or(mtime);
 
     let attr = FileAttr {
         ino,
@@ -1111,27 +1613,24 @@ impl FileSystemContext for RemoteFs {
         &self,
         name: &U16CStr,
         buf: Option<&mut [c_void]>,
-        _f: impl FnOnce(&U16CStr) -> Option<FileSecurity>,
+        resolve_reparse: impl FnOnce(&U16CStr) -> Option<FileSecurity>,
     ) -> WinFspResult<FileSecurity> {
         let path_abs = self.path_from_u16(name);
-        let rel = RemoteFs::rel_of(std::path::Path::new(&path_abs));
+        let rel = canonical_rel(std::path::Path::new(&path_abs));
         let is_root = rel == ".";
         if cfg!(debug_assertions) {
         println!("[GET_SECURITY_BY_NAME] path='{}' rel='{}'", path_abs, rel);
         }
 
-        let sd_bytes = RemoteFs::sd_from_sddl("O:BAG:BAD:(A;;FA;;;WD)(A;;FA;;;BA)(A;;FA;;;SY)")
-            .unwrap_or_else(|_| {
-                if cfg!(debug_assertions) {
-                eprintln!("[GET_SECURITY_BY_NAME] WARN: sd_from_sddl failed, using empty SD");}
-                Vec::new()
-            });
-
-        let required = sd_bytes.len();
-        if cfg!(debug_assertions) {
-        println!("[GET_SECURITY_BY_NAME] SD size={} bytes", required);}
-
-        if is_root {
+        if is_root || RemoteFs::is_control_rel(&rel) {
+            let sd_bytes = if rel == CONTROL_REFRESH {
+                self.security_descriptor(&rel, 0o644, false)
+            } else if rel == CONTROL_STATUS {
+                self.security_descriptor(&rel, 0o444, false)
+            } else {
+                self.security_descriptor(&rel, 0o755, true)
+            };
+            let required = sd_bytes.len();
             if let Some(buff) = buf {
                 if buff.len() >= required && required > 0 {
                     unsafe {
@@ -1150,9 +1649,22 @@ impl FileSystemContext for RemoteFs {
                 }
             }
 
+            if RemoteFs::is_control_rel(&rel)
+                && rel != CONTROL_DIR
+                && rel != CONTROL_REFRESH
+                && rel != CONTROL_STATUS
+            {
+                return Err(FspError::WIN32(ERROR_FILE_NOT_FOUND));
+            }
             return Ok(FileSecurity {
                 reparse: false,
-                attributes: FILE_ATTRIBUTE_DIRECTORY,
+                attributes: if rel == CONTROL_REFRESH {
+                    FILE_ATTRIBUTE_NORMAL
+                } else if rel == CONTROL_STATUS {
+                    FILE_ATTRIBUTE_READONLY
+                } else {
+                    FILE_ATTRIBUTE_DIRECTORY
+                },
                 sz_security_descriptor: required as u64,
             });
         }
@@ -1185,6 +1697,12 @@ impl FileSystemContext for RemoteFs {
             Err(e) => {
                 if cfg!(debug_assertions) {
                 eprintln!("[GET_SECURITY_BY_NAME] dir_entries FAILED: {}", e);}
+                // Un percorso che passa per un link lo attraversa come reparse point
+                if self.opts.windows_symlinks == WindowsSymlinks::Reparse {
+                    if let Some(security) = resolve_reparse(name) {
+                        return Ok(security);
+                    }
+                }
                 return Err(e);
             }
         };
@@ -1192,9 +1710,16 @@ impl FileSystemContext for RemoteFs {
             let is_dir = RemoteFs::is_dir(&de);
             let attrs = if is_dir {
                 FILE_ATTRIBUTE_DIRECTORY
+            } else if de.entry_kind() == EntryKind::Symlink {
+                // Presente solo con --windows-symlinks reparse, vedi win_link::present
+                FILE_ATTRIBUTE_REPARSE_POINT
             } else {
                 FILE_ATTRIBUTE_NORMAL
             };
+            let sd_bytes = self.security_descriptor(&rel, RemoteFs::parse_perm(&de.permissions), is_dir);
+            let required = sd_bytes.len();
+            if cfg!(debug_assertions) {
+            println!("[GET_SECURITY_BY_NAME] SD size={} bytes", required);}
 
             let _ = self.alloc_ino(std::path::Path::new(&path_abs));
             if let Some(buff) = buf {
@@ -1234,6 +1759,11 @@ impl FileSystemContext for RemoteFs {
             "[GET_SECURITY_BY_NAME] NOT FOUND '{}' in parent '{}'",
             name_only, parent_rel
         );}
+        if self.opts.windows_symlinks == WindowsSymlinks::Reparse {
+            if let Some(security) = resolve_reparse(name) {
+                return Ok(security);
+            }
+        }
 
         Err(FspError::WIN32(ERROR_FILE_NOT_FOUND))
     }
@@ -1246,12 +1776,28 @@ impl FileSystemContext for RemoteFs {
         if cfg!(debug_assertions) {
         println!("[GET_SECURITY] ino={}", context.ino);}
 
-        let sd_bytes = Self::sd_from_sddl("O:BAG:BAD:(A;;FA;;;WD)(A;;FA;;;BA)(A;;FA;;;SY)")
-            .unwrap_or_else(|_| {
-                if cfg!(debug_assertions) {
-                eprintln!("[GET_SECURITY] WARN: sd_from_sddl failed, using empty SD");}
-                Vec::new()
-            });
+        let sd_bytes = match context.ino {
+            CONTROL_REFRESH_INO => self.security_descriptor(CONTROL_REFRESH, 0o644, false),
+            CONTROL_STATUS_INO => self.security_descriptor(CONTROL_STATUS, 0o444, false),
+            CONTROL_DIR_INO => self.security_descriptor(CONTROL_DIR, 0o755, true),
+            _ => {
+                let rel = self
+                    .path_of(context.ino)
+                    .map(|p| canonical_rel(&p))
+                    .unwrap_or_else(|| ".".to_string());
+                let perm = if rel == "." {
+                    0o755
+                } else if let Some(attr) = self.get_attr_cache(Path::new(&rel)) {
+                    attr.perm
+                } else {
+                    // Attributi non in cache: chiede i permessi al backend
+                    self.block_on(self.api.mode(&rel))
+                        .map(|m| (m & 0o7777) as u16)
+                        .unwrap_or(if context.is_dir { 0o755 } else { 0o644 })
+                };
+                self.security_descriptor(&rel, perm, context.is_dir)
+            }
+        };
 
         let sd_len = sd_bytes.len();
         if cfg!(debug_assertions) {
@@ -1288,6 +1834,13 @@ impl FileSystemContext for RemoteFs {
             "[GET_FILE_INFO] start ino={} is_dir={}",
             context.ino, context.is_dir
         );}
+        if context.ino == CONTROL_DIR_INO
+            || context.ino == CONTROL_REFRESH_INO
+            || context.ino == CONTROL_STATUS_INO
+        {
+            RemoteFs::control_file_info(context.ino, file_info);
+            return Ok(());
+        }
 
         let path = match self.path_of(context.ino) {
             Some(p) => p,
@@ -1302,7 +1855,7 @@ impl FileSystemContext for RemoteFs {
                 ));
             }
         };
-        let rel = RemoteFs::rel_of(&path);
+        let rel = canonical_rel(&path);
         if cfg!(debug_assertions) {
         println!(
             "[GET_FILE_INFO] path_abs='{}' rel='{}'",
@@ -1320,6 +1873,8 @@ impl FileSystemContext for RemoteFs {
             );}
 
             if let Some(attr) = self.get_attr_cache(&PathBuf::from(&rel)) {
+                // 0 a meno di --dir-size-from-backend, vedi FileApi::present
+                file_info.file_size = attr.size;
                 file_info.creation_time = RemoteFs::nt_time_from_system_time(attr.crtime);
                 file_info.last_access_time = RemoteFs::nt_time_from_system_time(attr.atime);
                 file_info.last_write_time = RemoteFs::nt_time_from_system_time(attr.mtime);
@@ -1360,11 +1915,10 @@ impl FileSystemContext for RemoteFs {
             );}
 
             if let Some((_, de)) = entries.iter().find(|(_, d)| d.name == name_only) {
-                let t = std::time::UNIX_EPOCH
-                    .checked_add(std::time::Duration::from_secs(de.mtime.max(0) as u64))
-                    .unwrap_or_else(std::time::SystemTime::now);
+                let t = de.mtime_time();
                 let nt = RemoteFs::nt_time_from_system_time(t);
-                file_info.creation_time = nt;
+                file_info.file_size = de.size.max(0) as u64;
+                file_info.creation_time = RemoteFs::nt_time_from_system_time(de.crtime_time());
                 file_info.last_access_time = nt;
                 file_info.last_write_time = nt;
                 file_info.change_time = nt;
@@ -1398,6 +1952,10 @@ impl FileSystemContext for RemoteFs {
             file_info.last_access_time = RemoteFs::nt_time_from_system_time(attr.atime);
             file_info.last_write_time = RemoteFs::nt_time_from_system_time(attr.mtime);
             file_info.change_time = RemoteFs::nt_time_from_system_time(attr.ctime);
+            if self.reparse_target(&rel).is_some() {
+                file_info.file_attributes = FILE_ATTRIBUTE_REPARSE_POINT;
+                file_info.reparse_tag = win_link::IO_REPARSE_TAG_SYMLINK;
+            }
             if cfg!(debug_assertions) {
             println!(
                 "[GET_FILE_INFO] file cache hit: attrs={:#x} size={} cr={:#x} at={:#x} wt={:#x} ct={:#x} perm={:#o} readonly={}",
@@ -1445,10 +2003,11 @@ impl FileSystemContext for RemoteFs {
 
             if isdir {
                 file_info.file_attributes = FILE_ATTRIBUTE_DIRECTORY;
-                file_info.file_size = 0;
+                file_info.file_size = de.size.max(0) as u64;
                 if cfg!(debug_assertions) {
                 println!(
-                    "[GET_FILE_INFO] backend says DIR (context said file): force attrs=DIR size=0"
+                    "[GET_FILE_INFO] backend says DIR (context said file): force attrs=DIR size={}",
+                    file_info.file_size
                 );}
             } else {
                 let readonly = (perm & 0o222) == 0;
@@ -1464,12 +2023,14 @@ impl FileSystemContext for RemoteFs {
                     file_info.file_attributes, file_info.file_size, perm, readonly
                 );}
             }
+            if de.entry_kind() == EntryKind::Symlink {
+                file_info.file_attributes = FILE_ATTRIBUTE_REPARSE_POINT;
+                file_info.reparse_tag = win_link::IO_REPARSE_TAG_SYMLINK;
+            }
 
-            let t = std::time::UNIX_EPOCH
-                .checked_add(std::time::Duration::from_secs(de.mtime.max(0) as u64))
-                .unwrap_or_else(std::time::SystemTime::now);
+            let t = de.mtime_time();
             let nt = RemoteFs::nt_time_from_system_time(t);
-            file_info.creation_time = nt;
+            file_info.creation_time = RemoteFs::nt_time_from_system_time(de.crtime_time());
             file_info.last_access_time = nt;
             file_info.last_write_time = nt;
             file_info.change_time = nt;
@@ -1485,7 +2046,7 @@ impl FileSystemContext for RemoteFs {
                 NodeType::RegularFile
             };
             let size = if isdir { 0 } else { de.size.max(0) as u64 };
-            let attr = self.file_attr(&child_path, ty, size, Some(de.mtime), perm, de.nlink as u32);
+            let attr = self.file_attr(&child_path, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
             self.insert_attr_cache(child_path.clone(), attr);
             if cfg!(debug_assertions) {
             println!(
@@ -1513,10 +2074,13 @@ impl FileSystemContext for RemoteFs {
         new_file_name: &U16CStr,
         replace_if_exists: bool,
     ) -> WinFspResult<()> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         let src_abs = self.path_from_u16(file_name);
         let dst_abs = self.path_from_u16(new_file_name);
-        let src_rel = RemoteFs::rel_of(std::path::Path::new(&src_abs));
-        let dst_rel = RemoteFs::rel_of(std::path::Path::new(&dst_abs));
+        let src_rel = canonical_rel(std::path::Path::new(&src_abs));
+        let dst_rel = canonical_rel(std::path::Path::new(&dst_abs));
         if cfg!(debug_assertions) {
         println!(
             "[RENAME] start ino={} is_dir={} src='{}' -> dst='{}' replace={}",
@@ -1528,6 +2092,12 @@ impl FileSystemContext for RemoteFs {
             println!("[RENAME] denied: source is root");}
             return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
         }
+        if RemoteFs::is_control_rel(&src_rel) || RemoteFs::is_control_rel(&dst_rel) {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
+        if self.path_too_long(&dst_rel) {
+            return Err(FspError::WIN32(ERROR_FILENAME_EXCED_RANGE));
+        }
         if let Some(attr) = self.get_attr_cache(&PathBuf::from(&src_rel)) {
             if (attr.perm & 0o222) == 0 {
                 if cfg!(debug_assertions) {
@@ -1539,8 +2109,8 @@ impl FileSystemContext for RemoteFs {
             }
         }
 
-        let (src_parent_rel, src_name) = RemoteFs::split_parent_name(&src_rel);
-        let (dst_parent_rel, dst_name) = RemoteFs::split_parent_name(&dst_rel);
+        let (src_parent_rel, src_name) = parent_and_name(&src_rel);
+        let (dst_parent_rel, dst_name) = parent_and_name(&dst_rel);
         let src_parent_key = std::path::PathBuf::from(&src_parent_rel);
         let dst_parent_key = std::path::PathBuf::from(&dst_parent_rel);
 
@@ -1572,6 +2142,7 @@ impl FileSystemContext for RemoteFs {
             }
         };
         let src_is_dir = RemoteFs::is_dir(&src_de);
+        let mut replaced_dir = false;
 
         if let Some((_, dst_de)) = dst_list.iter().find(|(_, d)| d.name == dst_name) {
             let dst_is_dir = RemoteFs::is_dir(&dst_de);
@@ -1592,34 +2163,69 @@ impl FileSystemContext for RemoteFs {
                     windows_sys::Win32::Foundation::ERROR_ALREADY_EXISTS,
                 ));
             }
+            // Come in POSIX una directory sostituisce solo una directory vuota
             if dst_is_dir {
-                if cfg!(debug_assertions) {
-                eprintln!("[RENAME] replace directory not supported");}
-                return Err(FspError::WIN32(
-                    windows_sys::Win32::Foundation::ERROR_NOT_SUPPORTED,
-                ));
-            }
-            if let Err(e) = self.rt.block_on(self.api.delete(&dst_rel)) {
-                if cfg!(debug_assertions) {
-                eprintln!("[RENAME] pre-delete failed for '{}': {}", dst_rel, e);}
-                return Err(FspError::WIN32(
-                    windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED,
-                ));
+                let dst_children = self.dir_entries(&PathBuf::from(&dst_rel)).map_err(|e| {
+                    if cfg!(debug_assertions) {
+                    eprintln!("[RENAME] dir_entries('{}') failed: {}", dst_rel, e);}
+                    e
+                })?;
+                if !dst_children.is_empty() {
+                    if cfg!(debug_assertions) {
+                    eprintln!("[RENAME] destination directory '{}' is not empty", dst_rel);}
+                    return Err(FspError::WIN32(
+                        windows_sys::Win32::Foundation::ERROR_DIR_NOT_EMPTY,
+                    ));
+                }
+                replaced_dir = true;
             }
+            // The target is not deleted here: the backend replaces it only once the rename succeeds
+        }
+
+        // Editors save by writing a temp file and renaming it over the target: commit the
+        // source's buffered content first, so the rename moves the new data in one step
+        if let Some(tw) = &context.temp_write {
+            self.block_on(self.api.write_file(&src_rel, &tw.tem_path))
+                .map_err(|e| {
+                    if cfg!(debug_assertions) {
+                    eprintln!("[RENAME] commit of pending write for '{}' failed: {}", src_rel, e);}
+                    // Il sorgente puo' essere stato caricato a meta': i suoi attributi vanno riletti
+                    self.drop_rename_caches(&src_rel, &dst_rel, &src_parent_key, &dst_parent_key);
+                    FspError::from(io::Error::new(io::ErrorKind::Other, format!("{}", e)))
+                })?;
         }
 
-        if let Err(e) = self.rt.block_on(self.api.rename(&src_rel, &dst_rel)) {
+        if let Err(e) = self.block_on(self.api.rename(&src_rel, &dst_rel)) {
             if cfg!(debug_assertions) {
             eprintln!(
                 "[RENAME] backend rename failed: {} -> {} err={}",
                 src_rel, dst_rel, e
             );}
-            return Err(FspError::WIN32(
-                windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED,
-            ));
+            // Volumi diversi sul backend: nulla e' stato spostato, Explorer ripiega su copia+cancellazione
+            if e.is_cross_device() {
+                return Err(FspError::WIN32(ERROR_NOT_SAME_DEVICE));
+            }
+            // Nessuno stato locale e' stato ancora toccato, ma il backend puo' aver completato il
+            // rename prima dell'errore (es. risposta persa): si scarta la cache e si guarda lo stato reale
+            self.drop_rename_caches(&src_rel, &dst_rel, &src_parent_key, &dst_parent_key);
+            // Reported done but only partly carried out: not a permission problem
+            if e.is_incomplete_rename() {
+                return Err(FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string())));
+            }
+            if !self.rename_landed(&src_rel, &dst_rel) {
+                return Err(FspError::WIN32(
+                    windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED,
+                ));
+            }
+            if cfg!(debug_assertions) {
+            println!("[RENAME] '{}' is already at '{}', completing the rename", src_rel, dst_rel);}
         }
 
         self.evict_all_state_for(&src_rel);
+        if replaced_dir {
+            self.evict_all_state_for(&dst_rel);
+            self.state.remove_dir_cache(&PathBuf::from(&dst_rel));
+        }
         if let Err(e) = self.update_cache(&src_parent_key) {
             if cfg!(debug_assertions) {
             eprintln!("[RENAME] update_cache('{}') failed: {}", src_parent_rel, e);}
@@ -1631,7 +2237,7 @@ impl FileSystemContext for RemoteFs {
             }
         }
         if let Some(cur) = self.path_of(context.ino) {
-            if RemoteFs::rel_of(&cur) == src_rel {
+            if canonical_rel(&cur) == src_rel {
                 if let Ok(mut byino) = self.state.path_by_ino.lock() {
                     byino.insert(context.ino, PathBuf::from(dst_abs.clone()));
                 }
@@ -1663,17 +2269,28 @@ impl FileSystemContext for RemoteFs {
     ) -> WinFspResult<()> {
         if cfg!(debug_assertions) {
         println!("[GET_VOLUME_INFO] start");}
-        let stats = self.rt.block_on(self.api.statfs()).map_err(|e| {
-            if cfg!(debug_assertions) {
-            eprintln!("[GET_VOLUME_INFO] statfs backend failed: {}", e);}
-            FspError::from(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                e.to_string(),
-            ))
-        })?;
-
-        out_volume_info.total_size = stats.blocks * stats.bsize;
-        out_volume_info.free_size = stats.bfree * stats.bsize;
+        // Come su Linux e macOS, una capacita' sconosciuta (backend senza /stats o irraggiungibile)
+        // si presenta come 0 invece di far fallire la richiesta, che Explorer mostrerebbe come errore
+        match self.block_on(self.api.capacity()) {
+            Ok(Some(stats)) => {
+                let (total, free) = stats.volume_bytes();
+                out_volume_info.total_size = total;
+                out_volume_info.free_size = free;
+            }
+            Ok(None) => {
+                out_volume_info.total_size = 0;
+                out_volume_info.free_size = 0;
+            }
+            Err(e) => {
+                eprintln!("[GET_VOLUME_INFO] Backend /stats unreachable, reporting no free space: {}", e);
+                out_volume_info.total_size = 0;
+                out_volume_info.free_size = 0;
+            }
+        }
+        // --fs-name is the label Explorer shows next to the drive letter
+        if let Some(name) = &self.opts.fs_name {
+            out_volume_info.set_volume_label(name);
+        }
         if cfg!(debug_assertions) {
         println!(
             "[GET_VOLUME_INFO] total={} free={} (in bytes)",
@@ -1697,7 +2314,7 @@ impl FileSystemContext for RemoteFs {
             println!("[OPEN] .0 entry");
         }
         let path = self.path_from_u16(file_name);
-        let rel = RemoteFs::rel_of(std::path::Path::new(&path));
+        let rel = canonical_rel(std::path::Path::new(&path));
         if cfg!(debug_assertions) {
             println!("[OPEN] .1 path_from_u16 -> path='{}' rel='{}'", path, rel);
         }
@@ -1726,12 +2343,14 @@ impl FileSystemContext for RemoteFs {
         }
 
         let wants_delete = (granted_access & DELETE) != 0;
-        let wants_write =
-            (granted_access & FILE_WRITE_DATA) != 0 || (granted_access & GENERIC_WRITE) != 0;
+        let wants_write = (granted_access & (FILE_WRITE_DATA | FILE_APPEND_DATA | GENERIC_WRITE)) != 0;
         let wants_read =
             (granted_access & FILE_READ_DATA) != 0 || (granted_access & GENERIC_READ) != 0;
         let wants_read_attributes = (granted_access & FILE_READ_ATTRIBUTES) != 0;
         let has_delete_child = granted_access & FILE_DELETE_CHILD != 0;
+        if self.read_only() && (wants_write || wants_delete) {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         if cfg!(debug_assertions) {
             println!(
             "[OPEN] .3 wants_delete={} wants_write={} wants_read={}  wants_read_attributes={} has_delete_child={}",
@@ -1739,6 +2358,29 @@ impl FileSystemContext for RemoteFs {
         );
         }
 
+        if rel == CONTROL_DIR || rel == CONTROL_REFRESH || rel == CONTROL_STATUS {
+            let is_dir = rel == CONTROL_DIR;
+            let ino = if is_dir {
+                CONTROL_DIR_INO
+            } else if rel == CONTROL_STATUS {
+                // Il file di stato e' in sola lettura
+                if wants_write || wants_delete {
+                    return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+                }
+                CONTROL_STATUS_INO
+            } else {
+                CONTROL_REFRESH_INO
+            };
+            RemoteFs::control_file_info(ino, open_info.as_mut());
+            return Ok(MyFileContext {
+                ino,
+                is_dir,
+                delete_on_close: AtomicBool::new(false),
+                temp_write: None,
+                access_mask: granted_access,
+            });
+        }
+
         if rel == "." {
             if cfg!(debug_assertions) {
                 println!("[OPEN] .4 root case -> returning dir context");
@@ -1817,6 +2459,10 @@ impl FileSystemContext for RemoteFs {
         if cfg!(debug_assertions) {
             println!("[OPEN] .8 is_dir={}", is_dir);
         }
+        // Scorciatoie e reparse point stanno per un link del backend, che da qui non si modifica
+        if de.symlink_target.is_some() && (wants_write || wants_delete) {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
 
         let ino = self.alloc_ino(&child_path); // FIX
         if cfg!(debug_assertions) {
@@ -1831,7 +2477,7 @@ impl FileSystemContext for RemoteFs {
 
             if let Some(attr) = self.get_attr_cache(&child_path) {
                 fi.file_attributes = FILE_ATTRIBUTE_DIRECTORY;
-                fi.file_size = 0;
+                fi.file_size = attr.size;
                 fi.creation_time = RemoteFs::nt_time_from_system_time(attr.crtime);
                 fi.last_access_time = RemoteFs::nt_time_from_system_time(attr.atime);
                 fi.last_write_time = RemoteFs::nt_time_from_system_time(attr.mtime);
@@ -1867,20 +2513,9 @@ impl FileSystemContext for RemoteFs {
                 println!("[OPEN] .11 attr cache HIT for '{}'", child_path.display());
                 println!("[OPEN] .11 attr: size={} de.size={}", attr.size, de.size);
             }
-            if attr.size == 0 && de.size > 0 {
-                if cfg!(debug_assertions) {
-                    println!(
-                        "[OPEN] .11.1 Backend reports size={}, updating cache",
-                        de.size
-                    );
-                }
-                attr.size = de.size as u64;
-                attr.blocks = (attr.size + 511) / 512;
-                attr.mtime = std::time::UNIX_EPOCH
-                    .checked_add(std::time::Duration::from_secs(de.mtime as u64))
-                    .unwrap_or_else(std::time::SystemTime::now);
-
-                self.insert_attr_cache(child_path.clone(), attr.clone());
+            // Il listing vede un file piu' grande: la dimensione viene confermata dal backend come in read
+            if de.size.max(0) as u64 > attr.size {
+                attr = self.refresh_grown_attr(&child_path, attr);
             }
 
             let readonly = (attr.perm & 0o222) == 0;
@@ -1890,7 +2525,7 @@ impl FileSystemContext for RemoteFs {
                 FILE_ATTRIBUTE_NORMAL
             };
             fi.file_size = attr.size;
-            fi.allocation_size = ((attr.size + 4095) / 4096) * 4096;
+            fi.allocation_size = ((attr.blocks * 512 + 4095) / 4096) * 4096;
             fi.creation_time = RemoteFs::nt_time_from_system_time(attr.crtime);
             fi.last_access_time = RemoteFs::nt_time_from_system_time(attr.atime);
             fi.last_write_time = RemoteFs::nt_time_from_system_time(attr.mtime);
@@ -1922,14 +2557,13 @@ impl FileSystemContext for RemoteFs {
 
             fi.file_attributes = FILE_ATTRIBUTE_NORMAL;
             fi.file_size = de.size.max(0) as u64;
-            fi.allocation_size = ((de.size.max(0) as u64 + 4095) / 4096) * 4096;
+            // Su un backend che comprime i file lo spazio occupato e' quello memorizzato
+            fi.allocation_size = ((de.blocks() * 512 + 4095) / 4096) * 4096;
 
-            let t = std::time::UNIX_EPOCH
-                .checked_add(std::time::Duration::from_secs(de.mtime as u64))
-                .unwrap_or_else(std::time::SystemTime::now);
+            let t = de.mtime_time();
             let nt = RemoteFs::nt_time_from_system_time(t);
 
-            fi.creation_time = nt;
+            fi.creation_time = RemoteFs::nt_time_from_system_time(de.crtime_time());
             fi.last_access_time = nt;
             fi.last_write_time = nt;
             fi.change_time = nt;
@@ -1950,7 +2584,18 @@ impl FileSystemContext for RemoteFs {
             );
             }
         }
+        if de.entry_kind() == EntryKind::Symlink {
+            fi.file_attributes = FILE_ATTRIBUTE_REPARSE_POINT;
+            fi.reparse_tag = win_link::IO_REPARSE_TAG_SYMLINK;
+        }
         let temp_write = if wants_write {
+            // A second writer on an already open file reuses its slot
+            if !self.state.writes.lock().unwrap().contains_key(&ino) && self.write_handles_exhausted() {
+                if cfg!(debug_assertions) {
+                    eprintln!("[OPEN] Too many files open for writing, refusing '{}'", rel);
+                }
+                return Err(FspError::WIN32(ERROR_TOO_MANY_OPEN_FILES));
+            }
             if cfg!(debug_assertions) {
                 println!(
                     "[OPEN] .13 wants_write=true -> create temp file for ino={}",
@@ -2002,12 +2647,12 @@ impl FileSystemContext for RemoteFs {
                         NodeType::RegularFile
                     };
                     let perm = Self::parse_perm(&de.permissions);
-                    let size = if is_dir { 0 } else { de.size.max(0) as u64 };
+                    let size = de.size.max(0) as u64;
                     let a = self.file_attr(
                         &child_path,
                         ty,
                         size,
-                        Some(de.mtime),
+                        Some(de.mtime_time()),
                         perm,
                         de.nlink as u32,
                     );
@@ -2035,7 +2680,7 @@ impl FileSystemContext for RemoteFs {
                             &child_path,
                             ty,
                             0,
-                            Some(de.mtime),
+                            Some(de.mtime_time()),
                             perm,
                             de.nlink as u32,
                         );
@@ -2043,117 +2688,42 @@ impl FileSystemContext for RemoteFs {
                         a
                     }
                 };
-
-                if attr.size == 0 {
+                // Meglio fallire subito che riempire a metà la cartella temporanea
+                if let Err(e) = crate::temp_dir::ensure_room(&self.temp_dir, attr.size) {
                     if cfg!(debug_assertions) {
-                        println!("[OPEN] .13.4 attr.size==0 -> create empty temp");
+                        eprintln!("[OPEN] Spazio insufficiente per il temp file di '{}': {}", rel, e);
                     }
-                    std::fs::File::create(&temp_path).map_err(|e| {
-                        FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
-                    })?;
-                } else {
-                    let start_u64 = 0u64;
-                    let end_u64 = attr.size.saturating_sub(1);
-                    match self
-                        .rt
-                        .block_on(self.api.read_range(&rel, start_u64, end_u64))
-                    {
-                        Ok(existing_data) if !existing_data.is_empty() => {
-                            if let Err(e) = std::fs::write(&temp_path, &existing_data) {
-                                if cfg!(debug_assertions) {
-                                    eprintln!("[OPEN] WARN: pre-populate failed: {}", e);
-                                }
-                                std::fs::File::create(&temp_path).map_err(|e| {
-                                    FspError::from(io::Error::new(
-                                        io::ErrorKind::Other,
-                                        e.to_string(),
-                                    ))
-                                })?;
-                            } else {
-                                if cfg!(debug_assertions) {
-                                    println!(
-                                        "[OPEN] .13.4 Pre-populated temp with {} bytes",
-                                        existing_data.len()
-                                    );
-                                }
-                            }
-                        }
-                        Ok(_) => {
-                            if cfg!(debug_assertions) {
-                                println!(
-                                    "[OPEN] .13.5 Backend returned empty -> create empty temp"
-                                );
-                            }
-                            std::fs::File::create(&temp_path).map_err(|e| {
-                                FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
-                            })?;
+                    return Err(FspError::WIN32(windows_sys::Win32::Foundation::ERROR_DISK_FULL));
+                }
+
+                // 404 means the file is gone, an empty body an empty file: only the first is an error
+                let data = match self.block_on(self.api.read_all(&rel, attr.size)) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        if cfg!(debug_assertions) {
+                            eprintln!("[OPEN] .13.5 Backend read of '{}' failed: {}", rel, e);
                         }
-                        Err(e) => {
-                            if cfg!(debug_assertions) {
-                                eprintln!(
-                                    "[OPEN] .13.5 Backend read failed: {} -> trying fallback",
-                                    e
-                                );
-                            }
-                            let alt = if rel.starts_with("./") {
-                                rel.trim_start_matches("./").to_string()
-                            } else {
-                                format!("./{}", rel.trim_start_matches("./"))
-                            };
-                            match self
-                                .rt
-                                .block_on(self.api.read_range(&alt, start_u64, end_u64))
-                            {
-                                Ok(d2) if !d2.is_empty() => {
-                                    if let Err(e) = std::fs::write(&temp_path, &d2) {
-                                        if cfg!(debug_assertions) {
-                                            eprintln!(
-                                                "[OPEN] WARN: fallback pre-populate failed: {}",
-                                                e
-                                            );
-                                        }
-                                        std::fs::File::create(&temp_path).map_err(|e| {
-                                            FspError::from(io::Error::new(
-                                                io::ErrorKind::Other,
-                                                e.to_string(),
-                                            ))
-                                        })?;
-                                    } else {
-                                        if cfg!(debug_assertions) {
-                                            println!("[OPEN] .13.4 Fallback pre-populated temp with {} bytes", d2.len());
-                                        }
-                                    }
-                                }
-                                Ok(_) => {
-                                    if cfg!(debug_assertions) {
-                                        println!(
-                                        "[OPEN] .13.6 Fallback returned empty -> create empty temp"
-                                    );
-                                    }
-                                    std::fs::File::create(&temp_path).map_err(|e| {
-                                        FspError::from(io::Error::new(
-                                            io::ErrorKind::Other,
-                                            e.to_string(),
-                                        ))
-                                    })?;
-                                }
-                                Err(e2) => {
-                                    if cfg!(debug_assertions) {
-                                        eprintln!(
-                                        "[OPEN] fallback read also failed: {} -> create empty temp",
-                                        e2
-                                    );
-                                    }
-                                    std::fs::File::create(&temp_path).map_err(|e| {
-                                        FspError::from(io::Error::new(
-                                            io::ErrorKind::Other,
-                                            e.to_string(),
-                                        ))
-                                    })?;
-                                }
-                            }
+                        let _ = std::fs::remove_file(&temp_path);
+                        if e.is_not_found() {
+                            self.state.remove_attr(&child_path);
+                            return Err(FspError::WIN32(ERROR_FILE_NOT_FOUND));
                         }
+                        return Err(FspError::from(io::Error::new(
+                            io::ErrorKind::Other,
+                            e.to_string(),
+                        )));
+                    }
+                };
+                // A temp left empty here would replace the file with nothing on commit
+                if let Err(e) = std::fs::write(&temp_path, &data) {
+                    if cfg!(debug_assertions) {
+                        eprintln!("[OPEN] pre-populate of '{}' failed: {}", temp_path.display(), e);
                     }
+                    let _ = std::fs::remove_file(&temp_path);
+                    return Err(FspError::from(e));
+                }
+                if cfg!(debug_assertions) {
+                    println!("[OPEN] .13.4 Pre-populated temp with {} bytes", data.len());
                 }
             } else {
                 if cfg!(debug_assertions) {
@@ -2182,9 +2752,16 @@ impl FileSystemContext for RemoteFs {
                 );
             }
 
+            let (io, dirty) = match self.state.writes.lock().unwrap().get(&ino) {
+                Some(open) => (open.io.clone(), open.dirty.clone()),
+                None => Default::default(),
+            };
             let tw = TempWrite {
+                rel: rel.clone(),
                 tem_path: temp_path,
                 _size: size,
+                io,
+                dirty,
             };
             self.state.writes.lock().unwrap().insert(ino, tw.clone());
             if cfg!(debug_assertions) {
@@ -2226,6 +2803,16 @@ impl FileSystemContext for RemoteFs {
                 return;
             }
         };
+        let _committing = self.state.commit_lock.lock().unwrap();
+        self.state.dirty_inos.lock().unwrap().remove(&file_context.ino);
+        if self.state.is_deleted(file_context.ino) {
+            // The file was removed or replaced remotely: --conflict-policy decides where the data goes
+            if cfg!(debug_assertions) {
+            eprintln!("[CLOSE] ino={} is stale, applying {:?}", file_context.ino, self.opts.conflict_policy);}
+            self.resolve_conflict(&temp_write);
+            let _ = std::fs::remove_file(&temp_write.tem_path);
+            return;
+        }
 
         if !temp_write.tem_path.exists() {
             if cfg!(debug_assertions) {
@@ -2259,7 +2846,7 @@ impl FileSystemContext for RemoteFs {
             println!("[CLOSE] PowerShell might be using a different API");
         }}
 
-        let rel_path = RemoteFs::rel_of(&self.path_of(file_context.ino).unwrap());
+        let rel_path = canonical_rel(&self.path_of(file_context.ino).unwrap());
         if cfg!(debug_assertions) {
         println!(
             "[CLOSE] syncing rel='{}' from temp='{}' (real_size={})",
@@ -2267,12 +2854,27 @@ impl FileSystemContext for RemoteFs {
             temp_write.tem_path.display(),
             real_size
         );}
-        if let Err(e) = self.rt.block_on(
-            self.api
-                .write_file(&rel_path, &temp_write.tem_path.to_string_lossy()),
-        ) {
+        let mut recovered = false;
+        self.state.commit_failures.lock().unwrap().remove(&file_context.ino);
+        let abandoned = self.state.abandoned_inos.lock().unwrap().remove(&file_context.ino);
+        // Unchanged since open or create, the backend already has these bytes: no PUT
+        let upload = if abandoned {
+            // Rifiutato a ogni tentativo: la versione finale sostituisce la copia nella cartella di recupero
+            Err(ApiError::Io(io::Error::other("commit abbandonato dopo --commit-attempts tentativi")))
+        } else if temp_write.dirty.load(Ordering::SeqCst) {
+            self.block_on(self.api.write_file(&rel_path, &temp_write.tem_path))
+        } else {
+            if cfg!(debug_assertions) {
+            println!("[CLOSE] '{}' unchanged, nothing to upload", rel_path);}
+            Ok(())
+        };
+        if let Err(e) = upload {
             if cfg!(debug_assertions) {
             eprintln!("[CLOSE] Errore commit file {}: {:?}", rel_path, e);}
+            // --recovery-dir: i dati non inviati vengono conservati invece di essere cancellati
+            if let Some(dir) = &self.opts.recovery_dir {
+                recovered = crate::recovery::save(dir, &rel_path, &temp_write.tem_path).is_ok();
+            }
         } else {
             let parent_rel = Path::new(&rel_path)
                 .parent()
@@ -2286,7 +2888,7 @@ impl FileSystemContext for RemoteFs {
                 parent_rel
             );}
 
-            if let Ok(list) = self.rt.block_on(self.api.ls(&parent_rel)) {
+            if let Ok(list) = self.block_on(self.api.ls(&parent_rel)) {
                 if let Some(de) = list.into_iter().find(|d| {
                     d.name
                         == Path::new(&rel_path)
@@ -2322,9 +2924,7 @@ impl FileSystemContext for RemoteFs {
                                 "[CLOSE] backend perm {:#o} != cached perm {:#o} -> reapplying cached perm",
                                 backend_perm, cperm
                             );}
-                            let _ = self
-                                .rt
-                                .block_on(self.api.chmod(&rel_path, cperm as u32))
+                            let _ = self.block_on(self.api.chmod(&rel_path, cperm as u32))
                                 .map_err(|e|if cfg!(debug_assertions) { eprintln!("[CLOSE] chmod post-commit failed: {}", e)});
                             cperm
                         } else {
@@ -2335,7 +2935,7 @@ impl FileSystemContext for RemoteFs {
                     };
                     let perm = final_perm as u16;
                     let attr =
-                        self.file_attr(&child, ty, size, Some(de.mtime), perm, de.nlink as u32);
+                        self.file_attr(&child, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
                     if cfg!(debug_assertions) {
                     println!(
                         "[CLOSE] updating attr_cache for '{}' size={}",
@@ -2347,9 +2947,11 @@ impl FileSystemContext for RemoteFs {
                 let _ = self.update_cache(&parent_key);
             }
         }
-        if let Err(e) = std::fs::remove_file(&temp_write.tem_path) {
-            if cfg!(debug_assertions) {
-            eprintln!("[CLOSE] Errore rimozione temp file: {}", e);}
+        if !recovered {
+            if let Err(e) = std::fs::remove_file(&temp_write.tem_path) {
+                if cfg!(debug_assertions) {
+                eprintln!("[CLOSE] Errore rimozione temp file: {}", e);}
+            }
         }
         self.state.writes.lock().unwrap().remove(&file_context.ino);
         if cfg!(debug_assertions) {
@@ -2369,10 +2971,34 @@ impl FileSystemContext for RemoteFs {
             offset,
             file_context.temp_write.is_some()
         );}
+        if file_context.ino == CONTROL_REFRESH_INO || file_context.ino == CONTROL_STATUS_INO {
+            let status = if file_context.ino == CONTROL_STATUS_INO {
+                self.status_json()
+            } else {
+                self.control_status()
+            }
+            .into_bytes();
+            let start = (offset as usize).min(status.len());
+            let end = (start + buffer.len()).min(status.len());
+            buffer[..end - start].copy_from_slice(&status[start..end]);
+            return Ok((end - start) as u32);
+        }
+        if self.state.is_deleted(file_context.ino) {
+            if cfg!(debug_assertions) {
+            eprintln!("[READ] ino={} is stale, file changed on the backend", file_context.ino);}
+            return Err(FspError::WIN32(windows_sys::Win32::Foundation::ERROR_FILE_INVALID));
+        }
         let path = self.path_of(file_context.ino).ok_or(FspError::WIN32(1))?;
-        let rel_path = RemoteFs::rel_of(&path);
+        let rel_path = canonical_rel(&path);
         if cfg!(debug_assertions) {
         println!("[READ] rel='{}'", rel_path);}
+        // La scorciatoia di un link non esiste sul backend: il contenuto viene dal target
+        if let Some(data) = self.shortcut_data(&rel_path) {
+            let start = (offset as usize).min(data.len());
+            let end = (start + buffer.len()).min(data.len());
+            buffer[..end - start].copy_from_slice(&data[start..end]);
+            return Ok((end - start) as u32);
+        }
         let mut attr = self.get_attr_cache(&path);
 
         if attr.is_none() {
@@ -2393,10 +3019,10 @@ impl FileSystemContext for RemoteFs {
                             NodeType::RegularFile
                         };
                         let perm = Self::parse_perm(&de.permissions);
-                        let size = if is_dir { 0 } else { de.size.max(0) as u64 };
+                        let size = de.size.max(0) as u64;
 
                         let a =
-                            self.file_attr(&path, ty, size, Some(de.mtime), perm, de.nlink as u32);
+                            self.file_attr(&path, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
                         self.insert_attr_cache(path.clone(), a.clone());
                         attr = Some(a);
                     }
@@ -2411,6 +3037,12 @@ impl FileSystemContext for RemoteFs {
             Some(a) => a,
             None => return Err(FspError::WIN32(ERROR_FILE_NOT_FOUND)),
         };
+        // La dimensione in cache puo' precedere una scrittura fatta sul backend: va confermata prima di tagliare la lettura
+        let attr = if file_context.temp_write.is_none() && offset + buffer.len() as u64 > attr.size {
+            self.refresh_grown_attr(&path, attr)
+        } else {
+            attr
+        };
         if offset as u64 >= attr.size {
             return Ok(0);
         }
@@ -2421,58 +3053,14 @@ impl FileSystemContext for RemoteFs {
         let start_u64 = offset as u64;
         let end_u64 = (start_u64 + (buffer.len() as u64) - 1).min(attr.size.saturating_sub(1));
 
-        let data: Vec<u8> = if let Some(tw) = &file_context.temp_write {
+        if let Some(tw) = &file_context.temp_write {
             if cfg!(debug_assertions) {
             println!("[READ] reading from temp '{}'", tw.tem_path.display());}
-            match std::fs::read(&tw.tem_path) {
-                Ok(d) => d,
-                Err(e) => {
-                    if cfg!(debug_assertions) {
-                    eprintln!("[READ] failed read temp: {}", e);}
-                    return Err(FspError::from(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        e.to_string(),
-                    )));
-                }
-            }
-        } else {
-            if cfg!(debug_assertions) {
-            println!("[READ] reading from backend with rel='{}'", rel_path);}
-            match self
-                .rt
-                .block_on(self.api.read_range(&rel_path, start_u64, end_u64))
-            {
-                Ok(d) => d,
-                Err(e) => {
-                    if cfg!(debug_assertions) {
-                    eprintln!("[READ] backend read failed for '{}': {}", rel_path, e);}
-                    let alt = if rel_path.starts_with("./") {
-                        rel_path.trim_start_matches("./").to_string()
-                    } else {
-                        format!("./{}", rel_path.trim_start_matches("./"))
-                    };
-                    if cfg!(debug_assertions) {
-                    eprintln!("[READ] trying fallback rel='{}'", alt);}
-                    match self
-                        .rt
-                        .block_on(self.api.read_range(&alt, start_u64, end_u64))
-                    {
-                        Ok(d2) => d2,
-                        Err(e2) => {
-                            if cfg!(debug_assertions) {
-                            eprintln!("[READ] backend read fallback failed for '{}': {}", alt, e2);}
-                            return Err(FspError::from(std::io::Error::new(
-                                std::io::ErrorKind::Other,
-                                e2.to_string(),
-                            )));
-                        }
-                    }
-                }
-            }
-        };
-
-        let bytes_to_copy: &[u8];
-        if file_context.temp_write.is_some() {
+            let data = std::fs::read(&tw.tem_path).map_err(|e| {
+                if cfg!(debug_assertions) {
+                eprintln!("[READ] failed read temp: {}", e);}
+                FspError::from(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+            })?;
             let start = offset as usize;
             if start >= data.len() {
                 if cfg!(debug_assertions) {
@@ -2480,20 +3068,43 @@ impl FileSystemContext for RemoteFs {
                 return Ok(0);
             }
             let end = std::cmp::min(start + buffer.len(), data.len());
-            bytes_to_copy = &data[start..end];
-        } else {
-            let end = std::cmp::min(buffer.len(), data.len());
-            bytes_to_copy = &data[0..end];
+            buffer[..end - start].copy_from_slice(&data[start..end]);
+            if cfg!(debug_assertions) {
+            println!("[READ] copied {} bytes ({} of {})", end - start, offset, data.len());}
+            return Ok((end - start) as u32);
         }
-        buffer[..bytes_to_copy.len()].copy_from_slice(bytes_to_copy);
+
+        // Dal backend i dati vengono scritti direttamente nel buffer di WinFsp
         if cfg!(debug_assertions) {
-        println!(
-            "[READ] copied {} bytes ({} of {})",
-            bytes_to_copy.len(),
-            offset,
-            data.len()
-        );}
-        Ok(bytes_to_copy.len() as u32)
+        println!("[READ] reading from backend with rel='{}'", rel_path);}
+        let len = (end_u64 - start_u64 + 1) as usize;
+        let n = match self.block_on(self.api.read_into(&rel_path, start_u64, &mut buffer[..len]))
+        {
+            Ok(n) => n,
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                eprintln!("[READ] backend read failed for '{}': {}", rel_path, e);}
+                let alt = if rel_path.starts_with("./") {
+                    rel_path.trim_start_matches("./").to_string()
+                } else {
+                    format!("./{}", rel_path.trim_start_matches("./"))
+                };
+                if cfg!(debug_assertions) {
+                eprintln!("[READ] trying fallback rel='{}'", alt);}
+                self.block_on(self.api.read_into(&alt, start_u64, &mut buffer[..len]))
+                    .map_err(|e2| {
+                        if cfg!(debug_assertions) {
+                        eprintln!("[READ] backend read fallback failed for '{}': {}", alt, e2);}
+                        FspError::from(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            e2.to_string(),
+                        ))
+                    })?
+            }
+        };
+        if cfg!(debug_assertions) {
+        println!("[READ] copied {} bytes at offset {}", n, offset);}
+        Ok(n as u32)
     }
 
     fn write(
@@ -2505,6 +3116,9 @@ impl FileSystemContext for RemoteFs {
         constrained_io: bool,
         file_info: &mut FileInfo,
     ) -> WinFspResult<u32> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         if cfg!(debug_assertions) {
         println!(
             "[WRITE] CALLED! ino={} offset={} len={} write_to_eof={} constrained={}",
@@ -2514,6 +3128,23 @@ impl FileSystemContext for RemoteFs {
             write_to_end_of_file,
             constrained_io
         );}
+        if file_context.ino == CONTROL_STATUS_INO {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
+        if file_context.ino == CONTROL_REFRESH_INO {
+            if cfg!(debug_assertions) {
+                println!("[WRITE] refresh richiesto tramite file di controllo");
+            }
+            self.refresh_mount();
+            file_info.file_size = 0;
+            file_info.allocation_size = 0;
+            return Ok(buffer.len() as u32);
+        }
+        if self.state.is_deleted(file_context.ino) {
+            if cfg!(debug_assertions) {
+            eprintln!("[WRITE] ino={} is stale, file changed on the backend", file_context.ino);}
+            return Err(FspError::WIN32(windows_sys::Win32::Foundation::ERROR_FILE_INVALID));
+        }
         if let Some(path) = self.path_of(file_context.ino) {
             if let Some(attr) = self.get_attr_cache(&path) {
                 if (attr.perm & 0o222) == 0 {
@@ -2534,40 +3165,30 @@ impl FileSystemContext for RemoteFs {
         }
         let tw = match &file_context.temp_write {
             Some(tw) => tw,
-            None => return Err(FspError::WIN32(1)),
+            // Handle aperto senza accesso in scrittura
+            None => return Err(FspError::WIN32(ERROR_ACCESS_DENIED)),
         };
 
-        let mut file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&tw.tem_path)
-            .map_err(|e| {
-                if cfg!(debug_assertions) {
-                eprintln!("[WRITE] ERROR opening temp: {}", e);}
-                FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
-            })?;
-            if cfg!(debug_assertions) {
-        println!("[WRITE] Seeking to offset {}", offset);}
-
-        file.seek(std::io::SeekFrom::Start(offset)).map_err(|e| {
-            if cfg!(debug_assertions) {
-            eprintln!("[WRITE] ERROR seeking: {}", e);}
-            FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
-        })?;
+        // Concurrent writes on the handles of the file go one at a time: each one finds the
+        // length the previous one left
+        let serial = tw.io.lock().unwrap();
+        let old_size = std::fs::metadata(&tw.tem_path).map(|m| m.len()).unwrap_or(0);
         if cfg!(debug_assertions) {
-        println!("[WRITE] Writing {} bytes", buffer.len());}
-
-        file.write_all(buffer).map_err(|e| {
-            if cfg!(debug_assertions) {
-            eprintln!("[WRITE] ERROR writing: {}", e);}
-            FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
-        })?;
+        println!("[WRITE] Writing {} bytes at offset {}", buffer.len(), offset);}
 
-        file.flush().map_err(|e| {
+        // Con il disco pieno il temp file torna alla lunghezza di prima e il client riceve
+        // ERROR_DISK_FULL invece di un errore generico
+        crate::temp_dir::write_at(&tw.tem_path, old_size, offset, buffer).map_err(|e| {
             if cfg!(debug_assertions) {
-            eprintln!("[WRITE] ERROR flushing: {}", e);}
-            FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
+            eprintln!("[WRITE] ERROR writing temp: {}", e);}
+            if crate::temp_dir::is_disk_full(&e) {
+                FspError::WIN32(windows_sys::Win32::Foundation::ERROR_DISK_FULL)
+            } else {
+                FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
+            }
         })?;
+        // Nient'altro del file aspettava di essere inviato prima di questa write
+        let rest_sent = !tw.dirty.swap(true, Ordering::SeqCst);
 
         if let Ok(metadata) = std::fs::metadata(&tw.tem_path) {
             let new_size = metadata.len();
@@ -2580,6 +3201,44 @@ impl FileSystemContext for RemoteFs {
                 new_size
             );}
         }
+        drop(serial);
+
+        match self.opts.commit_strategy {
+            CommitStrategy::WriteThrough => {
+                // Il temp file è solo un buffer: ogni write viene inviata subito al backend
+                let path = self.path_of(file_context.ino).ok_or(FspError::WIN32(
+                    windows_sys::Win32::Foundation::ERROR_FILE_NOT_FOUND,
+                ))?;
+                let rel = canonical_rel(&path);
+                // Solo il range scritto, se il resto è già sul backend: a offset 0 il backend
+                // riparte da un file vuoto, quindi lì serve un range che arrivi alla fine
+                let size = std::fs::metadata(&tw.tem_path).map(|m| m.len()).unwrap_or(0);
+                let ranged = if rest_sent && (offset > 0 || buffer.len() as u64 >= size) {
+                    self.block_on(self.api.write_range(&rel, offset, buffer))
+                } else {
+                    Ok(false)
+                };
+                ranged
+                    .and_then(|done| {
+                        if done {
+                            Ok(())
+                        } else {
+                            self.block_on(self.api.write_file(&rel, &tw.tem_path))
+                        }
+                    })
+                    .map_err(|e| {
+                        if cfg!(debug_assertions) {
+                        eprintln!("[WRITE] commit write-through fallito per {}: {:?}", rel, e);}
+                        FspError::from(io::Error::new(io::ErrorKind::Other, e.to_string()))
+                    })?;
+                // Tutto inviato: la close non deve ricaricare il file
+                tw.dirty.store(false, Ordering::SeqCst);
+            }
+            CommitStrategy::Interval => {
+                self.state.dirty_inos.lock().unwrap().insert(file_context.ino);
+            }
+            CommitStrategy::Close => {}
+        }
 
         Ok(buffer.len() as u32)
     }
@@ -2587,17 +3246,28 @@ impl FileSystemContext for RemoteFs {
     fn overwrite(
         &self,
         context: &Self::FileContext,
-        _file_attributes: FILE_FLAGS_AND_ATTRIBUTES,
+        file_attributes: FILE_FLAGS_AND_ATTRIBUTES,
         replace_file_attributes: bool,
         allocation_size: u64,
+        // Extended attributes are not supported, ea_size is always 0
         _extra_buffer: Option<&[u8]>,
         file_info: &mut FileInfo,
     ) -> Result<(), FspError> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         if cfg!(debug_assertions) {
         println!(
             "[OVERWRITE] ino={} replace_attrs={} allocation_size={}",
             context.ino, replace_file_attributes, allocation_size
         );}
+        if context.ino == CONTROL_STATUS_INO {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
+        if context.ino == CONTROL_REFRESH_INO {
+            RemoteFs::control_file_info(context.ino, file_info);
+            return Ok(());
+        }
 
         if let Some(tw) = &context.temp_write {
             if cfg!(debug_assertions) {
@@ -2605,28 +3275,64 @@ impl FileSystemContext for RemoteFs {
                 "[OVERWRITE] truncating temp file '{}' to 0",
                 tw.tem_path.display()
             );}
+            // Lo spazio chiesto con allocation_size deve essere disponibile nella cartella temporanea
+            if let Err(e) = crate::temp_dir::ensure_room(&self.temp_dir, allocation_size) {
+                if cfg!(debug_assertions) {
+                eprintln!("[OVERWRITE] {}", e);}
+                return Err(FspError::WIN32(windows_sys::Win32::Foundation::ERROR_DISK_FULL));
+            }
             let result = std::fs::OpenOptions::new()
                 .write(true)
                 .truncate(true)
                 .open(&tw.tem_path);
 
             match result {
-                Ok(_) => {
+                Ok(file) => {
                     if cfg!(debug_assertions) {
                     println!("[OVERWRITE] Temp file truncated successfully");}
-                    file_info.file_size = 0;
-                    file_info.allocation_size = 0;
+                    tw.dirty.store(true, Ordering::SeqCst);
+                    if allocation_size > 0 {
+                        if let Err(e) = crate::temp_dir::preallocate(&file, allocation_size) {
+                            if cfg!(debug_assertions) {
+                            eprintln!("[OVERWRITE] preallocation of {} bytes failed: {}", allocation_size, e);}
+                        }
+                    }
                     let path = self.path_of(context.ino).ok_or(FspError::WIN32(
                         windows_sys::Win32::Foundation::ERROR_FILE_NOT_FOUND,
                     ))?;
 
-                    if let Some(mut attr) = self.get_attr_cache(&path) {
-                        attr.size = 0;
-                        attr.blocks = 0;
-                        attr.mtime = SystemTime::now();
-                        attr.ctime = attr.mtime;
-                        self.insert_attr_cache(path, attr);
-                    }
+                    // Gli attributi sostituiscono quelli esistenti o vi si aggiungono (OR); dei flag
+                    // Windows solo READONLY ha un equivalente, i bit di scrittura del modo
+                    let now = SystemTime::now();
+                    let mut attr = self.get_attr_cache(&path).unwrap_or_else(|| {
+                        self.file_attr(&path, NodeType::RegularFile, 0, Some(now), 0o644, 1)
+                    });
+                    attr.perm = crate::acl::overwrite_perm(
+                        attr.perm,
+                        (file_attributes & FILE_ATTRIBUTE_READONLY) != 0,
+                        replace_file_attributes,
+                    );
+                    let readonly = (attr.perm & 0o222) == 0;
+                    attr.size = 0;
+                    attr.blocks = 0;
+                    attr.mtime = now;
+                    attr.ctime = now;
+                    // Il chmod arriva al backend in close, dopo il commit: su un file gia' in sola
+                    // lettura l'upload verrebbe rifiutato
+                    self.insert_attr_cache(path, attr);
+
+                    let now_nt = RemoteFs::nt_time_from_system_time(now);
+                    file_info.file_attributes = if readonly {
+                        FILE_ATTRIBUTE_NORMAL | FILE_ATTRIBUTE_READONLY
+                    } else {
+                        FILE_ATTRIBUTE_NORMAL
+                    };
+                    file_info.file_size = 0;
+                    file_info.allocation_size = allocation_size.div_ceil(4096) * 4096;
+                    file_info.last_write_time = now_nt;
+                    file_info.change_time = now_nt;
+                    file_info.index_number = context.ino;
+                    file_info.ea_size = 0;
                 }
                 Err(e) => {
                     if cfg!(debug_assertions) {
@@ -2655,9 +3361,21 @@ impl FileSystemContext for RemoteFs {
     ) -> WinFspResult<u32> {
         if cfg!(debug_assertions) {
         println!("Siamo in read_dir");}
-        let dir_path = self.path_of(file_context.ino).ok_or(FspError::WIN32(1))?;
-
-        let mut entries = self.dir_entries(&dir_path)?;
+        let mut entries = if file_context.ino == CONTROL_DIR_INO {
+            vec![
+                (
+                    PathBuf::from(CONTROL_REFRESH),
+                    RemoteFs::control_dir_entry("refresh", "644"),
+                ),
+                (
+                    PathBuf::from(CONTROL_STATUS),
+                    RemoteFs::control_dir_entry("status", "444"),
+                ),
+            ]
+        } else {
+            let dir_path = self.path_of(file_context.ino).ok_or(FspError::WIN32(1))?;
+            self.dir_entries(&dir_path)?
+        };
         let marker_name: Option<String> = marker
             .inner_as_cstr()
             .map(|w: &U16CStr| w.to_string_lossy().to_string());
@@ -2703,6 +3421,9 @@ impl FileSystemContext for RemoteFs {
                 let is_dir = Self::is_dir(&de);
                 (*dir_info_ptr).FileInfo.FileAttributes = if is_dir {
                     FILE_ATTRIBUTE_DIRECTORY
+                } else if de.entry_kind() == EntryKind::Symlink {
+                    (*dir_info_ptr).FileInfo.ReparseTag = win_link::IO_REPARSE_TAG_SYMLINK;
+                    FILE_ATTRIBUTE_REPARSE_POINT
                 } else {
                     FILE_ATTRIBUTE_NORMAL
                 };
@@ -2720,9 +3441,7 @@ impl FileSystemContext for RemoteFs {
                     };
                     (*dir_info_ptr).FileInfo.AllocationSize = alloc;
                 }
-                let mtime = UNIX_EPOCH
-                    .checked_add(Duration::from_secs(de.mtime as u64))
-                    .unwrap_or_else(SystemTime::now);
+                let mtime = de.mtime_time();
                 let t = RemoteFs::nt_time_from_system_time(mtime);
                 (*dir_info_ptr).FileInfo.CreationTime = t;
                 (*dir_info_ptr).FileInfo.LastAccessTime = t;
@@ -2771,12 +3490,21 @@ impl FileSystemContext for RemoteFs {
         _write_through: bool,
         file_info: &mut OpenFileInfo,
     ) -> WinFspResult<Self::FileContext> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         if cfg!(debug_assertions) {
         println!("Siamo in create");}
 
         let path_str = self.path_from_u16(path);
-        let rel = RemoteFs::rel_of(Path::new(&path_str));
+        let rel = canonical_rel(Path::new(&path_str));
         let is_dir = (create_options & CREATE_DIRECTORY) != 0;
+        if RemoteFs::is_control_rel(&rel) {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
+        if self.path_too_long(&rel) {
+            return Err(FspError::WIN32(ERROR_FILENAME_EXCED_RANGE));
+        }
 
         let now = SystemTime::now();
         let nt_time = RemoteFs::nt_time_from_system_time(now);
@@ -2792,7 +3520,7 @@ impl FileSystemContext for RemoteFs {
 
         let parent_path = PathBuf::from(&parent_rel);
         if is_dir {
-            match self.rt.block_on(self.api.mkdir(&rel)) {
+            match self.block_on(self.api.mkdir(&rel)) {
                 Ok(_) => {
                     fi.file_attributes = FILE_ATTRIBUTE_DIRECTORY;
                     fi.file_size = 0;
@@ -2831,76 +3559,14 @@ impl FileSystemContext for RemoteFs {
                 }
             }
         }
-        if self.backend_entry_exists(&rel) {
-            return Err(FspError::WIN32(ERROR_ALREADY_EXISTS));
-        }
-
-        let ino = self.alloc_ino(Path::new(&path_str));
-        if cfg!(debug_assertions) {
-        println!("[CREATE] file ino: {:?}", ino);}
-        let temp_path = self.get_temporary_path(ino);
-        if let Err(e) = std::fs::File::create(&temp_path) {
-            if cfg!(debug_assertions) {
-            eprintln!("[CREATE] Errore creazione file temporaneo: {}", e);}
-            return Err(FspError::WIN32(ERROR_INVALID_PARAMETER as u32));
-        }
-
-        match self
-            .rt
-            .block_on(self.api.write_file(&rel, &temp_path.to_str().unwrap()))
-        {
-            Ok(_) => {
-                let desired_mode: u32 = 0o644;
-                let temp_path = self.get_temporary_path(ino);
-                if let Err(e) = std::fs::File::create(&temp_path) {
-                    if cfg!(debug_assertions) {
-                    eprintln!("[CREATE] Errore creazione file temporaneo: {}", e);}
-                    return Err(FspError::WIN32(ERROR_INVALID_PARAMETER as u32));
-                }
-                let temp_write = TempWrite {
-                    tem_path: temp_path,
-                    _size: 0,
-                };
-                self.state
-                    .writes
-                    .lock()
-                    .unwrap()
-                    .insert(ino, temp_write.clone());
-
-                let file_context = MyFileContext {
-                    ino,
-                    temp_write: Some(temp_write),
-                    delete_on_close: AtomicBool::new(false),
-                    is_dir: false,
-                    access_mask: 0,
-                };
-                fi.file_attributes = FILE_ATTRIBUTE_NORMAL;
-                fi.file_size = 0;
-                fi.creation_time = nt_time;
-                fi.last_access_time = nt_time;
-                fi.last_write_time = nt_time;
-                fi.change_time = nt_time;
-
-                let attr = self.file_attr(
-                    Path::new(&path_str),
-                    NodeType::RegularFile,
-                    0,
-                    None,
-                    desired_mode as u16,
-                    1,
-                );
-                self.insert_attr_cache(Path::new(&rel).to_path_buf(), attr);
-                let _ = self.update_cache(&parent_path);
-
-                Ok(file_context)
-            }
-            Err(e) => {
-                if cfg!(debug_assertions) {
-                eprintln!("[CREATE] Errore creazione file sul backend: {}", e);}
-                let _ = std::fs::remove_file(&temp_path);
-                Err(FspError::WIN32(ERROR_INVALID_PARAMETER as u32))
-            }
-        }
+        // Due create concorrenti dello stesso nome vengono eseguite una alla volta
+        let create_lock = self.state.create_lock(Path::new(&rel));
+        let creating = create_lock.lock().unwrap();
+        let result = self.create_file(&path_str, &rel, &parent_path, nt_time, file_info);
+        drop(creating);
+        drop(create_lock);
+        self.state.release_create_lock(Path::new(&rel));
+        result
     }
 
     fn set_basic_info(
@@ -2913,11 +3579,21 @@ impl FileSystemContext for RemoteFs {
     _change_time: u64,
     file_info: &mut FileInfo,
 ) -> WinFspResult<()> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
+    if file_context.ino == CONTROL_DIR_INO
+        || file_context.ino == CONTROL_REFRESH_INO
+        || file_context.ino == CONTROL_STATUS_INO
+    {
+        RemoteFs::control_file_info(file_context.ino, file_info);
+        return Ok(());
+    }
     let path = self.path_of(file_context.ino).ok_or(FspError::WIN32(
         windows_sys::Win32::Foundation::ERROR_FILE_NOT_FOUND,
     ))?;
 
-    let rel = RemoteFs::rel_of(&path);
+    let rel = canonical_rel(&path);
     let rel_key = PathBuf::from(rel.clone());
     let parent_rel = std::path::Path::new(&rel)
         .parent()
@@ -2944,8 +3620,8 @@ impl FileSystemContext for RemoteFs {
                         NodeType::RegularFile
                     };
                     let perm = Self::parse_perm(&de.permissions);
-                    let size = if is_dir { 0 } else { de.size.max(0) as u64 };
-                    let a = self.file_attr(&p, ty, size, Some(de.mtime), perm, de.nlink as u32);
+                    let size = de.size.max(0) as u64;
+                    let a = self.file_attr(&p, ty, size, Some(de.mtime_time()), perm, de.nlink as u32);
                     self.insert_attr_cache(p.clone(), a.clone());
                     a
                 } else {
@@ -3002,7 +3678,7 @@ impl FileSystemContext for RemoteFs {
     println!("[SET_BASIC_INFO] Final decision: mode={:#o}", mode);}
     
     // Applica chmod al backend
-    self.rt.block_on(self.api.chmod(&rel, mode)).map_err(|e| {
+    self.block_on(self.api.chmod(&rel, mode)).map_err(|e| {
         if cfg!(debug_assertions) {
         eprintln!("[SET_BASIC_INFO] chmod failed: {}", e);}
         FspError::from(io::Error::new(io::ErrorKind::Other, format!("{}", e)))
@@ -3062,7 +3738,7 @@ impl FileSystemContext for RemoteFs {
         }
 
         let path = self.path_from_u16(file_name);
-        let rel = RemoteFs::rel_of(std::path::Path::new(&path));
+        let rel = canonical_rel(std::path::Path::new(&path));
         if cfg!(debug_assertions) {
             println!("[GET_DIR_INFO_BY_NAME] rel='{}'", rel);
         }
@@ -3130,6 +3806,16 @@ impl FileSystemContext for RemoteFs {
         set_allocation_size: bool,
         file_info: &mut FileInfo,
     ) -> WinFspResult<()> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
+        if file_context.ino == CONTROL_STATUS_INO {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
+        if file_context.ino == CONTROL_REFRESH_INO {
+            RemoteFs::control_file_info(file_context.ino, file_info);
+            return Ok(());
+        }
         if set_allocation_size {
             if cfg!(debug_assertions) {
                 println!(
@@ -3174,6 +3860,7 @@ impl FileSystemContext for RemoteFs {
                 );
             }
 
+            let _serial = tw.io.lock().unwrap();
             let f = std::fs::OpenOptions::new()
                 .write(true)
                 .open(&tw.tem_path)
@@ -3196,14 +3883,14 @@ impl FileSystemContext for RemoteFs {
                     e.to_string(),
                 ))
             })?;
+            tw.dirty.store(true, Ordering::SeqCst);
         } else {
             if cfg!(debug_assertions) {
                 println!("[SET_FILE_SIZE] Resizing BACKEND directly to {}", new_size);
             }
 
-            let rel = RemoteFs::rel_of(&path);
-            self.rt
-                .block_on(self.api.truncate(&rel, new_size))
+            let rel = canonical_rel(&path);
+            self.block_on(self.api.truncate(&rel, new_size))
                 .map_err(|e| {
                     if cfg!(debug_assertions) {
                         eprintln!("[SET_FILE_SIZE] backend truncate failed: {}", e);
@@ -3268,7 +3955,7 @@ impl FileSystemContext for RemoteFs {
                 .ok_or(FspError::WIN32(
                     windows_sys::Win32::Foundation::ERROR_FILE_NOT_FOUND,
                 ))?;
-            let rel = RemoteFs::rel_of(&path);
+            let rel = canonical_rel(&path);
 
             let parent_rel = std::path::Path::new(&rel)
                 .parent()
@@ -3276,8 +3963,7 @@ impl FileSystemContext for RemoteFs {
                 .filter(|s| !s.is_empty())
                 .unwrap_or_else(|| ".".to_string());
 
-            self.rt
-                .block_on(self.api.write_file(&rel, &tw.tem_path.to_string_lossy()))
+            self.block_on(self.api.write_file(&rel, &tw.tem_path))
                 .map_err(|e| {
                     let io_err = io::Error::new(io::ErrorKind::Other, format!("{}", e));
                     FspError::from(io_err)
@@ -3304,7 +3990,7 @@ impl FileSystemContext for RemoteFs {
         &self,
         context: &Self::FileContext,
         file_name: &U16CStr,
-        _buffer: &mut [u8],
+        buffer: &mut [u8],
     ) -> Result<u64, FspError> {
         if cfg!(debug_assertions) {
             println!(
@@ -3313,7 +3999,27 @@ impl FileSystemContext for RemoteFs {
                 file_name.to_string_lossy()
             );
         }
-        Ok(0)
+        let path = self.path_of(context.ino).ok_or(FspError::WIN32(ERROR_FILE_NOT_FOUND))?;
+        let target = self
+            .reparse_target(&canonical_rel(&path))
+            .ok_or(FspError::WIN32(ERROR_NOT_A_REPARSE_POINT))?;
+        Self::copy_reparse_data(&target, Some(buffer))
+    }
+
+    fn get_reparse_point_by_name(
+        &self,
+        file_name: &U16CStr,
+        _is_directory: bool,
+        buffer: Option<&mut [u8]>,
+    ) -> Result<u64, FspError> {
+        let rel = canonical_rel(Path::new(&self.path_from_u16(file_name)));
+        if cfg!(debug_assertions) {
+            println!("[GET_REPARSE_POINT_BY_NAME] rel='{}'", rel);
+        }
+        let target = self
+            .reparse_target(&rel)
+            .ok_or(FspError::WIN32(ERROR_NOT_A_REPARSE_POINT))?;
+        Self::copy_reparse_data(&target, buffer)
     }
 
     fn set_delete(
@@ -3322,12 +4028,21 @@ impl FileSystemContext for RemoteFs {
         file_name: &U16CStr,
         delete: bool,
     ) -> WinFspResult<()> {
+        if self.read_only() {
+            return Err(FspError::WIN32(ERROR_WRITE_PROTECT));
+        }
         if cfg!(debug_assertions) {
             println!(
                 "set_delete: delete={} for path={:?}, ino={}",
                 delete, file_name, file_context.ino
             );
         }
+        if file_context.ino == CONTROL_DIR_INO
+            || file_context.ino == CONTROL_REFRESH_INO
+            || file_context.ino == CONTROL_STATUS_INO
+        {
+            return Err(FspError::WIN32(ERROR_ACCESS_DENIED));
+        }
 
         let percorso = Some(file_name);
 
@@ -3353,7 +4068,7 @@ impl FileSystemContext for RemoteFs {
             p
         };
 
-        let rel = RemoteFs::rel_of(std::path::Path::new(&path));
+        let rel = canonical_rel(std::path::Path::new(&path));
         if let Some(attr) = self.get_attr_cache(&PathBuf::from(&rel)) {
             if (attr.perm & 0o222) == 0 {
                 if cfg!(debug_assertions) {
@@ -3390,6 +4105,12 @@ impl FileSystemContext for RemoteFs {
                 file_context.delete_on_close.load(Ordering::Relaxed)
             );
         }
+        if file_context.ino == CONTROL_DIR_INO
+            || file_context.ino == CONTROL_REFRESH_INO
+            || file_context.ino == CONTROL_STATUS_INO
+        {
+            return;
+        }
         let path = if let Some(name) = file_name {
             self.path_from_u16(name)
         } else if let Some(p) = self.path_of(file_context.ino) {
@@ -3401,7 +4122,7 @@ impl FileSystemContext for RemoteFs {
             return;
         };
 
-        let rel = RemoteFs::rel_of(std::path::Path::new(&path));
+        let rel = canonical_rel(std::path::Path::new(&path));
 
         if rel == "." {
             if cfg!(debug_assertions) {
@@ -3516,7 +4237,13 @@ impl FileSystemContext for RemoteFs {
                 }
             }
         }
-        match self.rt.block_on(self.api.delete(&rel)) {
+        // Con --trash l'entry finisce nel cestino del backend invece di essere eliminata
+        let deleted = if self.opts.trash {
+            self.block_on(self.api.trash(&rel))
+        } else {
+            self.block_on(self.api.delete(&rel))
+        };
+        match deleted {
             Ok(_) => {
                 if cfg!(debug_assertions) {
                     println!("[DEBUG] cleanup: '{}' eliminato", rel)
@@ -3537,10 +4264,146 @@ impl FileSystemContext for RemoteFs {
     }
 }
 
-pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<()> {
-    let rt = Arc::new(Runtime::new()?);
-    let fs = RemoteFs::new(api, rt.clone());
+// Function that sends to the backend, every COMMIT_INTERVAL, the files written since the previous tick (--commit-strategy interval)
+fn spawn_interval_committer(state: Arc<FsState>, api: FileApi, rt: Arc<Runtime>, opts: MountOptions) {
+    thread::spawn(move || loop {
+        thread::sleep(COMMIT_INTERVAL);
+        if crate::shutting_down() {
+            break;
+        }
+        let _committing = state.commit_lock.lock().unwrap();
+        let dirty: Vec<u64> = state.dirty_inos.lock().unwrap().drain().collect();
+        for ino in dirty {
+            if state.is_deleted(ino) || state.abandoned_inos.lock().unwrap().contains(&ino) {
+                continue;
+            }
+            // Ancora in attesa del backoff dopo un fallimento: resta da inviare
+            let waiting = state
+                .commit_failures
+                .lock()
+                .unwrap()
+                .get(&ino)
+                .is_some_and(|(_, retry_at)| *retry_at > Instant::now());
+            if waiting {
+                state.dirty_inos.lock().unwrap().insert(ino);
+                continue;
+            }
+            let Some(tw) = state.writes.lock().unwrap().get(&ino).cloned() else {
+                continue;
+            };
+            let Some(path) = state.path_of(ino) else {
+                continue;
+            };
+            let rel = canonical_rel(&path);
+            if let Err(e) = crate::block_on(&rt, api.write_file(&rel, &tw.tem_path)) {
+                if cfg!(debug_assertions) {
+                eprintln!("[COMMIT] commit periodico fallito per {}: {:?}", rel, e);}
+                // Riprovato dopo il backoff o alla chiusura
+                let failures = {
+                    let mut all = state.commit_failures.lock().unwrap();
+                    let entry = all.entry(ino).or_insert((0, Instant::now()));
+                    entry.0 += 1;
+                    entry.1 = Instant::now() + opts.commit_retry_delay(entry.0);
+                    entry.0
+                };
+                if failures >= opts.commit_attempts() {
+                    state.abandoned_inos.lock().unwrap().insert(ino);
+                    abandon_commit(&state, &opts, &rel, &tw.tem_path);
+                } else {
+                    state.dirty_inos.lock().unwrap().insert(ino);
+                }
+            } else {
+                state.commit_failures.lock().unwrap().remove(&ino);
+            }
+        }
+    });
+}
+
+// Smette di inviare un file che il backend continua a rifiutare: i dati vengono copiati nella cartella
+// di recupero, gli handle continuano a scrivere sul temp file e la chiusura vi salva la versione finale
+fn abandon_commit(state: &FsState, opts: &MountOptions, rel: &str, tmp_path: &Path) {
+    state.abandoned_commits.fetch_add(1, Ordering::Relaxed);
+    eprintln!(
+        "[WARN] ********** {} rifiutato dal backend {} volte di seguito: l'invio e' sospeso **********",
+        rel,
+        opts.commit_attempts()
+    );
+    match opts.recovery_dir.as_deref().map(|dir| crate::recovery::keep(dir, rel, tmp_path)) {
+        Some(Ok(dest)) => eprintln!("[WARN] I dati di {} sono stati salvati in {:?}", rel, dest),
+        Some(Err(e)) => eprintln!("[WARN] Impossibile salvare i dati di {} in --recovery-dir: {}", rel, e),
+        None => eprintln!(
+            "[WARN] Senza --recovery-dir i dati di {} restano solo nel file temporaneo {:?} e vanno persi alla chiusura",
+            rel, tmp_path
+        ),
+    }
+}
+
+pub fn mount_fs(
+    mountpoint: &str,
+    api: FileApi,
+    url: String,
+    opts: MountOptions,
+) -> anyhow::Result<()> {
+    mount_all(vec![MountSpec {
+        mountpoint: mountpoint.to_string(),
+        api,
+        url,
+        opts,
+    }])
+}
+
+// Verifica se il volume è ancora montato senza passare dal filesystem stesso: una lettera di unità
+// espulsa sparisce da GetLogicalDrives, una cartella di mount smontata sparisce dal disco
+fn volume_present(mp: &str) -> bool {
+    let b = mp.as_bytes();
+    if b.len() == 2 && b[1] == b':' && b[0].is_ascii_alphabetic() {
+        let bit = (b[0].to_ascii_uppercase() - b'A') as u32;
+        return unsafe { windows_sys::Win32::Storage::FileSystem::GetLogicalDrives() } & (1 << bit) != 0;
+    }
+    std::fs::symlink_metadata(mp).is_ok()
+}
+
+// Ferma i filesystem il cui volume è stato espulso o smontato da fuori; true quando non ne resta nessuno
+fn reap_removed_volumes(hosts: &mut Vec<(String, FileSystemHost<'static>)>) -> bool {
+    let (removed, present): (Vec<_>, Vec<_>) = std::mem::take(hosts)
+        .into_iter()
+        .partition(|(mp, _)| !volume_present(mp));
+    *hosts = present;
+    for (mp, mut host) in removed {
+        host.stop();
+        let msg = format!("Filesystem {} smontato dall'esterno", mp);
+        eprintln!("[STOP] {}", msg);
+        crate::write_status(&msg);
+    }
+    hosts.is_empty()
+}
+
+// Monta un filesystem sul runtime condiviso e avvia il suo listener WebSocket
+fn spawn_mount(spec: MountSpec, rt: &Arc<Runtime>) -> anyhow::Result<FileSystemHost<'static>> {
+    let MountSpec {
+        mountpoint,
+        api,
+        url,
+        mut opts,
+    } = spec;
+    if let Err(msg) = crate::mountpoint::verify(&mountpoint, opts.force) {
+        crate::write_status(&msg);
+        return Err(anyhow::anyhow!(msg));
+    }
+    let read_only = opts.snapshot.is_some();
+    if opts.trash && !read_only && !rt.block_on(api.trash_supported()) {
+        eprintln!("[WARN] Il backend non supporta il cestino: con --trash le cancellazioni restano definitive");
+        opts.trash = false;
+    }
+    let handshake = api.handshake_headers();
+    let fs = RemoteFs::new(api, rt.clone(), opts);
     let fs_state = fs.state.clone();
+    if fs.opts.commit_strategy == CommitStrategy::Interval && !read_only {
+        spawn_interval_committer(fs_state.clone(), fs.api.clone(), rt.clone(), fs.opts.clone());
+    }
+    if let (Some(dir), true) = (&fs.opts.recovery_dir, fs.opts.recovery_retry) {
+        crate::recovery::spawn_retry(dir.clone(), fs.api.clone(), rt.clone());
+    }
     fs.init_cache();
     let mut vparams = VolumeParams::default();
     vparams.sectors_per_allocation_unit(64);
@@ -3550,18 +4413,44 @@ pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<(
     vparams.case_preserved_names(true);
     vparams.unicode_on_disk(true);
     vparams.pass_query_directory_filename(true);
+    vparams.read_only_volume(read_only);
+    // Con --windows-symlinks reparse i link del backend sono reparse point che Windows segue da se'
+    vparams.reparse_points(fs.opts.windows_symlinks == WindowsSymlinks::Reparse);
+    // --fs-type is what "Tipo di file system" shows in the volume properties
+    if let Some(fs_type) = &fs.opts.fs_type {
+        vparams.filesystem_name(fs_type);
+    }
 
+    let fuse_threads = fs.opts.fuse_threads;
     let mut host = FileSystemHost::new(vparams, fs)?;
-    host.mount(mountpoint)?;
-    host.start()?;
+    host.mount(&mountpoint)?;
+    // WinFsp dispatches every operation on its own threads, --fuse-threads only sizes the pool
+    match fuse_threads {
+        Some(n) => host.start_with_threads(n as u32)?,
+        None => host.start()?,
+    }
     if cfg!(debug_assertions) {
     println!("[Mount] Starting WebSocket listener for: {}", url);}
-    {
-        let url_clone = url.clone();
+    if !read_only {
         rt.spawn(async move {
-            start_websocket_listener(&url_clone, fs_state);
+            start_websocket_listener(&url, handshake, fs_state);
         });
     }
+    Ok(host)
+}
+
+// Monta tutti i filesystem su un unico runtime e li smonta tutti al Ctrl-C
+pub fn mount_all(specs: Vec<MountSpec>) -> anyhow::Result<()> {
+    let rt = Arc::new(crate::io_runtime(crate::io_threads_of(&specs))?);
+    let mut hosts = Vec::new();
+    for spec in specs {
+        let mp = spec.mountpoint.clone();
+        // Se un mount fallisce, quelli già attivi vengono smontati al drop di `hosts`
+        let host = spawn_mount(spec, &rt)?;
+        if cfg!(debug_assertions) {
+        println!(" Filesystem montato su: {} ", mp);}
+        hosts.push((mp, host));
+    }
 
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -3571,19 +4460,28 @@ pub fn mount_fs(mountpoint: &str, api: FileApi, url: String) -> anyhow::Result<(
         r.store(false, Ordering::SeqCst);
     })?;
     if cfg!(debug_assertions) {
-    println!(
-        " Filesystem montato su: {} ",
-        mountpoint
-    );
-    println!("  Backend URL: {}  ", url);
+    println!("  {} filesystem montati", hosts.len());
     println!("  WebSocket attivo per notifiche real-time  ║");
-   
+
     println!(" Premi Ctrl-C per smontare e uscire ");}
 
+    let mut ticks = 0u32;
     while running.load(Ordering::SeqCst) {
         thread::sleep(std::time::Duration::from_millis(100));
+        ticks += 1;
+        // Una volta al secondo: un'unità espulsa o smontata da fuori viene fermata qui
+        if ticks % 10 == 0 && reap_removed_volumes(&mut hosts) {
+            crate::shutdown_runtime(rt);
+            return Err(anyhow::anyhow!("Every filesystem was unmounted from outside the client"));
+        }
     }
     if cfg!(debug_assertions) {
-    println!("[Mount] Smonto il filesystem...");}
+    println!("[Mount] Smonto i filesystem...");}
+    for (_, mut host) in hosts {
+        host.stop();
+        host.unmount();
+    }
+    // Fermati i dispatcher di WinFsp, nessuna operazione usa più il runtime
+    crate::shutdown_runtime(rt);
     Ok(())
 }
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long events are collected before the kernel is notified
+pub const COALESCE_WINDOW: Duration = Duration::from_millis(50);
+/// Events folded into a single batch at most, a longer burst is flushed in several batches
+pub const MAX_BATCH_EVENTS: usize = 1024;
+/// Past this many names in one directory only the directory itself is invalidated
+pub const MAX_ENTRIES_PER_DIR: usize = 64;
+
+/// A kernel cache invalidation, as sent to the FUSE notifier
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Inval {
+    Entry(u64, OsString),
+    Inode(u64),
+}
+
+/// Invalidations received during one window, grouped by parent directory
+#[derive(Debug, Default)]
+pub struct InvalBatch {
+    events: usize,
+    dirs: Vec<u64>,
+    // None once the directory went past MAX_ENTRIES_PER_DIR names
+    names: HashMap<u64, Option<Vec<OsString>>>,
+    inodes: Vec<u64>,
+    seen_inodes: HashSet<u64>,
+}
+
+impl InvalBatch {
+    pub fn push(&mut self, inval: Inval) {
+        self.events += 1;
+        match inval {
+            Inval::Entry(parent, name) => {
+                if !self.names.contains_key(&parent) {
+                    self.dirs.push(parent);
+                    self.names.insert(parent, Some(Vec::new()));
+                }
+                let slot = self.names.get_mut(&parent).unwrap();
+                let overflow = match slot {
+                    Some(list) if !list.contains(&name) => {
+                        if list.len() >= MAX_ENTRIES_PER_DIR {
+                            true
+                        } else {
+                            list.push(name);
+                            false
+                        }
+                    }
+                    _ => false,
+                };
+                if overflow {
+                    *slot = None;
+                }
+            }
+            Inval::Inode(ino) => {
+                if self.seen_inodes.insert(ino) {
+                    self.inodes.push(ino);
+                }
+            }
+        }
+    }
+
+    /// Number of events pushed, duplicates included
+    pub fn len(&self) -> usize {
+        self.events
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events == 0
+    }
+
+    // Function that returns the collapsed invalidations: the names of each directory, the directory once, then the other inodes
+    pub fn drain(mut self) -> Vec<Inval> {
+        let mut out = Vec::new();
+        let mut done = HashSet::new();
+        for dir in self.dirs {
+            if let Some(Some(names)) = self.names.remove(&dir) {
+                out.extend(names.into_iter().map(|n| Inval::Entry(dir, n)));
+            }
+            out.push(Inval::Inode(dir));
+            done.insert(dir);
+        }
+        out.extend(
+            self.inodes
+                .into_iter()
+                .filter(|ino| !done.contains(ino))
+                .map(Inval::Inode),
+        );
+        out
+    }
+}
+
+/// Handle used by the websocket handlers to queue invalidations
+#[derive(Clone)]
+pub struct InvalSender(Sender<Inval>);
+
+impl InvalSender {
+    pub fn entry(&self, parent: u64, name: &OsStr) {
+        self.send(Inval::Entry(parent, name.to_os_string()));
+    }
+
+    pub fn inode(&self, ino: u64) {
+        self.send(Inval::Inode(ino));
+    }
+
+    fn send(&self, inval: Inval) {
+        let _ = self.0.send(inval);
+    }
+}
+
+// Function that starts the thread folding bursts of events, `sink` receives the collapsed invalidations
+// The thread ends when every InvalSender has been dropped
+pub fn spawn_coalescer<F>(mut sink: F) -> InvalSender
+where
+    F: FnMut(Inval) + Send + 'static,
+{
+    let (tx, rx) = channel::<Inval>();
+    thread::spawn(move || {
+        while let Ok(first) = rx.recv() {
+            let mut batch = InvalBatch::default();
+            batch.push(first);
+            let deadline = Instant::now() + COALESCE_WINDOW;
+            while batch.len() < MAX_BATCH_EVENTS {
+                let left = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(left) {
+                    Ok(inval) => batch.push(inval),
+                    Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            for inval in batch.drain() {
+                sink(inval);
+            }
+        }
+    });
+    InvalSender(tx)
+}
+
+// Function that starts the thread holding invalidations back for `tolerance` (--stale-tolerance)
+// before passing them to `out`: the cache may serve stale data until then, and every repeat of a
+// held invalidation is folded into it, so a burst of changes to one file costs one refresh.
+// The deadline is set by the first change, so a file changing without pause is still refreshed
+pub fn spawn_debouncer(tolerance: Duration, out: InvalSender) -> InvalSender {
+    let (tx, rx) = channel::<Inval>();
+    thread::spawn(move || {
+        // Same tolerance for all, so the queue is ordered by deadline
+        let mut held: VecDeque<(Instant, Inval)> = VecDeque::new();
+        let mut held_set: HashSet<Inval> = HashSet::new();
+        loop {
+            let received = match held.front() {
+                Some((due, _)) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(inval) => {
+                    if held_set.insert(inval.clone()) {
+                        held.push_back((Instant::now() + tolerance, inval));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    for (_, inval) in held {
+                        out.send(inval);
+                    }
+                    return;
+                }
+            }
+            let now = Instant::now();
+            while held.front().is_some_and(|(due, _)| *due <= now) {
+                let (_, inval) = held.pop_front().unwrap();
+                held_set.remove(&inval);
+                out.send(inval);
+            }
+        }
+    });
+    InvalSender(tx)
+}
@@ -1,6 +1,106 @@
+pub mod acl;
+pub mod address;
+pub mod api_error;
+pub mod audit;
+pub mod cache_stats;
+pub mod check;
 pub mod file_api;
+pub mod fs_event;
+pub mod invalidation;
+pub mod lru;
+#[cfg(unix)]
+pub mod mount_error;
+pub mod mountpoint;
+pub mod options;
+pub mod overlay;
+pub mod path;
+pub mod recovery;
+pub mod temp_dir;
+pub mod throttle;
+pub mod walk;
+pub mod win_link;
+pub mod workers;
 
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long the tasks still running on the runtime (the websocket listeners) get at exit
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Set once the mounts are gone and the runtime is being stopped
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// A filesystem to mount: the backend client and where it appears locally
+pub struct MountSpec {
+    pub mountpoint: String,
+    pub api: file_api::FileApi,
+    pub url: String,
+    pub opts: options::MountOptions,
+}
+
+// Function that builds the multi-threaded runtime shared by the mounts, sized by --io-threads. A new
+// runtime ends the shutdown of the previous one, so mounting again in the same process works
+pub fn io_runtime(io_threads: Option<usize>) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(n) = io_threads {
+        builder.worker_threads(n);
+    }
+    let rt = builder.thread_name("remote-fs-io").enable_all().build()?;
+    SHUTTING_DOWN.store(false, Ordering::SeqCst);
+    Ok(rt)
+}
+
+// Function that picks the --io-threads of the shared runtime: each mount may ask for its own, the
+// largest one serves them all
+pub fn io_threads_of(specs: &[MountSpec]) -> Option<usize> {
+    specs.iter().filter_map(|spec| spec.opts.io_threads).max()
+}
+
+pub fn shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+// Function that runs a backend call to completion from a filesystem or background thread. Once the
+// shutdown began the call is not started and fails with an I/O error, shown to the caller as EIO.
+// On a thread already driving a runtime, where a plain block_on would panic, a worker of a
+// multi-threaded runtime is handed back to it for the duration of the call, any other one runs the
+// call on a thread of its own
+pub fn block_on<T: Send>(
+    rt: &tokio::runtime::Runtime,
+    fut: impl Future<Output = api_error::ApiResult<T>> + Send,
+) -> api_error::ApiResult<T> {
+    if shutting_down() {
+        return Err(api_error::ApiError::Io(std::io::Error::other(
+            "runtime shutting down",
+        )));
+    }
+    match tokio::runtime::Handle::try_current() {
+        Err(_) => rt.block_on(fut),
+        Ok(current) if current.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| rt.block_on(fut))
+        }
+        Ok(_) => std::thread::scope(|s| {
+            s.spawn(|| rt.block_on(fut))
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }),
+    }
+}
+
+// Function that stops the runtime once every mount has been unmounted, so no filesystem operation is
+// still in flight: later backend calls fail at once and the tasks still running get SHUTDOWN_GRACE
+// instead of holding up the exit
+pub fn shutdown_runtime(rt: Arc<tokio::runtime::Runtime>) {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    match Arc::try_unwrap(rt) {
+        Ok(rt) => rt.shutdown_timeout(SHUTDOWN_GRACE),
+        // Background threads still hold it and let it go at their next tick
+        Err(rt) => drop(rt),
+    }
+}
 
 pub fn status_file() -> PathBuf {
     let mut dir = std::env::temp_dir();
@@ -22,18 +122,18 @@ pub fn clear_status() {
 mod fuse_linux;
 
 #[cfg(target_os = "linux")]
-pub use fuse_linux::mount_fs;
+pub use fuse_linux::{mount_all, mount_fs};
 
 #[cfg(target_os = "macos")]
 mod fuse_mac;
 
 #[cfg(target_os = "macos")]
-pub use fuse_mac::mount_fs;
+pub use fuse_mac::{mount_all, mount_fs};
 #[cfg(target_os = "windows")]
 mod fuse_windows;
 
 #[cfg(target_os = "windows")]
-pub use fuse_windows::mount_fs;
+pub use fuse_windows::{mount_all, mount_fs};
 
 #[cfg(not(any(
     target_os = "linux",
@@ -43,9 +143,21 @@ pub use fuse_windows::mount_fs;
 pub fn mount_fs(
     _mountpoint: &str,
     _api: file_api::FileApi,
-    _url: String
+    _url: String,
+    _opts: options::MountOptions,
 ) -> anyhow::Result<()> {
     Err(anyhow::anyhow!(
         "mount_fs is only supported on Linux, macOS or Windows"
     ))
 }
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "windows"
+)))]
+pub fn mount_all(_specs: Vec<MountSpec>) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "mount_all is only supported on Linux, macOS or Windows"
+    ))
+}
@@ -0,0 +1,113 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Map holding at most `capacity` entries: an insert past the cap evicts the least recently used
+/// ones and hands them back, so the caller can drop what hangs on them
+#[derive(Debug)]
+pub struct LruMap<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (V, u64)>,
+    // Keys by the tick of their last use, oldest first
+    order: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> LruMap<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        LruMap {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Function that returns the value of `key` and marks it as the most recently used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tick += 1;
+        let (stored, (_, used)) = self.entries.get_key_value(key)?;
+        let stored = stored.clone();
+        self.order.remove(used);
+        self.order.insert(self.tick, stored.clone());
+        let entry = self.entries.get_mut::<K>(&stored)?;
+        entry.1 = self.tick;
+        Some(&entry.0)
+    }
+
+    // Function that returns the value of `key` without touching the order of use
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    // Function that stores `value` as the most recently used entry; returns the entries evicted to
+    // stay within the capacity
+    pub fn insert(&mut self, key: K, value: V) -> Vec<(K, V)> {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key);
+        let mut evicted = Vec::new();
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((value, _)) = self.entries.remove(&oldest) {
+                evicted.push((oldest, value));
+            }
+        }
+        evicted
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, used) = self.entries.remove(key)?;
+        self.order.remove(&used);
+        Some(value)
+    }
+
+    // Function that lists the entries in no particular order, without touching the order of use
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, (value, _))| (key, value))
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        let order = &mut self.order;
+        self.entries.retain(|key, (value, used)| {
+            let kept = keep(key, value);
+            if !kept {
+                order.remove(used);
+            }
+            kept
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
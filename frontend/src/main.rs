@@ -1,10 +1,24 @@
 use anyhow::Result;
-use frontend::{file_api::FileApi, mount_fs};
+use frontend::{
+    address::backend_url,
+    audit::{audit_backend, checkpoint_path},
+    check::check_backend,
+    file_api::{FileApi, DEFAULT_DATA_TIMEOUT, DEFAULT_MAX_REDIRECTS, DEFAULT_METADATA_TIMEOUT},
+    mount_all,
+    options::MountOptions,
+    overlay::Overlay,
+    path::DEFAULT_PATH_MAX,
+    walk::WalkEnd,
+    MountSpec,
+};
 use std::{
     env, fs,
     io::{self, Write},
-    net::IpAddr,
     path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -72,55 +86,194 @@ fn main() -> Result<(), anyhow::Error> {
         return stop_windows_process();
     }
 
-    let ip = if args.len() > 1 && args[1].parse::<IpAddr>().is_ok() {
-        args[1].clone()
-    } else if args.contains(&"deamon".to_string()) {
-        return Err(anyhow::anyhow!("Errore: IP mancante per l'avvio in background.\nUso: cargo run -- <IP> --deamon"));
+    let opts = MountOptions::from_args(&args)?;
+
+    // (backend URL, mountpoint) pairs: the repeated --mount options, or the single default mount
+    let targets: Vec<(String, String)> = if opts.mounts.is_empty() {
+        // The first argument is the backend address unless it is an option or the daemon flag
+        let positional = args
+            .get(1)
+            .filter(|a| !a.starts_with("--") && a.as_str() != "deamon");
+        let ip = if let Some(addr) = positional {
+            addr.clone()
+        } else if args.contains(&"deamon".to_string()) {
+            return Err(anyhow::anyhow!("Errore: IP mancante per l'avvio in background.\nUso: cargo run -- <IP> deamon"));
+        } else {
+            let mut ip_input = String::new();
+            print!("Inserisci l'indirizzo IP o l'URL del backend: ");
+            io::stdout().flush()?;
+            io::stdin().read_line(&mut ip_input)?;
+            ip_input
+        };
+        vec![(backend_url(&ip)?, get_resolved_mountpoint()?)]
     } else {
-        let mut ip_input = String::new();
-        print!("Inserisci l'indirizzo IP del backend: ");
-        io::stdout().flush()?;
-        io::stdin().read_line(&mut ip_input)?;
-        ip_input.trim().to_string()
+        opts.mounts
+            .iter()
+            .map(|m| Ok((backend_url(&m.backend)?, m.point.clone())))
+            .collect::<Result<_>>()?
     };
 
-    ip.parse::<IpAddr>().map_err(|_| anyhow::anyhow!("Formato IP non valido: {}", ip))?;
-    let mp = get_resolved_mountpoint()?;
+    if opts.check {
+        return check_targets(&targets, &opts);
+    }
+    if opts.audit {
+        return audit_targets(&targets, &opts);
+    }
 
     if args.contains(&"deamon".to_string()) {
-        println!("Avvio del filesystem in background su {}...", mp);
+        let points: Vec<&str> = targets.iter().map(|(_, mp)| mp.as_str()).collect();
+        println!("Avvio del filesystem in background su {}...", points.join(", "));
         #[cfg(any(target_os = "linux", target_os = "macos"))]
-        return run_as_daemon_unix(&ip, &mp);
+        return run_as_daemon_unix(targets, opts);
         
         #[cfg(target_os = "windows")]
-        return run_as_detached_windows(&ip, &mp);
+        return run_as_detached_windows();
     }
 
-    start_filesystem(&ip, &mp)
+    start_filesystem(targets, opts)
 }
 
-fn start_filesystem(ip: &str, mp: &str) -> anyhow::Result<()> {
+fn start_filesystem(targets: Vec<(String, String)>, opts: MountOptions) -> anyhow::Result<()> {
     write_pid()?;
     
-    let url = format!("http://{}:3001", ip);
-    let api = FileApi::new(&url);
     let rt = tokio::runtime::Runtime::new()?;
-    
-    rt.block_on(FileApi::health(ip))?;
-    
-    if cfg!(debug_assertions) {
-        println!("[START] Connesso al backend. Mountpoint: {}", mp);
+    let mut specs = Vec::with_capacity(targets.len());
+    let several = targets.len() > 1;
+    for (i, (url, mp)) in targets.into_iter().enumerate() {
+        let overlay = match overlay_for(&opts, i, several) {
+            Ok(overlay) => overlay,
+            Err(e) => {
+                remove_pid();
+                return Err(e);
+            }
+        };
+        let api = build_api(&url, &opts, overlay);
+
+        if let Err(e) = rt.block_on(api.health()) {
+            remove_pid();
+            return Err(anyhow::anyhow!("Backend non raggiungibile su {}: {}", url, e));
+        }
+        if let (Some(user), Some(password)) = (&opts.user, &opts.password) {
+            if let Err(e) = rt.block_on(api.login(user, password)) {
+                remove_pid();
+                return Err(anyhow::anyhow!("Login fallito su {}: {}", url, e));
+            }
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[START] Connesso al backend {}. Mountpoint: {}", url, mp);
+        }
+        specs.push(MountSpec {
+            mountpoint: mp,
+            api,
+            url,
+            opts: opts.clone(),
+        });
     }
 
-    let res = mount_fs(mp, api, url);
+    let res = mount_all(specs);
     
     remove_pid();
     res
 }
 
+// Function that builds the FileApi of `url` with every option that shapes it, the same for the mount,
+// --check and --audit
+fn build_api(url: &str, opts: &MountOptions, overlay: Option<Overlay>) -> FileApi {
+    FileApi::new(url)
+        .with_snapshot(opts.snapshot.clone())
+        .with_rate_limits(opts.max_upload_rate, opts.max_download_rate)
+        .with_fixed_mtime(opts.fixed_mtime)
+        .with_dir_size_from_backend(opts.dir_size_from_backend)
+        .with_overlay(overlay)
+        .with_max_redirects(opts.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS))
+        .with_headers(&opts.headers)
+        .with_credentials(opts.user.clone(), opts.password.clone())
+        .with_timeouts(
+            opts.metadata_timeout.unwrap_or(DEFAULT_METADATA_TIMEOUT),
+            opts.data_timeout.unwrap_or(DEFAULT_DATA_TIMEOUT),
+        )
+}
+
+// Function that opens the --overlay directory of the i-th backend; each one keeps its changes apart
+// when several are mounted
+fn overlay_for(opts: &MountOptions, i: usize, several: bool) -> anyhow::Result<Option<Overlay>> {
+    let Some(dir) = &opts.overlay else {
+        return Ok(None);
+    };
+    let dir = if several { dir.join(i.to_string()) } else { dir.clone() };
+    Overlay::new(&dir)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("Cartella di overlay {} non utilizzabile: {}", dir.display(), e))
+}
+
+// Function that runs --check against every backend and fails if any endpoint did not pass
+fn check_targets(targets: &[(String, String)], opts: &MountOptions) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let mut failed = Vec::new();
+    for (url, _) in targets {
+        // The probes exercise the backend itself, not an overlay in front of it
+        let api = build_api(url, opts, None);
+        let report = check_backend(&api, &rt);
+        report.print(url);
+        if !report.passed() {
+            failed.push(url.as_str());
+        }
+    }
+    if failed.is_empty() {
+        println!("Il backend implementa tutte le API richieste dal client.");
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Verifica fallita per {}", failed.join(", ")))
+    }
+}
+
+// Function that runs --audit on every backend in turn; Ctrl-C stops it, leaving a checkpoint the next
+// --audit resumes from
+fn audit_targets(targets: &[(String, String)], opts: &MountOptions) -> anyhow::Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))?;
+    let mut failed = Vec::new();
+    let several = targets.len() > 1;
+    for (i, (url, _)) in targets.iter().enumerate() {
+        let api = build_api(url, opts, overlay_for(opts, i, several)?);
+        if let (Some(user), Some(password)) = (&opts.user, &opts.password) {
+            rt.block_on(api.login(user, password))
+                .map_err(|e| anyhow::anyhow!("Login fallito su {}: {}", url, e))?;
+        }
+        println!("Verifica dell'albero del backend {}:", url);
+        let checkpoint = checkpoint_path(opts.audit_checkpoint.as_deref(), url);
+        let path_max = opts.max_path_len.unwrap_or(DEFAULT_PATH_MAX);
+        let audit = audit_backend(&api, &rt, &checkpoint, stop.clone(), path_max)
+            .map_err(|e| anyhow::anyhow!("Verifica di {} non riuscita: {}", url, e))?;
+        let t = &audit.totals;
+        println!(
+            "  {} cartelle, {} file, {} byte, {} problemi",
+            t.dirs, t.files, t.bytes, t.problems
+        );
+        if audit.end == WalkEnd::Interrupted {
+            return Err(anyhow::anyhow!(
+                "Verifica interrotta dopo {}: rilanciando --audit riprende da lì ({})",
+                t.cursor.as_deref().unwrap_or("."),
+                checkpoint.display()
+            ));
+        }
+        if t.problems > 0 {
+            failed.push(url.as_str());
+        }
+    }
+    if failed.is_empty() {
+        println!("Nessun problema trovato.");
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Problemi trovati su {}", failed.join(", ")))
+    }
+}
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
-fn run_as_daemon_unix(ip: &str, mp: &str) -> anyhow::Result<()> {
+fn run_as_daemon_unix(targets: Vec<(String, String)>, opts: MountOptions) -> anyhow::Result<()> {
     let daemon = Daemonize::new()
         .pid_file(pid_file())
         .working_directory(env::current_dir().unwrap_or_else(|_| PathBuf::from("/")))
@@ -129,7 +282,7 @@ fn run_as_daemon_unix(ip: &str, mp: &str) -> anyhow::Result<()> {
 
     daemon.start().map_err(|e| anyhow::anyhow!("Errore demone: {}", e))?;
     
-    start_filesystem(ip, mp)
+    start_filesystem(targets, opts)
 }
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -173,11 +326,11 @@ fn stop_daemon() -> anyhow::Result<()> {
 }
 
 #[cfg(target_os = "windows")]
-fn run_as_detached_windows(ip: &str, _mp: &str) -> anyhow::Result<()> {
+fn run_as_detached_windows() -> anyhow::Result<()> {
     use std::os::windows::process::CommandExt;
     
     let child = std::process::Command::new(std::env::current_exe()?)
-        .arg(ip)
+        .args(env::args().skip(1).filter(|a| a != "deamon"))
         .creation_flags(0x00000008) 
         .spawn();
 
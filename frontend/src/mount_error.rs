@@ -0,0 +1,61 @@
+use std::io;
+
+/// Common reasons for which the kernel refuses the FUSE mount
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MountFailure {
+    /// libfuse/fusermount or macFUSE are not installed, or /dev/fuse does not exist
+    FuseMissing,
+    /// The user may not open /dev/fuse or mount on the mountpoint
+    PermissionDenied,
+    /// Something is already mounted there, or a previous mount was not cleaned up
+    MountpointBusy,
+    /// The mountpoint directory does not exist
+    MountpointMissing,
+    Other,
+}
+
+// Function that maps the error returned by the mount call to one of the known causes
+pub fn classify(err: &io::Error) -> MountFailure {
+    let msg = err.to_string().to_lowercase();
+    match err.raw_os_error() {
+        Some(libc::EACCES) | Some(libc::EPERM) => return MountFailure::PermissionDenied,
+        Some(libc::EBUSY) | Some(libc::ENOTCONN) => return MountFailure::MountpointBusy,
+        Some(libc::ENODEV) => return MountFailure::FuseMissing,
+        _ => {}
+    }
+    if msg.contains("fusermount") || msg.contains("/dev/fuse") || msg.contains("macfuse") {
+        return MountFailure::FuseMissing;
+    }
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => MountFailure::PermissionDenied,
+        io::ErrorKind::NotFound => MountFailure::MountpointMissing,
+        _ if msg.contains("busy") || msg.contains("not connected") => MountFailure::MountpointBusy,
+        _ => MountFailure::Other,
+    }
+}
+
+// Function that builds the message shown to the user, with a hint on how to fix the problem
+pub fn describe(err: &io::Error, mountpoint: &str) -> String {
+    let hint = match classify(err) {
+        MountFailure::FuseMissing => {
+            if cfg!(target_os = "macos") {
+                "FUSE non disponibile: installa macFUSE (https://osxfuse.github.io) e consenti l'estensione nelle impostazioni di sicurezza.".to_string()
+            } else {
+                "FUSE non disponibile: installa fuse3 (es. `sudo apt install fuse3`) e verifica che esista /dev/fuse (`sudo modprobe fuse`).".to_string()
+            }
+        }
+        MountFailure::PermissionDenied => {
+            "Permesso negato: aggiungi l'utente al gruppo `fuse` o controlla i permessi di /dev/fuse e del mountpoint.".to_string()
+        }
+        MountFailure::MountpointBusy => format!(
+            "Mountpoint occupato: {} è già montato o è rimasto un mount precedente, smontalo con `fusermount -u {}` (`umount {}` su macOS).",
+            mountpoint, mountpoint, mountpoint
+        ),
+        MountFailure::MountpointMissing => format!(
+            "Il mountpoint {} non esiste: crealo prima di avviare il client.",
+            mountpoint
+        ),
+        MountFailure::Other => "Errore di mount non riconosciuto.".to_string(),
+    };
+    format!("Impossibile montare il filesystem su {}: {}\n{}", mountpoint, err, hint)
+}
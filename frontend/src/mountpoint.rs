@@ -0,0 +1,67 @@
+use std::path::Path;
+
+// Function that lists why `path` is not a safe place to mount for the current user: owned by
+// someone else, or with permissions that let the owner lose control of it. A missing mountpoint is
+// not reported here, the mount itself explains it
+#[cfg(unix)]
+pub fn problems(path: &Path) -> Vec<String> {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(meta) = std::fs::metadata(path) else {
+        return Vec::new();
+    };
+    if !meta.is_dir() {
+        return vec!["non è una cartella".to_string()];
+    }
+    let mut found = Vec::new();
+    let uid = unsafe { libc::getuid() };
+    let mode = meta.mode() & 0o7777;
+    if meta.uid() != uid {
+        found.push(format!("appartiene all'utente {} e non a {}", meta.uid(), uid));
+    } else if mode & 0o700 != 0o700 {
+        found.push(format!("il proprietario non ha i permessi rwx (modo {:o})", mode));
+    }
+    // Without the sticky bit anyone could remove or replace it while it is not mounted
+    if mode & 0o002 != 0 && mode & 0o1000 == 0 {
+        found.push(format!("è scrivibile da tutti gli utenti (modo {:o})", mode));
+    }
+    found
+}
+
+// Function that lists why WinFsp could not create `path`: a directory mountpoint is created by the
+// mount, so its parent must exist and be writable. Drive letters are not checked
+#[cfg(windows)]
+pub fn problems(path: &Path) -> Vec<String> {
+    let is_drive = path.to_string_lossy().trim_end_matches(['\\', '/']).len() == 2;
+    let parent = match path.parent() {
+        Some(p) if !is_drive && !p.as_os_str().is_empty() => p,
+        _ => return Vec::new(),
+    };
+    match std::fs::metadata(parent) {
+        Ok(meta) if !meta.is_dir() => vec![format!("{} non è una cartella", parent.display())],
+        Ok(meta) if meta.permissions().readonly() => {
+            vec![format!("la cartella {} è in sola lettura", parent.display())]
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => vec![format!("la cartella {} non è accessibile: {}", parent.display(), e)],
+    }
+}
+
+// Function that checks the mountpoint before mounting: a problem refuses the mount, or with
+// --force is only printed as a warning
+pub fn verify(mountpoint: &str, force: bool) -> Result<(), String> {
+    let found = problems(Path::new(mountpoint));
+    if found.is_empty() {
+        return Ok(());
+    }
+    if force {
+        for problem in &found {
+            eprintln!("[WARN] Il mountpoint {} {}: montato comunque (--force)", mountpoint, problem);
+        }
+        return Ok(());
+    }
+    Err(format!(
+        "Mountpoint {} rifiutato: {}.\nScegli una cartella tua con permessi rwx, o usa --force per montare comunque.",
+        mountpoint,
+        found.join(", ")
+    ))
+}
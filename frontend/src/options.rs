@@ -0,0 +1,742 @@
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Period between two uploads of the dirty handles with `--commit-strategy interval`
+pub const COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Failed commits of a file after which its data goes to the recovery directory and is not sent again
+pub const DEFAULT_COMMIT_ATTEMPTS: usize = 5;
+
+/// Entries kept by each of the attribute and listing caches when `--max-cache-entries` is not given
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = 50_000;
+
+/// How long a name found missing is remembered, by the client and the kernel, without `--negative-ttl`
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(1);
+
+/// Longest wait between two commit attempts of a file, whatever `--commit-backoff` says
+pub const MAX_COMMIT_BACKOFF: Duration = Duration::from_secs(300);
+
+/// How symlinks whose target leaves the mount root are handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Refuse to create or follow links that escape the mount root
+    #[default]
+    Strict,
+    /// Hand the target to the kernel as is, it resolves it against the local filesystem
+    Passthrough,
+}
+
+impl SymlinkPolicy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "strict" => Ok(SymlinkPolicy::Strict),
+            "passthrough" => Ok(SymlinkPolicy::Passthrough),
+            other => Err(anyhow!(
+                "Valore non valido per --symlink-policy: {} (strict|passthrough)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymlinkPolicy::Strict => "strict",
+            SymlinkPolicy::Passthrough => "passthrough",
+        }
+    }
+
+    /// True when a link placed in `link_dir` (relative to the mount root) may point to `target`
+    pub fn allows(&self, link_dir: &Path, target: &Path) -> bool {
+        *self == SymlinkPolicy::Passthrough || !symlink_escapes_root(link_dir, target)
+    }
+}
+
+/// How the Windows layer presents the symlinks reported by the backend
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowsSymlinks {
+    /// NTFS reparse points, following them needs SeCreateSymbolicLinkPrivilege or developer mode
+    Reparse,
+    /// `.lnk` shortcut files that Explorer follows
+    Shortcut,
+    /// Not listed at all
+    #[default]
+    Hide,
+}
+
+impl WindowsSymlinks {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "reparse" => Ok(WindowsSymlinks::Reparse),
+            "shortcut" => Ok(WindowsSymlinks::Shortcut),
+            "hide" => Ok(WindowsSymlinks::Hide),
+            other => Err(anyhow!(
+                "Valore non valido per --windows-symlinks: {} (reparse|shortcut|hide)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WindowsSymlinks::Reparse => "reparse",
+            WindowsSymlinks::Shortcut => "shortcut",
+            WindowsSymlinks::Hide => "hide",
+        }
+    }
+}
+
+/// When the data written through a handle is sent to the backend
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitStrategy {
+    /// Upload when the handle is closed or fsync'd
+    #[default]
+    Close,
+    /// Upload after every write, the tempfile is only a staging buffer
+    WriteThrough,
+    /// Upload the dirty handles every COMMIT_INTERVAL, and at close
+    Interval,
+}
+
+impl CommitStrategy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "close" => Ok(CommitStrategy::Close),
+            "writethrough" => Ok(CommitStrategy::WriteThrough),
+            "interval" => Ok(CommitStrategy::Interval),
+            other => Err(anyhow!(
+                "Valore non valido per --commit-strategy: {} (close|writethrough|interval)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitStrategy::Close => "close",
+            CommitStrategy::WriteThrough => "writethrough",
+            CommitStrategy::Interval => "interval",
+        }
+    }
+}
+
+/// What happens at close to the unsent data of a file removed or replaced on the backend while open
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Discard the local data and report ESTALE
+    #[default]
+    Fail,
+    /// Upload the local data at the path the file was opened at, last writer wins
+    Overwrite,
+    /// Upload the local data next to it, under a `.conflict` name
+    Rename,
+}
+
+impl ConflictPolicy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "fail" => Ok(ConflictPolicy::Fail),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "rename" => Ok(ConflictPolicy::Rename),
+            other => Err(anyhow!(
+                "Valore non valido per --conflict-policy: {} (fail|overwrite|rename)",
+                other
+            )),
+        }
+    }
+}
+
+/// Source of the mount when --fs-name is not given
+pub const DEFAULT_FS_NAME: &str = "remote_fs";
+
+/// Bounds of --max-read/--max-write: one page, and the largest request fuser accepts
+pub const MIN_TRANSFER_SIZE: u32 = 4096;
+pub const MAX_TRANSFER_SIZE: u32 = 16 * 1024 * 1024;
+
+/// What `unlink` does with a file that is still open
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnlinkPolicy {
+    /// Hide the file and delete it on the backend when its last handle is released (POSIX)
+    #[default]
+    Deferred,
+    /// Delete it on the backend right away, the open handles lose their data
+    Immediate,
+}
+
+impl UnlinkPolicy {
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "deferred" => Ok(UnlinkPolicy::Deferred),
+            "immediate" => Ok(UnlinkPolicy::Immediate),
+            other => Err(anyhow!(
+                "Valore non valido per --unlink-policy: {} (deferred|immediate)",
+                other
+            )),
+        }
+    }
+}
+
+/// A `--cache-rule` entry: paths matching `pattern` are cached for `ttl` instead of the default
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheRule {
+    pub pattern: String,
+    pub ttl: Duration,
+}
+
+impl CacheRule {
+    // Function that parses a comma separated list of `glob=seconds` rules
+    fn parse_list(value: &str) -> Result<Vec<CacheRule>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (pattern, secs) = rule
+                    .rsplit_once('=')
+                    .ok_or_else(|| anyhow!("Regola non valida per --cache-rule: {} (glob=secondi)", rule))?;
+                let secs = secs.trim().parse::<u64>().map_err(|_| {
+                    anyhow!("TTL non valido per --cache-rule: {} (secondi)", rule)
+                })?;
+                let pattern = pattern.trim().trim_start_matches("./").trim_start_matches('/');
+                Ok(CacheRule {
+                    pattern: pattern.to_string(),
+                    ttl: Duration::from_secs(secs),
+                })
+            })
+            .collect()
+    }
+
+    /// True when the canonical path `rel` ("." or "./a/b") matches the rule; a pattern without '/' is matched against the name only
+    pub fn matches(&self, rel: &str) -> bool {
+        let path = rel.trim_start_matches('.').trim_start_matches('/');
+        let target = if self.pattern.contains('/') {
+            path
+        } else {
+            path.rsplit('/').next().unwrap_or(path)
+        };
+        glob_match(self.pattern.as_bytes(), target.as_bytes())
+    }
+}
+
+/// A `--mount backend=<address>,point=<dir>` entry, several of them mount several backends in one process
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MountTarget {
+    pub backend: String,
+    pub point: String,
+}
+
+impl MountTarget {
+    fn parse(value: &str) -> Result<Self> {
+        let mut backend = None;
+        let mut point = None;
+        for part in value.split(',') {
+            match part.trim().split_once('=') {
+                Some(("backend", v)) if !v.trim().is_empty() => backend = Some(v.trim().to_string()),
+                Some(("point", v)) if !v.trim().is_empty() => point = Some(v.trim().to_string()),
+                _ => {
+                    return Err(anyhow!(
+                        "Valore non valido per --mount: {} (backend=<indirizzo>,point=<cartella>)",
+                        value
+                    ))
+                }
+            }
+        }
+        match (backend, point) {
+            (Some(backend), Some(point)) => Ok(MountTarget { backend, point }),
+            _ => Err(anyhow!(
+                "Valore non valido per --mount: {} (servono sia backend che point)",
+                value
+            )),
+        }
+    }
+}
+
+/// Options read from the command line and forwarded to the mounted filesystem
+#[derive(Clone, Debug, Default)]
+pub struct MountOptions {
+    pub symlink_policy: SymlinkPolicy,
+    /// --windows-symlinks: presentation of the backend symlinks on Windows
+    pub windows_symlinks: WindowsSymlinks,
+    /// Cap on the handles open for writing at the same time, `None` means unlimited
+    pub max_open_handles: Option<usize>,
+    /// Backend version or timestamp of a read-only snapshot view
+    pub snapshot: Option<String>,
+    pub commit_strategy: CommitStrategy,
+    /// --commit-attempts: failed commits of a file before it is given up, `None` means
+    /// DEFAULT_COMMIT_ATTEMPTS
+    pub commit_attempts: Option<usize>,
+    /// --commit-backoff: wait before retrying a failed commit, doubled at each failure, `None`
+    /// means COMMIT_INTERVAL
+    pub commit_backoff: Option<Duration>,
+    pub conflict_policy: ConflictPolicy,
+    /// Per-path TTL overrides, the first matching rule wins
+    pub cache_rules: Vec<CacheRule>,
+    /// --max-cache-entries: entries of the attribute and listing caches before the least recently
+    /// used are dropped, `None` means DEFAULT_MAX_CACHE_ENTRIES
+    pub max_cache_entries: Option<usize>,
+    /// --negative-ttl: how long a lookup that found nothing is trusted, `None` means
+    /// DEFAULT_NEGATIVE_TTL and zero asks the backend every time
+    pub negative_ttl: Option<Duration>,
+    /// How long a file changed on the backend may be served stale, `None` refreshes it right away
+    pub stale_tolerance: Option<Duration>,
+    /// How often a file held open is re-stat'ed to follow its size and mtime, `None` never does
+    pub coherency_interval: Option<Duration>,
+    /// Inactivity after which the websocket is closed until the next operation, `None` keeps it open
+    pub ws_idle_timeout: Option<Duration>,
+    /// Timeout of metadata and mutation requests, `None` keeps the FileApi default
+    pub metadata_timeout: Option<Duration>,
+    /// Timeout of each read or upload request, `None` keeps the FileApi default
+    pub data_timeout: Option<Duration>,
+    /// Backends mounted by this process, empty for the single default mount
+    pub mounts: Vec<MountTarget>,
+    /// Worker threads of the tokio runtime running backend I/O, `None` means one per CPU
+    pub io_threads: Option<usize>,
+    /// Threads serving the reads of the filesystem in parallel, `None` serves them one at a time on
+    /// the session thread (Linux, macOS) or keeps the WinFsp default (Windows)
+    pub fuse_threads: Option<usize>,
+    pub unlink_policy: UnlinkPolicy,
+    /// Largest read request the kernel sends, in bytes, `None` keeps the kernel default
+    pub max_read: Option<u32>,
+    /// Largest write request the kernel sends, in bytes, `None` keeps the fuser default
+    pub max_write: Option<u32>,
+    /// Directory buffering open files, `None` lets temp_dir::choose pick a disk-backed one
+    pub temp_dir: Option<PathBuf>,
+    /// Where writes the backend did not take are kept instead of being deleted, `None` drops them
+    pub recovery_dir: Option<PathBuf>,
+    /// --recovery-retry: upload the files of recovery_dir once the backend answers again
+    pub recovery_retry: bool,
+    /// --overlay: local directory taking every change, the backend is only read
+    pub overlay: Option<PathBuf>,
+    /// Bytes per second sent to the backend at most, `None` means unthrottled
+    pub max_upload_rate: Option<u64>,
+    /// Bytes per second read from the backend at most, `None` means unthrottled
+    pub max_download_rate: Option<u64>,
+    /// mtime shown for every entry (--fixed-mtime, seconds since the epoch), `None` shows the backend's
+    pub fixed_mtime: Option<SystemTime>,
+    /// Longest canonical path the backend accepts, in bytes, `None` means path::DEFAULT_PATH_MAX
+    pub max_path_len: Option<usize>,
+    /// Redirects followed by each backend request, `None` keeps the FileApi default
+    pub max_redirects: Option<usize>,
+    /// --trash: deletes move entries to the backend trash instead of removing them
+    pub trash: bool,
+    /// --user / --password (or REMOTEFS_USER / REMOTEFS_PASSWORD): credentials of the backend
+    /// session login, `None` when the backend needs none
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// --header NAME=VALUE, repeatable: headers added to every backend request and to the websocket
+    /// handshake
+    pub headers: Vec<(String, String)>,
+    /// --acl: present the backend's POSIX ACLs as system.posix_acl_* xattrs (Linux)
+    pub acl: bool,
+    /// --dir-size-from-backend: show the size the backend reports for directories instead of 0
+    pub dir_size_from_backend: bool,
+    /// --force: mount on a mountpoint failing the ownership and permission checks, with a warning
+    pub force: bool,
+    /// --backend-auth: no local permission checks, the backend alone accepts or refuses each operation
+    pub backend_auth: bool,
+    /// --check: probe the backend endpoints and exit instead of mounting
+    pub check: bool,
+    /// --audit: walk the whole backend tree reporting what the client cannot show, then exit
+    pub audit: bool,
+    /// --audit-checkpoint: where an interrupted --audit leaves its checkpoint, `None` means the
+    /// remote-fs folder of the system temp dir
+    pub audit_checkpoint: Option<PathBuf>,
+    /// --fs-name: source shown by mount and /proc/mounts (the volume label on Windows), `None`
+    /// keeps DEFAULT_FS_NAME
+    pub fs_name: Option<String>,
+    /// --fs-type: subtype, shown as fuse.<type> (the file system name on Windows), `None` sets none
+    pub fs_type: Option<String>,
+}
+
+impl MountOptions {
+    // Function that parses the `--flag value` / `--flag=value` options, positional arguments are left to the caller
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut opts = MountOptions::default();
+        let mut it = args.iter().skip(1);
+        while let Some(arg) = it.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            match flag {
+                "--symlink-policy" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.symlink_policy = SymlinkPolicy::parse(&value)?;
+                }
+                "--windows-symlinks" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.windows_symlinks = WindowsSymlinks::parse(&value)?;
+                }
+                "--max-open-handles" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_open_handles = Some(parse_positive(flag, &value)?);
+                }
+                "--snapshot" => {
+                    opts.snapshot = Some(flag_value(flag, inline, &mut it)?);
+                }
+                "--commit-strategy" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.commit_strategy = CommitStrategy::parse(&value)?;
+                }
+                "--commit-attempts" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.commit_attempts = Some(parse_positive(flag, &value)?);
+                }
+                "--commit-backoff" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.commit_backoff = Some(Duration::from_secs(parse_positive(flag, &value)? as u64));
+                }
+                "--conflict-policy" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.conflict_policy = ConflictPolicy::parse(&value)?;
+                }
+                "--cache-rule" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.cache_rules.extend(CacheRule::parse_list(&value)?);
+                }
+                "--max-cache-entries" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_cache_entries = Some(parse_positive(flag, &value)?);
+                }
+                "--negative-ttl" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    let millis = value.parse::<u64>().map_err(|_| {
+                        anyhow!("Valore non valido per {}: {} (millisecondi)", flag, value)
+                    })?;
+                    opts.negative_ttl = Some(Duration::from_millis(millis));
+                }
+                "--metadata-timeout" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.metadata_timeout = Some(Duration::from_secs(parse_positive(flag, &value)? as u64));
+                }
+                "--stale-tolerance" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.stale_tolerance = Some(Duration::from_millis(parse_positive(flag, &value)? as u64));
+                }
+                "--coherency-interval" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.coherency_interval = Some(Duration::from_secs(parse_positive(flag, &value)? as u64));
+                }
+                "--ws-idle-timeout" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.ws_idle_timeout = Some(Duration::from_secs(parse_positive(flag, &value)? as u64));
+                }
+                "--data-timeout" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.data_timeout = Some(Duration::from_secs(parse_positive(flag, &value)? as u64));
+                }
+                "--mount" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.mounts.push(MountTarget::parse(&value)?);
+                }
+                "--io-threads" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.io_threads = Some(parse_positive(flag, &value)?);
+                }
+                "--fs-name" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.fs_name = Some(parse_fs_name(flag, &value)?);
+                }
+                "--fs-type" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.fs_type = Some(parse_fs_type(flag, &value)?);
+                }
+                "--fuse-threads" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.fuse_threads = Some(parse_positive(flag, &value)?);
+                }
+                "--unlink-policy" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.unlink_policy = UnlinkPolicy::parse(&value)?;
+                }
+                "--max-read" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_read = Some(parse_transfer_size(flag, &value)?);
+                }
+                "--max-write" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_write = Some(parse_transfer_size(flag, &value)?);
+                }
+                "--temp-dir" => {
+                    let value = PathBuf::from(flag_value(flag, inline, &mut it)?);
+                    if !value.is_dir() {
+                        return Err(anyhow!(
+                            "La cartella indicata da --temp-dir non esiste: {}",
+                            value.display()
+                        ));
+                    }
+                    opts.temp_dir = Some(value);
+                }
+                "--recovery-dir" => {
+                    let value = PathBuf::from(flag_value(flag, inline, &mut it)?);
+                    if !value.is_dir() {
+                        return Err(anyhow!(
+                            "La cartella indicata da --recovery-dir non esiste: {}",
+                            value.display()
+                        ));
+                    }
+                    opts.recovery_dir = Some(value);
+                }
+                "--overlay" => {
+                    opts.overlay = Some(PathBuf::from(flag_value(flag, inline, &mut it)?));
+                }
+                "--max-upload-rate" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_upload_rate = Some(parse_rate(flag, &value)?);
+                }
+                "--max-download-rate" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_download_rate = Some(parse_rate(flag, &value)?);
+                }
+                "--fixed-mtime" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    let secs = value.parse::<u64>().map_err(|_| {
+                        anyhow!("Valore non valido per {}: {} (secondi dall'epoch)", flag, value)
+                    })?;
+                    opts.fixed_mtime = Some(UNIX_EPOCH + Duration::from_secs(secs));
+                }
+                "--max-path-len" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.max_path_len = Some(parse_positive(flag, &value)?);
+                }
+                "--max-redirects" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    let max = value.parse::<usize>().map_err(|_| {
+                        anyhow!("Valore non valido per {}: {} (intero non negativo)", flag, value)
+                    })?;
+                    opts.max_redirects = Some(max);
+                }
+                "--user" => {
+                    opts.user = Some(flag_value(flag, inline, &mut it)?);
+                }
+                "--password" => {
+                    opts.password = Some(flag_value(flag, inline, &mut it)?);
+                }
+                "--header" => {
+                    let value = flag_value(flag, inline, &mut it)?;
+                    opts.headers.push(parse_header(flag, &value)?);
+                }
+                "--backend-auth" => opts.backend_auth = true,
+                "--recovery-retry" => opts.recovery_retry = true,
+                "--trash" => opts.trash = true,
+                "--force" => opts.force = true,
+                "--dir-size-from-backend" => opts.dir_size_from_backend = true,
+                "--acl" => opts.acl = true,
+                "--check" => opts.check = true,
+                "--audit" => opts.audit = true,
+                "--audit-checkpoint" => {
+                    opts.audit_checkpoint = Some(PathBuf::from(flag_value(flag, inline, &mut it)?));
+                }
+                // A mistyped option would mount with the opposite of what was asked
+                _ if flag.starts_with("--") => return Err(anyhow!("Opzione sconosciuta: {}", flag)),
+                // The backend address and `deamon`, read by main
+                _ => {}
+            }
+        }
+        // The environment keeps the password out of the process list
+        opts.user = opts.user.or_else(|| std::env::var("REMOTEFS_USER").ok());
+        opts.password = opts.password.or_else(|| std::env::var("REMOTEFS_PASSWORD").ok());
+        if opts.user.is_some() != opts.password.is_some() {
+            return Err(anyhow!("--user e --password vanno indicati insieme"));
+        }
+        if opts.recovery_retry && opts.recovery_dir.is_none() {
+            return Err(anyhow!("--recovery-retry richiede --recovery-dir"));
+        }
+        Ok(opts)
+    }
+
+    pub fn max_cache_entries(&self) -> usize {
+        self.max_cache_entries.unwrap_or(DEFAULT_MAX_CACHE_ENTRIES)
+    }
+
+    pub fn negative_ttl(&self) -> Duration {
+        self.negative_ttl.unwrap_or(DEFAULT_NEGATIVE_TTL)
+    }
+
+    pub fn commit_attempts(&self) -> usize {
+        self.commit_attempts.unwrap_or(DEFAULT_COMMIT_ATTEMPTS)
+    }
+
+    // Function that returns how long a commit that failed `failures` times waits before the next attempt
+    pub fn commit_retry_delay(&self, failures: usize) -> Duration {
+        let base = self.commit_backoff.unwrap_or(COMMIT_INTERVAL);
+        let doublings = failures.saturating_sub(1).min(16) as u32;
+        base.saturating_mul(1 << doublings).min(MAX_COMMIT_BACKOFF)
+    }
+
+    // Function that returns the TTL of the first --cache-rule matching the canonical path, if any
+    pub fn cache_ttl_for(&self, rel: &str) -> Option<Duration> {
+        self.cache_rules
+            .iter()
+            .find(|rule| rule.matches(rel))
+            .map(|rule| rule.ttl)
+    }
+}
+
+fn flag_value<'a>(
+    flag: &str,
+    inline: Option<String>,
+    it: &mut impl Iterator<Item = &'a String>,
+) -> Result<String> {
+    match inline {
+        Some(value) => Ok(value),
+        None => it
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("Valore mancante per {}", flag)),
+    }
+}
+
+fn parse_positive(flag: &str, value: &str) -> Result<usize> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(anyhow!("Valore non valido per {}: {} (intero positivo)", flag, value)),
+    }
+}
+
+// Function that checks a --fs-name against what the platform accepts: mount options are split at
+// commas and whitespace, a Windows volume label has 32 characters at most and no punctuation
+fn parse_fs_name(flag: &str, value: &str) -> Result<String> {
+    let (max, forbidden): (usize, &[char]) = if cfg!(windows) {
+        (32, &['*', '?', '/', '\\', '|', '.', ',', ';', ':', '+', '=', '[', ']', '<', '>', '"'])
+    } else {
+        (255, &[','])
+    };
+    let valid = !value.is_empty()
+        && value.chars().count() <= max
+        && !value.chars().any(|c| c.is_control() || c.is_whitespace() || forbidden.contains(&c));
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(anyhow!(
+            "Valore non valido per {}: {:?} (al massimo {} caratteri, senza spazi, virgole{})",
+            flag,
+            value,
+            max,
+            if cfg!(windows) { " e punteggiatura" } else { "" }
+        ))
+    }
+}
+
+// Function that checks a --fs-type: letters, digits, '_' and '-' only, since it becomes part of
+// the type fuse.<type>; WinFsp keeps 15 characters of a file system name
+fn parse_fs_type(flag: &str, value: &str) -> Result<String> {
+    let max = if cfg!(windows) { 15 } else { 32 };
+    let valid = !value.is_empty()
+        && value.len() <= max
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(anyhow!(
+            "Valore non valido per {}: {:?} (lettere, cifre, '_' e '-', al massimo {} caratteri)",
+            flag,
+            value,
+            max
+        ))
+    }
+}
+
+fn parse_transfer_size(flag: &str, value: &str) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(n) if (MIN_TRANSFER_SIZE..=MAX_TRANSFER_SIZE).contains(&n) => Ok(n),
+        _ => Err(anyhow!(
+            "Valore non valido per {}: {} (byte, da {} a {})",
+            flag,
+            value,
+            MIN_TRANSFER_SIZE,
+            MAX_TRANSFER_SIZE
+        )),
+    }
+}
+
+// Function that parses a `NAME=VALUE` header, refusing the ones the client sets itself
+fn parse_header(flag: &str, value: &str) -> Result<(String, String)> {
+    const RESERVED: [&str; 5] = ["host", "content-length", "transfer-encoding", "range", "cookie"];
+    let (name, header) = value
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Valore non valido per {}: {} (NOME=VALORE)", flag, value))?;
+    let name = name.trim();
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(anyhow!("Nome di header non valido per {}: {:?}", flag, name));
+    }
+    if RESERVED.contains(&name.to_ascii_lowercase().as_str()) {
+        return Err(anyhow!("L'header {} è gestito dal client e non si può impostare con {}", name, flag));
+    }
+    // HeaderValue takes any byte past 0x7f too, which servers read in different charsets
+    let visible = header.chars().all(|c| c == ' ' || c == '\t' || c.is_ascii_graphic());
+    if !visible || HeaderValue::from_str(header).is_err() {
+        return Err(anyhow!("Valore di header non valido per {}: {:?} (solo ASCII visibile)", flag, header));
+    }
+    Ok((name.to_string(), header.to_string()))
+}
+
+// Function that parses a rate in bytes per second, with an optional K, M or G suffix (powers of 1024)
+fn parse_rate(flag: &str, value: &str) -> Result<u64> {
+    let (digits, unit) = match value.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&value[..i], c.to_ascii_uppercase()),
+        _ => (value, 'B'),
+    };
+    let multiplier: u64 = match unit {
+        'B' => 1,
+        'K' => 1024,
+        'M' => 1024 * 1024,
+        'G' => 1024 * 1024 * 1024,
+        _ => 0,
+    };
+    match digits.parse::<u64>() {
+        Ok(n) if n > 0 && multiplier > 0 => n
+            .checked_mul(multiplier)
+            .ok_or_else(|| anyhow!("Valore troppo grande per {}: {}", flag, value)),
+        _ => Err(anyhow!(
+            "Valore non valido per {}: {} (byte al secondo, es. 512K o 2M)",
+            flag,
+            value
+        )),
+    }
+}
+
+// Function that matches `text` against a glob: `*` stops at '/', `**` crosses it, `?` is a single character
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => match rest.split_first() {
+            Some((b'*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+            _ => (0..=text.len())
+                .take_while(|&i| i == 0 || text[i - 1] != b'/')
+                .any(|i| glob_match(rest, &text[i..])),
+        },
+        Some((b'?', rest)) => {
+            matches!(text.split_first(), Some((c, tail)) if *c != b'/' && glob_match(rest, tail))
+        }
+        Some((p, rest)) => {
+            matches!(text.split_first(), Some((c, tail)) if c == p && glob_match(rest, tail))
+        }
+    }
+}
+
+/// Returns true when `target`, resolved from `link_dir` (relative to the mount root), ends up outside the root
+pub fn symlink_escapes_root(link_dir: &Path, target: &Path) -> bool {
+    if target.has_root() {
+        return true;
+    }
+    let mut depth = link_dir
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count();
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    false
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::api_error::ApiError;
+use crate::file_api::{split_time, DirectoryEntry};
+use crate::path::{canonical_rel, child_of, parent_and_name};
+
+/// Prefix of the marker left in the upper directory for an entry deleted from the view (aufs naming)
+const WHITEOUT_PREFIX: &str = ".wh.";
+/// Marker of an upper directory that hides the backend directory at the same path entirely
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+
+/// Local directory of an --overlay mount: modified and new entries live here (the upper layer),
+/// untouched ones are read from the backend (the lower layer)
+pub struct Overlay {
+    root: PathBuf,
+}
+
+impl Overlay {
+    pub fn new(root: &Path) -> io::Result<Self> {
+        fs::create_dir_all(root)?;
+        Ok(Overlay {
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// Place of a relPath in the upper directory
+    pub fn upper(&self, rel: &str) -> PathBuf {
+        let canonical = canonical_rel(Path::new(rel));
+        match canonical.strip_prefix("./") {
+            Some(inner) => self.root.join(inner),
+            None => self.root.clone(),
+        }
+    }
+
+    fn whiteout(&self, rel: &str) -> PathBuf {
+        let (parent, name) = parent_and_name(rel);
+        self.upper(&parent).join(format!("{}{}", WHITEOUT_PREFIX, name))
+    }
+
+    /// The entry is served by the upper directory
+    pub fn has_upper(&self, rel: &str) -> bool {
+        fs::symlink_metadata(self.upper(rel)).is_ok()
+    }
+
+    pub fn is_opaque(&self, rel: &str) -> bool {
+        self.upper(rel).join(OPAQUE_MARKER).exists()
+    }
+
+    // Function that tells whether the backend entry at `rel` is out of the view: it or one of its
+    // parents was deleted through the overlay, or a parent was recreated as an opaque directory
+    pub fn hides(&self, rel: &str) -> bool {
+        let mut dir = String::from(".");
+        for name in canonical_rel(Path::new(rel)).split('/').skip(1) {
+            let child = child_of(&dir, name).to_string_lossy().into_owned();
+            if self.whiteout(&child).exists() || (dir != "." && self.is_opaque(&dir)) {
+                return true;
+            }
+            dir = child;
+        }
+        false
+    }
+
+    /// The backend must not be asked about `rel`: the upper directory serves it or hides it
+    pub fn shadows(&self, rel: &str) -> bool {
+        self.has_upper(rel) || self.hides(rel)
+    }
+
+    /// Entry of the upper directory at `rel`, in the form the backend lists
+    pub fn entry(&self, rel: &str) -> io::Result<DirectoryEntry> {
+        let meta = fs::symlink_metadata(self.upper(rel))?;
+        let (_, name) = parent_and_name(rel);
+        let (secs, nanos) = split_time(meta.modified().unwrap_or(UNIX_EPOCH));
+        let crtime = meta.created().ok().map(split_time);
+        let kind = if meta.is_dir() {
+            "dir"
+        } else if meta.file_type().is_symlink() {
+            "symlink"
+        } else {
+            "file"
+        };
+        Ok(DirectoryEntry {
+            name,
+            path: Some(canonical_rel(Path::new(rel))),
+            size: if meta.is_dir() { 0 } else { meta.len() as i64 },
+            stored_size: None,
+            mtime: secs as i64,
+            permissions: format!("{:o}", permissions_of(&meta)),
+            is_dir: meta.is_dir() as i64,
+            version: 0,
+            nlink: if meta.is_dir() { 2 } else { 1 },
+            mtime_ns: nanos as i64,
+            kind: Some(kind.to_string()),
+            symlink_target: fs::read_link(self.upper(rel))
+                .ok()
+                .map(|t| t.to_string_lossy().into_owned()),
+            partial: false,
+            crtime: crtime.map(|(s, _)| s as i64),
+            crtime_ns: crtime.map_or(0, |(_, n)| n as i64),
+        })
+    }
+
+    // Function that lays the upper directory over the backend listing of `rel`: whiteouts drop
+    // entries, upper entries replace or join them
+    pub fn merge(&self, rel: &str, lower: Vec<DirectoryEntry>) -> Vec<DirectoryEntry> {
+        let dir = self.upper(rel);
+        let mut upper = Vec::new();
+        if let Ok(read) = fs::read_dir(&dir) {
+            for item in read.flatten() {
+                let Some(name) = item.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if name.starts_with(WHITEOUT_PREFIX) {
+                    continue;
+                }
+                if let Ok(de) = self.entry(&child_of(rel, &name).to_string_lossy()) {
+                    upper.push(de);
+                }
+            }
+        }
+        let names: HashSet<String> = upper.iter().map(|de| de.name.clone()).collect();
+        let mut merged = Vec::new();
+        if !self.hides(rel) && !self.is_opaque(rel) {
+            merged.extend(lower.into_iter().filter(|de| {
+                !names.contains(&de.name) && !self.whiteout(&child_of(rel, &de.name).to_string_lossy()).exists()
+            }));
+        }
+        merged.extend(upper);
+        merged
+    }
+
+    // Function that makes room for a new upper entry at `rel`: its parent directories are created
+    // and its whiteout removed. Returns the upper path and whether a whiteout was there
+    pub fn prepare(&self, rel: &str) -> io::Result<(PathBuf, bool)> {
+        let (_, name) = parent_and_name(rel);
+        if name.starts_with(WHITEOUT_PREFIX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:?}: names starting with {} are reserved by --overlay", name, WHITEOUT_PREFIX),
+            ));
+        }
+        let path = self.upper(rel);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let had_whiteout = fs::remove_file(self.whiteout(rel)).is_ok();
+        Ok((path, had_whiteout))
+    }
+
+    /// Replaces the content of the upper file at `rel` with `data`, creating it when needed
+    pub fn write(&self, rel: &str, data: &mut impl Read) -> io::Result<()> {
+        let (path, _) = self.prepare(rel)?;
+        let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+        io::copy(data, &mut file)?;
+        Ok(())
+    }
+
+    // Function that creates the upper directory `rel`. Recreated over a deleted backend directory
+    // it is opaque, so the old content does not come back
+    pub fn mkdir(&self, rel: &str) -> io::Result<()> {
+        let (path, had_whiteout) = self.prepare(rel)?;
+        fs::create_dir(&path)?;
+        if had_whiteout {
+            fs::File::create(path.join(OPAQUE_MARKER))?;
+        }
+        Ok(())
+    }
+
+    // Function that deletes `rel` from the view: the upper copy goes away, and a whiteout hides the
+    // backend entry when `in_backend`
+    pub fn remove(&self, rel: &str, in_backend: bool) -> io::Result<()> {
+        let path = self.upper(rel);
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_dir() => fs::remove_dir_all(&path)?,
+            Ok(_) => fs::remove_file(&path)?,
+            Err(_) => {}
+        }
+        if in_backend {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::File::create(self.whiteout(rel))?;
+        }
+        Ok(())
+    }
+
+    // Function that moves the upper entry `from` to `to`; `from_in_backend` leaves a whiteout for
+    // the backend entry it was copied up from
+    pub fn rename(&self, from: &str, to: &str, from_in_backend: bool) -> io::Result<()> {
+        let (target, had_whiteout) = self.prepare(to)?;
+        let source = self.upper(from);
+        let is_dir = source.is_dir();
+        fs::rename(&source, &target)?;
+        if is_dir && had_whiteout {
+            fs::File::create(target.join(OPAQUE_MARKER))?;
+        }
+        if from_in_backend {
+            fs::File::create(self.whiteout(from))?;
+        }
+        Ok(())
+    }
+
+    // Function that creates the upper symlink `rel`; the target is stored as given, never resolved
+    #[cfg(unix)]
+    pub fn symlink(&self, target: &str, rel: &str) -> io::Result<()> {
+        let (path, _) = self.prepare(rel)?;
+        std::os::unix::fs::symlink(target, path)
+    }
+
+    #[cfg(windows)]
+    pub fn symlink(&self, _target: &str, _rel: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "--overlay keeps no symlinks on Windows"))
+    }
+
+    pub fn readlink(&self, rel: &str) -> io::Result<String> {
+        Ok(fs::read_link(self.upper(rel))?.to_string_lossy().into_owned())
+    }
+
+    /// Bytes of the upper file at `rel` from `offset`, at most `len`
+    pub fn read_at(&self, rel: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut file = fs::File::open(self.upper(rel))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut out = Vec::with_capacity(len.min(1 << 20));
+        file.take(len as u64).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    pub fn set_len(&self, rel: &str, size: u64) -> io::Result<()> {
+        fs::OpenOptions::new().write(true).open(self.upper(rel))?.set_len(size)
+    }
+
+    pub fn set_times(&self, rel: &str, atime: SystemTime, mtime: SystemTime) -> io::Result<()> {
+        let times = fs::FileTimes::new().set_accessed(atime).set_modified(mtime);
+        let path = self.upper(rel);
+        let file = if path.is_dir() {
+            fs::File::open(&path)?
+        } else {
+            fs::File::options().write(true).open(&path)?
+        };
+        file.set_times(times)
+    }
+
+    #[cfg(unix)]
+    pub fn chmod(&self, rel: &str, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(self.upper(rel), fs::Permissions::from_mode(mode & 0o7777))
+    }
+
+    #[cfg(unix)]
+    pub fn chown(&self, rel: &str, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        std::os::unix::fs::lchown(self.upper(rel), uid, gid)
+    }
+
+    #[cfg(windows)]
+    pub fn chown(&self, _rel: &str, _uid: Option<u32>, _gid: Option<u32>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no POSIX owners on Windows"))
+    }
+
+    // Only the write bits survive on Windows, as the read-only attribute
+    #[cfg(windows)]
+    pub fn chmod(&self, rel: &str, mode: u32) -> io::Result<()> {
+        let path = self.upper(rel);
+        let mut perms = fs::metadata(&path)?.permissions();
+        perms.set_readonly(mode & 0o222 == 0);
+        fs::set_permissions(path, perms)
+    }
+}
+
+#[cfg(unix)]
+fn permissions_of(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(windows)]
+fn permissions_of(meta: &fs::Metadata) -> u32 {
+    let base = if meta.is_dir() { 0o755 } else { 0o644 };
+    if meta.permissions().readonly() {
+        base & !0o222
+    } else {
+        base
+    }
+}
+
+/// Error the backend would answer for `op`, so a refusal of an --overlay mount reaches the
+/// platform layers in the form they already map
+pub fn backend_error(op: &'static str, status: u16, body: serde_json::Value) -> ApiError {
+    ApiError::Http {
+        op,
+        status,
+        body: body.to_string(),
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+/// Longest name of a single entry, in bytes, that local filesystems and the backend accept
+pub const NAME_MAX: usize = 255;
+/// Longest canonical path sent to the backend unless --max-path-len says otherwise
+pub const DEFAULT_PATH_MAX: usize = 4096;
+
+// Splits a path on its separators, both of them on Windows (on Unix a backslash is part of the
+// name), dropping empty and "." components; ".." drops the one before it and stops at the root
+fn components(path: &Path) -> Vec<String> {
+    let text = path.to_string_lossy();
+    #[cfg(windows)]
+    let text = text.replace('\\', "/");
+    let mut parts: Vec<String> = Vec::new();
+    for c in text.split('/') {
+        match c {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(c.to_string()),
+        }
+    }
+    parts
+}
+
+/// Backend form of a path: "." for the root, "./a/b" for everything else
+pub fn canonical_rel(path: &Path) -> String {
+    let parts = components(path);
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        format!("./{}", parts.join("/"))
+    }
+}
+
+/// Splits a path into its canonical parent and its name; the root has an empty name
+pub fn parent_and_name(rel: &str) -> (String, String) {
+    let mut parts = components(Path::new(rel));
+    let name = parts.pop().unwrap_or_default();
+    let parent = if parts.is_empty() {
+        ".".to_string()
+    } else {
+        format!("./{}", parts.join("/"))
+    };
+    (parent, name)
+}
+
+/// Canonical path of the entry `name` inside `parent_rel`
+pub fn child_of(parent_rel: &str, name: &str) -> PathBuf {
+    PathBuf::from(canonical_rel(&Path::new(parent_rel).join(name)))
+}
+
+/// Whether a component of `path` is longer than NAME_MAX, or its canonical form longer than
+/// `path_max` bytes: such an entry cannot be created, the request is not even sent
+pub fn too_long(path: &Path, path_max: usize) -> bool {
+    components(path).iter().any(|c| c.len() > NAME_MAX) || canonical_rel(path).len() > path_max
+}
+
+/// Mount-side form of a path, as seen under the mountpoint: "/" for the root, "/a/b" otherwise
+pub fn mount_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("/{}", components(path).join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_rel_of_the_root() {
+        for root in ["", ".", "/", "./", "//", "/./", "..", "/.."] {
+            assert_eq!(canonical_rel(Path::new(root)), ".", "{:?}", root);
+        }
+    }
+
+    #[test]
+    fn canonical_rel_of_nested_paths() {
+        assert_eq!(canonical_rel(Path::new("a")), "./a");
+        assert_eq!(canonical_rel(Path::new("/a/b")), "./a/b");
+        assert_eq!(canonical_rel(Path::new("./a/b")), "./a/b");
+        assert_eq!(canonical_rel(Path::new("a/b/")), "./a/b");
+        assert_eq!(canonical_rel(Path::new("//a//b//")), "./a/b");
+        assert_eq!(canonical_rel(Path::new("/a/./b/.")), "./a/b");
+    }
+
+    #[test]
+    fn dot_dot_never_leaves_the_root() {
+        assert_eq!(canonical_rel(Path::new("/a/../b")), "./b");
+        assert_eq!(canonical_rel(Path::new("a/b/../..")), ".");
+        assert_eq!(canonical_rel(Path::new("/../../etc/passwd")), "./etc/passwd");
+        assert_eq!(mount_path(Path::new("../a")), Path::new("/a"));
+    }
+
+    #[test]
+    fn backslashes_are_separators_on_windows_only() {
+        let rel = canonical_rel(Path::new("a\\b"));
+        if cfg!(windows) {
+            assert_eq!(rel, "./a/b");
+            assert_eq!(canonical_rel(Path::new("\\a\\b\\")), "./a/b");
+            assert_eq!(parent_and_name("a\\b\\c.txt"), ("./a/b".to_string(), "c.txt".to_string()));
+            assert_eq!(child_of(".\\a", "b"), Path::new("./a/b"));
+            assert_eq!(mount_path(Path::new("a\\b")), Path::new("/a/b"));
+        } else {
+            assert_eq!(rel, "./a\\b");
+            assert_eq!(parent_and_name("a\\b/c.txt"), ("./a\\b".to_string(), "c.txt".to_string()));
+        }
+    }
+
+    #[test]
+    fn parent_and_name_splits_the_last_component() {
+        assert_eq!(parent_and_name("./a/b/c.txt"), ("./a/b".to_string(), "c.txt".to_string()));
+        assert_eq!(parent_and_name("/a.txt"), (".".to_string(), "a.txt".to_string()));
+        assert_eq!(parent_and_name("a/b/"), ("./a".to_string(), "b".to_string()));
+        // The root has no name
+        assert_eq!(parent_and_name("."), (".".to_string(), String::new()));
+        assert_eq!(parent_and_name(""), (".".to_string(), String::new()));
+    }
+
+    #[test]
+    fn child_of_joins_canonically() {
+        assert_eq!(child_of(".", "a"), Path::new("./a"));
+        assert_eq!(child_of("/", "a"), Path::new("./a"));
+        assert_eq!(child_of("./a/", "b"), Path::new("./a/b"));
+        assert_eq!(child_of("a//b", "c.txt"), Path::new("./a/b/c.txt"));
+        // An empty name is the parent itself, ".." its parent
+        assert_eq!(child_of("./a", ""), Path::new("./a"));
+        assert_eq!(child_of("./a/b", ".."), Path::new("./a"));
+    }
+
+    #[test]
+    fn too_long_checks_names_and_the_whole_path() {
+        let name = "n".repeat(NAME_MAX);
+        assert!(!too_long(Path::new(&format!("/{}", name)), DEFAULT_PATH_MAX));
+        assert!(too_long(Path::new(&format!("/{}n", name)), DEFAULT_PATH_MAX));
+        // "./" plus 8 bytes fits 10 bytes, one more does not
+        assert!(!too_long(Path::new("/abcd/efg"), 10));
+        assert!(too_long(Path::new("/abcd/efgh"), 10));
+        // Separators that collapse do not count
+        assert!(!too_long(Path::new("//abcd//efg//"), 10));
+    }
+
+    #[test]
+    fn mount_path_is_absolute() {
+        assert_eq!(mount_path(Path::new(".")), Path::new("/"));
+        assert_eq!(mount_path(Path::new("")), Path::new("/"));
+        assert_eq!(mount_path(Path::new("./a/b")), Path::new("/a/b"));
+        assert_eq!(mount_path(Path::new("a/b/")), Path::new("/a/b"));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::file_api::FileApi;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+
+/// How often --recovery-retry checks whether the backend answers again
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A file of --recovery-dir: data written to `rel` that never reached the backend
+pub struct Recovered {
+    /// Name in the recovery directory and under .remotefs/recovered
+    pub name: String,
+    /// Canonical backend path the data was meant for
+    pub rel: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime: SystemTime,
+}
+
+// Function that turns a canonical path into a single file name; '%' and '/' are escaped so the
+// name decodes back to the path
+fn encode(rel: &str) -> String {
+    rel.trim_start_matches("./")
+        .replace('%', "%25")
+        .replace('/', "%2F")
+}
+
+fn decode(name: &str) -> String {
+    format!("./{}", name.replace("%2F", "/").replace("%25", "%"))
+}
+
+// Function that moves the tempfile of a write the backend did not take into the recovery
+// directory; a newer version of the same file replaces the older one
+pub fn save(dir: &Path, rel: &str, temp: &Path) -> io::Result<PathBuf> {
+    let dest = dir.join(encode(rel));
+    if std::fs::rename(temp, &dest).is_err() {
+        // The tempfile may live on another filesystem; the mtime is kept, retry compares it
+        std::fs::copy(temp, &dest)?;
+        if let Ok(mtime) = std::fs::metadata(temp).and_then(|m| m.modified()) {
+            let _ = std::fs::File::options().write(true).open(&dest).and_then(|f| f.set_modified(mtime));
+        }
+        let _ = std::fs::remove_file(temp);
+    }
+    eprintln!(
+        "[WARN] {} non e' stato inviato al backend: i dati sono stati salvati in {:?}",
+        rel, dest
+    );
+    Ok(dest)
+}
+
+// Function that copies the tempfile of a handle still open into the recovery directory, keeping its
+// mtime; the handle goes on writing to the tempfile, a later save replaces the copy
+pub fn keep(dir: &Path, rel: &str, temp: &Path) -> io::Result<PathBuf> {
+    let dest = dir.join(encode(rel));
+    std::fs::copy(temp, &dest)?;
+    if let Ok(mtime) = std::fs::metadata(temp).and_then(|m| m.modified()) {
+        let _ = std::fs::File::options().write(true).open(&dest).and_then(|f| f.set_modified(mtime));
+    }
+    Ok(dest)
+}
+
+pub fn stat(dir: &Path, name: &str) -> Option<Recovered> {
+    let path = dir.join(name);
+    let meta = std::fs::metadata(&path).ok().filter(|m| m.is_file())?;
+    Some(Recovered {
+        name: name.to_string(),
+        rel: decode(name),
+        path,
+        size: meta.len(),
+        mtime: meta.modified().unwrap_or(UNIX_EPOCH),
+    })
+}
+
+// Function that lists the recovered files, sorted by name
+pub fn list(dir: &Path) -> Vec<Recovered> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut out: Vec<Recovered> = entries
+        .flatten()
+        .filter_map(|e| stat(dir, &e.file_name().to_string_lossy()))
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+// Function that uploads the recovered files and removes the ones the backend took; a file changed
+// on the backend after the data was saved is left alone, uploading it would undo that change
+pub async fn retry(dir: &Path, api: &FileApi) {
+    for r in list(dir) {
+        match api.get_update_metadata(&r.rel).await {
+            Ok(de) if de.mtime_time() > r.mtime => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[RECOVERY] {} changed on the backend, left in {:?}", r.rel, r.path);
+                }
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) if e.is_not_found() => {}
+            // The backend went away again, the next round retries
+            Err(_) => return,
+        }
+        match api.write_file(&r.rel, &r.path).await {
+            Ok(()) => {
+                let _ = std::fs::remove_file(&r.path);
+                println!("[INFO] {} recuperato e inviato al backend", r.rel);
+            }
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    eprintln!("[RECOVERY] Retry of {} failed: {}", r.rel, e);
+                }
+                return;
+            }
+        }
+    }
+}
+
+// Function that starts the thread committing the recovered files whenever the backend answers
+// (--recovery-retry)
+pub fn spawn_retry(dir: PathBuf, api: FileApi, rt: Arc<Runtime>) {
+    thread::spawn(move || loop {
+        if crate::shutting_down() {
+            break;
+        }
+        if !list(&dir).is_empty() && crate::block_on(&rt, api.health()).is_ok() {
+            let _ = crate::block_on(&rt, async {
+                retry(&dir, &api).await;
+                Ok(())
+            });
+        }
+        thread::sleep(RETRY_INTERVAL);
+    });
+}
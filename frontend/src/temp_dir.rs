@@ -0,0 +1,154 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A candidate directory with less free space than this is skipped when buffering writes
+pub const MIN_FREE_SPACE: u64 = 64 * 1024 * 1024;
+
+// Function that returns the bytes an unprivileged user can still write in the filesystem of `dir`
+#[cfg(unix)]
+pub fn free_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 {
+        return None;
+    }
+    Some(st.f_bavail as u64 * st.f_frsize as u64)
+}
+
+#[cfg(windows)]
+pub fn free_space(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+// Function that tells whether `dir` lives in RAM (tmpfs/ramfs), where large buffers eat memory
+#[cfg(target_os = "linux")]
+fn is_ram_backed(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut st: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut st) } != 0 {
+        return false;
+    }
+    // f_type has a different width on each libc, both magics fit in 32 bits
+    const TMPFS_MAGIC: u32 = 0x0102_1994;
+    const RAMFS_MAGIC: u32 = 0x8584_58f6;
+    let fs_type = st.f_type as u32;
+    fs_type == TMPFS_MAGIC || fs_type == RAMFS_MAGIC
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_ram_backed(_dir: &Path) -> bool {
+    false
+}
+
+// Function that picks where open files are buffered: --temp-dir when given, otherwise the first
+// disk-backed candidate with room, since the system temp dir is often a small tmpfs
+pub fn choose(override_dir: Option<&Path>) -> PathBuf {
+    if let Some(dir) = override_dir {
+        return dir.to_path_buf();
+    }
+    let system = std::env::temp_dir();
+    let mut candidates = vec![system.clone()];
+    if cfg!(unix) {
+        candidates.push(PathBuf::from("/var/tmp"));
+    }
+    if let Some(cache) = dirs::cache_dir() {
+        candidates.push(cache.join("remote-fs"));
+    }
+    pick(&candidates, |dir| {
+        std::fs::create_dir_all(dir)
+            .ok()
+            .map(|_| (is_ram_backed(dir), free_space(dir)))
+    })
+    .map(Path::to_path_buf)
+    .unwrap_or(system)
+}
+
+// Function that returns the first candidate on disk with room to buffer writes. `probe` tells
+// whether a directory is RAM-backed and its free space, None when it cannot be used at all
+pub fn pick(
+    candidates: &[PathBuf],
+    probe: impl Fn(&Path) -> Option<(bool, Option<u64>)>,
+) -> Option<&Path> {
+    candidates.iter().map(PathBuf::as_path).find(|dir| {
+        probe(dir).is_some_and(|(ram_backed, free)| {
+            !ram_backed && free.is_none_or(|free| free >= MIN_FREE_SPACE)
+        })
+    })
+}
+
+// Function that reserves `size` bytes on disk for a tempfile without changing its length, as
+// requested by the allocation size of a Windows CreateFile
+#[cfg(windows)]
+pub fn preallocate(file: &std::fs::File, size: u64) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAllocationInfo, SetFileInformationByHandle, FILE_ALLOCATION_INFO,
+    };
+    let info = FILE_ALLOCATION_INFO {
+        AllocationSize: size as i64,
+    };
+    let ok = unsafe {
+        SetFileInformationByHandle(
+            file.as_raw_handle(),
+            FileAllocationInfo,
+            &info as *const FILE_ALLOCATION_INFO as *const std::ffi::c_void,
+            std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+        )
+    };
+    if ok != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// Function that fails with ENOSPC when `dir` cannot hold `needed` more bytes
+pub fn ensure_room(dir: &Path, needed: u64) -> io::Result<()> {
+    match free_space(dir) {
+        Some(free) if free < needed => Err(io::Error::new(
+            io::ErrorKind::StorageFull,
+            format!(
+                "{} bytes needed in {:?}, only {} available",
+                needed, dir, free
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Function that writes `data` at `offset` in the tempfile of a handle whose length is `size`, and
+// returns the new length. When the write stops midway (a full disk) the file is cut back to `size`,
+// so it never holds more bytes than the handle accounts for
+pub fn write_at(path: &Path, size: u64, offset: u64, data: &[u8]) -> io::Result<u64> {
+    use std::io::{Seek, SeekFrom, Write};
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let written = file
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| file.write_all(data));
+    if let Err(e) = written {
+        let _ = file.set_len(size);
+        return Err(e);
+    }
+    Ok(size.max(offset + data.len() as u64))
+}
+
+// Function that tells whether a tempfile write failed because the local disk is full
+pub fn is_disk_full(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::StorageFull
+}
@@ -0,0 +1,39 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Smallest piece an upload is split into while throttled
+pub const MIN_THROTTLED_CHUNK: usize = 64 * 1024;
+
+/// Caps the bytes per second moved in one direction, shared by every request of a FileApi
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    // When the bytes granted so far have been paid for at the configured rate
+    paid_until: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec: bytes_per_sec.max(1),
+            paid_until: Mutex::new(Instant::now()),
+        }
+    }
+
+    // Function that sizes uploads to about one second of transfer, so the rate stays smooth
+    pub fn chunk_size(&self, max: usize) -> usize {
+        (self.bytes_per_sec as usize).clamp(MIN_THROTTLED_CHUNK.min(max), max)
+    }
+
+    // Function that waits until `bytes` more fit in the rate; idle time does not build up credit
+    pub async fn consume(&self, bytes: usize) {
+        let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let wait = {
+            let mut paid_until = self.paid_until.lock().unwrap();
+            let now = Instant::now();
+            *paid_until = (*paid_until).max(now) + cost;
+            *paid_until - now
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,163 @@
+use crate::api_error::ApiResult;
+use crate::file_api::{DirectoryEntry, EntryKind, FileApi};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often a listing in flight looks at the stop flag
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Where an interrupted walk resumes: the last directory it listed and the totals up to there
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Backend walked, a checkpoint of another backend is ignored
+    pub backend: String,
+    /// Canonical path of the last directory listed, `None` before the first one
+    pub cursor: Option<String>,
+    pub dirs: u64,
+    pub files: u64,
+    pub bytes: u64,
+    pub problems: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WalkEnd {
+    /// Every directory was listed, the checkpoint is gone
+    Done,
+    /// Stopped by the flag, the checkpoint holds where to resume
+    Interrupted,
+}
+
+/// Depth-first walk of the backend tree, in name order, saving a checkpoint after each directory.
+/// Only the subdirectories still to visit along the current branch are kept in memory, and a walk
+/// started again on the same checkpoint skips what the previous one already listed
+pub struct Walk<'a> {
+    api: &'a FileApi,
+    checkpoint_path: PathBuf,
+    stop: Arc<AtomicBool>,
+    pub state: Checkpoint,
+}
+
+impl<'a> Walk<'a> {
+    // Function that prepares a walk, resuming from the checkpoint file if one was left for this backend
+    pub fn new(api: &'a FileApi, checkpoint_path: &Path, stop: Arc<AtomicBool>) -> Self {
+        let state = std::fs::read(checkpoint_path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Checkpoint>(&data).ok())
+            .filter(|c| c.backend == api.base_url())
+            .unwrap_or_else(|| Checkpoint {
+                backend: api.base_url().to_string(),
+                ..Default::default()
+            });
+        Walk {
+            api,
+            checkpoint_path: checkpoint_path.to_path_buf(),
+            stop,
+            state,
+        }
+    }
+
+    // Function that runs the walk, handing each directory listed to `visit` with its entries or the
+    // error that kept it from being listed; `visit` may update the totals of the checkpoint
+    pub async fn run(
+        &mut self,
+        mut visit: impl FnMut(&str, ApiResult<&[DirectoryEntry]>, &mut Checkpoint),
+    ) -> ApiResult<WalkEnd> {
+        let resume = self.state.cursor.as_deref().map(parts);
+        let mut pending: Vec<Vec<String>> = vec![Vec::new()];
+        while let Some(dir) = pending.pop() {
+            let listed = resume.as_ref().is_some_and(|cursor| dir <= *cursor);
+            // Before the cursor and not one of its ancestors: the whole subtree was walked already
+            if listed && !resume.as_ref().is_some_and(|cursor| cursor.starts_with(&dir)) {
+                continue;
+            }
+            let rel = rel_of(&dir);
+            let Some(result) = self.list(&rel).await else {
+                self.save()?;
+                return Ok(WalkEnd::Interrupted);
+            };
+            // An unreadable directory is reported and its subtree skipped, the walk goes on
+            let entries = match result {
+                Ok(entries) => entries,
+                Err(e) => {
+                    if !listed {
+                        visit(&rel, Err(e), &mut self.state);
+                        self.checkpoint(rel)?;
+                    }
+                    continue;
+                }
+            };
+            if !listed {
+                visit(&rel, Ok(&entries), &mut self.state);
+                self.checkpoint(rel)?;
+            }
+            // A name that is not a single component would lead the walk back up the tree
+            let mut subdirs: Vec<String> = entries
+                .into_iter()
+                .filter(|de| de.entry_kind() == EntryKind::Dir)
+                .filter(|de| !matches!(de.name.as_str(), "" | "." | "..") && !de.name.contains('/'))
+                .map(|de| de.name)
+                .collect();
+            subdirs.sort();
+            for name in subdirs.into_iter().rev() {
+                let mut child = dir.clone();
+                child.push(name);
+                pending.push(child);
+            }
+        }
+        let _ = std::fs::remove_file(&self.checkpoint_path);
+        Ok(WalkEnd::Done)
+    }
+
+    // Function that lists a directory, giving up as soon as the stop flag is raised
+    async fn list(&self, rel: &str) -> Option<ApiResult<Vec<DirectoryEntry>>> {
+        if self.stop.load(Ordering::SeqCst) {
+            return None;
+        }
+        tokio::select! {
+            res = self.api.ls(rel) => Some(res),
+            _ = stopped(&self.stop) => None,
+        }
+    }
+
+    fn checkpoint(&mut self, rel: String) -> ApiResult<()> {
+        self.state.cursor = Some(rel);
+        self.save()
+    }
+
+    // Function that writes the checkpoint next to its final name and moves it there, so an interruption
+    // while saving leaves the previous one
+    fn save(&self) -> ApiResult<()> {
+        if let Some(dir) = self.checkpoint_path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.checkpoint_path.with_extension("tmp");
+        let data = serde_json::to_vec(&self.state).map_err(std::io::Error::other)?;
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &self.checkpoint_path)?;
+        Ok(())
+    }
+}
+
+async fn stopped(stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        tokio::time::sleep(STOP_POLL).await;
+    }
+}
+
+fn parts(rel: &str) -> Vec<String> {
+    rel.split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .map(str::to_string)
+        .collect()
+}
+
+fn rel_of(parts: &[String]) -> String {
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        format!("./{}", parts.join("/"))
+    }
+}
@@ -0,0 +1,87 @@
+use crate::file_api::{DirectoryEntry, EntryKind};
+use crate::options::WindowsSymlinks;
+
+/// Reparse tag of an NTFS symbolic link
+pub const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+/// Extension of the shortcut files standing for backend symlinks with --windows-symlinks shortcut
+pub const SHORTCUT_EXT: &str = ".lnk";
+
+// ShellLinkHeader fields of MS-SHLLINK: the CLSID every shortcut carries, HasRelativePath | IsUnicode
+// as the only flags and SW_SHOWNORMAL
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const IS_UNICODE: u32 = 0x80;
+const SW_SHOWNORMAL: u32 = 1;
+const SYMLINK_FLAG_RELATIVE: u32 = 1;
+
+// Function that returns how the Windows layer lists a backend entry: unchanged unless it is a
+// symlink, which is dropped with `hide`, kept (and marked as a reparse point) with `reparse`, or
+// turned into a `<name>.lnk` file with `shortcut`. A link whose target the listing lacks is hidden
+pub fn present(mode: WindowsSymlinks, mut de: DirectoryEntry) -> Option<DirectoryEntry> {
+    if de.entry_kind() != EntryKind::Symlink {
+        return Some(de);
+    }
+    let target = de.symlink_target.clone()?;
+    match mode {
+        WindowsSymlinks::Hide => None,
+        WindowsSymlinks::Reparse => Some(de),
+        WindowsSymlinks::Shortcut => {
+            de.name.push_str(SHORTCUT_EXT);
+            de.kind = Some("file".to_string());
+            de.size = shortcut(&target).len() as i64;
+            de.stored_size = None;
+            Some(de)
+        }
+    }
+}
+
+/// True for an entry `present` turned into a shortcut: a file that still carries the link target
+pub fn is_shortcut(de: &DirectoryEntry) -> bool {
+    de.entry_kind() == EntryKind::File && de.symlink_target.is_some()
+}
+
+// Function that converts a backend link target to a Windows relative path
+fn windows_path(target: &str) -> Vec<u16> {
+    target.replace('/', "\\").encode_utf16().collect()
+}
+
+// Function that builds the .lnk file pointing at `target`: a ShellLinkHeader, the target as
+// RELATIVE_PATH string data and the terminal block, the smallest shortcut Explorer follows
+pub fn shortcut(target: &str) -> Vec<u8> {
+    let path = windows_path(target);
+    let mut out = Vec::with_capacity(76 + 2 + path.len() * 2 + 4);
+    out.extend_from_slice(&0x4Cu32.to_le_bytes());
+    out.extend_from_slice(&LINK_CLSID);
+    out.extend_from_slice(&(HAS_RELATIVE_PATH | IS_UNICODE).to_le_bytes());
+    // FileAttributes, the three FILETIMEs, FileSize and IconIndex are left to 0
+    out.extend_from_slice(&[0; 4 + 24 + 4 + 4]);
+    out.extend_from_slice(&SW_SHOWNORMAL.to_le_bytes());
+    // HotKey and the three reserved fields
+    out.extend_from_slice(&[0; 2 + 2 + 4 + 4]);
+    out.extend_from_slice(&(path.len() as u16).to_le_bytes());
+    out.extend(path.iter().flat_map(|c| c.to_le_bytes()));
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out
+}
+
+// Function that builds the REPARSE_DATA_BUFFER of a relative symbolic link to `target`, returned
+// by get_reparse_point with --windows-symlinks reparse. The substitute and the print name are the
+// same path, one after the other
+pub fn reparse_data(target: &str) -> Vec<u8> {
+    let path = windows_path(target);
+    let name_len = (path.len() * 2) as u16;
+    let mut out = Vec::with_capacity(20 + 2 * name_len as usize);
+    out.extend_from_slice(&IO_REPARSE_TAG_SYMLINK.to_le_bytes());
+    out.extend_from_slice(&(12 + 2 * name_len).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    for field in [0, name_len, name_len, name_len] {
+        out.extend_from_slice(&field.to_le_bytes());
+    }
+    out.extend_from_slice(&SYMLINK_FLAG_RELATIVE.to_le_bytes());
+    for _ in 0..2 {
+        out.extend(path.iter().flat_map(|c| c.to_le_bytes()));
+    }
+    out
+}
@@ -0,0 +1,41 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads the FUSE layer hands blocking operations to (--fuse-threads), so the session loop goes
+/// on reading requests while the backend answers. The reply travels with the job
+pub struct WorkerPool {
+    jobs: Sender<Job>,
+}
+
+impl WorkerPool {
+    pub fn new(threads: usize, name: &str) -> std::io::Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for i in 0..threads.max(1) {
+            let queue = queue.clone();
+            std::thread::Builder::new()
+                .name(format!("{}-{}", name, i))
+                .spawn(move || work(&queue))?;
+        }
+        Ok(WorkerPool { jobs })
+    }
+
+    // Function that queues `job` for the first free thread
+    pub fn run(&self, job: impl FnOnce() + Send + 'static) {
+        // The threads only stop once the pool is dropped, the queue is always open here
+        let _ = self.jobs.send(Box::new(job));
+    }
+}
+
+// Threads end when the pool, and with it the sending side, goes away
+fn work(queue: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = queue.lock().unwrap().recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
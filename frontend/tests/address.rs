@@ -0,0 +1,31 @@
+use frontend::address::backend_url;
+
+#[test]
+fn an_empty_or_blank_address_is_reported_as_missing() {
+    for input in ["", "   ", "\n", " \t\r\n"] {
+        let err = backend_url(input).unwrap_err().to_string();
+        assert!(err.starts_with("Indirizzo del backend mancante"), "{:?}: {}", input, err);
+    }
+}
+
+#[test]
+fn a_bare_ip_gets_the_default_port() {
+    assert_eq!(backend_url("192.168.1.10\n").unwrap(), "http://192.168.1.10:3001");
+    assert_eq!(backend_url("  10.0.0.1:8080 ").unwrap(), "http://10.0.0.1:8080");
+    assert_eq!(backend_url("::1").unwrap(), "http://[::1]:3001");
+    assert_eq!(backend_url("nas.local").unwrap(), "http://nas.local:3001");
+
+    let err = backend_url("300.1.2.3").unwrap_err().to_string();
+    assert!(err.starts_with("Formato IP non valido"), "{}", err);
+}
+
+#[test]
+fn a_full_url_is_kept_as_given() {
+    assert_eq!(backend_url("http://nas.local:3001/\n").unwrap(), "http://nas.local:3001");
+    assert_eq!(backend_url(" https://files.example.com ").unwrap(), "https://files.example.com");
+
+    for input in ["ftp://nas.local", "http://", "http://exa mple.com"] {
+        let err = backend_url(input).unwrap_err().to_string();
+        assert!(err.starts_with("URL del backend non valido"), "{:?}: {}", input, err);
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{FakeBackend, Node, Response};
+use frontend::api_error::ApiError;
+use frontend::file_api::FileApi;
+use serde_json::json;
+use std::io::ErrorKind;
+
+#[tokio::test]
+async fn a_refused_call_is_an_http_error_with_its_status_and_body() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let api = backend.api();
+
+    let err = api.ls("./missing").await.unwrap_err();
+    assert!(matches!(err, ApiError::Http { op: "ls", status: 404, .. }), "{:?}", err);
+    assert!(err.is_not_found());
+    let err = api.delete("./missing.txt").await.unwrap_err();
+    assert!(matches!(err, ApiError::Http { op: "delete", status: 404, .. }), "{:?}", err);
+    assert_eq!(err.status(), Some(404));
+    let err = api.read_range("./missing.txt", 0, 9).await.unwrap_err();
+    assert!(matches!(err, ApiError::Http { op: "read_range", status: 404, .. }), "{:?}", err);
+
+    backend.set_hook(|req, _| match req.route().as_str() {
+        "POST /mkdir" => Some(Response::json(500, json!({ "error": "disk on fire" }))),
+        "PATCH /files/rename" => Some(Response::json(
+            500,
+            json!({ "error": "cross-device link", "code": "EXDEV" }),
+        )),
+        _ => None,
+    });
+    let err = api.mkdir("./new").await.unwrap_err();
+    match &err {
+        ApiError::Http { op, status, body } => {
+            assert_eq!((*op, *status), ("mkdir", 500));
+            assert!(body.contains("disk on fire"), "{}", body);
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(!err.is_not_found());
+    assert!(err.to_string().starts_with("mkdir failed: 500 - "), "{}", err);
+    let err = api.rename("./a.txt", "./b.txt").await.unwrap_err();
+    assert!(matches!(err, ApiError::Http { op: "rename", status: 500, .. }), "{:?}", err);
+    assert!(err.is_cross_device());
+}
+
+#[tokio::test]
+async fn an_unreachable_backend_is_a_network_error() {
+    // Nothing listens on the discard port
+    let api = FileApi::new("http://127.0.0.1:9");
+    let err = api.ls(".").await.unwrap_err();
+    assert!(matches!(err, ApiError::Network(ref e) if e.is_connect()), "{:?}", err);
+    assert_eq!(err.status(), None);
+    let err = api.write_bytes("./a.txt", b"a").await.unwrap_err();
+    assert!(matches!(err, ApiError::Network(_)), "{:?}", err);
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[tokio::test]
+async fn an_answer_that_cannot_be_understood_is_a_decode_error() {
+    let backend = FakeBackend::start();
+    backend.set_hook(|req, _| match req.route().as_str() {
+        "GET /list" => Some(Response::bytes(200, b"<html>not json</html>".to_vec())),
+        "GET /stats" => Some(Response::json(200, json!({ "unexpected": true }))),
+        _ => None,
+    });
+    let api = backend.api();
+
+    let err = api.ls(".").await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(_)), "{:?}", err);
+    let err = api.statfs().await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(_)), "{:?}", err);
+    assert!(err.to_string().starts_with("invalid backend answer: "), "{}", err);
+}
+
+#[tokio::test]
+async fn a_local_failure_is_an_io_error() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+
+    let err = api
+        .write_file("./a.txt", std::path::Path::new("/nonexistent/upload.bin"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ApiError::Io(ref e) if e.kind() == ErrorKind::NotFound), "{:?}", err);
+
+    let snapshot = backend.api().with_snapshot(Some("2025-01-01".to_string()));
+    let err = snapshot.write_bytes("./a.txt", b"a").await.unwrap_err();
+    assert!(matches!(err, ApiError::Io(ref e) if e.kind() == ErrorKind::ReadOnlyFilesystem), "{:?}", err);
+    assert_eq!(backend.server.count("PUT /files"), 0);
+}
+
+#[tokio::test]
+async fn the_error_converts_to_anyhow_and_back() {
+    let backend = FakeBackend::start();
+    let err: anyhow::Error = backend.api().ls("./missing").await.unwrap_err().into();
+    let api_err = err.downcast_ref::<ApiError>().unwrap();
+    assert_eq!(api_err.status(), Some(404));
+}
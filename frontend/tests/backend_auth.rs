@@ -0,0 +1,42 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+
+#[test]
+fn the_flag_is_parsed() {
+    let args: Vec<String> = ["frontend", "--backend-auth"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).unwrap().backend_auth);
+    assert!(!MountOptions::default().backend_auth);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_kernel_does_not_check_and_the_backend_refusal_is_honored() {
+    let backend = FakeBackend::start();
+    backend.insert("./secret.txt", Node::file(b"top"));
+    backend.insert("./open.txt", Node::file(b"free"));
+    backend.set_hook(|req, _| {
+        (req.route() == "DELETE /files" && req.query("relPath") == Some("./secret.txt"))
+            .then(|| Response::json(403, json!({ "error": "Forbidden" })))
+    });
+    let opts = MountOptions { backend_auth: true, ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    // Mounted without default_permissions
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").unwrap();
+    let line = mountinfo.lines().find(|l| l.contains(mnt.dir.path().to_str().unwrap())).unwrap();
+    assert!(!line.contains("default_permissions"), "{}", line);
+
+    // The requests reach the backend, which decides
+    let err = std::fs::remove_file(mnt.path("secret.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EACCES));
+    assert_eq!(backend.server.count("DELETE /files"), 1);
+    assert_eq!(backend.data("./secret.txt").unwrap(), b"top");
+    std::fs::remove_file(mnt.path("open.txt")).unwrap();
+    assert!(backend.get("./open.txt").is_none());
+    mnt.unmount();
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::cache_stats::CacheMetrics;
+use frontend::lru::LruMap;
+use frontend::options::{MountOptions, DEFAULT_MAX_CACHE_ENTRIES};
+use std::path::Path;
+
+#[test]
+fn the_least_recently_used_entries_are_evicted() {
+    let mut map = LruMap::new(3);
+    assert!(map.insert("a", 1).is_empty());
+    assert!(map.insert("b", 2).is_empty());
+    assert!(map.insert("c", 3).is_empty());
+    // "a" becomes the most recently used, "b" is now the oldest
+    assert_eq!(map.get("a"), Some(&1));
+    assert_eq!(map.insert("d", 4), vec![("b", 2)]);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.peek("b"), None);
+
+    // Replacing a value refreshes it without evicting
+    assert!(map.insert("c", 30).is_empty());
+    assert_eq!(map.insert("e", 5), vec![("a", 1)]);
+    assert_eq!(map.insert("f", 6), vec![("d", 4)]);
+    assert_eq!(map.peek("c"), Some(&30));
+}
+
+#[test]
+fn removed_and_retained_entries_keep_the_order_consistent() {
+    let mut map = LruMap::new(2);
+    map.insert(1, "one");
+    map.insert(2, "two");
+    assert_eq!(map.remove(&1), Some("one"));
+    assert!(map.insert(3, "three").is_empty());
+    map.retain(|k, _| *k != 2);
+    assert!(map.insert(4, "four").is_empty());
+    assert_eq!(map.insert(5, "five"), vec![(3, "three")]);
+    map.clear();
+    assert!(map.is_empty());
+    assert!(map.insert(6, "six").is_empty());
+}
+
+#[test]
+fn cache_metrics_keep_the_most_recently_used_directories() {
+    let metrics = CacheMetrics::new(3);
+    for i in 0..100 {
+        metrics.record_miss(Path::new(&format!("/d{}", i)), None);
+    }
+    assert_eq!(metrics.len(), 3);
+    assert_eq!(metrics.get(Path::new("/d0")).misses, 0);
+    assert_eq!(metrics.get(Path::new("/d99")).misses, 1);
+
+    // A directory still in use keeps its counters while new ones come and go
+    metrics.record_hit(Path::new("/d98"));
+    metrics.record_hit(Path::new("/hot"));
+    metrics.record_hit(Path::new("/d98"));
+    metrics.record_miss(Path::new("/other"), None);
+    let hot = metrics.get(Path::new("/d98"));
+    assert_eq!((hot.hits, hot.misses), (2, 1));
+    assert_eq!(metrics.get(Path::new("/d99")).misses, 0);
+    assert_eq!(metrics.report("\n").lines().count(), 3);
+}
+
+#[test]
+fn max_cache_entries_is_parsed() {
+    let args: Vec<String> = ["frontend", "--max-cache-entries", "100"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().max_cache_entries(), 100);
+    assert_eq!(MountOptions::default().max_cache_entries(), DEFAULT_MAX_CACHE_ENTRIES);
+    let args: Vec<String> = ["frontend", "--max-cache-entries=0"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_large_directory_stays_within_the_cap() {
+    let backend = FakeBackend::start();
+    backend.insert("./big", Node::dir());
+    for i in 0..200 {
+        backend.insert(&format!("./big/f{}", i), Node::file(format!("data {}", i).as_bytes()));
+    }
+    let opts = MountOptions {
+        max_cache_entries: Some(20),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    let status = || -> serde_json::Value {
+        serde_json::from_slice(&std::fs::read(mnt.path(".remotefs/status")).unwrap()).unwrap()
+    };
+
+    // Listing the parent caches every child, only the ones looked up are held by the kernel
+    assert_eq!(std::fs::read(mnt.path("big/f0")).unwrap(), b"data 0");
+    let s = status();
+    assert!(s["cache"]["attr_entries"].as_u64().unwrap() <= 20, "{}", s);
+    assert!(s["inodes"].as_u64().unwrap() <= 30, "{}", s);
+
+    // Evicted entries are fetched again under a new inode
+    assert_eq!(std::fs::read(mnt.path("big/f150")).unwrap(), b"data 150");
+    assert_eq!(std::fs::read(mnt.path("big/f7")).unwrap(), b"data 7");
+    assert_eq!(std::fs::read(mnt.path("big/f0")).unwrap(), b"data 0");
+    let s = status();
+    assert!(s["cache"]["attr_entries"].as_u64().unwrap() <= 20, "{}", s);
+    assert!(s["inodes"].as_u64().unwrap() <= 30, "{}", s);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_file_whose_attributes_were_evicted_opens_for_writing() {
+    use std::io::{Read, Seek, Write};
+    let backend = FakeBackend::start();
+    backend.insert("./big", Node::dir());
+    for i in 0..100 {
+        backend.insert(&format!("./big/f{}", i), Node::file(format!("data {}", i).as_bytes()));
+    }
+    let opts = MountOptions {
+        max_cache_entries: Some(10),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    // The kernel keeps the inode of f0, the client evicts its attributes while caching the others
+    assert!(std::fs::metadata(mnt.path("big/f0")).unwrap().is_file());
+    for i in 50..100 {
+        std::fs::metadata(mnt.path(&format!("big/f{}", i))).unwrap();
+    }
+
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(mnt.path("big/f0")).unwrap();
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "data 0");
+    file.seek(std::io::SeekFrom::Start(0)).unwrap();
+    file.write_all(b"DATA").unwrap();
+    drop(file);
+    common::wait_for(|| (backend.data("./big/f0")? == b"DATA 0").then_some(()));
+    mnt.unmount();
+}
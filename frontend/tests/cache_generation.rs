@@ -0,0 +1,65 @@
+mod common;
+
+use common::{entry_json, wait_for, FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_listing_fetched_before_a_resync_is_not_cached() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./docs", Node::dir());
+    backend.insert("./docs/a.txt", Node::file(b"a"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+    assert!(std::fs::metadata(mnt.path("docs")).unwrap().is_dir());
+
+    // The next listing of docs was computed before a change the websocket never delivered, and
+    // only arrives after the reconnect
+    let slow = Arc::new(AtomicBool::new(true));
+    let released = Arc::new(AtomicBool::new(false));
+    let (armed, release) = (slow.clone(), released.clone());
+    backend.set_hook(move |req, tree| {
+        if req.route() != "GET /list" || req.query("relPath") != Some("./docs") || !armed.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        let mut stale = vec![entry_json("./docs/a.txt", &tree["./docs/a.txt"])];
+        stale.push(entry_json("./docs/gone.txt", &Node::file(b"old")));
+        for _ in 0..100 {
+            if release.load(Ordering::SeqCst) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Some(Response::json(200, Value::Array(stale)))
+    });
+    let read_docs = {
+        let docs = mnt.path("docs");
+        move || {
+            let mut names: Vec<String> = std::fs::read_dir(&docs)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .collect();
+            names.sort();
+            names
+        }
+    };
+    let listing = std::thread::spawn(read_docs.clone());
+    wait_for(|| (!slow.load(Ordering::SeqCst)).then_some(()));
+    backend.drop_socket();
+    wait_for(|| (backend.socket_sessions() == 2 && backend.socket_connected()).then_some(()));
+    assert!(!listing.is_finished());
+    released.store(true, Ordering::SeqCst);
+    // The caller still gets the answer it waited for
+    assert!(listing.join().unwrap().contains(&"a.txt".to_string()));
+
+    // The stale answer was not kept: the next listing goes back to the backend
+    assert_eq!(read_docs(), ["a.txt"]);
+    mnt.unmount();
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::cache_stats::CacheMetrics;
+use frontend::options::MountOptions;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn hit_ratio_follows_the_recorded_hits_and_misses() {
+    let metrics = CacheMetrics::new(10);
+    let dir = Path::new("/docs");
+    for _ in 0..3 {
+        metrics.record_hit(dir);
+    }
+    metrics.record_miss(dir, None);
+    metrics.record_miss(Path::new("/other"), Some(SystemTime::now() - Duration::from_secs(2)));
+
+    let stats = metrics.get(dir);
+    assert_eq!((stats.hits, stats.misses, stats.refetches), (3, 1, 0));
+    assert_eq!(stats.hit_ratio(), 0.75);
+    let other = metrics.get(Path::new("/other"));
+    assert_eq!((other.hits, other.misses, other.refetches), (0, 1, 1));
+    assert_eq!(other.hit_ratio(), 0.0);
+    assert!((2000..3000).contains(&other.avg_refetch_age_ms()), "{}", other.avg_refetch_age_ms());
+
+    let report = metrics.report("\n");
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "cache[/docs]: hits=3 misses=1 hit_ratio=0.75 avg_refetch_age_ms=0");
+    assert!(lines[1].starts_with("cache[/other]: hits=0 misses=1 hit_ratio=0.00 "), "{}", lines[1]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_status_reports_the_ratio_of_the_mount() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    backend.insert("./b.txt", Node::file(b"b"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    for _ in 0..3 {
+        std::fs::metadata(mnt.path("a.txt")).unwrap();
+        std::fs::metadata(mnt.path("b.txt")).unwrap();
+    }
+
+    let status = std::fs::read_to_string(mnt.path(".remotefs/refresh")).unwrap();
+    let line = status.lines().find(|l| l.starts_with("cache[/]: ")).unwrap();
+    let field = |name: &str| -> f64 {
+        let value = line.split(' ').find_map(|kv| kv.strip_prefix(&format!("{}=", name))).unwrap();
+        value.parse().unwrap()
+    };
+    let (hits, misses) = (field("hits"), field("misses"));
+    // The root was listed at least once, and later lookups found the listing cached
+    assert!(misses >= 1.0, "{}", line);
+    assert!(hits >= 1.0, "{}", line);
+    assert_eq!(format!("{:.2}", field("hit_ratio")), format!("{:.2}", hits / (hits + misses)), "{}", line);
+    mnt.unmount();
+}
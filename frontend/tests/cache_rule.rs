@@ -0,0 +1,57 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::Duration;
+
+fn opts(rules: &str) -> MountOptions {
+    let args: Vec<String> = ["frontend", "--cache-rule", rules].iter().map(|s| s.to_string()).collect();
+    MountOptions::from_args(&args).unwrap()
+}
+
+#[test]
+fn cache_rules_pick_the_ttl_of_a_path() {
+    let opts = opts("*.log=0, /artifacts/*=3600");
+    assert_eq!(opts.cache_ttl_for("./app.log"), Some(Duration::ZERO));
+    assert_eq!(opts.cache_ttl_for("./deep/in/tree/app.log"), Some(Duration::ZERO));
+    assert_eq!(opts.cache_ttl_for("./artifacts/v1.tar"), Some(Duration::from_secs(3600)));
+    assert_eq!(opts.cache_ttl_for("./artifacts"), None);
+    assert_eq!(opts.cache_ttl_for("./other/artifacts/v1.tar"), None);
+    assert_eq!(opts.cache_ttl_for("./notes.txt"), None);
+
+    for bad in ["*.log", "*.log=soon"] {
+        let args: Vec<String> = ["frontend", "--cache-rule", bad].iter().map(|s| s.to_string()).collect();
+        assert!(MountOptions::from_args(&args).is_err(), "{}", bad);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_zero_ttl_path_is_fetched_again_and_a_long_ttl_one_is_cached() {
+    let backend = FakeBackend::start();
+    backend.insert("./app.log", Node::file(b"1"));
+    backend.insert("./artifacts", Node::dir());
+    backend.insert("./artifacts/v1.tar", Node::file(b"1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts("*.log=0,/artifacts/*=3600")) else {
+        return;
+    };
+    let size = |name: &str| std::fs::metadata(mnt.path(name)).unwrap().len();
+    assert_eq!(size("app.log"), 1);
+    assert_eq!(size("artifacts/v1.tar"), 1);
+
+    // Changed on the backend without a notification
+    backend.insert("./app.log", Node::file(b"22"));
+    backend.insert("./artifacts/v1.tar", Node::file(b"22"));
+    backend.server.clear();
+    assert_eq!(size("app.log"), 2);
+    assert!(backend.server.total() > 0);
+
+    // Past the default TTL the long rule still serves the cached size: only the parents, which
+    // follow the default, are looked up again
+    std::thread::sleep(Duration::from_millis(2500));
+    backend.server.clear();
+    assert_eq!(size("artifacts/v1.tar"), 1);
+    assert!(backend.server.requests().iter().all(|r| r.query("relPath") == Some(".")));
+    assert_eq!(size("app.log"), 2);
+    mnt.unmount();
+}
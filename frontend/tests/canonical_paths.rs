@@ -0,0 +1,65 @@
+mod common;
+
+use common::{entry_json, route, wait_for, FakeBackend, Mounted, Node, Response};
+use frontend::file_api::DirectoryEntry;
+use frontend::options::MountOptions;
+use serde_json::{json, Value};
+use std::os::unix::fs::FileExt;
+
+#[test]
+fn the_backend_path_names_the_entry() {
+    let entry = |path: Value| -> DirectoryEntry {
+        let mut raw = entry_json("./docs/a.txt", &Node::file(b"a"));
+        raw["path"] = path;
+        serde_json::from_value(raw).unwrap()
+    };
+    let listed = entry(json!("Docs/A.txt"));
+    assert_eq!((listed.name.as_str(), listed.path.as_deref()), ("A.txt", Some("./Docs/A.txt")));
+    assert_eq!(listed.parent_rel().as_deref(), Some("./Docs"));
+    let plain = entry(Value::Null);
+    assert_eq!((plain.name.as_str(), plain.path.as_deref(), plain.parent_rel()), ("a.txt", None, None));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_directory_the_backend_spells_differently_is_cached_once() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./Docs", Node::dir());
+    backend.insert("./Docs/a.txt", Node::file(b"content"));
+    // A case-insensitive backend: ./docs reaches ./Docs, and the root lists it the way it was asked for
+    backend.set_hook(|req, tree| {
+        if req.route() == "GET /list" && req.query("relPath") == Some(".") {
+            let mut docs = entry_json("./Docs", &tree["./Docs"]);
+            docs["name"] = json!("docs");
+            docs["path"] = json!("./docs");
+            return Some(Response::json(200, json!([docs])));
+        }
+        let mut req = req.clone();
+        let mut rewritten = false;
+        for (_, value) in req.query.iter_mut() {
+            if let Some(rest) = value.strip_prefix("./docs") {
+                *value = format!("./Docs{}", rest);
+                rewritten = true;
+            }
+        }
+        rewritten.then(|| route(&req, tree))
+    });
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+
+    assert_eq!(std::fs::read(mnt.path("docs/a.txt")).unwrap(), b"content");
+    let file = std::fs::File::open(mnt.path("docs/a.txt")).unwrap();
+    let mut buf = [0u8; 7];
+    file.read_exact_at(&mut buf, 0).unwrap();
+
+    // Notifications name the entry the backend's way, and reach the file opened under the other one
+    backend.remove("./Docs/a.txt");
+    backend.emit_change("unlink", "./Docs/a.txt");
+    let err = wait_for(|| file.read_at(&mut buf, 0).err());
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+    drop(file);
+    mnt.unmount();
+}
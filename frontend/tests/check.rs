@@ -0,0 +1,73 @@
+mod common;
+
+use common::{FakeBackend, Response};
+use frontend::check::{check_backend, Report};
+
+fn run_check(backend: &FakeBackend) -> Report {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    check_backend(&backend.api(), &rt)
+}
+
+fn failed(report: &Report) -> Vec<(&'static str, String)> {
+    report
+        .probes
+        .iter()
+        .filter_map(|p| match &p.result {
+            Some(Err(e)) => Some((p.endpoint, e.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn a_complete_backend_passes_every_probe_and_is_left_clean() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+
+    let report = run_check(&backend);
+    assert_eq!(failed(&report), []);
+    assert!(report.probes.iter().all(|p| p.result.is_some()));
+    assert!(report.passed());
+    for endpoint in ["GET /health", "Socket.IO", "POST /mkdir", "GET /files", "PATCH /files/rename", "DELETE /files"] {
+        assert!(report.probes.iter().any(|p| p.endpoint == endpoint), "{}", endpoint);
+    }
+    // The scratch directory is gone
+    assert!(backend.get(&format!("./.remotefs-check-{}", std::process::id())).is_none());
+}
+
+#[test]
+fn a_broken_endpoint_is_the_only_failure_reported() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.set_hook(|req, _| {
+        (req.route() == "PATCH /files/truncate").then(|| Response::json(500, serde_json::json!({ "error": "boom" })))
+    });
+
+    let report = run_check(&backend);
+    let failed = failed(&report);
+    assert_eq!(failed.len(), 1, "{:?}", failed);
+    assert_eq!(failed[0].0, "PATCH /files/truncate");
+    assert!(failed[0].1.contains("500"), "{}", failed[0].1);
+    assert!(!report.passed());
+}
+
+#[test]
+fn a_backend_without_socket_io_fails_that_probe_alone() {
+    let backend = FakeBackend::start();
+
+    let report = run_check(&backend);
+    assert_eq!(failed(&report).iter().map(|f| f.0).collect::<Vec<_>>(), ["Socket.IO"]);
+}
+
+#[test]
+fn probes_depending_on_a_failed_mkdir_are_skipped() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.set_hook(|req, _| (req.route() == "POST /mkdir").then(|| Response::empty(404)));
+
+    let report = run_check(&backend);
+    assert_eq!(failed(&report).iter().map(|f| f.0).collect::<Vec<_>>(), ["POST /mkdir"]);
+    let skipped: Vec<&str> = report.probes.iter().filter(|p| p.result.is_none()).map(|p| p.endpoint).collect();
+    assert!(skipped.contains(&"GET /list") && skipped.contains(&"DELETE /files"), "{:?}", skipped);
+    assert_eq!(backend.server.count("PUT /files"), 0);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+/// Owners the backend took, by relPath: (uid, gid), None where the request left the id alone
+type Owners = Arc<Mutex<Vec<(String, Option<u32>, Option<u32>)>>>;
+
+// A backend implementing PATCH /files/chown; with `refuse` it answers 403 as for a user that may not chown
+fn with_chown(backend: &FakeBackend, refuse: bool) -> Owners {
+    let owners = Owners::default();
+    let seen = owners.clone();
+    backend.set_hook(move |req, tree| {
+        if req.route() != "PATCH /files/chown" {
+            return None;
+        }
+        let rel = req.query("relPath")?.to_string();
+        if !tree.contains_key(&rel) {
+            return Some(Response::json(404, json!({ "error": "File not found" })));
+        }
+        if refuse {
+            return Some(Response::json(403, json!({ "error": "Operation not permitted" })));
+        }
+        let id = |name| req.query(name).map(|v| v.parse::<u32>().unwrap());
+        seen.lock().unwrap().push((rel, id("uid"), id("gid")));
+        Some(Response::empty(200))
+    });
+    owners
+}
+
+#[tokio::test]
+async fn chown_sends_only_the_ids_given() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    let owners = with_chown(&backend, false);
+    let api = backend.api();
+
+    api.chown("./a.txt", Some(4242), Some(4343)).await.unwrap();
+    api.chown("./a.txt", None, Some(50)).await.unwrap();
+    api.chown("./a.txt", Some(7), None).await.unwrap();
+    assert_eq!(
+        *owners.lock().unwrap(),
+        [
+            ("./a.txt".to_string(), Some(4242), Some(4343)),
+            ("./a.txt".to_string(), None, Some(50)),
+            ("./a.txt".to_string(), Some(7), None),
+        ]
+    );
+    assert_eq!(api.chown("./missing.txt", Some(1), None).await.unwrap_err().status(), Some(404));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn chown_through_the_mount_reaches_the_backend() {
+    use std::os::unix::fs::MetadataExt;
+
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    let owners = with_chown(&backend, false);
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    std::os::unix::fs::chown(mnt.path("a.txt"), Some(4242), Some(4343)).unwrap();
+    assert_eq!(*owners.lock().unwrap(), [("./a.txt".to_string(), Some(4242), Some(4343))]);
+    let meta = std::fs::metadata(mnt.path("a.txt")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (4242, 4343));
+
+    // Only the group: the owner stays as it is
+    std::os::unix::fs::chown(mnt.path("a.txt"), None, Some(50)).unwrap();
+    assert_eq!(owners.lock().unwrap()[1], ("./a.txt".to_string(), None, Some(50)));
+    let meta = std::fs::metadata(mnt.path("a.txt")).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (4242, 50));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_chown_the_backend_refuses_is_eacces_and_changes_nothing() {
+    use std::os::unix::fs::MetadataExt;
+
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    with_chown(&backend, true);
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let before = std::fs::metadata(mnt.path("a.txt")).unwrap();
+
+    let err = std::os::unix::fs::chown(mnt.path("a.txt"), Some(4242), None).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EACCES));
+    assert_eq!(backend.server.count("PATCH /files/chown"), 1);
+    let after = std::fs::metadata(mnt.path("a.txt")).unwrap();
+    assert_eq!((after.uid(), after.gid()), (before.uid(), before.gid()));
+    mnt.unmount();
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{entry_json, wait_for, FakeBackend, Mounted, Node, Request, Response};
+use frontend::options::MountOptions;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const TEXT: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// Function that answers a read streamed chunked, without Content-Length; with `honor_range` false
+// the Range header is ignored and the whole file sent
+fn chunked_read(req: &Request, tree: &BTreeMap<String, Node>, honor_range: bool) -> Option<Response> {
+    if req.route() != "GET /files" {
+        return None;
+    }
+    let data = tree.get(req.query("relPath")?)?.data.clone();
+    let (start, end) = req.header("Range")?.strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+    let resp = if !honor_range {
+        Response::bytes(200, data)
+    } else if start >= data.len() {
+        Response::empty(416)
+    } else {
+        Response::bytes(206, data[start..=end.min(data.len() - 1)].to_vec())
+    };
+    Some(resp.with_header("Transfer-Encoding", "chunked"))
+}
+
+#[tokio::test]
+async fn chunked_reads_return_exactly_the_requested_range() {
+    for honor_range in [true, false] {
+        let backend = FakeBackend::start();
+        backend.insert("./a.txt", Node::file(TEXT));
+        backend.set_hook(move |req, tree| chunked_read(req, tree, honor_range));
+        let api = backend.api();
+        let raw = reqwest::Client::new()
+            .get(format!("{}/files?relPath=./a.txt", backend.url()))
+            .header("Range", "bytes=0-9")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(raw.content_length(), None);
+
+        assert_eq!(api.read_range("./a.txt", 10, 19).await.unwrap(), b"abcdefghij", "{}", honor_range);
+        let mut buf = [0u8; 4];
+        assert_eq!(api.read_into("./a.txt", 30, &mut buf).await.unwrap(), 4);
+        assert_eq!(&buf, b"uvwx");
+        // Past the end only what is there
+        let mut buf = [0u8; 16];
+        assert_eq!(api.read_into("./a.txt", 30, &mut buf).await.unwrap(), 6);
+        assert_eq!(&buf[..6], b"uvwxyz");
+        assert_eq!(api.read_all("./a.txt", TEXT.len() as u64).await.unwrap(), TEXT);
+        // A size cached larger than the file gives the bytes there are, not an error
+        assert_eq!(api.read_all("./a.txt", 100).await.unwrap(), TEXT);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_reads_a_chunked_file_and_learns_it_is_shorter() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(TEXT));
+    backend.insert("./b.txt", Node::file(b"short"));
+    // The listing claims more than the body of b.txt holds
+    backend.set_hook(|req, tree| {
+        if req.route() != "GET /list" {
+            return chunked_read(req, tree, true);
+        }
+        let entries: Vec<Value> = tree
+            .iter()
+            .filter(|(rel, _)| rel.matches('/').count() == 1)
+            .map(|(rel, node)| {
+                let mut entry = entry_json(rel, node);
+                if rel == "./b.txt" {
+                    entry["size"] = 64.into();
+                }
+                entry
+            })
+            .collect();
+        Some(Response::json(200, Value::Array(entries)))
+    });
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), TEXT);
+    assert_eq!(std::fs::metadata(mnt.path("b.txt")).unwrap().len(), 64);
+    assert_eq!(std::fs::read(mnt.path("b.txt")).unwrap(), b"short");
+    wait_for(|| (std::fs::metadata(mnt.path("b.txt")).ok()?.len() == 5).then_some(()));
+    mnt.unmount();
+}
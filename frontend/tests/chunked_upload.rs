@@ -0,0 +1,57 @@
+mod common;
+
+use common::{FakeBackend, TempDir};
+
+// Size of each PUT write_file sends when uploads are not throttled
+const CHUNK: usize = 1024 * 1024;
+
+#[tokio::test]
+async fn write_file_uploads_a_large_file_in_bounded_chunks() {
+    let backend = FakeBackend::start();
+    let local = TempDir::new("upload");
+    let path = local.path().join("big.bin");
+    let data: Vec<u8> = (0..5 * CHUNK + CHUNK / 2).map(|i| (i % 253) as u8).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    backend.api().write_file("./big.bin", &path).await.unwrap();
+    assert!(backend.data("./big.bin").unwrap() == data, "content differs");
+
+    // One PUT per chunk at consecutive offsets, none holding more than a chunk, each with its own length
+    let puts: Vec<_> = backend.server.requests().into_iter().filter(|r| r.route() == "PUT /files").collect();
+    assert_eq!(puts.len(), 6);
+    let mut offset = 0;
+    for put in &puts {
+        assert_eq!(put.query("offset"), Some(offset.to_string().as_str()));
+        assert!(put.body.len() <= CHUNK);
+        assert_eq!(put.header("Content-Length"), Some(put.body.len().to_string().as_str()));
+        offset += put.body.len();
+    }
+    assert_eq!(offset, data.len());
+}
+
+#[tokio::test]
+async fn write_file_of_an_empty_file_still_creates_it() {
+    let backend = FakeBackend::start();
+    let local = TempDir::new("upload");
+    let path = local.path().join("empty.bin");
+    std::fs::write(&path, b"").unwrap();
+
+    backend.api().write_file("./empty.bin", &path).await.unwrap();
+    assert_eq!(backend.data("./empty.bin").unwrap(), b"");
+}
+
+#[tokio::test]
+async fn a_failed_chunk_keeps_the_backend_error() {
+    let backend = FakeBackend::start();
+    backend.set_hook(|req, _| {
+        (req.route() == "PUT /files" && req.query("offset") != Some("0"))
+            .then(|| common::Response::json(507, serde_json::json!({ "error": "Insufficient storage" })))
+    });
+    let local = TempDir::new("upload");
+    let path = local.path().join("big.bin");
+    std::fs::write(&path, vec![0u8; 2 * CHUNK]).unwrap();
+
+    let err = backend.api().write_file("./big.bin", &path).await.unwrap_err();
+    assert_eq!(err.status(), Some(507));
+    assert_eq!(backend.server.count("PUT /files"), 2);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::{CacheRule, MountOptions};
+use std::os::unix::fs::FileExt;
+use std::time::{Duration, Instant};
+
+// Function that mounts a backend holding `./app.log` cached for a minute, the way a log is left
+// open by tail -f, and returns what a reader holding it open sees after another client appends
+#[cfg(target_os = "linux")]
+fn tail_after_append(coherency_interval: Option<Duration>, patience: Duration) -> Option<Vec<u8>> {
+    let backend = FakeBackend::start();
+    backend.insert("./app.log", Node::file(b"l1\n"));
+    let opts = MountOptions {
+        cache_rules: vec![CacheRule { pattern: "*.log".to_string(), ttl: Duration::from_secs(60) }],
+        coherency_interval,
+        ..MountOptions::default()
+    };
+    let mnt = Mounted::start(backend.api(), &backend.url(), opts)?;
+    let log = std::fs::File::open(mnt.path("app.log")).unwrap();
+    let mut buf = [0u8; 64];
+    assert_eq!(log.read_at(&mut buf, 0).unwrap(), 3);
+
+    backend.insert("./app.log", Node::file(b"l1\nl2\n"));
+    let deadline = Instant::now() + patience;
+    let mut seen = None;
+    while Instant::now() < deadline {
+        let n = log.read_at(&mut buf, 3).unwrap();
+        if n > 0 {
+            seen = Some(buf[..n].to_vec());
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    drop(log);
+    mnt.unmount();
+    seen
+}
+
+#[test]
+fn the_interval_is_parsed_in_seconds() {
+    let args: Vec<String> = ["frontend", "--coherency-interval", "5"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().coherency_interval, Some(Duration::from_secs(5)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_reader_holding_the_file_open_sees_it_grow_after_the_interval() {
+    if !common::fuse_available() {
+        return;
+    }
+    let seen = tail_after_append(Some(Duration::from_secs(1)), Duration::from_secs(5));
+    assert_eq!(seen.as_deref(), Some(&b"l2\n"[..]));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn without_the_interval_the_cached_size_holds() {
+    if !common::fuse_available() {
+        return;
+    }
+    assert_eq!(tail_after_append(None, Duration::from_secs(3)), None);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response, TempDir};
+use frontend::options::{MountOptions, COMMIT_INTERVAL, MAX_COMMIT_BACKOFF};
+use serde_json::json;
+use std::time::Duration;
+
+fn parse(list: &[&str]) -> anyhow::Result<MountOptions> {
+    let args: Vec<String> = std::iter::once("frontend").chain(list.iter().copied()).map(String::from).collect();
+    MountOptions::from_args(&args)
+}
+
+#[test]
+fn commit_attempts_and_backoff_are_parsed() {
+    let opts = parse(&["--commit-attempts", "3", "--commit-backoff=2"]).unwrap();
+    assert_eq!(opts.commit_attempts(), 3);
+    assert_eq!(opts.commit_backoff, Some(Duration::from_secs(2)));
+    assert!(parse(&["--commit-attempts", "0"]).is_err());
+    assert!(parse(&["--commit-backoff", "soon"]).is_err());
+}
+
+#[test]
+fn the_backoff_doubles_up_to_its_cap() {
+    let opts = parse(&["--commit-backoff", "2"]).unwrap();
+    assert_eq!(opts.commit_retry_delay(1), Duration::from_secs(2));
+    assert_eq!(opts.commit_retry_delay(2), Duration::from_secs(4));
+    assert_eq!(opts.commit_retry_delay(4), Duration::from_secs(16));
+    assert_eq!(opts.commit_retry_delay(60), MAX_COMMIT_BACKOFF);
+    assert_eq!(MountOptions::default().commit_retry_delay(1), COMMIT_INTERVAL);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn commits_refused_every_time_end_up_in_recovery() {
+    use std::io::Write;
+
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"old"));
+    let recovery = TempDir::new("recovery");
+    let opts = MountOptions {
+        recovery_dir: Some(recovery.path().to_path_buf()),
+        commit_attempts: Some(3),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    backend.set_hook(|req, _| {
+        (req.route() == "PUT /files").then(|| Response::json(500, json!({ "error": "disk full" })))
+    });
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("doc.txt")).unwrap();
+    file.write_all(b"new data").unwrap();
+    for _ in 0..3 {
+        assert!(file.sync_all().is_err());
+    }
+    assert_eq!(backend.server.count("PUT /files"), 3);
+    let saved = recovery.path().join("doc.txt");
+    assert_eq!(std::fs::read(&saved).unwrap(), b"new data");
+
+    // Given up: later syncs and the close fail without asking the backend again
+    assert!(file.sync_all().is_err());
+    file.write_all(b", more").unwrap();
+    drop(file);
+    wait_for_content(&saved, b"new data, more");
+    assert_eq!(backend.server.count("PUT /files"), 3);
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"old");
+
+    let status: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(mnt.path(".remotefs/status")).unwrap()).unwrap();
+    assert_eq!(status["abandoned_commits"], 1);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_content(path: &std::path::Path, expected: &[u8]) {
+    common::wait_for(|| (std::fs::read(path).ok()? == expected).then_some(()));
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::{CommitStrategy, MountOptions};
+use std::io::Write;
+
+#[test]
+fn commit_strategy_is_parsed() {
+    for (value, expected) in [
+        ("close", CommitStrategy::Close),
+        ("writethrough", CommitStrategy::WriteThrough),
+        ("interval", CommitStrategy::Interval),
+    ] {
+        let args: Vec<String> = ["frontend", "--commit-strategy", value].iter().map(|s| s.to_string()).collect();
+        assert_eq!(MountOptions::from_args(&args).unwrap().commit_strategy, expected);
+    }
+    let args: Vec<String> = ["frontend", "--commit-strategy", "never"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+    assert_eq!(MountOptions::default().commit_strategy, CommitStrategy::Close);
+}
+
+#[cfg(target_os = "linux")]
+fn mount_with(backend: &FakeBackend, commit_strategy: CommitStrategy) -> Option<Mounted> {
+    let opts = MountOptions {
+        commit_strategy,
+        ..MountOptions::default()
+    };
+    Mounted::start(backend.api(), &backend.url(), opts)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn writethrough_commits_every_write() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b""));
+    let Some(mnt) = mount_with(&backend, CommitStrategy::WriteThrough) else {
+        return;
+    };
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap();
+    file.write_all(b"first ").unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"first ");
+    let after_one = backend.server.count("PUT /files");
+    assert!(after_one >= 1);
+    file.write_all(b"second").unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"first second");
+    assert!(backend.server.count("PUT /files") > after_one);
+    drop(file);
+    assert_eq!(backend.data("./a.txt").unwrap(), b"first second");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+fn uploaded_bytes(backend: &FakeBackend) -> usize {
+    backend
+        .server
+        .requests()
+        .iter()
+        .filter(|r| r.route() == "PUT /files")
+        .map(|r| r.body.len())
+        .sum()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn writethrough_uploads_only_the_range_written() {
+    const WRITE: usize = 16 * 1024;
+    const WRITES: usize = 64;
+    let backend = FakeBackend::start();
+    backend.insert("./a.bin", Node::file(b""));
+    let Some(mnt) = mount_with(&backend, CommitStrategy::WriteThrough) else {
+        return;
+    };
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.bin")).unwrap();
+    let mut expected = Vec::new();
+    for i in 0..WRITES {
+        let chunk = vec![i as u8; WRITE];
+        file.write_all(&chunk).unwrap();
+        expected.extend_from_slice(&chunk);
+    }
+    assert_eq!(backend.data("./a.bin").unwrap(), expected);
+    // Each write sends its own bytes, not the whole file again
+    assert_eq!(uploaded_bytes(&backend), WRITE * WRITES);
+    drop(file);
+    assert_eq!(backend.data("./a.bin").unwrap(), expected);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn writethrough_overwrites_keep_the_rest_of_the_file() {
+    use std::io::{Seek, SeekFrom};
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"0123456789"));
+    let Some(mnt) = mount_with(&backend, CommitStrategy::WriteThrough) else {
+        return;
+    };
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap();
+    file.seek(SeekFrom::Start(3)).unwrap();
+    file.write_all(b"abc").unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"012abc6789");
+    assert_eq!(uploaded_bytes(&backend), 3);
+
+    // A range at offset 0 would start the backend file over: the whole file goes instead
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(b"XY").unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"XY2abc6789");
+    drop(file);
+    assert_eq!(backend.data("./a.txt").unwrap(), b"XY2abc6789");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn truncating_an_untouched_handle_still_commits() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"old content"));
+    let Some(mnt) = mount_with(&backend, CommitStrategy::Close) else {
+        return;
+    };
+
+    let file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap();
+    file.set_len(3).unwrap();
+    drop(file);
+    common::wait_for(|| (backend.data("./a.txt")? == b"old").then_some(()));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn close_mode_commits_only_at_close() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"old content"));
+    let Some(mnt) = mount_with(&backend, CommitStrategy::Close) else {
+        return;
+    };
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap();
+    file.write_all(b"new").unwrap();
+    file.write_all(b" stuff").unwrap();
+    assert_eq!(backend.server.count("PUT /files"), 0);
+    assert_eq!(backend.data("./a.txt").unwrap(), b"old content");
+    drop(file);
+    common::wait_for(|| (backend.data("./a.txt")? == b"new stuffnt").then_some(()));
+    assert_eq!(backend.server.count("PUT /files"), 1);
+    mnt.unmount();
+}
@@ -0,0 +1,846 @@
+// Shared by the integration tests: a mock backend speaking the HTTP API of backend/ and a helper
+// mounting the client on a temporary directory against it
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+/// A request as the mock server received it
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The request line without the query, e.g. "GET /list"
+    pub fn route(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(status: u16, value: Value) -> Self {
+        Response {
+            status,
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    pub fn bytes(status: u16, body: Vec<u8>) -> Self {
+        Response {
+            status,
+            headers: vec![("Content-Type".into(), "application/octet-stream".into())],
+            body,
+        }
+    }
+
+    pub fn empty(status: u16) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// HTTP/1.1 server on a random local port, one thread per connection, every request logged
+pub struct MockServer {
+    pub url: String,
+    log: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let server_log = log.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = handler.clone();
+                let log = server_log.clone();
+                std::thread::spawn(move || serve(stream, &*handler, &log));
+            }
+        });
+        MockServer { url, log }
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.log.lock().unwrap().clone()
+    }
+
+    /// Requests to `route` ("GET /list") received so far
+    pub fn count(&self, route: &str) -> usize {
+        self.log.lock().unwrap().iter().filter(|r| r.route() == route).count()
+    }
+
+    /// Requests received so far, the websocket polling aside
+    pub fn total(&self) -> usize {
+        self.log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| !r.path.starts_with("/socket.io"))
+            .count()
+    }
+
+    pub fn clear(&self) {
+        self.log.lock().unwrap().clear();
+    }
+}
+
+// Size of the chunks of a chunked response
+const CHUNK: usize = 7;
+
+fn serve(stream: TcpStream, handler: &Handler, log: &Mutex<Vec<Request>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+    while let Some(req) = read_request(&mut reader) {
+        let close = req.header("Connection").is_some_and(|v| v.eq_ignore_ascii_case("close"));
+        log.lock().unwrap().push(req.clone());
+        let resp = handler(&req);
+        // A response marked chunked goes without Content-Length, in small chunks
+        let chunked = resp
+            .headers
+            .iter()
+            .any(|(k, v)| k.eq_ignore_ascii_case("Transfer-Encoding") && v.eq_ignore_ascii_case("chunked"));
+        let mut head = format!("HTTP/1.1 {} X\r\n", resp.status);
+        if !chunked {
+            head.push_str(&format!("Content-Length: {}\r\n", resp.body.len()));
+        }
+        for (k, v) in &resp.headers {
+            head.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        head.push_str("\r\n");
+        if stream.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+        let mut body = resp.body;
+        if chunked {
+            body = body
+                .chunks(CHUNK)
+                .flat_map(|c| [format!("{:x}\r\n", c.len()).into_bytes(), c.to_vec(), b"\r\n".to_vec()].concat())
+                .chain(b"0\r\n\r\n".iter().copied())
+                .collect();
+        }
+        if req.method != "HEAD" && stream.write_all(&body).is_err() {
+            return;
+        }
+        if close {
+            return;
+        }
+    }
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Request> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+    let mut headers = Vec::new();
+    loop {
+        let mut h = String::new();
+        reader.read_line(&mut h).ok()?;
+        let h = h.trim_end();
+        if h.is_empty() {
+            break;
+        }
+        let (k, v) = h.split_once(':')?;
+        headers.push((k.trim().to_string(), v.trim().to_string()));
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _): &&(String, String)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    };
+    let mut body = Vec::new();
+    if let Some(len) = header("Content-Length").and_then(|v| v.parse::<usize>().ok()) {
+        body.resize(len, 0);
+        reader.read_exact(&mut body).ok()?;
+    } else if header("Transfer-Encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).ok()?;
+            let size = usize::from_str_radix(size.trim(), 16).ok()?;
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).ok()?;
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let query = query
+        .split('&')
+        .filter(|kv| !kv.is_empty())
+        .map(|kv| {
+            let (k, v) = kv.split_once('=').unwrap_or((kv, ""));
+            (decode(k), decode(v))
+        })
+        .collect();
+    Some(Request {
+        method,
+        path: path.to_string(),
+        query,
+        headers,
+        body,
+    })
+}
+
+fn decode(s: &str) -> String {
+    urlencoding::decode(&s.replace('+', " "))
+        .map(|c| c.into_owned())
+        .unwrap_or_else(|_| s.to_string())
+}
+
+/// An entry of the fake backend tree
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub is_dir: bool,
+    pub data: Vec<u8>,
+    pub mode: u32,
+    pub mtime: (i64, i64),
+    pub symlink_target: Option<String>,
+    /// Kind reported instead of the one the other fields imply ("fifo", "socket", ...)
+    pub kind: Option<&'static str>,
+}
+
+impl Node {
+    pub fn file(data: &[u8]) -> Self {
+        Node {
+            is_dir: false,
+            data: data.to_vec(),
+            mode: 0o644,
+            mtime: now(),
+            symlink_target: None,
+            kind: None,
+        }
+    }
+
+    pub fn dir() -> Self {
+        Node {
+            is_dir: true,
+            data: Vec::new(),
+            mode: 0o755,
+            mtime: now(),
+            symlink_target: None,
+            kind: None,
+        }
+    }
+}
+
+fn now() -> (i64, i64) {
+    let d = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    (d.as_secs() as i64, d.subsec_nanos() as i64)
+}
+
+type Hook = dyn Fn(&Request, &mut BTreeMap<String, Node>) -> Option<Response> + Send + Sync;
+
+// How long a long-polling GET of the socket waits for a packet before answering with a noop
+const SOCKET_POLL: Duration = Duration::from_millis(200);
+
+/// Socket.IO session of the fake backend, over engine.io long polling: enough for the client to
+/// connect and receive the events a test emits
+#[derive(Default)]
+struct Socket {
+    enabled: bool,
+    /// Current engine.io session, 0 before the first handshake
+    sid: u64,
+    /// The session was dropped by the test: its polls fail until the client opens a new one
+    dropped: bool,
+    /// The client joined the namespace (socket.io CONNECT)
+    connected: bool,
+    /// Packets waiting for the next poll of the client
+    outbox: VecDeque<String>,
+}
+
+type SocketHub = Arc<(Mutex<Socket>, Condvar)>;
+
+/// In-memory backend implementing the routes of backend/, keyed by canonical relPath ("." and "./a/b")
+pub struct FakeBackend {
+    pub server: MockServer,
+    tree: Arc<Mutex<BTreeMap<String, Node>>>,
+    hook: Arc<Mutex<Option<Box<Hook>>>>,
+    delays: Arc<Mutex<Vec<(String, Duration)>>>,
+    socket: SocketHub,
+}
+
+impl FakeBackend {
+    pub fn start() -> Self {
+        let mut tree = BTreeMap::new();
+        tree.insert(".".to_string(), Node::dir());
+        let tree = Arc::new(Mutex::new(tree));
+        let hook: Arc<Mutex<Option<Box<Hook>>>> = Arc::new(Mutex::new(None));
+        let delays: Arc<Mutex<Vec<(String, Duration)>>> = Arc::new(Mutex::new(Vec::new()));
+        let socket: SocketHub = Arc::default();
+        let (t, h, d, s) = (tree.clone(), hook.clone(), delays.clone(), socket.clone());
+        let server = MockServer::start(move |req| {
+            // Before taking the tree, so delayed requests still overlap
+            let delay = d.lock().unwrap().iter().find(|(r, _)| *r == req.route()).map(|(_, d)| *d);
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            // A long poll holds no lock the other requests need
+            if req.path.trim_end_matches('/') == "/socket.io" {
+                if let Some(resp) = socket_route(&s, req) {
+                    return resp;
+                }
+            }
+            let mut tree = t.lock().unwrap();
+            if let Some(hook) = h.lock().unwrap().as_ref() {
+                if let Some(resp) = hook(req, &mut tree) {
+                    return resp;
+                }
+            }
+            route(req, &mut tree)
+        });
+        FakeBackend {
+            server,
+            tree,
+            hook,
+            delays,
+            socket,
+        }
+    }
+
+    /// Serves Socket.IO, so a mount receives the events sent with `emit`; without it the handshake
+    /// fails as on a backend without websocket
+    pub fn enable_socket(&self) {
+        self.socket.0.lock().unwrap().enabled = true;
+    }
+
+    /// True once a client joined the Socket.IO namespace and its session was not dropped since
+    pub fn socket_connected(&self) -> bool {
+        let socket = self.socket.0.lock().unwrap();
+        socket.connected && !socket.dropped
+    }
+
+    /// Engine.io sessions opened so far, one per (re)connection of the client
+    pub fn socket_sessions(&self) -> u64 {
+        self.socket.0.lock().unwrap().sid
+    }
+
+    /// Sends a Socket.IO event to the connected client
+    pub fn emit(&self, event: &str, payload: Value) {
+        let (lock, cvar) = &*self.socket;
+        let mut socket = lock.lock().unwrap();
+        socket.outbox.push_back(format!("42{}", json!([event, payload])));
+        cvar.notify_all();
+    }
+
+    /// Sends one engine.io packet exactly as given, to feed the client frames the backend never sends
+    pub fn emit_packet(&self, packet: &str) {
+        let (lock, cvar) = &*self.socket;
+        let mut socket = lock.lock().unwrap();
+        socket.outbox.push_back(packet.to_string());
+        cvar.notify_all();
+    }
+
+    /// Sends the fs_change the backend sends for `op` on `rel`: the metadata of the entry when it
+    /// exists, as after add/change/write, only its name otherwise, as after unlink
+    pub fn emit_change(&self, op: &str, rel: &str) {
+        let mut payload = match self.get(rel) {
+            Some(node) => entry_json(rel, &node),
+            None => json!({ "name": name_of(rel), "is_dir": false, "size": 0 }),
+        };
+        payload["op"] = json!(op);
+        payload["relPath"] = json!(rel);
+        payload["parent"] = json!(parent_of(rel));
+        self.emit("fs_change", payload);
+    }
+
+    /// Ends the Socket.IO session as a backend restart would: the client sees its polls fail
+    pub fn drop_socket(&self) {
+        let (lock, cvar) = &*self.socket;
+        let mut socket = lock.lock().unwrap();
+        socket.dropped = true;
+        socket.connected = false;
+        socket.outbox.clear();
+        cvar.notify_all();
+    }
+
+    pub fn url(&self) -> String {
+        self.server.url.clone()
+    }
+
+    pub fn api(&self) -> frontend::file_api::FileApi {
+        frontend::file_api::FileApi::new(&self.server.url)
+    }
+
+    /// Puts `node` at `rel`, creating nothing else: the parent must be there
+    pub fn insert(&self, rel: &str, node: Node) {
+        self.tree.lock().unwrap().insert(rel.to_string(), node);
+    }
+
+    pub fn get(&self, rel: &str) -> Option<Node> {
+        self.tree.lock().unwrap().get(rel).cloned()
+    }
+
+    /// Takes the entry at `rel` away, as another client deleting it would
+    pub fn remove(&self, rel: &str) -> Option<Node> {
+        self.tree.lock().unwrap().remove(rel)
+    }
+
+    pub fn data(&self, rel: &str) -> Option<Vec<u8>> {
+        self.get(rel).map(|n| n.data)
+    }
+
+    /// Answers the requests `hook` returns Some for, before the regular routes
+    pub fn set_hook(
+        &self,
+        hook: impl Fn(&Request, &mut BTreeMap<String, Node>) -> Option<Response> + Send + Sync + 'static,
+    ) {
+        *self.hook.lock().unwrap() = Some(Box::new(hook));
+    }
+
+    /// Makes every request to `route` ("GET /files") wait `delay` before it is answered
+    pub fn set_delay(&self, route: &str, delay: Duration) {
+        self.delays.lock().unwrap().push((route.to_string(), delay));
+    }
+
+    pub fn clear_hook(&self) {
+        *self.hook.lock().unwrap() = None;
+    }
+}
+
+fn text(status: u16, body: &str) -> Response {
+    Response {
+        status,
+        headers: vec![("Content-Type".into(), "text/plain; charset=UTF-8".into())],
+        body: body.as_bytes().to_vec(),
+    }
+}
+
+// Function that answers the engine.io polling transport: the handshake, the packets the client posts
+// and its long polls. None when the socket is not enabled, so the route 404s
+fn socket_route(hub: &SocketHub, req: &Request) -> Option<Response> {
+    let (lock, cvar) = &**hub;
+    let mut socket = lock.lock().unwrap();
+    if !socket.enabled {
+        return None;
+    }
+    let Some(sid) = req.query("sid") else {
+        socket.sid += 1;
+        socket.dropped = false;
+        socket.connected = false;
+        socket.outbox.clear();
+        cvar.notify_all();
+        let open = json!({
+            "sid": socket.sid.to_string(),
+            "upgrades": [],
+            "pingInterval": 25000,
+            "pingTimeout": 20000,
+            "maxPayload": 1000000,
+        });
+        return Some(text(200, &format!("0{}", open)));
+    };
+    let current = |socket: &Socket| !socket.dropped && sid == socket.sid.to_string();
+    let unknown = || Response::json(400, json!({ "code": 1, "message": "Session ID unknown" }));
+    if !current(&socket) {
+        return Some(unknown());
+    }
+    if req.method == "POST" {
+        for packet in String::from_utf8_lossy(&req.body).split('\x1e') {
+            if packet.starts_with("40") {
+                socket.connected = true;
+                let ack = format!("40{}", json!({ "sid": format!("s{}", socket.sid) }));
+                socket.outbox.push_back(ack);
+            } else if packet.starts_with("41") || packet == "1" {
+                socket.connected = false;
+            }
+        }
+        cvar.notify_all();
+        return Some(text(200, "ok"));
+    }
+    let (mut socket, _) = cvar
+        .wait_timeout_while(socket, SOCKET_POLL, |s| current(s) && s.outbox.is_empty())
+        .unwrap();
+    if !current(&socket) {
+        return Some(unknown());
+    }
+    if socket.outbox.is_empty() {
+        return Some(text(200, "6"));
+    }
+    let packets: Vec<String> = socket.outbox.drain(..).collect();
+    Some(text(200, &packets.join("\x1e")))
+}
+
+fn parent_of(rel: &str) -> String {
+    match rel.rsplit_once('/') {
+        Some((parent, _)) if parent != "." => parent.to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+fn name_of(rel: &str) -> &str {
+    rel.rsplit_once('/').map_or(rel, |(_, name)| name)
+}
+
+pub fn entry_json(rel: &str, node: &Node) -> Value {
+    let kind = if let Some(kind) = node.kind {
+        kind
+    } else if node.is_dir {
+        "dir"
+    } else if node.symlink_target.is_some() {
+        "symlink"
+    } else {
+        "file"
+    };
+    json!({
+        "name": if rel == "." { "root" } else { name_of(rel) },
+        "path": rel,
+        "size": node.data.len(),
+        "mtime": node.mtime.0,
+        "mtime_ns": node.mtime.1,
+        "permissions": format!("{:o}", node.mode),
+        "is_dir": node.is_dir as i64,
+        "kind": kind,
+        "symlink_target": node.symlink_target,
+        "version": 1,
+        "nlink": if node.is_dir { 2 } else { 1 },
+    })
+}
+
+fn not_found() -> Response {
+    Response::json(404, json!({ "error": "File not found" }))
+}
+
+/// Answers a request as backend/ would; hooks call it to answer a request they rewrote
+pub fn route(req: &Request, tree: &mut BTreeMap<String, Node>) -> Response {
+    let rel = req.query("relPath").map(normalize).unwrap_or_else(|| ".".to_string());
+    match (req.method.as_str(), req.path.as_str()) {
+        ("GET", "/health") => Response::json(200, json!({ "service": "project-api-2025" })),
+        ("GET", "/stats") => Response::json(
+            200,
+            json!({ "bsize": 4096, "blocks": 1000, "bfree": 500, "bavail": 500, "files": 100, "ffree": 50 }),
+        ),
+        ("GET", "/list") => match tree.get(&rel) {
+            Some(n) if n.is_dir => {
+                let children: Vec<Value> = tree
+                    .iter()
+                    .filter(|(k, _)| k.as_str() != "." && parent_of(k) == rel)
+                    .map(|(k, n)| entry_json(k, n))
+                    .collect();
+                Response::json(200, Value::Array(children))
+            }
+            _ => not_found(),
+        },
+        ("GET", "/list/updatedMetadata") => match tree.get(&rel) {
+            Some(n) => Response::json(200, entry_json(&rel, n)),
+            None => not_found(),
+        },
+        ("HEAD", "/files") => match tree.get(&rel) {
+            Some(_) => Response::empty(200),
+            None => Response::empty(404),
+        },
+        ("GET", "/files") => match tree.get(&rel) {
+            Some(n) if !n.is_dir => read_file(req, &n.data),
+            _ => not_found(),
+        },
+        ("PUT", "/files") => {
+            if !tree.get(&parent_of(&rel)).is_some_and(|p| p.is_dir) {
+                return Response::json(400, json!({ "error": "Parent directory not found" }));
+            }
+            let offset: usize = req.query("offset").and_then(|o| o.parse().ok()).unwrap_or(0);
+            let node = tree.entry(rel).or_insert_with(|| Node::file(b""));
+            if offset == 0 {
+                node.data.clear();
+            }
+            if node.data.len() < offset + req.body.len() {
+                node.data.resize(offset + req.body.len(), 0);
+            }
+            node.data[offset..offset + req.body.len()].copy_from_slice(&req.body);
+            node.mtime = now();
+            Response::json(200, json!({ "written": req.body.len() }))
+        }
+        ("POST", "/files/create") => create(tree, rel, Node::file(b"")),
+        ("POST", "/files/touch") => {
+            if !tree.get(&parent_of(&rel)).is_some_and(|p| p.is_dir) {
+                return Response::json(400, json!({ "error": "Parent directory not found" }));
+            }
+            tree.entry(rel).or_insert_with(|| Node::file(b"")).mtime = now();
+            Response::json(200, json!({ "ok": true }))
+        }
+        ("POST", "/mkdir") => create(tree, rel, Node::dir()),
+        ("POST", "/files/symlink") => {
+            let mut node = Node::file(b"");
+            let target = req.query("target").unwrap_or_default().to_string();
+            node.data = target.clone().into_bytes();
+            node.mode = 0o777;
+            node.symlink_target = Some(target);
+            create(tree, rel, node)
+        }
+        ("GET", "/files/readlink") => match tree.get(&rel) {
+            Some(Node { symlink_target: Some(t), .. }) => Response::json(200, json!({ "target": t })),
+            Some(_) => Response::json(400, json!({ "error": "Not a symlink" })),
+            None => not_found(),
+        },
+        ("DELETE", "/files") => {
+            if tree.remove(&rel).is_none() {
+                return not_found();
+            }
+            let prefix = format!("{}/", rel);
+            tree.retain(|k, _| !k.starts_with(&prefix));
+            Response::json(200, json!({ "message": "Deletion completed" }))
+        }
+        ("PATCH", "/files/rename") => {
+            let old = normalize(req.query("oldRelPath").unwrap_or_default());
+            let new = normalize(req.query("newRelPath").unwrap_or_default());
+            if !tree.contains_key(&old) {
+                return Response::json(404, json!({ "error": "File not found for rename" }));
+            }
+            if !tree.get(&parent_of(&new)).is_some_and(|p| p.is_dir) {
+                return Response::json(404, json!({ "error": "New parent directory does not exist" }));
+            }
+            let old_prefix = format!("{}/", old);
+            let moved: Vec<(String, Node)> = tree
+                .iter()
+                .filter(|(k, _)| **k == old || k.starts_with(&old_prefix))
+                .map(|(k, n)| (k.clone(), n.clone()))
+                .collect();
+            tree.remove(&new);
+            for (k, n) in moved {
+                tree.remove(&k);
+                tree.insert(format!("{}{}", new, &k[old.len()..]), n);
+            }
+            Response::json(200, json!({ "ok": true }))
+        }
+        ("PATCH", "/files/truncate") => match tree.get_mut(&rel) {
+            Some(n) => {
+                let size = req.query("size").and_then(|s| s.parse().ok()).unwrap_or(0);
+                n.data.resize(size, 0);
+                Response::json(200, json!({ "ok": true }))
+            }
+            None => not_found(),
+        },
+        ("PATCH", "/files/chmod") => match tree.get_mut(&rel) {
+            Some(n) => {
+                n.mode = u32::from_str_radix(req.query("perm").unwrap_or("644"), 8).unwrap_or(0o644);
+                Response::json(200, json!({ "ok": true }))
+            }
+            None => not_found(),
+        },
+        ("PATCH", "/files/utimes") => match tree.get_mut(&rel) {
+            Some(n) => {
+                let secs = req.query("mtime").and_then(|s| s.parse().ok()).unwrap_or(0);
+                let nanos = req.query("mtimeNs").and_then(|s| s.parse().ok()).unwrap_or(0);
+                n.mtime = (secs, nanos);
+                Response::json(200, json!({ "ok": true }))
+            }
+            None => not_found(),
+        },
+        // A route the backend does not have: 404 without a JSON body
+        _ => Response::bytes(404, b"Cannot handle this route".to_vec()),
+    }
+}
+
+fn create(tree: &mut BTreeMap<String, Node>, rel: String, node: Node) -> Response {
+    if tree.contains_key(&rel) {
+        return Response::json(409, json!({ "error": "File already exists" }));
+    }
+    if !tree.get(&parent_of(&rel)).is_some_and(|p| p.is_dir) {
+        return Response::json(404, json!({ "error": "Parent directory not found" }));
+    }
+    tree.insert(rel, node);
+    Response::json(201, json!({ "ok": true }))
+}
+
+fn read_file(req: &Request, data: &[u8]) -> Response {
+    let Some(range) = req.header("Range").and_then(|r| r.strip_prefix("bytes=")) else {
+        return Response::bytes(200, data.to_vec());
+    };
+    if data.is_empty() {
+        return Response::empty(416);
+    }
+    let (start, end) = range.split_once('-').unwrap_or((range, ""));
+    let start: usize = start.parse().unwrap_or(0);
+    let end: usize = end.parse().unwrap_or(data.len() - 1).min(data.len() - 1);
+    if start >= data.len() {
+        return Response::empty(416);
+    }
+    Response::bytes(206, data[start..=end].to_vec())
+        .with_header("Content-Range", &format!("bytes {}-{}/{}", start, end, data.len()))
+}
+
+// Same canonical form the backend gives relPath: "." for the root, "./a/b" otherwise
+fn normalize(raw: &str) -> String {
+    let parts: Vec<&str> = raw.split(['/', '\\']).filter(|c| !c.is_empty() && *c != ".").collect();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        format!("./{}", parts.join("/"))
+    }
+}
+
+/// A unique empty directory under the system temp dir, removed on drop
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(tag: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "remote-fs-test-{}-{}-{}",
+            tag,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// FUSE mounts of one test binary are serialized: the kernel side is shared
+static MOUNT_LOCK: Mutex<()> = Mutex::new(());
+
+/// The client mounted on a temporary directory; unmounted on drop
+pub struct Mounted {
+    pub dir: TempDir,
+    handle: Option<JoinHandle<anyhow::Result<()>>>,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Mounted {
+    /// Mounts `api` with `opts`, None (the test is skipped) where FUSE cannot be used
+    #[cfg(target_os = "linux")]
+    pub fn start(api: frontend::file_api::FileApi, url: &str, opts: frontend::options::MountOptions) -> Option<Self> {
+        if !fuse_available() {
+            eprintln!("FUSE not available, skipping");
+            return None;
+        }
+        let guard = MOUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = TempDir::new("mnt");
+        let spec = frontend::MountSpec {
+            mountpoint: dir.path().to_string_lossy().into_owned(),
+            api,
+            url: url.to_string(),
+            opts: frontend::options::MountOptions { force: true, ..opts },
+        };
+        let handle = std::thread::spawn(move || frontend::mount_all(vec![spec]));
+        let mounted = Mounted {
+            dir,
+            handle: Some(handle),
+            _guard: guard,
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !mounted.is_mounted() {
+            if Instant::now() > deadline || mounted.handle.as_ref().unwrap().is_finished() {
+                panic!("the mount did not come up");
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Some(mounted)
+    }
+
+    pub fn path(&self, rel: &str) -> PathBuf {
+        self.dir.path().join(rel)
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        let mp = self.dir.path().to_string_lossy().into_owned();
+        std::fs::read_to_string("/proc/mounts")
+            .unwrap_or_default()
+            .lines()
+            .any(|l| l.split_whitespace().nth(1) == Some(mp.as_str()))
+    }
+
+    /// Unmounts and checks mount_all returned; it reports the unmount from outside as an error
+    pub fn unmount(mut self) {
+        assert!(self.stop().is_some(), "mount_all did not return after the unmount");
+    }
+
+    /// Waits, up to `patience`, for mount_all to return by itself; None if it is still running
+    pub fn wait_exit(&mut self, patience: Duration) -> Option<anyhow::Result<()>> {
+        let deadline = Instant::now() + patience;
+        while !self.handle.as_ref()?.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let handle = self.handle.take_if(|h| h.is_finished())?;
+        Some(handle.join().unwrap())
+    }
+
+    fn stop(&mut self) -> Option<anyhow::Result<()>> {
+        let handle = self.handle.take()?;
+        let _ = std::process::Command::new("umount").arg("-l").arg(self.dir.path()).status();
+        let deadline = Instant::now() + Duration::from_secs(15);
+        while !handle.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        handle.is_finished().then(|| handle.join().unwrap())
+    }
+}
+
+impl Drop for Mounted {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Root with /dev/fuse: the tests mounting the client run only there
+pub fn fuse_available() -> bool {
+    cfg!(target_os = "linux") && Path::new("/dev/fuse").exists() && unsafe { libc::geteuid() } == 0
+}
+
+/// Polls `f` until it returns Some, for what the client does after the syscall returned (release)
+pub fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(v) = f() {
+            return v;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for the backend");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
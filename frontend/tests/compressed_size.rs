@@ -0,0 +1,66 @@
+mod common;
+
+use common::{entry_json, FakeBackend, Mounted, Node, Response};
+use frontend::file_api::DirectoryEntry;
+use frontend::options::MountOptions;
+use serde_json::{json, Value};
+use std::os::unix::fs::MetadataExt;
+
+// Logical size of the file the backend stores compressed, and what it takes there
+const LOGICAL: usize = 100_000;
+const STORED: u64 = 1_000;
+
+fn entry(extra: Value) -> DirectoryEntry {
+    let mut raw = entry_json("./a.bin", &Node::file(b""));
+    raw.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+    serde_json::from_value(raw).unwrap()
+}
+
+#[test]
+fn the_logical_size_is_the_size_and_the_stored_one_counts_the_blocks() {
+    // An uncompressed entry: one size for both
+    let plain = entry(json!({ "size": 4096 }));
+    assert_eq!((plain.size, plain.stored_size, plain.blocks()), (4096, None, 8));
+
+    let compressed = entry(json!({ "size": STORED, "logical_size": LOGICAL, "stored_size": STORED }));
+    assert_eq!((compressed.size, compressed.stored_size), (LOGICAL as i64, Some(STORED)));
+    assert_eq!(compressed.blocks(), 2);
+    assert!(!compressed.partial);
+
+    // Without stored_size, size is the stored one
+    let implied = entry(json!({ "size": STORED, "logical_size": LOGICAL }));
+    assert_eq!((implied.size, implied.stored_size, implied.blocks()), (LOGICAL as i64, Some(STORED), 2));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn stat_reports_the_logical_size_and_the_stored_blocks() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.bin", Node::file(&vec![b'x'; LOGICAL]));
+    backend.set_hook(|req, tree| match req.route().as_str() {
+        "GET /list" if req.query("relPath") == Some(".") => {
+            let entries: Vec<Value> = tree
+                .iter()
+                .filter(|(rel, _)| rel.matches('/').count() == 1)
+                .map(|(rel, node)| {
+                    let mut entry = entry_json(rel, node);
+                    entry["size"] = STORED.into();
+                    entry["logical_size"] = node.data.len().into();
+                    entry["stored_size"] = STORED.into();
+                    entry
+                })
+                .collect();
+            Some(Response::json(200, Value::Array(entries)))
+        }
+        _ => None,
+    });
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let meta = std::fs::metadata(mnt.path("a.bin")).unwrap();
+    assert_eq!(meta.len(), LOGICAL as u64);
+    assert_eq!(meta.blocks(), STORED.div_ceil(512));
+    assert_eq!(std::fs::read(mnt.path("a.bin")).unwrap().len(), LOGICAL);
+    mnt.unmount();
+}
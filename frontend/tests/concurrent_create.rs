@@ -0,0 +1,75 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::api_error::ApiError;
+use frontend::options::MountOptions;
+use std::io::Write;
+use std::sync::{Arc, Barrier};
+
+#[tokio::test]
+async fn create_exclusive_refuses_an_existing_name() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+    api.create_exclusive("./new.txt").await.unwrap();
+    let err = api.create_exclusive("./new.txt").await.unwrap_err();
+    assert!(matches!(err, ApiError::Http { status: 409, .. }), "{:?}", err);
+    assert_eq!(backend.data("./new.txt").unwrap(), b"");
+}
+
+#[cfg(target_os = "linux")]
+fn race<T: Send + 'static>(run: impl Fn(usize) -> T + Send + Sync + 'static) -> Vec<T> {
+    let run = Arc::new(run);
+    let barrier = Arc::new(Barrier::new(2));
+    let threads: Vec<_> = (0..2)
+        .map(|i| {
+            let (run, barrier) = (run.clone(), barrier.clone());
+            std::thread::spawn(move || {
+                barrier.wait();
+                run(i)
+            })
+        })
+        .collect();
+    threads.into_iter().map(|t| t.join().unwrap()).collect()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn two_exclusive_creates_of_one_name_let_exactly_one_win() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let path = mnt.path("race.txt");
+    let results = race(move |i| {
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+        file.write_all(format!("writer {}", i).as_bytes())
+    });
+
+    let (won, lost): (Vec<_>, Vec<_>) = results.into_iter().partition(|r| r.is_ok());
+    assert_eq!((won.len(), lost.len()), (1, 1));
+    assert_eq!(lost[0].as_ref().unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(backend.server.count("POST /files/create"), 1);
+    // The release that commits runs after close returns
+    wait_for(|| backend.data("./race.txt").filter(|d| d == b"writer 0" || d == b"writer 1"));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn two_plain_creates_of_one_name_share_the_file() {
+    let backend = FakeBackend::start();
+    backend.insert("./dir", Node::dir());
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let path = mnt.path("dir/shared.txt");
+    let results = race(move |i| {
+        let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(false).open(&path)?;
+        file.write_all(format!("writer {}", i).as_bytes())
+    });
+
+    // The second one opens the file the first is still creating, whichever closes last wins
+    assert!(results.iter().all(|r| r.is_ok()), "{:?}", results);
+    wait_for(|| backend.data("./dir/shared.txt").filter(|d| d == b"writer 0" || d == b"writer 1"));
+    mnt.unmount();
+}
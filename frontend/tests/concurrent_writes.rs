@@ -0,0 +1,51 @@
+mod common;
+
+use std::os::unix::fs::FileExt;
+use std::sync::Arc;
+
+use common::{wait_for, FakeBackend, Mounted};
+use frontend::options::MountOptions;
+
+const BLOCK: usize = 4096;
+const WRITERS: usize = 8;
+const ROUNDS: usize = 50;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn overlapping_writes_on_one_handle_do_not_tear() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let file = Arc::new(std::fs::File::create(mnt.path("shared.bin")).unwrap());
+    let threads: Vec<_> = (0..WRITERS)
+        .map(|w| {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                let block = vec![b'a' + w as u8; BLOCK];
+                for round in 0..ROUNDS {
+                    // Every writer hits the same block, half of them shifted by half a block
+                    let offset = if (w + round) % 2 == 0 { 0 } else { BLOCK as u64 / 2 };
+                    file.write_all_at(&block, offset).unwrap();
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    drop(file);
+
+    // The upload happens at release, which the kernel sends once close returned
+    let data = wait_for(|| backend.data("./shared.bin").filter(|d| d.len() == BLOCK + BLOCK / 2));
+    // Each half block holds a single writer's bytes: writes landed whole, one after the other
+    for half in data.chunks(BLOCK / 2) {
+        assert!(
+            half.iter().all(|b| *b == half[0]),
+            "interleaved bytes in {:?}",
+            String::from_utf8_lossy(half)
+        );
+    }
+    mnt.unmount();
+}
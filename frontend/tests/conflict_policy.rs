@@ -0,0 +1,80 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::{ConflictPolicy, MountOptions};
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+
+#[test]
+fn the_policy_is_parsed() {
+    let parse = |value: &str| {
+        let args: Vec<String> = ["frontend", "--conflict-policy", value].iter().map(|s| s.to_string()).collect();
+        MountOptions::from_args(&args).map(|o| o.conflict_policy)
+    };
+    assert_eq!(parse("fail").unwrap(), ConflictPolicy::Fail);
+    assert_eq!(parse("overwrite").unwrap(), ConflictPolicy::Overwrite);
+    assert_eq!(parse("rename").unwrap(), ConflictPolicy::Rename);
+    assert!(parse("merge").is_err());
+    assert_eq!(MountOptions::default().conflict_policy, ConflictPolicy::Fail);
+}
+
+// Function that writes "mine" to doc.txt while another client replaces it with "theirs", then closes
+// it and returns the backend once the handle is released
+#[cfg(target_os = "linux")]
+fn write_during_a_remote_replace(policy: ConflictPolicy) -> Option<FakeBackend> {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./doc.txt", Node::file(b"v1"));
+    let opts = MountOptions { conflict_policy: policy, ..MountOptions::default() };
+    let mnt = Mounted::start(backend.api(), &backend.url(), opts)?;
+    wait_for(|| backend.socket_connected().then_some(()));
+
+    let mut file = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .truncate(true)
+        .open(mnt.path("doc.txt"))
+        .unwrap();
+    file.write_all(b"mine").unwrap();
+    backend.remove("./doc.txt");
+    backend.emit_change("unlink", "./doc.txt");
+    backend.insert("./doc.txt", Node::file(b"theirs"));
+    // The handle learns it went stale
+    let err = wait_for(|| file.read_at(&mut [0u8; 4], 0).err());
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+    drop(file);
+    let status = || std::fs::read_to_string(mnt.path(".remotefs/status")).unwrap();
+    wait_for(|| status().contains("\"open_writes\":0").then_some(()));
+    mnt.unmount();
+    Some(backend)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fail_discards_the_local_changes() {
+    let Some(backend) = write_during_a_remote_replace(ConflictPolicy::Fail) else {
+        return;
+    };
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"theirs");
+    assert!(backend.get("./doc.txt.conflict").is_none());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn overwrite_makes_the_last_writer_win() {
+    let Some(backend) = write_during_a_remote_replace(ConflictPolicy::Overwrite) else {
+        return;
+    };
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"mine");
+    assert!(backend.get("./doc.txt.conflict").is_none());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn rename_keeps_both_versions() {
+    let Some(backend) = write_during_a_remote_replace(ConflictPolicy::Rename) else {
+        return;
+    };
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"theirs");
+    assert_eq!(backend.data("./doc.txt.conflict").unwrap(), b"mine");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, TempDir};
+use frontend::api_error::ApiError;
+use frontend::options::MountOptions;
+use std::io::ErrorKind;
+
+#[tokio::test]
+async fn names_with_a_tab_or_a_newline_round_trip() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+    let local = TempDir::new("control-chars");
+    let upload = local.path().join("upload.bin");
+    std::fs::write(&upload, b"from disk").unwrap();
+
+    api.write_bytes("./tab\there.txt", b"tabbed").await.unwrap();
+    api.write_file("./line\nbreak.txt", &upload).await.unwrap();
+
+    // Stored under the exact names, not split or truncated at the control character
+    assert_eq!(backend.data("./tab\there.txt").unwrap(), b"tabbed");
+    assert_eq!(backend.data("./line\nbreak.txt").unwrap(), b"from disk");
+    let mut names: Vec<String> = api.ls(".").await.unwrap().into_iter().map(|de| de.name).collect();
+    names.sort();
+    assert_eq!(names, ["line\nbreak.txt", "tab\there.txt"]);
+    assert_eq!(api.read_all("./tab\there.txt", 6).await.unwrap(), b"tabbed");
+    assert_eq!(api.read_all("./line\nbreak.txt", 9).await.unwrap(), b"from disk");
+    // The backend decodes them back from the query
+    assert!(backend.server.requests().iter().any(|r| r.query("relPath") == Some("./line\nbreak.txt")));
+}
+
+#[tokio::test]
+async fn a_name_with_a_nul_is_refused_before_any_request() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+
+    let err = api.write_bytes("./nul\0name.txt", b"x").await.unwrap_err();
+    assert!(matches!(err, ApiError::Io(ref e) if e.kind() == ErrorKind::InvalidInput), "{:?}", err);
+    let err = api.ls("./dir\0").await.unwrap_err();
+    assert!(matches!(err, ApiError::Io(ref e) if e.kind() == ErrorKind::InvalidInput), "{:?}", err);
+    let err = api.rename("./a.txt", "./b\0.txt").await.unwrap_err();
+    assert!(matches!(err, ApiError::Io(ref e) if e.kind() == ErrorKind::InvalidInput), "{:?}", err);
+    assert_eq!(backend.server.total(), 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_creates_and_lists_names_with_control_characters() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    std::fs::write(mnt.path("tab\there.txt"), b"tabbed").unwrap();
+    std::fs::write(mnt.path("line\nbreak.txt"), b"broken").unwrap();
+    wait_for(|| (backend.data("./line\nbreak.txt")? == b"broken").then_some(()));
+    wait_for(|| (backend.data("./tab\there.txt")? == b"tabbed").then_some(()));
+
+    let mut names: Vec<String> = std::fs::read_dir(mnt.path(""))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|n| !n.starts_with('.'))
+        .collect();
+    names.sort();
+    assert_eq!(names, ["line\nbreak.txt", "tab\there.txt"]);
+    assert_eq!(std::fs::read(mnt.path("line\nbreak.txt")).unwrap(), b"broken");
+    mnt.unmount();
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn writing_refresh_clears_the_caches_and_reconnects() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./a.txt", Node::file(b"one"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+    assert_eq!(backend.socket_sessions(), 1);
+
+    // Reading it returns the status of the mount
+    let status = || std::fs::read_to_string(mnt.path(".remotefs/refresh")).unwrap();
+    let field = |status: &str, name: &str| -> String {
+        let line = status.lines().find(|l| l.starts_with(&format!("{}: ", name))).unwrap();
+        line[name.len() + 2..].to_string()
+    };
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"one");
+    let before = status();
+    assert_eq!(field(&before, "backend_url"), backend.url(), "{}", before);
+    assert_eq!(field(&before, "websocket"), "connected", "{}", before);
+    assert_ne!(field(&before, "attr_cache_entries"), "0", "{}", before);
+    assert_ne!(field(&before, "dir_cache_entries"), "0", "{}", before);
+
+    backend.server.clear();
+    std::fs::write(mnt.path(".remotefs/refresh"), b"1").unwrap();
+    let after = status();
+    assert_eq!(field(&after, "attr_cache_entries"), "0", "{}", after);
+    assert_eq!(field(&after, "dir_cache_entries"), "0", "{}", after);
+    // The control files never reach the backend
+    assert!(backend.server.requests().iter().all(|r| !r.query.iter().any(|(_, v)| v.contains(".remotefs"))));
+
+    // The websocket is opened again, and the next access goes back to the backend
+    wait_for(|| (backend.socket_sessions() == 2 && backend.socket_connected()).then_some(()));
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"one");
+    assert!(backend.server.count("GET /list") > 0);
+    mnt.unmount();
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    backend.insert("./src", Node::dir());
+    backend.insert("./src/a.txt", Node::file(b"alpha"));
+    backend.insert("./src/sub", Node::dir());
+    let mut script = Node::file(b"#!/bin/sh\n");
+    script.mode = 0o755;
+    backend.insert("./src/sub/run.sh", script);
+    backend.insert("./src/sub/empty", Node::dir());
+    backend
+}
+
+// A backend implementing POST /files/copy: the source and everything under it land at the destination
+fn with_copy_endpoint(backend: &FakeBackend) {
+    backend.set_hook(|req, tree| {
+        if req.route() != "POST /files/copy" {
+            return None;
+        }
+        let (src, dst) = (req.query("srcRelPath")?.to_string(), req.query("dstRelPath")?.to_string());
+        if !tree.contains_key(&src) {
+            return Some(Response::json(404, json!({ "error": "File not found" })));
+        }
+        if tree.contains_key(&dst) {
+            return Some(Response::json(409, json!({ "error": "Destination already exists" })));
+        }
+        let copied: Vec<(String, common::Node)> = tree
+            .iter()
+            .filter_map(|(rel, node)| {
+                let rest = rel.strip_prefix(&src)?;
+                (rest.is_empty() || rest.starts_with('/')).then(|| (format!("{}{}", dst, rest), node.clone()))
+            })
+            .collect();
+        tree.extend(copied);
+        Some(Response::empty(200))
+    });
+}
+
+// Function that lists the subtree under `root`: relative path, kind, content and mode of each entry
+fn subtree(backend: &FakeBackend, root: &str) -> Vec<(String, bool, Vec<u8>, u32)> {
+    let prefix = format!("{}/", root);
+    let mut out: Vec<_> = ["./src/a.txt", "./src/sub", "./src/sub/run.sh", "./src/sub/empty"]
+        .iter()
+        .map(|rel| rel.replacen("./src/", &prefix, 1))
+        .filter_map(|rel| {
+            let node = backend.get(&rel)?;
+            Some((rel[prefix.len()..].to_string(), node.is_dir, node.data, node.mode))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+#[tokio::test]
+async fn the_backend_copies_the_tree_in_one_request() {
+    let backend = tree();
+    with_copy_endpoint(&backend);
+    let api = backend.api();
+
+    api.copy_recursive("./src", "./dst").await.unwrap();
+    let sent = backend.server.requests();
+    let copy = sent.iter().find(|r| r.route() == "POST /files/copy").unwrap();
+    assert_eq!(
+        (copy.query("srcRelPath"), copy.query("dstRelPath"), copy.query("recursive")),
+        (Some("./src"), Some("./dst"), Some("true"))
+    );
+    // No data went through the client
+    assert_eq!(backend.server.count("PUT /files") + backend.server.count("GET /files"), 0);
+    assert_eq!(subtree(&backend, "./dst"), subtree(&backend, "./src"));
+    assert_eq!(subtree(&backend, "./dst").len(), 4);
+
+    let err = api.copy_recursive("./src", "./dst").await.unwrap_err();
+    assert_eq!(err.status(), Some(409));
+}
+
+#[tokio::test]
+async fn without_the_endpoint_the_client_walks_the_tree() {
+    let backend = tree();
+    let api = backend.api();
+
+    api.copy_recursive("./src", "./dst").await.unwrap();
+    assert_eq!(subtree(&backend, "./dst"), subtree(&backend, "./src"));
+    assert_eq!(subtree(&backend, "./dst").len(), 4);
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+
+    // The missing endpoint is remembered, also by clones
+    api.clone().copy_recursive("./src/sub", "./sub-copy").await.unwrap();
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+    assert_eq!(backend.data("./sub-copy/run.sh").unwrap(), b"#!/bin/sh\n");
+
+    // A copy into itself or over an existing entry is refused before anything is written
+    assert!(api.copy_recursive("./src", "./src/inner").await.is_err());
+    assert!(backend.get("./src/inner").is_none());
+    assert_eq!(api.copy_recursive("./src", "./dst").await.unwrap_err().status(), Some(409));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_control_file_offloads_the_copy_and_the_mount_shows_it() {
+    let backend = tree();
+    with_copy_endpoint(&backend);
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    // Listed before the copy: the root listing must be fetched again to show dst
+    assert!(!mnt.path("dst").exists());
+
+    std::fs::write(mnt.path(".remotefs/copy"), b"src\ndst\n").unwrap();
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+    assert_eq!(std::fs::read(mnt.path("dst/sub/run.sh")).unwrap(), b"#!/bin/sh\n");
+    assert!(mnt.path("dst/sub/empty").is_dir());
+
+    // The error of the copy comes back from the write
+    let err = std::fs::write(mnt.path(".remotefs/copy"), b"src\ndst\n").unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+    let err = std::fs::write(mnt.path(".remotefs/copy"), b"only one line\n").unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    mnt.unmount();
+}
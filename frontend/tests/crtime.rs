@@ -0,0 +1,55 @@
+mod common;
+
+use common::{entry_json, FakeBackend, Mounted, Node, Response};
+use frontend::file_api::DirectoryEntry;
+use frontend::options::MountOptions;
+use serde_json::Value;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn the_birth_time_is_kept_apart_from_the_mtime() {
+    let entry = |extra: &str| -> DirectoryEntry {
+        serde_json::from_str(&format!(
+            r#"{{"name":"a","size":1,"mtime":2000,"mtime_ns":5,"permissions":"644","is_dir":0,"version":1,"nlink":1{}}}"#,
+            extra
+        ))
+        .unwrap()
+    };
+    let born = entry(r#","crtime":1000,"crtime_ns":7"#);
+    assert_eq!(born.crtime_time(), UNIX_EPOCH + Duration::new(1000, 7));
+    assert_eq!(born.mtime_time(), UNIX_EPOCH + Duration::new(2000, 5));
+    // A backend without birth times falls back to the mtime
+    let unknown = entry(r#","crtime":null"#);
+    assert_eq!(unknown.crtime_time(), unknown.mtime_time());
+    assert_eq!(entry("").crtime_time(), UNIX_EPOCH + Duration::new(2000, 5));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn getattr_carries_the_birth_time_of_the_backend() {
+    let backend = FakeBackend::start();
+    let mut node = Node::file(b"born");
+    node.mtime = (2_000_000_000, 0);
+    backend.insert("./born.txt", node);
+    backend.set_hook(|req, tree| {
+        if req.route() != "GET /list" || req.query("relPath") != Some(".") {
+            return None;
+        }
+        let mut entry = entry_json("./born.txt", &tree["./born.txt"]);
+        entry["crtime"] = 1_500_000_000.into();
+        entry["crtime_ns"] = 250.into();
+        Some(Response::json(200, Value::Array(vec![entry])))
+    });
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let meta = std::fs::metadata(mnt.path("born.txt")).unwrap();
+    assert_eq!(meta.modified().unwrap(), UNIX_EPOCH + Duration::new(2_000_000_000, 0));
+    // statx only reports a birth time where the kernel asks the filesystem for one
+    match meta.created() {
+        Ok(created) => assert_eq!(created, UNIX_EPOCH + Duration::new(1_500_000_000, 250)),
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::Unsupported),
+    }
+    mnt.unmount();
+}
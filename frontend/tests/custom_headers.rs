@@ -0,0 +1,74 @@
+mod common;
+
+use common::{FakeBackend, Response};
+use frontend::file_api::FileApi;
+use frontend::options::MountOptions;
+
+fn with_tenant(backend: &FakeBackend) -> FileApi {
+    backend.api().with_headers(&[("X-Tenant".to_string(), "acme".to_string())])
+}
+
+fn parse(value: &str) -> anyhow::Result<MountOptions> {
+    let args: Vec<String> = ["frontend", "--header", value].iter().map(|s| s.to_string()).collect();
+    MountOptions::from_args(&args)
+}
+
+#[test]
+fn header_options_are_validated() {
+    let opts = parse("X-Tenant=acme").unwrap();
+    assert_eq!(opts.headers, vec![("X-Tenant".to_string(), "acme".to_string())]);
+    assert!(parse("X-Tenant").is_err());
+    assert!(parse("Bad Name=x").is_err());
+    assert!(parse("Cookie=session=1").is_err());
+    assert!(parse("X-Tenant=caffè").is_err());
+}
+
+#[tokio::test]
+async fn configured_headers_reach_every_request() {
+    let backend = FakeBackend::start();
+    let api = with_tenant(&backend);
+    api.mkdir("/docs").await.unwrap();
+    api.write_bytes("/docs/a.txt", b"hi").await.unwrap();
+    api.chmod("/docs/a.txt", 0o600).await.unwrap();
+    assert_eq!(api.ls("/docs").await.unwrap().len(), 1);
+    assert_eq!(api.read_all("/docs/a.txt", 2).await.unwrap(), b"hi");
+    api.delete("/docs/a.txt").await.unwrap();
+
+    let requests = backend.server.requests();
+    assert!(requests.len() >= 6);
+    for req in &requests {
+        assert_eq!(req.header("X-Tenant"), Some("acme"), "{}", req.route());
+    }
+    assert!(api
+        .handshake_headers()
+        .contains(&("x-tenant".to_string(), "acme".to_string())));
+}
+
+#[tokio::test]
+async fn configured_headers_stay_with_the_backend_origin() {
+    // A redirect within the backend keeps them
+    let backend = FakeBackend::start();
+    backend.set_hook(|req, _| {
+        (req.route() == "GET /list" && req.query("moved").is_none())
+            .then(|| Response::empty(302).with_header("Location", "/list?relPath=.&moved=1"))
+    });
+    with_tenant(&backend).ls("/").await.unwrap();
+    let requests = backend.server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|r| r.header("X-Tenant") == Some("acme")));
+
+    // Another port is another origin, whether the client follows the redirect itself or not
+    for status in [301, 302, 307, 308] {
+        let backend = FakeBackend::start();
+        let other = FakeBackend::start();
+        let target = format!("{}/list?relPath=.", other.url());
+        backend.set_hook(move |req, _| {
+            (req.route() == "GET /list").then(|| Response::empty(status).with_header("Location", &target))
+        });
+        with_tenant(&backend).ls("/").await.unwrap();
+        assert_eq!(backend.server.requests()[0].header("X-Tenant"), Some("acme"));
+        let followed = other.server.requests();
+        assert_eq!(followed.len(), 1, "{}", status);
+        assert_eq!(followed[0].header("X-Tenant"), None, "{}", status);
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+// Size the backend reports for ./docs, as a filesystem that charges directories their blocks
+const DOCS_SIZE: usize = 4096;
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    let mut docs = Node::dir();
+    docs.data = vec![0; DOCS_SIZE];
+    backend.insert("./docs", docs);
+    backend.insert("./docs/a.txt", Node::file(b"hello"));
+    backend
+}
+
+#[test]
+fn dir_size_from_backend_is_off_by_default() {
+    let args: Vec<String> = ["frontend", "--dir-size-from-backend"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).unwrap().dir_size_from_backend);
+    assert!(!MountOptions::default().dir_size_from_backend);
+}
+
+#[tokio::test]
+async fn listings_keep_the_directory_size_only_when_asked() {
+    let backend = tree();
+    let size_of = |entries: Vec<frontend::file_api::DirectoryEntry>, name: &str| {
+        entries.into_iter().find(|e| e.name == name).unwrap().size
+    };
+
+    let api = backend.api();
+    assert_eq!(size_of(api.ls(".").await.unwrap(), "docs"), 0);
+    assert_eq!(api.get_update_metadata("./docs").await.unwrap().size, 0);
+
+    let api = backend.api().with_dir_size_from_backend(true);
+    assert_eq!(size_of(api.ls(".").await.unwrap(), "docs"), DOCS_SIZE as i64);
+    assert_eq!(api.get_update_metadata("./docs").await.unwrap().size, DOCS_SIZE as i64);
+    // Files are not touched either way
+    assert_eq!(size_of(api.ls("./docs").await.unwrap(), "a.txt"), 5);
+}
+
+#[cfg(target_os = "linux")]
+fn docs_len(from_backend: bool) -> Option<u64> {
+    let backend = tree();
+    let opts = MountOptions {
+        dir_size_from_backend: from_backend,
+        ..MountOptions::default()
+    };
+    let api = backend.api().with_dir_size_from_backend(from_backend);
+    let mnt = Mounted::start(api, &backend.url(), opts)?;
+    let len = std::fs::metadata(mnt.path("docs")).unwrap().len();
+    assert_eq!(std::fs::metadata(mnt.path("docs/a.txt")).unwrap().len(), 5);
+    mnt.unmount();
+    Some(len)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_directory_shows_the_backend_size_when_enabled_and_0_otherwise() {
+    let Some(enabled) = docs_len(true) else {
+        return;
+    };
+    assert_eq!(enabled, DOCS_SIZE as u64);
+    assert_eq!(docs_len(false), Some(0));
+}
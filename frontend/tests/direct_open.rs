@@ -0,0 +1,70 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::fs::OpenOptions;
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+
+#[cfg(target_os = "linux")]
+#[test]
+fn every_read_on_a_direct_handle_goes_to_the_backend() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"cached content"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    // On a plain handle the first read fills the page cache, and the next ones are served from it
+    let file = OpenOptions::new().read(true).open(mnt.path("doc.txt")).unwrap();
+    let mut buf = [0u8; 64];
+    assert_eq!(file.read_at(&mut buf, 0).unwrap(), 14);
+    let before = backend.server.count("GET /files");
+    for _ in 0..3 {
+        assert_eq!(file.read_at(&mut buf, 0).unwrap(), 14);
+    }
+    assert_eq!(backend.server.count("GET /files"), before);
+    drop(file);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(mnt.path("doc.txt"))
+        .unwrap();
+    let before = backend.server.count("GET /files");
+    for _ in 0..3 {
+        let n = file.read_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf[..n], b"cached content");
+    }
+    assert_eq!(backend.server.count("GET /files"), before + 3);
+
+    // A change the mount was not told about is seen at once, size included
+    backend.insert("./doc.txt", Node::file(b"fresh"));
+    let n = file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..n], b"fresh");
+    assert_eq!(file.metadata().unwrap().len(), 5);
+    drop(file);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_write_on_a_direct_handle_is_committed_before_close() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"old data"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let file = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(mnt.path("doc.txt"))
+        .unwrap();
+    file.write_all_at(b"new", 0).unwrap();
+    // Still open: nothing waits for release
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"new data");
+    file.write_all_at(b"!", 8).unwrap();
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"new data!");
+    drop(file);
+    mnt.unmount();
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::TempDir;
+#[cfg(target_os = "linux")]
+use common::{wait_for, FakeBackend, Mounted};
+#[cfg(target_os = "linux")]
+use frontend::options::MountOptions;
+use frontend::temp_dir::{is_disk_full, write_at};
+use std::path::Path;
+
+// Size of the filesystem the tempfiles are written to: small enough to fill in a test
+const DISK: usize = 64 * 1024;
+
+/// A tmpfs of DISK bytes mounted on a fresh directory, unmounted on drop
+struct SmallDisk(TempDir);
+
+impl SmallDisk {
+    // Function that mounts the tmpfs, None (the test is skipped) when not running as root
+    fn mount() -> Option<Self> {
+        if unsafe { libc::geteuid() } != 0 {
+            eprintln!("not root, skipping");
+            return None;
+        }
+        let dir = TempDir::new("small-disk");
+        let c = |s: &str| std::ffi::CString::new(s).unwrap();
+        let target = c(&dir.path().to_string_lossy());
+        let data = c(&format!("size={}", DISK));
+        let rc = unsafe {
+            libc::mount(c("tmpfs").as_ptr(), target.as_ptr(), c("tmpfs").as_ptr(), 0, data.as_ptr().cast())
+        };
+        assert_eq!(rc, 0, "mount tmpfs: {}", std::io::Error::last_os_error());
+        Some(SmallDisk(dir))
+    }
+
+    fn path(&self) -> &Path {
+        self.0.path()
+    }
+}
+
+impl Drop for SmallDisk {
+    fn drop(&mut self) {
+        let target = std::ffi::CString::new(self.0.path().to_string_lossy().as_bytes()).unwrap();
+        unsafe { libc::umount2(target.as_ptr(), libc::MNT_DETACH) };
+    }
+}
+
+#[test]
+fn a_write_that_fills_the_disk_is_reported_and_leaves_the_length_as_it_was() {
+    let Some(disk) = SmallDisk::mount() else {
+        return;
+    };
+    let path = disk.path().join("fh.part");
+    std::fs::write(&path, b"0123456789").unwrap();
+
+    assert_eq!(write_at(&path, 10, 10, b"abc").unwrap(), 13);
+    let err = write_at(&path, 13, 13, &vec![b'x'; 2 * DISK]).unwrap_err();
+    assert!(is_disk_full(&err), "{:?}", err);
+    assert_eq!(err.raw_os_error(), Some(libc::ENOSPC));
+    // The bytes that did fit are cut away again
+    assert_eq!(std::fs::read(&path).unwrap(), b"0123456789abc");
+    assert!(!is_disk_full(&std::io::Error::from_raw_os_error(libc::EIO)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_write_through_the_mount_that_fills_the_temp_disk_fails_with_enospc() {
+    use std::io::Write;
+    use std::os::unix::fs::FileExt;
+
+    let Some(disk) = SmallDisk::mount() else {
+        return;
+    };
+    let backend = FakeBackend::start();
+    let opts = MountOptions {
+        temp_dir: Some(disk.path().to_path_buf()),
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    let fits = vec![b'a'; DISK / 2];
+    let mut file = std::fs::File::create(mnt.path("big.bin")).unwrap();
+    file.write_all(&fits).unwrap();
+    let err = file.write_all_at(&vec![b'b'; DISK], fits.len() as u64).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::ENOSPC));
+    // The handle still accounts for what was written before, and that is what gets committed
+    assert_eq!(file.metadata().unwrap().len(), fits.len() as u64);
+    drop(file);
+    wait_for(|| (backend.data("./big.bin")? == fits).then_some(()));
+    mnt.unmount();
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::{FakeBackend, Node, Response};
+
+#[tokio::test]
+async fn exists_asks_with_a_head_and_no_listing() {
+    let backend = FakeBackend::start();
+    backend.insert("./docs", Node::dir());
+    backend.insert("./docs/a.txt", Node::file(b"a"));
+    let api = backend.api();
+
+    assert!(api.exists("/docs/a.txt").await.unwrap());
+    assert!(api.exists("/docs").await.unwrap());
+    assert!(!api.exists("/docs/missing.txt").await.unwrap());
+    assert!(!api.exists("/nowhere/a.txt").await.unwrap());
+
+    let routes: Vec<String> = backend.server.requests().iter().map(|r| r.route()).collect();
+    assert_eq!(routes, ["HEAD /files"; 4]);
+    assert_eq!(backend.server.requests()[0].query("relPath"), Some("./docs/a.txt"));
+}
+
+#[tokio::test]
+async fn a_failing_backend_is_an_error_not_a_missing_file() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    backend.set_hook(|req, _| (req.route() == "HEAD /files").then(|| Response::empty(500)));
+    assert!(backend.api().exists("/a.txt").await.is_err());
+}
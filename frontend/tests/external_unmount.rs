@@ -0,0 +1,65 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{FakeBackend, Mounted, Node, TempDir};
+use frontend::options::MountOptions;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::time::{Duration, Instant};
+
+// Function that ends the FUSE connection of `mountpoint` through fusectl, as a crashed or killed
+// kernel-side session would: every request fails from then on and the session reads EOF
+fn abort_connection(mountpoint: &std::path::Path) {
+    let dev = std::fs::metadata(mountpoint).unwrap().dev();
+    let minor = libc::minor(dev);
+    std::fs::write(format!("/sys/fs/fuse/connections/{}/abort", minor), b"1").unwrap();
+}
+
+#[test]
+fn an_unmount_from_outside_ends_mount_all_promptly() {
+    let backend = FakeBackend::start();
+    let Some(mut mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let status = std::process::Command::new("umount").arg("-l").arg(mnt.dir.path()).status().unwrap();
+    assert!(status.success());
+
+    let started = Instant::now();
+    let result = mnt.wait_exit(Duration::from_secs(5)).expect("mount_all still running");
+    assert!(started.elapsed() < Duration::from_secs(3), "took {:?}", started.elapsed());
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("unmounted from outside"), "{}", err);
+}
+
+#[test]
+fn an_aborted_connection_commits_the_open_writes_and_cleans_up() {
+    if !std::path::Path::new("/sys/fs/fuse/connections").exists() {
+        eprintln!("fusectl not mounted, skipping");
+        return;
+    }
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"old"));
+    let temp = TempDir::new("temp");
+    let opts = MountOptions {
+        temp_dir: Some(temp.path().to_path_buf()),
+        ..MountOptions::default()
+    };
+    let Some(mut mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    // A handle with unsent writes when the connection goes away: release never comes
+    let mut file = std::fs::OpenOptions::new().write(true).truncate(true).open(mnt.path("a.txt")).unwrap();
+    file.write_all(b"written before the abort").unwrap();
+    assert_ne!(backend.data("./a.txt").unwrap(), b"written before the abort");
+    assert!(std::fs::read_dir(temp.path()).unwrap().count() > 0);
+    abort_connection(mnt.dir.path());
+
+    let result = mnt.wait_exit(Duration::from_secs(5));
+    drop(file);
+    let _ = std::process::Command::new("umount").arg("-l").arg(mnt.dir.path()).status();
+    assert!(result.expect("mount_all still running").is_err());
+    assert_eq!(backend.data("./a.txt").unwrap(), b"written before the abort");
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0, "tempfiles left behind");
+}
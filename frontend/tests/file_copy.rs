@@ -0,0 +1,125 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response, TempDir};
+use frontend::options::MountOptions;
+use frontend::overlay::Overlay;
+use serde_json::json;
+
+// A backend implementing POST /files/copy?overwrite=true: the content of the source replaces the
+// destination's, which keeps its mode
+fn with_copy_endpoint(backend: &FakeBackend) {
+    backend.set_hook(|req, tree| {
+        if req.route() != "POST /files/copy" {
+            return None;
+        }
+        let (src, dst) = (req.query("srcRelPath")?.to_string(), req.query("dstRelPath")?.to_string());
+        let Some(data) = tree.get(&src).map(|n| n.data.clone()) else {
+            return Some(Response::json(404, json!({ "error": "File not found" })));
+        };
+        match tree.get_mut(&dst) {
+            Some(node) if req.query("overwrite") == Some("true") => node.data = data,
+            Some(_) => return Some(Response::json(409, json!({ "error": "Destination already exists" }))),
+            None => {
+                tree.insert(dst, Node::file(&data));
+            }
+        }
+        Some(Response::empty(200))
+    });
+}
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"the source"));
+    let mut dst = Node::file(b"");
+    dst.mode = 0o600;
+    backend.insert("./b.txt", dst);
+    backend
+}
+
+#[tokio::test]
+async fn copy_replaces_the_destination_on_the_backend() {
+    let backend = tree();
+    with_copy_endpoint(&backend);
+
+    assert!(backend.api().copy("./a.txt", "./b.txt").await.unwrap());
+    let sent = backend.server.requests();
+    let copy = sent.iter().find(|r| r.route() == "POST /files/copy").unwrap();
+    assert_eq!(
+        (copy.query("srcRelPath"), copy.query("dstRelPath"), copy.query("overwrite")),
+        (Some("./a.txt"), Some("./b.txt"), Some("true"))
+    );
+    assert_eq!(backend.data("./b.txt").unwrap(), b"the source");
+    assert_eq!(backend.get("./b.txt").unwrap().mode, 0o600);
+    assert_eq!(backend.server.count("GET /files") + backend.server.count("PUT /files"), 0);
+
+    // A backend error that is not a missing route is an error
+    let err = backend.api().copy("./missing.txt", "./b.txt").await.unwrap_err();
+    assert_eq!(err.status(), Some(404));
+}
+
+#[tokio::test]
+async fn copy_declines_without_the_endpoint_or_with_an_overlay() {
+    let backend = tree();
+    let api = backend.api();
+    assert!(!api.copy("./a.txt", "./b.txt").await.unwrap());
+    // Not asked again once missing
+    assert!(!api.copy("./a.txt", "./b.txt").await.unwrap());
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+    assert_eq!(backend.data("./b.txt").unwrap(), b"");
+
+    let backend = tree();
+    with_copy_endpoint(&backend);
+    let upper = TempDir::new("upper");
+    let api = backend.api().with_overlay(Some(Overlay::new(upper.path()).unwrap()));
+    assert!(!api.copy("./a.txt", "./b.txt").await.unwrap());
+    assert_eq!(backend.server.count("POST /files/copy"), 0);
+}
+
+// Function that copies a.txt to a new c.txt through the mount with copy_file_range, as cp does
+#[cfg(target_os = "linux")]
+fn cp_through_the_mount(backend: &FakeBackend) -> Option<()> {
+    use std::os::fd::AsRawFd;
+
+    let mnt = Mounted::start(backend.api(), &backend.url(), MountOptions::default())?;
+    let src = std::fs::File::open(mnt.path("a.txt")).unwrap();
+    let dst = std::fs::File::create(mnt.path("c.txt")).unwrap();
+    let len = src.metadata().unwrap().len() as usize;
+    let mut copied = 0;
+    while copied < len {
+        let n = unsafe {
+            libc::copy_file_range(src.as_raw_fd(), std::ptr::null_mut(), dst.as_raw_fd(), std::ptr::null_mut(), len - copied, 0)
+        };
+        assert!(n > 0, "copy_file_range: {}", std::io::Error::last_os_error());
+        copied += n as usize;
+    }
+    drop((src, dst));
+    common::wait_for(|| (backend.data("./c.txt")? == b"the source").then_some(()));
+    assert_eq!(std::fs::read(mnt.path("c.txt")).unwrap(), b"the source");
+    mnt.unmount();
+    Some(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copy_file_range_is_done_by_the_backend() {
+    let backend = tree();
+    with_copy_endpoint(&backend);
+    if cp_through_the_mount(&backend).is_none() {
+        return;
+    }
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+    // The data never went through the client, and the empty handle did not overwrite the copy
+    assert!(backend.server.requests().iter().all(|r| r.route() != "PUT /files" || r.body.is_empty()));
+    assert_eq!(backend.data("./c.txt").unwrap(), b"the source");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copy_file_range_falls_back_to_reading_and_writing() {
+    let backend = tree();
+    if cp_through_the_mount(&backend).is_none() {
+        return;
+    }
+    assert_eq!(backend.server.count("POST /files/copy"), 1);
+    assert!(backend.server.requests().iter().any(|r| r.route() == "PUT /files" && r.body == b"the source"));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::file_api::EntryKind;
+use frontend::options::MountOptions;
+
+#[test]
+fn backend_kinds_are_parsed() {
+    for (kind, expected) in [
+        ("file", EntryKind::File),
+        ("dir", EntryKind::Dir),
+        ("symlink", EntryKind::Symlink),
+        ("fifo", EntryKind::Fifo),
+        ("socket", EntryKind::Socket),
+        ("char", EntryKind::CharDevice),
+        ("block", EntryKind::BlockDevice),
+    ] {
+        assert_eq!(EntryKind::parse(Some(kind), false), expected);
+    }
+    // An old backend sends is_dir only, an unknown kind falls back on it
+    assert_eq!(EntryKind::parse(None, true), EntryKind::Dir);
+    assert_eq!(EntryKind::parse(None, false), EntryKind::File);
+    assert_eq!(EntryKind::parse(Some("door"), false), EntryKind::File);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn special_files_keep_their_type_on_the_mount() {
+    use std::os::unix::fs::FileTypeExt;
+    let backend = FakeBackend::start();
+    for (name, kind) in [("pipe", "fifo"), ("sock", "socket"), ("tty", "char"), ("disk", "block")] {
+        backend.insert(
+            &format!("./{}", name),
+            Node {
+                kind: Some(kind),
+                ..Node::file(b"")
+            },
+        );
+    }
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let ty = |name: &str| std::fs::symlink_metadata(mnt.path(name)).unwrap().file_type();
+    assert!(ty("pipe").is_fifo());
+    assert!(ty("sock").is_socket());
+    assert!(ty("tty").is_char_device());
+    assert!(ty("disk").is_block_device());
+    let listed: Vec<bool> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_type().unwrap().is_file())
+        .collect();
+    assert_eq!(listed, [false; 4]);
+    mnt.unmount();
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::os::unix::fs::MetadataExt;
+use std::time::{Duration, UNIX_EPOCH};
+
+const FIXED: u64 = 315_532_800;
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    for (i, rel) in ["./a.txt", "./src/main.rs", "./src/lib.rs"].iter().enumerate() {
+        let mut node = Node::file(b"x");
+        node.mtime = (1_600_000_000 + i as i64 * 1000, 123);
+        backend.insert(rel, node);
+    }
+    backend.insert("./src", Node::dir());
+    backend
+}
+
+#[test]
+fn fixed_mtime_is_parsed_in_seconds() {
+    let args: Vec<String> = ["frontend", "--fixed-mtime", "315532800"].iter().map(|s| s.to_string()).collect();
+    let opts = MountOptions::from_args(&args).unwrap();
+    assert_eq!(opts.fixed_mtime, Some(UNIX_EPOCH + Duration::from_secs(FIXED)));
+    let args: Vec<String> = ["frontend", "--fixed-mtime", "yesterday"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+}
+
+#[tokio::test]
+async fn listings_carry_the_fixed_mtime() {
+    let backend = tree();
+    let api = backend.api().with_fixed_mtime(Some(UNIX_EPOCH + Duration::from_secs(FIXED)));
+    for entry in api.ls("./src").await.unwrap() {
+        assert_eq!((entry.mtime, entry.mtime_ns), (FIXED as i64, 0), "{}", entry.name);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn every_entry_of_the_mount_shows_the_fixed_mtime() {
+    let backend = tree();
+    let fixed = UNIX_EPOCH + Duration::from_secs(FIXED);
+    let opts = MountOptions {
+        fixed_mtime: Some(fixed),
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api().with_fixed_mtime(Some(fixed)), &backend.url(), opts) else {
+        return;
+    };
+
+    for rel in ["", "a.txt", "src", "src/main.rs", "src/lib.rs"] {
+        let meta = std::fs::metadata(mnt.path(rel)).unwrap();
+        assert_eq!(meta.modified().unwrap(), fixed, "{}", rel);
+        assert_eq!((meta.ctime() as u64, meta.ctime_nsec()), (FIXED, 0), "{}", rel);
+    }
+    mnt.unmount();
+}
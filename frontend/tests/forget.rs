@@ -0,0 +1,44 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn deleted_files_leave_the_inode_maps_once_forgotten() {
+    let backend = FakeBackend::start();
+    backend.insert("./churn", Node::dir());
+    for i in 0..50 {
+        backend.insert(&format!("./churn/f{}.txt", i), Node::file(b"x"));
+    }
+    backend.insert("./kept.txt", Node::file(b"k"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let inodes = || -> usize {
+        let status = std::fs::read_to_string(mnt.path(".remotefs/refresh")).unwrap();
+        let line = status.lines().find(|l| l.starts_with("inodes: ")).unwrap();
+        line["inodes: ".len()..].parse().unwrap()
+    };
+
+    // An open handle keeps its inode even after the kernel forgets it
+    let kept = std::fs::File::open(mnt.path("kept.txt")).unwrap();
+    for i in 0..50 {
+        std::fs::metadata(mnt.path(&format!("churn/f{}.txt", i))).unwrap();
+    }
+    let peak = inodes();
+    assert!(peak >= 52, "{}", peak);
+
+    for i in 0..50 {
+        std::fs::remove_file(mnt.path(&format!("churn/f{}.txt", i))).unwrap();
+    }
+    let left = wait_for(|| {
+        let n = inodes();
+        (n <= peak - 50).then_some(n)
+    });
+    assert!(left >= 2, "{}", left);
+    assert!(std::fs::metadata(mnt.path("churn")).unwrap().is_dir());
+    drop(kept);
+    assert_eq!(std::fs::read(mnt.path("kept.txt")).unwrap(), b"k");
+    mnt.unmount();
+}
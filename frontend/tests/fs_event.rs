@@ -0,0 +1,82 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::fs_event;
+use frontend::options::MountOptions;
+use rust_socketio::Payload;
+use serde_json::json;
+use std::os::unix::fs::FileExt;
+
+#[test]
+fn only_frames_holding_an_event_object_are_decoded() {
+    let event = json!({ "op": "unlink", "relPath": "./a.txt" });
+    let text = |values| Payload::Text(values);
+    assert_eq!(fs_event::decode(&text(vec![event.clone()])), Some(event.clone()));
+    assert_eq!(fs_event::decode(&text(vec![json!(event.to_string())])), Some(event.clone()));
+    assert_eq!(fs_event::decode(&Payload::Binary(event.to_string().into_bytes().into())), Some(event.clone()));
+
+    for bad in [vec![], vec![json!("")], vec![json!("{not json")], vec![json!(42)], vec![json!(null)], vec![json!([1, 2])]] {
+        assert_eq!(fs_event::decode(&text(bad.clone())), None, "{:?}", bad);
+    }
+    assert_eq!(fs_event::decode(&Payload::Binary(vec![0xff, 0x00].into())), None);
+}
+
+#[test]
+fn a_handler_panicking_loses_only_its_event() {
+    let mut handled = Vec::new();
+    fs_event::dispatch(&Payload::Text(vec![json!({ "op": "add" })]), |_| panic!("unexpected event"));
+    fs_event::dispatch(&Payload::Text(vec![json!("garbage")]), |_| unreachable!());
+    fs_event::dispatch(&Payload::Text(vec![json!({ "op": "change" })]), |e| handled.push(e["op"].clone()));
+    assert_eq!(handled, [json!("change")]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_listener_survives_malformed_frames() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./doc.txt", Node::file(b"v1"));
+    backend.insert("./log.txt", Node::file(b"v1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+    let doc = std::fs::File::open(mnt.path("doc.txt")).unwrap();
+    let log = std::fs::File::open(mnt.path("log.txt")).unwrap();
+    let mut buf = [0u8; 2];
+    doc.read_exact_at(&mut buf, 0).unwrap();
+    log.read_exact_at(&mut buf, 0).unwrap();
+
+    // fs_change events of every wrong shape, then a well-formed one that must still be applied
+    for payload in [
+        json!("{not json"),
+        json!(7),
+        json!([]),
+        json!({}),
+        json!({ "op": "change" }),
+        json!({ "op": "change", "relPath": 12 }),
+        json!({ "op": "add", "relPath": "./x.txt", "name": "x.txt", "mtime": i64::MAX, "mtime_ns": -1 }),
+    ] {
+        backend.emit("fs_change", payload);
+    }
+    backend.remove("./doc.txt");
+    backend.emit_change("unlink", "./doc.txt");
+    let err = wait_for(|| doc.read_at(&mut buf, 0).err());
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+
+    // Short and malformed packets. One engine.io cannot parse makes the client open a new session,
+    // and what was queued on the old one is lost: the event is sent again until it lands
+    for packet in ["4", "42", "42[", "42[\"fs_change\"]", "42[\"unknown\",{}]", "9x"] {
+        backend.emit_packet(packet);
+    }
+    backend.remove("./log.txt");
+    let err = wait_for(|| {
+        backend.emit_change("unlink", "./log.txt");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        log.read_at(&mut buf, 0).err()
+    });
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+    assert!(backend.socket_connected());
+    drop((doc, log));
+    mnt.unmount();
+}
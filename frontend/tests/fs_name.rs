@@ -0,0 +1,64 @@
+mod common;
+
+use common::{FakeBackend, Mounted};
+use frontend::options::MountOptions;
+
+fn parse(list: &[&str]) -> anyhow::Result<MountOptions> {
+    let args: Vec<String> = std::iter::once("frontend").chain(list.iter().copied()).map(String::from).collect();
+    MountOptions::from_args(&args)
+}
+
+#[test]
+fn fs_name_and_type_are_parsed() {
+    let opts = parse(&["--fs-name", "nas-backup", "--fs-type=remotefs"]).unwrap();
+    assert_eq!(opts.fs_name.as_deref(), Some("nas-backup"));
+    assert_eq!(opts.fs_type.as_deref(), Some("remotefs"));
+    let opts = parse(&[]).unwrap();
+    assert_eq!((opts.fs_name, opts.fs_type), (None, None));
+}
+
+#[test]
+fn fs_name_is_validated() {
+    // Mount options are split at commas and blanks
+    assert!(parse(&["--fs-name", ""]).is_err());
+    assert!(parse(&["--fs-name", "a,b"]).is_err());
+    assert!(parse(&["--fs-name", "two words"]).is_err());
+    assert!(parse(&["--fs-name", "tab\there"]).is_err());
+    assert!(parse(&["--fs-name", "x".repeat(300).as_str()]).is_err());
+}
+
+#[test]
+fn fs_type_is_validated() {
+    assert!(parse(&["--fs-type", ""]).is_err());
+    assert!(parse(&["--fs-type", "fuse.remote"]).is_err());
+    assert!(parse(&["--fs-type", "a/b"]).is_err());
+    assert!(parse(&["--fs-type", "x".repeat(40).as_str()]).is_err());
+    assert!(parse(&["--fs-type", "remote_fs-2"]).is_ok());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_shows_the_configured_name_and_type() {
+    let backend = FakeBackend::start();
+    let opts = MountOptions {
+        fs_name: Some("nas-backup".to_string()),
+        fs_type: Some("remotefs".to_string()),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    let mp = mnt.dir.path().to_string_lossy().into_owned();
+    let mounts = std::fs::read_to_string("/proc/mounts").unwrap();
+    let line = mounts
+        .lines()
+        .find(|l| l.split_whitespace().nth(1) == Some(mp.as_str()))
+        .unwrap()
+        .to_string();
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    assert_eq!(fields[0], "nas-backup", "{}", line);
+    // fuser applies the subtype through fusermount only: mounted by root the type stays "fuse"
+    let expected = if unsafe { libc::geteuid() } == 0 { "fuse" } else { "fuse.remotefs" };
+    assert_eq!(fields[2], expected, "{}", line);
+    mnt.unmount();
+}
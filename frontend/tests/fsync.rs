@@ -0,0 +1,61 @@
+mod common;
+
+use std::io::Write;
+
+use common::{FakeBackend, Mounted};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fsync_commits_mid_write_and_keeps_the_tempfile() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut file = std::fs::File::create(mnt.path("log.txt")).unwrap();
+    file.write_all(b"hello").unwrap();
+    file.sync_all().unwrap();
+    // On the backend while the file is still open
+    assert_eq!(backend.data("./log.txt").as_deref(), Some(&b"hello"[..]));
+    assert_eq!(std::fs::metadata(mnt.path("log.txt")).unwrap().len(), 5);
+
+    // Later writes land on the same tempfile, nothing written before is lost
+    file.write_all(b" world").unwrap();
+    file.sync_data().unwrap();
+    assert_eq!(backend.data("./log.txt").as_deref(), Some(&b"hello world"[..]));
+    drop(file);
+    assert_eq!(backend.data("./log.txt").as_deref(), Some(&b"hello world"[..]));
+    assert_eq!(std::fs::read(mnt.path("log.txt")).unwrap(), b"hello world");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn fdatasync_sends_only_the_data_and_fsync_also_the_pending_times() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let when = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+
+    let mut file = std::fs::File::create(mnt.path("log.txt")).unwrap();
+    file.write_all(b"hello").unwrap();
+    file.set_modified(when).unwrap();
+    let utimes = backend.server.count("PATCH /files/utimes");
+
+    // The content goes, the upload resets the mtime and the one set after writing stays pending
+    file.sync_data().unwrap();
+    assert_eq!(backend.data("./log.txt").as_deref(), Some(&b"hello"[..]));
+    assert_eq!(backend.server.count("PATCH /files/utimes"), utimes);
+    assert_ne!(backend.get("./log.txt").unwrap().mtime.0, 1_000_000_000);
+
+    // A full fsync sends it after the data, so the upload does not reset it
+    file.sync_all().unwrap();
+    assert_eq!(backend.server.count("PATCH /files/utimes"), utimes + 1);
+    assert_eq!(backend.get("./log.txt").unwrap().mtime.0, 1_000_000_000);
+    assert_eq!(std::fs::metadata(mnt.path("log.txt")).unwrap().modified().unwrap(), when);
+    drop(file);
+    mnt.unmount();
+    assert_eq!(backend.get("./log.txt").unwrap().mtime.0, 1_000_000_000);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+const READERS: usize = 4;
+const BACKEND_DELAY: Duration = Duration::from_millis(400);
+
+fn args(list: &[&str]) -> Vec<String> {
+    std::iter::once("frontend").chain(list.iter().copied()).map(String::from).collect()
+}
+
+#[test]
+fn fuse_threads_is_parsed_and_validated() {
+    let opts = MountOptions::from_args(&args(&["--fuse-threads", "8"])).unwrap();
+    assert_eq!(opts.fuse_threads, Some(8));
+    let opts = MountOptions::from_args(&args(&["--fuse-threads=2"])).unwrap();
+    assert_eq!(opts.fuse_threads, Some(2));
+    assert_eq!(MountOptions::from_args(&args(&[])).unwrap().fuse_threads, None);
+    assert!(MountOptions::from_args(&args(&["--fuse-threads", "0"])).is_err());
+    assert!(MountOptions::from_args(&args(&["--fuse-threads", "many"])).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn concurrent_reads_wait_for_the_backend_in_parallel() {
+    let backend = FakeBackend::start();
+    for i in 0..READERS {
+        backend.insert(&format!("./f{}", i), Node::file(&[b'0' + i as u8; 4096]));
+    }
+    let opts = MountOptions {
+        fuse_threads: Some(READERS),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    // Listing first, so only the reads themselves are left to wait for the backend
+    for i in 0..READERS {
+        std::fs::metadata(mnt.path(&format!("f{}", i))).unwrap();
+    }
+    backend.set_delay("GET /files", BACKEND_DELAY);
+
+    let started = Instant::now();
+    let readers: Vec<_> = (0..READERS)
+        .map(|i| {
+            let path = mnt.path(&format!("f{}", i));
+            std::thread::spawn(move || std::fs::read(path).unwrap())
+        })
+        .collect();
+    for (i, r) in readers.into_iter().enumerate() {
+        assert_eq!(r.join().unwrap(), vec![b'0' + i as u8; 4096]);
+    }
+    let elapsed = started.elapsed();
+    // One at a time they would take READERS delays
+    assert!(
+        elapsed < BACKEND_DELAY * (READERS as u32) / 2,
+        "reads took {:?}, they were served one after the other",
+        elapsed
+    );
+    assert_eq!(backend.server.count("GET /files"), READERS);
+    mnt.unmount();
+}
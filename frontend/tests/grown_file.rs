@@ -0,0 +1,53 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_read_past_the_cached_size_returns_what_the_backend_appended() {
+    let backend = FakeBackend::start();
+    backend.insert("./log.txt", Node::file(b"v1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    assert_eq!(std::fs::metadata(mnt.path("log.txt")).unwrap().len(), 2);
+    let file = std::fs::File::open(mnt.path("log.txt")).unwrap();
+
+    // Another client appends, no event tells the mount
+    backend.insert("./log.txt", Node::file(b"v1 and more"));
+
+    let mut buf = [0u8; 64];
+    let read = wait_for(|| {
+        let n = file.read_at(&mut buf, 0).unwrap();
+        (n > 2).then_some(n)
+    });
+    assert_eq!(&buf[..read], b"v1 and more");
+    assert_eq!(std::fs::metadata(mnt.path("log.txt")).unwrap().len(), 11);
+    drop(file);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_direct_read_sees_the_new_bytes_at_once() {
+    let backend = FakeBackend::start();
+    backend.insert("./log.txt", Node::file(b"v1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    assert_eq!(std::fs::metadata(mnt.path("log.txt")).unwrap().len(), 2);
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(mnt.path("log.txt"))
+        .unwrap();
+
+    backend.insert("./log.txt", Node::file(b"v1 and more"));
+    let mut buf = [0u8; 64];
+    let read = file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..read], b"v1 and more");
+    drop(file);
+    mnt.unmount();
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{entry_json, FakeBackend, Mounted, Node, Response};
+use frontend::api_error::ApiError;
+use frontend::options::MountOptions;
+use serde_json::Value;
+
+const TEXT: &[u8] = b"the quick brown fox jumps over the lazy dog, again and again and again";
+// What the file takes on the backend once gzipped
+const STORED: u64 = 41;
+
+// Function that makes the backend behave like one storing `./doc.txt` gzipped: the listing carries the
+// stored and logical sizes, and a read not asking for identity gets the compressed body
+fn gzip_backend(backend: &FakeBackend) {
+    backend.set_hook(|req, tree| match req.route().as_str() {
+        "GET /list" if req.query("relPath") == Some(".") => {
+            let entries: Vec<Value> = tree
+                .iter()
+                .filter(|(rel, _)| rel.matches('/').count() == 1)
+                .map(|(rel, node)| {
+                    let mut entry = entry_json(rel, node);
+                    if rel == "./doc.txt" {
+                        entry["size"] = STORED.into();
+                        entry["logical_size"] = node.data.len().into();
+                        entry["stored_size"] = STORED.into();
+                    }
+                    entry
+                })
+                .collect();
+            Some(Response::json(200, Value::Array(entries)))
+        }
+        "GET /files" if req.header("Accept-Encoding") != Some("identity") => Some(
+            Response::bytes(200, vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3]).with_header("Content-Encoding", "gzip"),
+        ),
+        _ => None,
+    });
+}
+
+#[tokio::test]
+async fn reads_ask_for_identity_and_get_the_decompressed_bytes() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(TEXT));
+    gzip_backend(&backend);
+    let api = backend.api();
+
+    let entry = api.ls(".").await.unwrap().into_iter().find(|e| e.name == "doc.txt").unwrap();
+    assert_eq!(entry.size, TEXT.len() as i64);
+    assert_eq!(entry.stored_size, Some(STORED));
+    assert_eq!(entry.blocks(), 1);
+
+    assert_eq!(api.read_all("./doc.txt", entry.size as u64).await.unwrap(), TEXT);
+    assert_eq!(api.read_range("./doc.txt", 4, 8).await.unwrap(), b"quick");
+    let mut buf = [0u8; 5];
+    assert_eq!(api.read_into("./doc.txt", 10, &mut buf).await.unwrap(), 5);
+    assert_eq!(&buf, b"brown");
+    for req in backend.server.requests().iter().filter(|r| r.route() == "GET /files") {
+        assert_eq!(req.header("Accept-Encoding"), Some("identity"));
+    }
+}
+
+#[tokio::test]
+async fn an_encoded_body_is_refused_rather_than_served_as_content() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(TEXT));
+    // A backend ignoring Accept-Encoding
+    backend.set_hook(|req, _| {
+        (req.route() == "GET /files").then(|| {
+            Response::bytes(200, vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3]).with_header("Content-Encoding", "gzip")
+        })
+    });
+    let api = backend.api();
+
+    let err = api.read_range("./doc.txt", 0, 9).await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(ref msg) if msg.contains("gzip")), "{:?}", err);
+    let mut buf = [0u8; 10];
+    let err = api.read_into("./doc.txt", 0, &mut buf).await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(_)), "{:?}", err);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_presents_the_decompressed_size_and_bytes() {
+    use std::os::unix::fs::MetadataExt;
+
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(TEXT));
+    gzip_backend(&backend);
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let meta = std::fs::metadata(mnt.path("doc.txt")).unwrap();
+    assert_eq!(meta.len(), TEXT.len() as u64);
+    assert_eq!(meta.blocks(), STORED.div_ceil(512));
+    assert_eq!(std::fs::read(mnt.path("doc.txt")).unwrap(), TEXT);
+    mnt.unmount();
+}
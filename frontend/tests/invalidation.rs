@@ -0,0 +1,112 @@
+use frontend::invalidation::{spawn_coalescer, spawn_debouncer, Inval, InvalBatch, MAX_ENTRIES_PER_DIR};
+use frontend::options::MountOptions;
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn entry(parent: u64, name: &str) -> Inval {
+    Inval::Entry(parent, OsString::from(name))
+}
+
+// Sink recording the invalidations the notifier would receive
+fn recorder() -> (Arc<Mutex<Vec<Inval>>>, impl FnMut(Inval) + Send + 'static) {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let sink = {
+        let calls = calls.clone();
+        move |inval| calls.lock().unwrap().push(inval)
+    };
+    (calls, sink)
+}
+
+fn settle(calls: &Mutex<Vec<Inval>>) -> Vec<Inval> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut last = usize::MAX;
+    loop {
+        std::thread::sleep(Duration::from_millis(150));
+        let now = calls.lock().unwrap().len();
+        if (now == last && now > 0) || Instant::now() > deadline {
+            return calls.lock().unwrap().clone();
+        }
+        last = now;
+    }
+}
+
+#[test]
+fn a_batch_folds_repeated_names_and_inodes() {
+    let mut batch = InvalBatch::default();
+    for i in 0..300 {
+        batch.push(entry(1, ["a", "b", "c"][i % 3]));
+        batch.push(Inval::Inode(1));
+        batch.push(Inval::Inode(7));
+    }
+    assert_eq!(batch.len(), 900);
+    assert_eq!(
+        batch.drain(),
+        [entry(1, "a"), entry(1, "b"), entry(1, "c"), Inval::Inode(1), Inval::Inode(7)]
+    );
+}
+
+#[test]
+fn too_many_names_invalidate_the_directory_only() {
+    let mut batch = InvalBatch::default();
+    for i in 0..=MAX_ENTRIES_PER_DIR {
+        batch.push(entry(1, &format!("f{}", i)));
+    }
+    batch.push(entry(2, "kept"));
+    assert_eq!(batch.drain(), [Inval::Inode(1), entry(2, "kept"), Inval::Inode(2)]);
+}
+
+#[test]
+fn a_burst_for_one_directory_reaches_the_notifier_collapsed() {
+    let (calls, sink) = recorder();
+    let sender = spawn_coalescer(sink);
+    for i in 0..500 {
+        sender.entry(1, OsString::from(format!("f{}", i % 4)).as_os_str());
+        sender.inode(1);
+    }
+    let calls = settle(&calls);
+    assert!(calls.len() <= 10, "{} notifier calls: {:?}", calls.len(), calls);
+    for name in ["f0", "f1", "f2", "f3"] {
+        assert!(calls.contains(&entry(1, name)), "{:?}", calls);
+    }
+    assert!(calls.contains(&Inval::Inode(1)));
+}
+
+#[test]
+fn the_debouncer_holds_repeats_until_the_tolerance() {
+    let (calls, sink) = recorder();
+    let out = spawn_coalescer(sink);
+    let sender = spawn_debouncer(Duration::from_millis(300), out);
+    let start = Instant::now();
+    for _ in 0..50 {
+        sender.inode(5);
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    std::thread::sleep(Duration::from_millis(200).saturating_sub(start.elapsed()));
+    assert!(calls.lock().unwrap().is_empty());
+    assert_eq!(settle(&calls), [Inval::Inode(5)]);
+}
+
+#[test]
+fn the_debouncer_lets_a_file_changing_without_pause_refresh_once_per_tolerance() {
+    let (calls, sink) = recorder();
+    let sender = spawn_debouncer(Duration::from_millis(200), spawn_coalescer(sink));
+    sender.inode(9);
+    sender.entry(1, std::ffi::OsStr::new("a"));
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(700) {
+        sender.inode(9);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let calls = settle(&calls);
+    // The deadline is set by the first change: a few refreshes, not one per change nor none at all
+    let refreshes = calls.iter().filter(|c| **c == Inval::Inode(9)).count();
+    assert!((3..=5).contains(&refreshes), "{:?}", calls);
+    assert_eq!(calls.iter().filter(|c| **c == entry(1, "a")).count(), 1, "{:?}", calls);
+}
+
+#[test]
+fn the_tolerance_is_parsed_in_milliseconds() {
+    let args: Vec<String> = ["frontend", "--stale-tolerance", "250"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().stale_tolerance, Some(Duration::from_millis(250)));
+}
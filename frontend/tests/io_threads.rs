@@ -0,0 +1,60 @@
+mod common;
+
+use common::FakeBackend;
+use frontend::options::MountOptions;
+use frontend::{io_threads_of, MountSpec};
+use std::time::{Duration, Instant};
+
+fn spec(backend: &FakeBackend, io_threads: Option<usize>) -> MountSpec {
+    MountSpec {
+        mountpoint: "/mnt/unused".into(),
+        api: backend.api(),
+        url: backend.url(),
+        opts: MountOptions {
+            io_threads,
+            ..MountOptions::default()
+        },
+    }
+}
+
+#[test]
+fn the_shared_runtime_gets_the_largest_io_threads() {
+    let backend = FakeBackend::start();
+    assert_eq!(io_threads_of(&[]), None);
+    assert_eq!(io_threads_of(&[spec(&backend, None), spec(&backend, None)]), None);
+    // Whatever the order of the mounts on the command line
+    let specs = [spec(&backend, Some(2)), spec(&backend, None), spec(&backend, Some(16)), spec(&backend, Some(4))];
+    assert_eq!(io_threads_of(&specs), Some(16));
+    assert_eq!(io_threads_of(&[spec(&backend, None), spec(&backend, Some(3))]), Some(3));
+}
+
+#[test]
+fn io_threads_sizes_the_runtime_and_reads_run_side_by_side() {
+    let rt = frontend::io_runtime(Some(3)).unwrap();
+    assert_eq!(rt.metrics().num_workers(), 3);
+
+    let backend = FakeBackend::start();
+    for i in 0..8 {
+        backend.insert(&format!("./f{}.bin", i), common::Node::file(&[i as u8; 1024]));
+    }
+    backend.set_delay("GET /files", Duration::from_millis(400));
+    let api = std::sync::Arc::new(backend.api());
+
+    // Eight reads of 400ms each take about one delay, not eight, when spawned on the runtime
+    let started = Instant::now();
+    let reads: Vec<_> = (0..8)
+        .map(|i| {
+            let api = api.clone();
+            rt.spawn(async move {
+                let mut buf = [0u8; 1024];
+                let n = api.read_into(&format!("./f{}.bin", i), 0, &mut buf).await.unwrap();
+                (n, buf[0])
+            })
+        })
+        .collect();
+    for (i, read) in reads.into_iter().enumerate() {
+        assert_eq!(rt.block_on(read).unwrap(), (1024, i as u8));
+    }
+    let elapsed = started.elapsed();
+    assert!(elapsed < Duration::from_millis(1600), "{:?}", elapsed);
+}
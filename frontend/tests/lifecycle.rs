@@ -0,0 +1,54 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn create_read_rename_delete_and_unmount() {
+    let backend = FakeBackend::start();
+    backend.insert("./shared.txt", Node::file(b"v1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    std::fs::create_dir(mnt.path("e2e")).unwrap();
+    assert!(backend.get("./e2e").unwrap().is_dir);
+    assert_eq!(backend.server.count("POST /mkdir"), 1);
+
+    std::fs::write(mnt.path("e2e/a.txt"), b"ciao").unwrap();
+    common::wait_for(|| (backend.data("./e2e/a.txt")? == b"ciao").then_some(()));
+    assert_eq!(std::fs::read(mnt.path("e2e/a.txt")).unwrap(), b"ciao");
+    assert_eq!(std::fs::metadata(mnt.path("e2e/a.txt")).unwrap().len(), 4);
+
+    std::fs::rename(mnt.path("e2e/a.txt"), mnt.path("e2e/b.txt")).unwrap();
+    assert!(backend.get("./e2e/a.txt").is_none());
+    assert_eq!(backend.data("./e2e/b.txt").unwrap(), b"ciao");
+    assert_eq!(backend.server.count("PATCH /files/rename"), 1);
+    assert!(std::fs::metadata(mnt.path("e2e/a.txt")).is_err());
+    assert_eq!(std::fs::read(mnt.path("e2e/b.txt")).unwrap(), b"ciao");
+
+    let names: Vec<_> = std::fs::read_dir(mnt.path("e2e"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["b.txt"]);
+
+    std::fs::remove_file(mnt.path("e2e/b.txt")).unwrap();
+    std::fs::remove_dir(mnt.path("e2e")).unwrap();
+    assert!(backend.get("./e2e/b.txt").is_none());
+    assert!(backend.get("./e2e").is_none());
+    assert!(std::fs::metadata(mnt.path("e2e")).is_err());
+
+    // A change on the backend nobody notified is picked up once the cache expires
+    assert_eq!(std::fs::read(mnt.path("shared.txt")).unwrap(), b"v1");
+    backend.insert("./shared.txt", Node::file(b"version 2"));
+    std::thread::sleep(Duration::from_millis(2500));
+    assert_eq!(std::fs::read(mnt.path("shared.txt")).unwrap(), b"version 2");
+
+    let mountpoint = mnt.dir.path().to_string_lossy().into_owned();
+    mnt.unmount();
+    let mounts = std::fs::read_to_string("/proc/mounts").unwrap();
+    assert!(!mounts.lines().any(|l| l.split_whitespace().nth(1) == Some(mountpoint.as_str())));
+}
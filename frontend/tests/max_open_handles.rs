@@ -0,0 +1,45 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::fs::File;
+
+#[test]
+fn max_open_handles_is_parsed() {
+    let args: Vec<String> = ["frontend", "--max-open-handles", "2"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().max_open_handles, Some(2));
+    let args: Vec<String> = ["frontend", "--max-open-handles", "0"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+    assert_eq!(MountOptions::default().max_open_handles, None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn opening_past_the_cap_fails_with_emfile() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    backend.insert("./b.txt", Node::file(b"b"));
+    let opts = MountOptions {
+        max_open_handles: Some(2),
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    let first = File::options().write(true).open(mnt.path("a.txt")).unwrap();
+    let second = File::create(mnt.path("new.txt")).unwrap();
+    let err = File::options().append(true).open(mnt.path("b.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EMFILE));
+    let err = File::create(mnt.path("other.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EMFILE));
+    assert!(backend.get("./other.txt").is_none());
+    // Reading needs no temp file and is not counted
+    assert_eq!(std::fs::read(mnt.path("b.txt")).unwrap(), b"b");
+
+    // A released handle frees its place, the kernel sends the release after close returns
+    drop(first);
+    let third = wait_for(|| File::options().append(true).open(mnt.path("b.txt")).ok());
+    drop((second, third));
+    mnt.unmount();
+}
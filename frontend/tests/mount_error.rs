@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::FakeBackend;
+use frontend::mount_error::{classify, describe, MountFailure};
+use std::io::{Error, ErrorKind};
+
+#[test]
+fn known_mount_errors_are_classified() {
+    let cases = [
+        (Error::from_raw_os_error(libc::EACCES), MountFailure::PermissionDenied),
+        (Error::from_raw_os_error(libc::EPERM), MountFailure::PermissionDenied),
+        (Error::from_raw_os_error(libc::EBUSY), MountFailure::MountpointBusy),
+        (Error::from_raw_os_error(libc::ENOTCONN), MountFailure::MountpointBusy),
+        (Error::from_raw_os_error(libc::ENODEV), MountFailure::FuseMissing),
+        (Error::new(ErrorKind::NotFound, "fusermount3: command not found"), MountFailure::FuseMissing),
+        (Error::other("failed to open /dev/fuse"), MountFailure::FuseMissing),
+        (Error::from_raw_os_error(libc::ENOENT), MountFailure::MountpointMissing),
+        (Error::other("Device or resource busy"), MountFailure::MountpointBusy),
+        (Error::other("something else"), MountFailure::Other),
+    ];
+    for (err, expected) in cases {
+        assert_eq!(classify(&err), expected, "{}", err);
+    }
+}
+
+#[test]
+fn the_message_names_the_mountpoint_and_the_fix() {
+    let busy = describe(&Error::from_raw_os_error(libc::EBUSY), "/mnt/remote");
+    assert!(busy.starts_with("Impossibile montare il filesystem su /mnt/remote: "), "{}", busy);
+    assert!(busy.contains("fusermount -u /mnt/remote"), "{}", busy);
+
+    let missing = describe(&Error::from_raw_os_error(libc::ENODEV), "/mnt/remote");
+    let install = if cfg!(target_os = "macos") { "macFUSE" } else { "fuse3" };
+    assert!(missing.contains(install), "{}", missing);
+
+    let denied = describe(&Error::from_raw_os_error(libc::EACCES), "/mnt/remote");
+    assert!(denied.contains("gruppo `fuse`"), "{}", denied);
+
+    let nowhere = describe(&Error::from_raw_os_error(libc::ENOENT), "/mnt/none");
+    assert!(nowhere.contains("Il mountpoint /mnt/none non esiste"), "{}", nowhere);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_failed_mount_returns_the_diagnosis() {
+    let backend = FakeBackend::start();
+    let dir = common::TempDir::new("mount-error");
+    let mountpoint = dir.path().join("missing");
+    let err = frontend::mount_fs(
+        &mountpoint.to_string_lossy(),
+        backend.api(),
+        backend.url(),
+        frontend::options::MountOptions::default(),
+    )
+    .unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains(&format!("Il mountpoint {} non esiste", mountpoint.display())), "{}", msg);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{FakeBackend, TempDir};
+use frontend::mountpoint::{problems, verify};
+use frontend::options::MountOptions;
+use frontend::MountSpec;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+fn chmod(path: &Path, mode: u32) {
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn only_a_directory_of_the_user_with_safe_permissions_passes() {
+    let dir = TempDir::new("mountpoint");
+    let mp = dir.path().join("mnt");
+    std::fs::create_dir(&mp).unwrap();
+    chmod(&mp, 0o755);
+    assert!(problems(&mp).is_empty());
+    assert_eq!(verify(mp.to_str().unwrap(), false), Ok(()));
+
+    chmod(&mp, 0o1777);
+    assert!(problems(&mp).is_empty());
+    chmod(&mp, 0o777);
+    assert_eq!(problems(&mp), ["è scrivibile da tutti gli utenti (modo 777)"]);
+    chmod(&mp, 0o500);
+    assert_eq!(problems(&mp), ["il proprietario non ha i permessi rwx (modo 500)"]);
+
+    let file = dir.path().join("file");
+    std::fs::write(&file, b"").unwrap();
+    assert_eq!(problems(&file), ["non è una cartella"]);
+    // Left to the mount to explain
+    assert!(problems(&dir.path().join("missing")).is_empty());
+}
+
+#[test]
+fn a_mountpoint_of_another_user_is_refused_unless_forced() {
+    if unsafe { libc::geteuid() } != 0 {
+        eprintln!("not root, cannot give the mountpoint away: skipping");
+        return;
+    }
+    let dir = TempDir::new("mountpoint");
+    let mp = dir.path().join("mnt");
+    std::fs::create_dir(&mp).unwrap();
+    std::os::unix::fs::chown(&mp, Some(4242), None).unwrap();
+    let mp = mp.to_str().unwrap();
+
+    let err = verify(mp, false).unwrap_err();
+    assert!(err.contains("appartiene all'utente 4242"), "{}", err);
+    assert!(err.contains("--force"), "{}", err);
+    assert_eq!(verify(mp, true), Ok(()));
+
+    // The mount itself stops there, before asking the backend anything
+    let backend = FakeBackend::start();
+    let spec = MountSpec {
+        mountpoint: mp.to_string(),
+        api: backend.api(),
+        url: backend.url(),
+        opts: MountOptions::default(),
+    };
+    let err = frontend::mount_all(vec![spec]).unwrap_err();
+    assert!(err.to_string().starts_with(&format!("Mountpoint {} rifiutato: ", mp)), "{}", err);
+    assert_eq!(backend.server.total(), 0);
+}
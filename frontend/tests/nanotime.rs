@@ -0,0 +1,46 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn precise() -> SystemTime {
+    UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789)
+}
+
+#[tokio::test]
+async fn utimes_sends_seconds_and_nanoseconds_apart() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    let api = backend.api();
+
+    api.utimes("/a.txt", precise(), precise()).await.unwrap();
+    let req = backend.server.requests().into_iter().find(|r| r.route() == "PATCH /files/utimes").unwrap();
+    assert_eq!(req.query("mtime"), Some("1700000000"));
+    assert_eq!(req.query("mtimeNs"), Some("123456789"));
+    assert_eq!(req.query("atimeNs"), Some("123456789"));
+
+    let listed = api.ls("/").await.unwrap();
+    assert_eq!(listed[0].mtime_time(), precise());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_nanosecond_mtime_survives_the_round_trip() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let file = std::fs::File::options().write(true).open(mnt.path("a.txt")).unwrap();
+    file.set_modified(precise()).unwrap();
+    drop(file);
+    assert_eq!(backend.get("./a.txt").unwrap().mtime, (1_700_000_000, 123_456_789));
+    assert_eq!(std::fs::metadata(mnt.path("a.txt")).unwrap().modified().unwrap(), precise());
+
+    // Read again from the listing once the cache expired
+    std::thread::sleep(Duration::from_millis(2500));
+    assert_eq!(std::fs::metadata(mnt.path("a.txt")).unwrap().modified().unwrap(), precise());
+    mnt.unmount();
+}
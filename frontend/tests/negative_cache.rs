@@ -0,0 +1,45 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn missing_names_are_remembered_until_created() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let listings = || {
+        backend
+            .server
+            .requests()
+            .iter()
+            .filter(|r| r.route().starts_with("GET /list"))
+            .count()
+    };
+
+    assert!(std::fs::metadata(mnt.path("ghost")).is_err());
+    backend.server.clear();
+    for _ in 0..5 {
+        assert!(std::fs::metadata(mnt.path("ghost")).is_err());
+    }
+    assert_eq!(listings(), 0);
+
+    // A name created through the mount is visible at once
+    std::fs::write(mnt.path("ghost"), b"boo").unwrap();
+    assert!(std::fs::metadata(mnt.path("ghost")).unwrap().is_file());
+    assert!(std::fs::metadata(mnt.path("spirit")).is_err());
+    std::fs::create_dir(mnt.path("spirit")).unwrap();
+    assert!(std::fs::metadata(mnt.path("spirit")).unwrap().is_dir());
+
+    // The negative entry expires: a name added on the backend shows up after the TTL
+    assert!(std::fs::metadata(mnt.path("later")).is_err());
+    backend.insert("./later", Node::file(b"x"));
+    std::thread::sleep(Duration::from_millis(1100));
+    backend.server.clear();
+    assert!(std::fs::metadata(mnt.path("later")).unwrap().is_file());
+    assert!(listings() > 0);
+    mnt.unmount();
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::{MountOptions, DEFAULT_NEGATIVE_TTL};
+use std::time::Duration;
+
+#[test]
+fn negative_ttl_is_parsed() {
+    let args: Vec<String> = ["frontend", "--negative-ttl", "250"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().negative_ttl(), Duration::from_millis(250));
+    let args: Vec<String> = ["frontend", "--negative-ttl=0"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).unwrap().negative_ttl().is_zero());
+    let args: Vec<String> = ["frontend", "--negative-ttl", "soon"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+    assert_eq!(MountOptions::default().negative_ttl(), DEFAULT_NEGATIVE_TTL);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_kernel_remembers_missing_names_until_they_are_created() {
+    let backend = FakeBackend::start();
+    backend.insert("./src.txt", Node::file(b"copied"));
+    let opts = MountOptions {
+        negative_ttl: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    let listings = || {
+        backend
+            .server
+            .requests()
+            .iter()
+            .filter(|r| r.route().starts_with("GET /list"))
+            .count()
+    };
+
+    assert!(std::fs::metadata(mnt.path(".git")).is_err());
+    assert!(listings() > 0);
+    // The refresh drops what the client remembers: only the kernel can still answer
+    std::fs::write(mnt.path(".remotefs/refresh"), b"1").unwrap();
+    backend.server.clear();
+    for _ in 0..5 {
+        assert!(std::fs::metadata(mnt.path(".git")).is_err());
+    }
+    assert_eq!(listings(), 0);
+
+    // Names created through the mount replace the negative entry
+    std::fs::create_dir(mnt.path(".git")).unwrap();
+    assert!(std::fs::metadata(mnt.path(".git")).unwrap().is_dir());
+
+    // So do names the client creates on the backend on its own
+    assert!(std::fs::metadata(mnt.path("dst.txt")).is_err());
+    std::fs::write(mnt.path(".remotefs/copy"), b"src.txt\ndst.txt\n").unwrap();
+    assert_eq!(std::fs::read(mnt.path("dst.txt")).unwrap(), b"copied");
+    mnt.unmount();
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+#[cfg(target_os = "linux")]
+#[test]
+fn files_opened_for_writing_start_from_their_content() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"old data"));
+    backend.insert("./log.txt", Node::file(b"line 1\n"));
+    backend.insert("./big.txt", Node::file(b"to be dropped"));
+    backend.insert("./empty.txt", Node::file(b""));
+    backend.insert("./gone.txt", Node::file(b"soon missing"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    // Overwriting the head keeps the rest of the file
+    let mut file = OpenOptions::new().write(true).open(mnt.path("doc.txt")).unwrap();
+    file.write_all(b"new").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./doc.txt", b"new data");
+
+    let mut file = OpenOptions::new().append(true).open(mnt.path("log.txt")).unwrap();
+    file.write_all(b"line 2\n").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./log.txt", b"line 1\nline 2\n");
+
+    let mut file = OpenOptions::new().write(true).truncate(true).open(mnt.path("big.txt")).unwrap();
+    file.write_all(b"short").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./big.txt", b"short");
+
+    // An empty file is a file: read and written like any other
+    let mut file = OpenOptions::new().read(true).write(true).open(mnt.path("empty.txt")).unwrap();
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).unwrap();
+    assert!(content.is_empty());
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(b"filled").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./empty.txt", b"filled");
+
+    // A file the backend no longer has is missing, not empty: nothing is uploaded over it
+    assert_eq!(std::fs::metadata(mnt.path("gone.txt")).unwrap().len(), 12);
+    backend.set_hook(|req, _| {
+        (req.route() == "GET /files").then(|| Response::json(404, json!({ "error": "not found" })))
+    });
+    backend.server.clear();
+    let err = OpenOptions::new().read(true).write(true).open(mnt.path("gone.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+    assert_eq!(backend.server.count("PUT /files"), 0);
+    backend.clear_hook();
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_truncating_open_downloads_nothing() {
+    let backend = FakeBackend::start();
+    backend.insert("./big.bin", Node::file(&vec![7u8; 4 * 1024 * 1024]));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    assert_eq!(std::fs::metadata(mnt.path("big.bin")).unwrap().len(), 4 * 1024 * 1024);
+
+    // The content O_TRUNC throws away is never fetched
+    backend.server.clear();
+    let mut file = OpenOptions::new().write(true).truncate(true).open(mnt.path("big.bin")).unwrap();
+    file.write_all(b"saved").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./big.bin", b"saved");
+    assert_eq!(backend.server.count("GET /files"), 0);
+
+    // Emptying it without writing anything, as `: > big.bin` does, reaches the backend too
+    drop(OpenOptions::new().write(true).truncate(true).open(mnt.path("big.bin")).unwrap());
+    wait_for_data(&backend, "./big.bin", b"");
+    assert_eq!(std::fs::metadata(mnt.path("big.bin")).unwrap().len(), 0);
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn writing_through_a_read_only_handle_is_ebadf() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"data"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut file = OpenOptions::new().read(true).open(mnt.path("doc.txt")).unwrap();
+    let err = file.write_all(b"nope").unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "data");
+    drop(file);
+    assert_eq!(backend.server.count("PUT /files"), 0);
+
+    // A handle opened for reading and writing gets its tempfile
+    let mut file = OpenOptions::new().read(true).write(true).open(mnt.path("doc.txt")).unwrap();
+    file.write_all(b"DA").unwrap();
+    drop(file);
+    wait_for_data(&backend, "./doc.txt", b"DAta");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_read_write_handle_sees_its_own_writes_and_the_untouched_content() {
+    use std::os::unix::fs::FileExt;
+
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"0123456789abcdefghij"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let file = OpenOptions::new().read(true).write(true).open(mnt.path("doc.txt")).unwrap();
+    let mut buf = [0u8; 64];
+    let n = file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..n], b"0123456789abcdefghij");
+
+    // A write in the middle, then reads on both sides of it and across it
+    file.write_all_at(b"XY", 5).unwrap();
+    let n = file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..n], b"01234XY789abcdefghij");
+    let n = file.read_at(&mut buf, 15).unwrap();
+    assert_eq!(&buf[..n], b"fghij");
+
+    // Growing the file past its end: the size follows and the old tail is still there
+    file.write_all_at(b"END", 22).unwrap();
+    assert_eq!(file.metadata().unwrap().len(), 25);
+    let n = file.read_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..n], b"01234XY789abcdefghij\0\0END");
+    drop(file);
+    wait_for_data(&backend, "./doc.txt", b"01234XY789abcdefghij\0\0END");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_data(backend: &FakeBackend, rel: &str, expected: &[u8]) {
+    // The upload runs on release, which the kernel sends after close returns
+    common::wait_for(|| (backend.data(rel)? == expected).then_some(()));
+}
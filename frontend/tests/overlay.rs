@@ -0,0 +1,146 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, TempDir};
+use frontend::file_api::FileApi;
+use frontend::options::MountOptions;
+use frontend::overlay::Overlay;
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"backend a"));
+    backend.insert("./b.txt", Node::file(b"backend b"));
+    backend.insert("./docs", Node::dir());
+    backend.insert("./docs/c.txt", Node::file(b"backend c"));
+    backend
+}
+
+fn with_overlay(backend: &FakeBackend, upper: &TempDir) -> FileApi {
+    backend.api().with_overlay(Some(Overlay::new(upper.path()).unwrap()))
+}
+
+// Function that asserts the backend was only read: every change stays in the overlay
+fn assert_only_read(backend: &FakeBackend) {
+    let writes: Vec<String> = backend
+        .server
+        .requests()
+        .iter()
+        .map(|r| r.route())
+        .filter(|route| !route.starts_with("GET ") && !route.starts_with("HEAD "))
+        .collect();
+    assert!(writes.is_empty(), "{:?}", writes);
+}
+
+async fn names(api: &FileApi, rel: &str) -> Vec<String> {
+    let mut names: Vec<String> = api.ls(rel).await.unwrap().into_iter().map(|de| de.name).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn overlay_takes_a_directory() {
+    let args: Vec<String> = ["frontend", "--overlay", "/var/lib/changes"].iter().map(|s| s.to_string()).collect();
+    let opts = MountOptions::from_args(&args).unwrap();
+    assert_eq!(opts.overlay.as_deref(), Some(std::path::Path::new("/var/lib/changes")));
+    assert!(MountOptions::default().overlay.is_none());
+}
+
+#[tokio::test]
+async fn untouched_entries_are_read_through_from_the_backend() {
+    let backend = tree();
+    let upper = TempDir::new("upper");
+    let api = with_overlay(&backend, &upper);
+
+    assert_eq!(api.read_all("./a.txt", 9).await.unwrap(), b"backend a");
+    assert_eq!(api.read_all("./docs/c.txt", 9).await.unwrap(), b"backend c");
+    assert_eq!(names(&api, ".").await, ["a.txt", "b.txt", "docs"]);
+    // Reading copies nothing up
+    assert_eq!(std::fs::read_dir(upper.path()).unwrap().count(), 0);
+    assert!(backend.server.count("GET /files") > 0);
+}
+
+#[tokio::test]
+async fn writes_go_to_the_upper_directory_and_win_over_the_backend() {
+    let backend = tree();
+    let upper = TempDir::new("upper");
+    let api = with_overlay(&backend, &upper);
+
+    api.write_bytes("./a.txt", b"local a").await.unwrap();
+    api.write_bytes("./docs/new.txt", b"new").await.unwrap();
+    api.mkdir("./made").await.unwrap();
+
+    assert_eq!(std::fs::read(upper.path().join("a.txt")).unwrap(), b"local a");
+    assert_eq!(std::fs::read(upper.path().join("docs/new.txt")).unwrap(), b"new");
+    assert!(upper.path().join("made").is_dir());
+    assert_eq!(api.read_all("./a.txt", 7).await.unwrap(), b"local a");
+    assert_eq!(api.get_update_metadata("./a.txt").await.unwrap().size, 7);
+    assert_eq!(names(&api, ".").await, ["a.txt", "b.txt", "docs", "made"]);
+    assert_eq!(names(&api, "./docs").await, ["c.txt", "new.txt"]);
+
+    assert_eq!(backend.data("./a.txt").unwrap(), b"backend a");
+    assert!(backend.get("./docs/new.txt").is_none());
+    assert!(backend.get("./made").is_none());
+    assert_only_read(&backend);
+}
+
+#[tokio::test]
+async fn a_delete_leaves_a_whiteout_and_the_backend_entry() {
+    let backend = tree();
+    let upper = TempDir::new("upper");
+    let api = with_overlay(&backend, &upper);
+
+    api.delete("./b.txt").await.unwrap();
+    assert!(upper.path().join(".wh.b.txt").exists());
+    assert_eq!(names(&api, ".").await, ["a.txt", "docs"]);
+    assert!(api.read_all("./b.txt", 9).await.is_err());
+    assert!(!api.exists("./b.txt").await.unwrap());
+
+    // Deleting a directory hides what the backend has inside it
+    api.delete("./docs").await.unwrap();
+    assert_eq!(names(&api, ".").await, ["a.txt"]);
+    assert!(api.read_all("./docs/c.txt", 9).await.is_err());
+
+    // A new file under the deleted name replaces the whiteout
+    api.write_bytes("./b.txt", b"again").await.unwrap();
+    assert!(!upper.path().join(".wh.b.txt").exists());
+    assert_eq!(api.read_all("./b.txt", 5).await.unwrap(), b"again");
+
+    assert_eq!(backend.data("./b.txt").unwrap(), b"backend b");
+    assert_eq!(backend.data("./docs/c.txt").unwrap(), b"backend c");
+    assert_only_read(&backend);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_reads_through_writes_up_and_whites_out() {
+    let backend = tree();
+    let upper = TempDir::new("upper");
+    let opts = MountOptions {
+        overlay: Some(upper.path().to_path_buf()),
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(with_overlay(&backend, &upper), &backend.url(), opts) else {
+        return;
+    };
+
+    assert_eq!(std::fs::read(mnt.path("docs/c.txt")).unwrap(), b"backend c");
+    std::fs::write(mnt.path("a.txt"), b"local a").unwrap();
+    std::fs::remove_file(mnt.path("b.txt")).unwrap();
+
+    // The upload runs on release, which the kernel sends after close returns
+    common::wait_for(|| (std::fs::read(upper.path().join("a.txt")).ok()? == b"local a").then_some(()));
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"local a");
+    assert!(upper.path().join(".wh.b.txt").exists());
+    assert!(!mnt.path("b.txt").exists());
+    let mut listed: Vec<String> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    listed.sort();
+    assert_eq!(listed, ["a.txt", "docs"]);
+
+    assert_eq!(backend.data("./a.txt").unwrap(), b"backend a");
+    assert_eq!(backend.data("./b.txt").unwrap(), b"backend b");
+    assert_only_read(&backend);
+    mnt.unmount();
+}
@@ -0,0 +1,26 @@
+use frontend::acl::{overwrite_perm, sddl_from_mode};
+
+#[test]
+fn replacing_the_attributes_applies_the_requested_readonly_flag() {
+    // Writable file overwritten as read-only
+    assert_eq!(overwrite_perm(0o644, true, true), 0o444);
+    // Read-only file overwritten without READONLY becomes writable by the owner
+    assert_eq!(overwrite_perm(0o444, false, true), 0o644);
+    assert_eq!(overwrite_perm(0o644, false, true), 0o644);
+    // The other bits are left alone
+    assert_eq!(overwrite_perm(0o755, true, true), 0o555);
+}
+
+#[test]
+fn merging_the_attributes_keeps_an_existing_readonly_flag() {
+    assert_eq!(overwrite_perm(0o444, false, false), 0o444);
+    assert_eq!(overwrite_perm(0o644, false, false), 0o644);
+    assert_eq!(overwrite_perm(0o640, true, false), 0o440);
+}
+
+#[test]
+fn the_file_shows_the_new_attributes_once_overwritten() {
+    // What Windows sees afterwards: no write for anybody on a file overwritten as read-only
+    let sddl = sddl_from_mode(overwrite_perm(0o664, true, true), false, "S-1-5-21-1-2-3-1001");
+    assert!(!sddl.contains("FW") && !sddl.contains("FA"), "{}", sddl);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{entry_json, FakeBackend, Mounted, Node, Response};
+use frontend::file_api::DirectoryEntry;
+use frontend::options::MountOptions;
+use serde_json::Value;
+use std::time::UNIX_EPOCH;
+
+#[test]
+fn null_size_or_mtime_deserialize_as_placeholders() {
+    let de: DirectoryEntry = serde_json::from_str(
+        r#"{"name":"ghost","size":null,"mtime":null,"mtime_ns":5,"permissions":"644","is_dir":0,"version":1,"nlink":1}"#,
+    )
+    .unwrap();
+    assert!(de.partial);
+    assert_eq!((de.size, de.mtime, de.mtime_ns), (0, 0, 0));
+    assert_eq!(de.mtime_time(), UNIX_EPOCH);
+
+    let de: DirectoryEntry = serde_json::from_str(
+        r#"{"name":"sized","size":7,"mtime":null,"permissions":"644","is_dir":0,"version":1,"nlink":1}"#,
+    )
+    .unwrap();
+    assert!(de.partial);
+    assert_eq!((de.size, de.mtime), (7, 0));
+
+    let de: DirectoryEntry = serde_json::from_str(
+        r#"{"name":"ok","size":3,"mtime":100,"mtime_ns":5,"permissions":"644","is_dir":0,"version":1,"nlink":1}"#,
+    )
+    .unwrap();
+    assert!(!de.partial);
+    assert_eq!((de.size, de.mtime, de.mtime_ns), (3, 100, 5));
+}
+
+// Function that makes the backend answer the listing of the root with a null size and mtime for `ghost.txt`
+fn null_ghost(backend: &FakeBackend) {
+    backend.set_hook(|req, tree| {
+        if req.route() != "GET /list" || req.query("relPath") != Some(".") {
+            return None;
+        }
+        let entries: Vec<Value> = tree
+            .iter()
+            .filter(|(rel, _)| rel.matches('/').count() == 1)
+            .map(|(rel, node)| {
+                let mut entry = entry_json(rel, node);
+                if rel == "./ghost.txt" {
+                    entry["size"] = Value::Null;
+                    entry["mtime"] = Value::Null;
+                }
+                entry
+            })
+            .collect();
+        Some(Response::json(200, Value::Array(entries)))
+    });
+}
+
+#[tokio::test]
+async fn a_listing_with_an_unknown_entry_is_still_returned() {
+    let backend = FakeBackend::start();
+    backend.insert("./ghost.txt", Node::file(b"boo"));
+    backend.insert("./real.txt", Node::file(b"here"));
+    null_ghost(&backend);
+
+    let mut entries = backend.api().ls(".").await.unwrap();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].partial && entries[0].size == 0);
+    assert!(!entries[1].partial && entries[1].size == 4);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_shows_an_unknown_entry_with_size_zero_and_epoch_mtime() {
+    let backend = FakeBackend::start();
+    backend.insert("./ghost.txt", Node::file(b"boo"));
+    backend.insert("./real.txt", Node::file(b"here"));
+    null_ghost(&backend);
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut names: Vec<String> = std::fs::read_dir(mnt.path(""))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|n| !n.starts_with('.'))
+        .collect();
+    names.sort();
+    assert_eq!(names, ["ghost.txt", "real.txt"]);
+    let ghost = std::fs::symlink_metadata(mnt.path("ghost.txt")).unwrap();
+    assert_eq!(ghost.len(), 0);
+    assert_eq!(ghost.modified().unwrap(), UNIX_EPOCH);
+    assert_eq!(std::fs::metadata(mnt.path("real.txt")).unwrap().len(), 4);
+    mnt.unmount();
+}
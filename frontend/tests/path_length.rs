@@ -0,0 +1,49 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[test]
+fn the_path_limit_is_parsed_in_bytes() {
+    let args: Vec<String> = ["frontend", "--max-path-len", "1024"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().max_path_len, Some(1024));
+    let args: Vec<String> = ["frontend", "--max-path-len=0"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn over_long_names_and_paths_fail_with_enametoolong_without_a_request() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    backend.insert(&format!("./{}", "d".repeat(19)), Node::dir());
+    let opts = MountOptions { max_path_len: Some(40), ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    std::fs::metadata(mnt.path("a.txt")).unwrap();
+    backend.server.clear();
+    let name_too_long = |err: std::io::Error| assert_eq!(err.raw_os_error(), Some(libc::ENAMETOOLONG), "{}", err);
+
+    // A name over 255 bytes, whatever the path limit
+    name_too_long(std::fs::File::create(mnt.path(&"n".repeat(256))).unwrap_err());
+    // "./" and 39 bytes of nested path go past 40
+    name_too_long(std::fs::create_dir(mnt.path(&format!("{}/{}", "d".repeat(19), "e".repeat(19)))).unwrap_err());
+    name_too_long(std::fs::File::create(mnt.path(&"f".repeat(39))).unwrap_err());
+    name_too_long(std::fs::rename(mnt.path("a.txt"), mnt.path(&"r".repeat(39))).unwrap_err());
+    name_too_long(std::os::unix::fs::symlink("a.txt", mnt.path(&"s".repeat(39))).unwrap_err());
+    let sent: Vec<String> = backend
+        .server
+        .requests()
+        .iter()
+        .map(|r| r.route())
+        .filter(|r| !r.starts_with("GET") && !r.starts_with("HEAD"))
+        .collect();
+    assert!(sent.is_empty(), "{:?}", sent);
+
+    // Right at the limit goes through
+    std::fs::write(mnt.path(&"f".repeat(38)), b"ok").unwrap();
+    wait_for(|| (backend.data(&format!("./{}", "f".repeat(38)))? == b"ok").then_some(()));
+    assert_eq!(backend.data("./a.txt").unwrap(), b"a");
+    mnt.unmount();
+}
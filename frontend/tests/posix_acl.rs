@@ -0,0 +1,114 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::acl;
+use frontend::options::MountOptions;
+use serde_json::{json, Value};
+use std::ffi::CString;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const ACL: &str = "user::rw-,user:1000:r--,group::r--,mask::r--,other::r--";
+
+#[test]
+fn an_acl_with_a_named_user_round_trips_through_the_xattr_form() {
+    let value = acl::to_xattr(ACL).unwrap();
+    // Version header, then five 8-byte records
+    assert_eq!(value.len(), 4 + 5 * 8);
+    assert_eq!(&value[..4], &2u32.to_le_bytes());
+    // The named user record comes second, after user::
+    assert_eq!(&value[12..20], &[0x02, 0, 4, 0, 0xe8, 0x03, 0, 0]);
+    assert_eq!(acl::from_xattr(&value).as_deref(), Some(ACL));
+
+    // Short tags, newlines and any order give the same value
+    let loose = "o::r--\nm::r--\ng::r--\nu:1000:r--\nu::rw-";
+    assert_eq!(acl::to_xattr(loose), Some(value.clone()));
+
+    assert_eq!(acl::to_xattr(""), None);
+    assert_eq!(acl::to_xattr("user::rwz"), None);
+    assert_eq!(acl::to_xattr("owner::rw-"), None);
+    assert_eq!(acl::from_xattr(&value[..10]), None);
+    let mut wrong_version = value;
+    wrong_version[0] = 1;
+    assert_eq!(acl::from_xattr(&wrong_version), None);
+}
+
+#[cfg(target_os = "linux")]
+fn getxattr(path: &Path, name: &str) -> Result<Vec<u8>, i32> {
+    let (path, name) = (CString::new(path.as_os_str().as_encoded_bytes()).unwrap(), CString::new(name).unwrap());
+    let mut buf = vec![0u8; 256];
+    let n = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf.as_mut_ptr().cast(), buf.len()) };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error().raw_os_error().unwrap());
+    }
+    buf.truncate(n as usize);
+    Ok(buf)
+}
+
+#[cfg(target_os = "linux")]
+fn setxattr(path: &Path, name: &str, value: &[u8]) -> Result<(), i32> {
+    let (path, name) = (CString::new(path.as_os_str().as_encoded_bytes()).unwrap(), CString::new(name).unwrap());
+    let rc = unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) };
+    if rc < 0 {
+        return Err(std::io::Error::last_os_error().raw_os_error().unwrap());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn an_acl_set_on_the_mount_is_stored_on_the_backend_and_read_back() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"data"));
+    // The access ACLs the backend keeps, by relPath
+    let acls: Arc<Mutex<Vec<(String, Value)>>> = Arc::default();
+    let store = acls.clone();
+    backend.set_hook(move |req, _| {
+        let rel = req.query("relPath")?.to_string();
+        let mut acls = store.lock().unwrap();
+        match req.route().as_str() {
+            "GET /files/acl" => {
+                let access = acls.iter().find(|(r, _)| *r == rel).map_or(Value::Null, |(_, a)| a.clone());
+                Some(Response::json(200, json!({ "access": access, "default": null })))
+            }
+            "PUT /files/acl" => {
+                let body: Value = serde_json::from_slice(&req.body).unwrap();
+                assert_eq!(req.query("kind"), Some("access"));
+                acls.retain(|(r, _)| *r != rel);
+                acls.push((rel, body["acl"].clone()));
+                Some(Response::json(200, json!({ "ok": true })))
+            }
+            _ => None,
+        }
+    });
+    let opts = MountOptions { acl: true, ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    let doc = mnt.path("doc.txt");
+    assert_eq!(getxattr(&doc, acl::XATTR_ACCESS), Err(libc::ENODATA));
+
+    // What setfacl -m u:1000:r doc.txt writes
+    let value = acl::to_xattr(ACL).unwrap();
+    setxattr(&doc, acl::XATTR_ACCESS, &value).unwrap();
+    assert_eq!(acls.lock().unwrap().as_slice(), [("./doc.txt".to_string(), json!(ACL))]);
+    assert_eq!(getxattr(&doc, acl::XATTR_ACCESS), Ok(value));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn without_backend_acls_setting_one_is_enotsup() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"data"));
+    backend.set_hook(|req, _| req.route().ends_with(" /files/acl").then(|| Response::empty(501)));
+    let opts = MountOptions { acl: true, ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    let doc = mnt.path("doc.txt");
+    assert_eq!(getxattr(&doc, acl::XATTR_ACCESS), Err(libc::ENODATA));
+    let err = setxattr(&doc, acl::XATTR_ACCESS, &acl::to_xattr(ACL).unwrap()).unwrap_err();
+    assert_eq!(err, libc::EOPNOTSUPP);
+    mnt.unmount();
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+
+const SIZE: usize = 4 * 1024 * 1024;
+
+fn big() -> Vec<u8> {
+    (0..SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+// Function that returns the bytes a ranged GET asked for, from its "bytes=a-b" header
+fn requested(range: &str) -> u64 {
+    let (a, b) = range.strip_prefix("bytes=").unwrap().split_once('-').unwrap();
+    b.parse::<u64>().unwrap() - a.parse::<u64>().unwrap() + 1
+}
+
+#[tokio::test]
+async fn read_range_sends_a_range_and_slices_a_full_answer() {
+    let backend = FakeBackend::start();
+    backend.insert("./data.bin", Node::file(b"0123456789"));
+    let api = backend.api();
+
+    assert_eq!(api.read_range("./data.bin", 2, 5).await.unwrap(), b"2345");
+    let sent = backend.server.requests();
+    assert_eq!(sent.last().unwrap().header("Range"), Some("bytes=2-5"));
+
+    // A server ignoring the header sends the whole file with 200
+    backend.set_hook(|req, _| (req.route() == "GET /files").then(|| Response::bytes(200, b"0123456789".to_vec())));
+    assert_eq!(api.read_range("./data.bin", 2, 5).await.unwrap(), b"2345");
+    assert_eq!(api.read_range("./data.bin", 8, 20).await.unwrap(), b"89");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn reading_a_large_file_through_the_mount_downloads_it_about_once() {
+    use std::io::Read;
+
+    let backend = FakeBackend::start();
+    let data = big();
+    backend.insert("./big.bin", Node::file(&data));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut file = std::fs::File::open(mnt.path("big.bin")).unwrap();
+    let mut read = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = file.read(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        read.extend_from_slice(&chunk[..n]);
+    }
+    assert!(read == data, "content differs");
+
+    // Every download is ranged, and together they add up to the file, not to a file per read
+    let gets: Vec<_> = backend.server.requests().into_iter().filter(|r| r.route() == "GET /files").collect();
+    assert!(gets.len() > 1);
+    let mut total = 0;
+    for get in &gets {
+        let range = get.header("Range").expect("a GET /files without Range");
+        total += requested(range);
+    }
+    assert!(total < 2 * SIZE as u64, "{} bytes asked for a {} byte file", total, SIZE);
+    drop(file);
+    mnt.unmount();
+}
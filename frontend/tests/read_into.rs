@@ -0,0 +1,40 @@
+mod common;
+
+use common::{FakeBackend, Node, Response};
+
+#[tokio::test]
+async fn read_into_fills_the_buffer_for_a_partial_and_a_full_range() {
+    let backend = FakeBackend::start();
+    backend.insert("./data.bin", Node::file(b"0123456789"));
+    let api = backend.api();
+
+    // A range inside the file
+    let mut buf = [0u8; 4];
+    assert_eq!(api.read_into("./data.bin", 3, &mut buf).await.unwrap(), 4);
+    assert_eq!(&buf, b"3456");
+    let range = backend.server.requests().last().unwrap().header("Range").map(str::to_string);
+    assert_eq!(range.as_deref(), Some("bytes=3-6"));
+
+    // The whole file
+    let mut buf = [0u8; 10];
+    assert_eq!(api.read_into("./data.bin", 0, &mut buf).await.unwrap(), 10);
+    assert_eq!(&buf, b"0123456789");
+
+    // A range past the end only fills what the file has, and leaves the rest untouched
+    let mut buf = [b'x'; 8];
+    assert_eq!(api.read_into("./data.bin", 6, &mut buf).await.unwrap(), 4);
+    assert_eq!(&buf, b"6789xxxx");
+    assert_eq!(api.read_into("./data.bin", 10, &mut buf).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn read_into_skips_to_the_offset_when_the_range_is_ignored() {
+    let backend = FakeBackend::start();
+    backend.insert("./data.bin", Node::file(b"0123456789"));
+    backend.set_hook(|req, _| (req.route() == "GET /files").then(|| Response::bytes(200, b"0123456789".to_vec())));
+    let api = backend.api();
+
+    let mut buf = [0u8; 3];
+    assert_eq!(api.read_into("./data.bin", 5, &mut buf).await.unwrap(), 3);
+    assert_eq!(&buf, b"567");
+}
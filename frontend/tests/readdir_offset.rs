@@ -0,0 +1,95 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::os::fd::AsRawFd;
+
+// Function that seeks the directory `fd` to `offset` and reads every entry from there with
+// getdents64, as (name, d_off) pairs
+fn entries_from(fd: &std::fs::File, offset: i64) -> std::io::Result<Vec<(String, i64)>> {
+    let fd = fd.as_raw_fd();
+    if unsafe { libc::lseek(fd, offset, libc::SEEK_SET) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = unsafe { libc::syscall(libc::SYS_getdents64, fd, buf.as_mut_ptr(), buf.len()) };
+        if n < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if n == 0 {
+            return Ok(out);
+        }
+        let mut pos = 0;
+        while pos < n as usize {
+            // struct linux_dirent64: d_ino u64, d_off i64, d_reclen u16, d_type u8, d_name
+            let d_off = i64::from_ne_bytes(buf[pos + 8..pos + 16].try_into().unwrap());
+            let reclen = u16::from_ne_bytes(buf[pos + 16..pos + 18].try_into().unwrap()) as usize;
+            let name = &buf[pos + 19..pos + reclen];
+            let name = &name[..name.iter().position(|&b| b == 0).unwrap()];
+            out.push((String::from_utf8_lossy(name).into_owned(), d_off));
+            pos += reclen;
+        }
+    }
+}
+
+fn names(entries: &[(String, i64)]) -> Vec<&str> {
+    entries.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+#[test]
+fn an_offset_past_the_end_is_an_empty_continuation() {
+    let backend = FakeBackend::start();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        backend.insert(&format!("./{}", name), Node::file(b"x"));
+    }
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let dir = std::fs::File::open(mnt.dir.path()).unwrap();
+
+    let all = entries_from(&dir, 0).unwrap();
+    let real: Vec<(String, i64)> = all.iter().filter(|(n, _)| !n.starts_with('.')).cloned().collect();
+    assert_eq!(names(&real), ["a.txt", "b.txt", "c.txt"]);
+    assert_eq!(names(&all[..2]), [".", ".."]);
+
+    // Resuming after each offset the kernel was given goes on with the next entry
+    for (i, (_, off)) in all.iter().enumerate() {
+        assert_eq!(entries_from(&dir, *off).unwrap(), all[i + 1..], "after offset {}", off);
+    }
+    // After the last entry, or far past the listing, there is nothing left and no error
+    assert_eq!(entries_from(&dir, all.last().unwrap().1).unwrap(), []);
+    assert_eq!(entries_from(&dir, 1000).unwrap(), []);
+    assert_eq!(entries_from(&dir, i64::MAX).unwrap(), []);
+    drop(dir);
+    mnt.unmount();
+}
+
+#[test]
+fn a_listing_that_shrank_ends_cleanly_at_the_old_offset() {
+    let backend = FakeBackend::start();
+    for i in 0..6 {
+        backend.insert(&format!("./f{}.txt", i), Node::file(b"x"));
+    }
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let before = entries_from(&std::fs::File::open(mnt.dir.path()).unwrap(), 0).unwrap();
+    let last = before.last().unwrap().1;
+
+    // Most entries go away; a new handle resumes at an offset past the new listing
+    for i in 1..6 {
+        backend.remove(&format!("./f{}.txt", i));
+    }
+    std::fs::write(mnt.path(".remotefs/refresh"), b"1").unwrap();
+    let dir = std::fs::File::open(mnt.dir.path()).unwrap();
+    let now = entries_from(&dir, 0).unwrap();
+    assert!(names(&now).contains(&"f0.txt"));
+    assert!(!names(&now).contains(&"f5.txt"));
+    assert_eq!(entries_from(&dir, last).unwrap(), []);
+    drop(dir);
+    mnt.unmount();
+}
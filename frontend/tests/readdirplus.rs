@@ -0,0 +1,52 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::path::Path;
+
+// Function that reads how many times the client answered from the cached listing of /photos
+#[cfg(target_os = "linux")]
+fn photos_hits(mountpoint: &Path) -> u64 {
+    let status = std::fs::read_to_string(mountpoint.join(".remotefs/refresh")).unwrap();
+    let line = status.lines().find(|l| l.starts_with("cache[/photos]: ")).unwrap();
+    line.split(' ').find_map(|kv| kv.strip_prefix("hits=")).unwrap().parse().unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn listed_children_are_stated_without_backend_calls() {
+    let backend = FakeBackend::start();
+    backend.insert("./photos", Node::dir());
+    for i in 0..20 {
+        backend.insert(&format!("./photos/img{:02}.jpg", i), Node::file(&vec![b'x'; i]));
+    }
+    backend.insert("./photos/album", Node::dir());
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut names: Vec<String> = std::fs::read_dir(mnt.path("photos"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 21);
+
+    // Every child came with its attributes: the kernel asks the client for none of them, and so
+    // neither does the client ask the backend
+    let hits = photos_hits(mnt.dir.path());
+    backend.server.clear();
+    for name in &names {
+        let meta = std::fs::symlink_metadata(mnt.path(&format!("photos/{}", name))).unwrap();
+        if name == "album" {
+            assert!(meta.is_dir());
+        } else {
+            let i: u64 = name[3..5].parse().unwrap();
+            assert!(meta.is_file());
+            assert_eq!(meta.len(), i);
+        }
+    }
+    assert_eq!(backend.server.total(), 0, "{:?}", backend.server.requests());
+    assert_eq!(photos_hits(mnt.dir.path()), hits);
+    mnt.unmount();
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response, TempDir};
+use frontend::options::MountOptions;
+use serde_json::json;
+
+#[test]
+fn recovered_files_keep_the_path_they_were_meant_for() {
+    let recovery = TempDir::new("recovery");
+    let temp = recovery.path().join("tmp-write");
+    std::fs::write(&temp, b"unsent").unwrap();
+
+    let saved = frontend::recovery::save(recovery.path(), "./docs/100%/a.txt", &temp).unwrap();
+    assert!(!temp.exists());
+    let listed = frontend::recovery::list(recovery.path());
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].path, saved);
+    assert_eq!(listed[0].rel, "./docs/100%/a.txt");
+    assert_eq!(listed[0].size, 6);
+}
+
+#[tokio::test]
+async fn retry_uploads_the_recovered_files_unless_the_backend_changed_since() {
+    let backend = FakeBackend::start();
+    let recovery = TempDir::new("recovery");
+    for (rel, data) in [("./new.txt", "saved new"), ("./old.txt", "saved old"), ("./newer.txt", "saved newer")] {
+        let temp = recovery.path().join("tmp");
+        std::fs::write(&temp, data).unwrap();
+        frontend::recovery::save(recovery.path(), rel, &temp).unwrap();
+    }
+    // Changed on the backend before the data was saved, and after
+    let mut old = Node::file(b"backend old");
+    old.mtime = (1_000, 0);
+    backend.insert("./old.txt", old);
+    let mut newer = Node::file(b"backend newer");
+    newer.mtime = (4_000_000_000, 0);
+    backend.insert("./newer.txt", newer);
+
+    frontend::recovery::retry(recovery.path(), &backend.api()).await;
+    assert_eq!(backend.data("./new.txt").unwrap(), b"saved new");
+    assert_eq!(backend.data("./old.txt").unwrap(), b"saved old");
+    assert_eq!(backend.data("./newer.txt").unwrap(), b"backend newer");
+    let left: Vec<String> = frontend::recovery::list(recovery.path()).into_iter().map(|r| r.rel).collect();
+    assert_eq!(left, ["./newer.txt"]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_write_lost_at_shutdown_is_saved_and_listed_under_recovered() {
+    use std::io::Write;
+    use std::os::unix::fs::MetadataExt;
+
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"old"));
+    backend.set_hook(|req, _| {
+        (req.route() == "PUT /files").then(|| Response::json(500, json!({ "error": "disk full" })))
+    });
+    let recovery = TempDir::new("recovery");
+    let opts = || MountOptions { recovery_dir: Some(recovery.path().to_path_buf()), ..Default::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts()) else {
+        return;
+    };
+
+    // The connection goes away with the file still open and its data unsent
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("doc.txt")).unwrap();
+    file.write_all(b"unsent data").unwrap();
+    let conn = libc::minor(std::fs::metadata(mnt.dir.path()).unwrap().dev());
+    std::fs::write(format!("/sys/fs/fuse/connections/{}/abort", conn), b"1").unwrap();
+    mnt.unmount();
+    drop(file);
+    assert_eq!(std::fs::read(recovery.path().join("doc.txt")).unwrap(), b"unsent data");
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"old");
+
+    // The next mount shows it, read-only, under the synthetic directory
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts()) else {
+        return;
+    };
+    let names: Vec<String> = std::fs::read_dir(mnt.path(".remotefs/recovered"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(names, ["doc.txt"]);
+    let recovered = mnt.path(".remotefs/recovered/doc.txt");
+    assert_eq!(std::fs::read(&recovered).unwrap(), b"unsent data");
+    assert!(std::fs::write(&recovered, b"x").is_err());
+    mnt.unmount();
+}
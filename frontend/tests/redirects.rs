@@ -0,0 +1,72 @@
+mod common;
+
+use common::{FakeBackend, Node, Request, Response};
+use frontend::api_error::ApiError;
+use frontend::options::MountOptions;
+
+// Function that answers `route` with a redirect of `status` to the same request marked as moved, up
+// to `hops` times
+fn redirect(backend: &FakeBackend, route: &'static str, status: u16, hops: usize) {
+    backend.set_hook(move |req: &Request, _| {
+        let hop: usize = req.query("hop").map_or(0, |h| h.parse().unwrap());
+        if req.route() != route || hop >= hops {
+            return None;
+        }
+        let mut params: Vec<(String, String)> = req.query.iter().filter(|(k, _)| k != "hop").cloned().collect();
+        params.push(("hop".to_string(), (hop + 1).to_string()));
+        let path = route.split_once(' ').unwrap().1;
+        let target = reqwest::Url::parse_with_params(&format!("http://x{}", path), &params).unwrap();
+        let location = format!("{}?{}", path, target.query().unwrap());
+        Some(Response::empty(status).with_header("Location", &location))
+    });
+}
+
+#[test]
+fn the_redirect_limit_is_parsed() {
+    let args: Vec<String> = ["frontend", "--max-redirects", "3"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(MountOptions::from_args(&args).unwrap().max_redirects, Some(3));
+}
+
+#[tokio::test]
+async fn a_redirected_upload_delivers_its_body_to_the_final_target() {
+    for status in [301, 302, 307, 308] {
+        let backend = FakeBackend::start();
+        redirect(&backend, "PUT /files", status, 2);
+        backend.api().write_bytes("./a.txt", b"payload").await.unwrap();
+
+        assert_eq!(backend.data("./a.txt").unwrap(), b"payload", "{}", status);
+        let puts: Vec<Request> = backend.server.requests().into_iter().filter(|r| r.route() == "PUT /files").collect();
+        // Every hop is still a PUT carrying the whole body
+        assert_eq!(puts.len(), 3, "{}", status);
+        assert!(puts.iter().all(|r| r.body == b"payload"), "{}", status);
+        assert_eq!(puts[2].query("hop"), Some("2"));
+    }
+}
+
+#[tokio::test]
+async fn a_redirected_rename_keeps_its_method() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    redirect(&backend, "PATCH /files/rename", 302, 1);
+    backend.api().rename("./a.txt", "./b.txt").await.unwrap();
+    assert_eq!(backend.data("./b.txt").unwrap(), b"a");
+    assert_eq!(backend.server.count("PATCH /files/rename"), 2);
+    assert_eq!(backend.server.count("GET /files/rename"), 0);
+}
+
+#[tokio::test]
+async fn redirects_past_the_limit_fail_without_reaching_the_target() {
+    for status in [302, 307] {
+        let backend = FakeBackend::start();
+        redirect(&backend, "PUT /files", status, 3);
+        let err = backend.api().with_max_redirects(2).write_bytes("./a.txt", b"payload").await.unwrap_err();
+        assert!(backend.data("./a.txt").is_none(), "{}", status);
+        // Two hops followed, the third refused
+        assert_eq!(backend.server.count("PUT /files"), 3, "{}", status);
+        match status {
+            // Re-sent by the client itself, the last redirect is returned as the failure
+            302 => assert!(matches!(err, ApiError::Http { op: "redirect", status: 302, .. }), "{:?}", err),
+            _ => assert!(matches!(err, ApiError::Network(ref e) if e.is_redirect()), "{:?}", err),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+
+// The backend answers the rename as done, `apply` decides what it really did
+fn lying_rename(backend: &FakeBackend, apply: fn(&mut std::collections::BTreeMap<String, Node>)) {
+    backend.set_hook(move |req, tree| {
+        (req.route() == "PATCH /files/rename").then(|| {
+            apply(tree);
+            Response::json(200, json!({ "ok": true }))
+        })
+    });
+}
+
+#[tokio::test]
+async fn rename_checks_the_entry_moved() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    backend.api().rename("/a.txt", "/b.txt").await.unwrap();
+    assert!(backend.get("./a.txt").is_none());
+    assert_eq!(backend.data("./b.txt").as_deref(), Some(&b"data"[..]));
+    assert_eq!(backend.server.count("HEAD /files"), 2);
+}
+
+#[tokio::test]
+async fn rename_that_left_the_source_is_an_error() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    // Copied, the delete of the source never happened
+    lying_rename(&backend, |tree| {
+        let node = tree["./a.txt"].clone();
+        tree.insert("./b.txt".into(), node);
+    });
+    let err = backend.api().rename("/a.txt", "/b.txt").await.unwrap_err();
+    assert!(err.is_incomplete_rename(), "{}", err);
+    assert_eq!(err.status(), Some(500));
+}
+
+#[tokio::test]
+async fn rename_that_moved_nothing_is_an_error() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    lying_rename(&backend, |_| {});
+    let err = backend.api().rename("/a.txt", "/b.txt").await.unwrap_err();
+    assert!(err.is_incomplete_rename(), "{}", err);
+}
+
+#[tokio::test]
+async fn rename_that_lost_the_entry_is_an_error() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    lying_rename(&backend, |tree| {
+        tree.remove("./a.txt");
+    });
+    let err = backend.api().rename("/a.txt", "/b.txt").await.unwrap_err();
+    assert!(err.is_incomplete_rename(), "{}", err);
+}
+
+// The backend removes the destination and then fails, before the source is moved
+fn rename_failing_after_pre_delete(backend: &FakeBackend) {
+    backend.set_hook(|req, tree| {
+        (req.route() == "PATCH /files/rename").then(|| {
+            tree.remove("./b.txt");
+            Response::json(500, json!({ "error": "rename failed" }))
+        })
+    });
+}
+
+#[tokio::test]
+async fn rename_failing_after_pre_delete_keeps_the_source() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"new"));
+    backend.insert("./b.txt", Node::file(b"old"));
+    rename_failing_after_pre_delete(&backend);
+    let api = backend.api();
+    assert!(api.rename("/a.txt", "/b.txt").await.is_err());
+    backend.clear_hook();
+
+    assert_eq!(backend.data("./a.txt").as_deref(), Some(&b"new"[..]));
+    let names: Vec<String> = api.ls("/").await.unwrap().into_iter().map(|de| de.name).collect();
+    assert_eq!(names, ["a.txt"]);
+    assert_eq!(api.get_update_metadata("/a.txt").await.unwrap().size, 3);
+    assert!(api.get_update_metadata("/b.txt").await.is_err());
+}
+
+#[tokio::test]
+async fn rename_changing_only_the_case_passes_on_a_case_insensitive_backend() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    // Both spellings find the one entry
+    backend.set_hook(|req, _| {
+        match req.route().as_str() {
+            "PATCH /files/rename" => Some(Response::json(200, json!({ "ok": true }))),
+            "HEAD /files" => Some(Response::empty(200)),
+            _ => None,
+        }
+    });
+    backend.api().rename("/a.txt", "/A.txt").await.unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn partial_rename_fails_and_the_mount_shows_the_backend_state() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"data"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"data");
+
+    lying_rename(&backend, |tree| {
+        let node = tree["./a.txt"].clone();
+        tree.insert("./b.txt".into(), node);
+    });
+    let err = std::fs::rename(mnt.path("a.txt"), mnt.path("b.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EIO));
+    backend.clear_hook();
+
+    // Both names are there on the backend, and so on the mount: no ghost, nothing lost
+    let mut names: Vec<String> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.txt", "b.txt"]);
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"data");
+    assert_eq!(std::fs::read(mnt.path("b.txt")).unwrap(), b"data");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn rename_failing_after_pre_delete_shows_the_backend_state() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"new"));
+    backend.insert("./b.txt", Node::file(b"old"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    // Both cached before the rename
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"new");
+    assert_eq!(std::fs::read(mnt.path("b.txt")).unwrap(), b"old");
+
+    rename_failing_after_pre_delete(&backend);
+    assert!(std::fs::rename(mnt.path("a.txt"), mnt.path("b.txt")).is_err());
+    backend.clear_hook();
+
+    // The source is untouched, the destination the backend removed is gone from the mount too
+    let names: Vec<String> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["a.txt"]);
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"new");
+    assert_eq!(std::fs::metadata(mnt.path("b.txt")).unwrap_err().raw_os_error(), Some(libc::ENOENT));
+    assert_eq!(backend.data("./a.txt").unwrap(), b"new");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn editor_save_renames_the_pending_temp_over_the_target() {
+    use std::io::Write;
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"old content"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    // A handle still writing the old file, and the temp the editor writes the new content to
+    let mut stale = std::fs::File::options().write(true).open(mnt.path("doc.txt")).unwrap();
+    stale.write_all(b"stale").unwrap();
+    let mut tmp = std::fs::File::create(mnt.path(".doc.txt.swp")).unwrap();
+    tmp.write_all(b"new content").unwrap();
+
+    // Renamed over the target before the temp is closed: the buffered content moves with it
+    std::fs::rename(mnt.path(".doc.txt.swp"), mnt.path("doc.txt")).unwrap();
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"new content");
+    assert!(backend.get("./.doc.txt.swp").is_none());
+
+    // Closing the handles later neither brings the temp back nor overwrites the new content
+    drop(tmp);
+    drop(stale);
+    assert_eq!(backend.data("./doc.txt").unwrap(), b"new content");
+    assert!(backend.get("./.doc.txt.swp").is_none());
+    assert_eq!(std::fs::read(mnt.path("doc.txt")).unwrap(), b"new content");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_rename_across_backend_volumes_is_exdev_and_mv_copies_instead() {
+    let backend = FakeBackend::start();
+    backend.insert("./vol1", Node::dir());
+    backend.insert("./vol2", Node::dir());
+    backend.insert("./vol1/a.txt", Node::file(b"data"));
+    backend.set_hook(|req, _| {
+        (req.route() == "PATCH /files/rename")
+            .then(|| Response::json(422, json!({ "error": "Cannot rename across devices", "code": "EXDEV" })))
+    });
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let err = std::fs::rename(mnt.path("vol1/a.txt"), mnt.path("vol2/a.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EXDEV));
+    assert_eq!(backend.data("./vol1/a.txt").unwrap(), b"data");
+
+    // mv falls back to copying and deleting the source
+    let status = std::process::Command::new("mv")
+        .arg(mnt.path("vol1/a.txt"))
+        .arg(mnt.path("vol2/a.txt"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    wait_for(|| (backend.data("./vol2/a.txt")? == b"data").then_some(()));
+    assert!(backend.get("./vol1/a.txt").is_none());
+    mnt.unmount();
+}
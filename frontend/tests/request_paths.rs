@@ -0,0 +1,43 @@
+mod common;
+
+use common::{FakeBackend, Node};
+
+#[tokio::test]
+async fn requests_carry_the_canonical_form_of_any_path() {
+    let backend = FakeBackend::start();
+    backend.insert("./docs", Node::dir());
+    backend.insert("./docs/a.txt", Node::file(b"a"));
+    let api = backend.api();
+
+    for root in ["/", ".", "", "./", "//"] {
+        api.ls(root).await.unwrap();
+    }
+    for docs in ["/docs", "docs/", "./docs", "//docs//", "/docs/./", "/x/../docs"] {
+        api.ls(docs).await.unwrap();
+    }
+    api.rename("docs//a.txt", "/docs/./b.txt").await.unwrap();
+    api.mkdir("/docs/new/").await.unwrap();
+    api.delete("./docs/new").await.unwrap();
+
+    let sent: Vec<(String, Vec<(String, String)>)> = backend
+        .server
+        .requests()
+        .into_iter()
+        .filter(|r| !r.route().starts_with("HEAD") && !r.route().starts_with("GET /list/updatedMetadata"))
+        .map(|r| (r.route(), r.query.clone()))
+        .collect();
+    let rel = |v: &str| vec![("relPath".to_string(), v.to_string())];
+    let mut expected: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    expected.extend((0..5).map(|_| ("GET /list".to_string(), rel("."))));
+    expected.extend((0..6).map(|_| ("GET /list".to_string(), rel("./docs"))));
+    expected.push((
+        "PATCH /files/rename".to_string(),
+        vec![
+            ("oldRelPath".to_string(), "./docs/a.txt".to_string()),
+            ("newRelPath".to_string(), "./docs/b.txt".to_string()),
+        ],
+    ));
+    expected.push(("POST /mkdir".to_string(), rel("./docs/new")));
+    expected.push(("DELETE /files".to_string(), rel("./docs/new")));
+    assert_eq!(sent, expected);
+}
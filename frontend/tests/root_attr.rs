@@ -0,0 +1,36 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::os::unix::fs::MetadataExt;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_root_shows_the_mode_the_backend_reports() {
+    let backend = FakeBackend::start();
+    let mut root = Node::dir();
+    root.mode = 0o700;
+    backend.insert(".", root);
+    backend.insert("./a.txt", Node::file(b"a"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let meta = std::fs::metadata(mnt.dir.path()).unwrap();
+    assert!(meta.is_dir());
+    assert_eq!(meta.mode() & 0o7777, 0o700);
+    assert_eq!(meta.ino(), 1);
+
+    // A change on the backend shows once the caches are dropped and the kernel asks again, on the
+    // same inode
+    let mut root = Node::dir();
+    root.mode = 0o750;
+    backend.insert(".", root);
+    std::fs::write(mnt.path(".remotefs/refresh"), b"1").unwrap();
+    let meta = wait_for(|| {
+        let meta = std::fs::metadata(mnt.dir.path()).unwrap();
+        (meta.mode() & 0o7777 == 0o750).then_some(meta)
+    });
+    assert_eq!(meta.ino(), 1);
+    mnt.unmount();
+}
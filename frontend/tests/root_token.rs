@@ -0,0 +1,39 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn listing_the_root_sends_one_request_with_the_root_token() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    std::fs::write(mnt.path(".remotefs/refresh"), b"1").unwrap();
+    backend.server.clear();
+
+    let names: Vec<String> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["a.txt"]);
+    assert_eq!(backend.server.count("GET /list"), 1);
+    // Every request about the root names it the same way
+    let root_queries: Vec<_> = backend
+        .server
+        .requests()
+        .into_iter()
+        .filter(|r| !r.route().starts_with("GET /socket.io"))
+        .map(|r| r.query("relPath").map(str::to_string))
+        .collect();
+    assert!(!root_queries.is_empty());
+    assert!(root_queries.iter().all(|q| q.as_deref() == Some(".")), "{:?}", root_queries);
+
+    // Listed again while cached, the backend is not asked
+    backend.server.clear();
+    assert_eq!(std::fs::read_dir(mnt.dir.path()).unwrap().count(), 1);
+    assert_eq!(backend.server.count("GET /list"), 0);
+    mnt.unmount();
+}
@@ -0,0 +1,42 @@
+use frontend::acl::sddl_from_mode;
+
+const OWNER: &str = "S-1-5-21-1-2-3-1001";
+
+#[test]
+fn a_private_file_grants_access_to_the_owner_only() {
+    let sddl = sddl_from_mode(0o600, false, OWNER);
+    assert_eq!(sddl, format!("O:{0}G:BUD:P(A;;FRFW;;;{0})", OWNER));
+    assert!(!sddl.contains(";;;BU)") && !sddl.contains(";;;WD)"), "{}", sddl);
+}
+
+#[test]
+fn each_class_of_the_mode_becomes_an_ace() {
+    assert_eq!(
+        sddl_from_mode(0o754, false, OWNER),
+        format!("O:{0}G:BUD:P(A;;FA;;;{0})(A;;FRFX;;;BU)(A;;FR;;;WD)", OWNER)
+    );
+    // A read-only file grants no write to anybody
+    let sddl = sddl_from_mode(0o444, false, OWNER);
+    assert!(!sddl.contains("FW") && !sddl.contains("FA"), "{}", sddl);
+    // Writing a directory lets its entries be removed, as on unix
+    assert_eq!(
+        sddl_from_mode(0o760, true, OWNER),
+        format!("O:{0}G:BUD:P(A;;FA;;;{0})(A;;FRFWDT;;;BU)", OWNER)
+    );
+    // No bits at all leave a protected DACL without ACEs: access denied to everybody
+    assert_eq!(sddl_from_mode(0o000, false, OWNER), format!("O:{}G:BUD:P", OWNER));
+}
+
+#[test]
+fn a_sticky_directory_lets_only_the_owner_remove_entries() {
+    assert_eq!(
+        sddl_from_mode(0o1777, true, OWNER),
+        format!("O:{0}G:BUD:P(A;;FA;;;{0})(A;;FRFWFX;;;BU)(A;;FRFWFX;;;WD)", OWNER)
+    );
+    assert_eq!(
+        sddl_from_mode(0o1775, true, OWNER),
+        format!("O:{0}G:BUD:P(A;;FA;;;{0})(A;;FRFWFX;;;BU)(A;;FRFX;;;WD)", OWNER)
+    );
+    // The bit means nothing on files
+    assert_eq!(sddl_from_mode(0o1777, false, OWNER), sddl_from_mode(0o777, false, OWNER));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{FakeBackend, Response};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Backend with cookie sessions: each login opens session n+1, only the latest one is accepted
+fn with_sessions(backend: &FakeBackend) -> Arc<AtomicUsize> {
+    let session = Arc::new(AtomicUsize::new(0));
+    let current = session.clone();
+    backend.set_hook(move |req, _| {
+        if req.route() == "POST /login" {
+            let body: serde_json::Value = serde_json::from_slice(&req.body).ok()?;
+            if body != json!({ "username": "ada", "password": "secret" }) {
+                return Some(Response::empty(403));
+            }
+            let n = current.fetch_add(1, Ordering::SeqCst) + 1;
+            return Some(Response::empty(200).with_header("Set-Cookie", &format!("sid={}; Path=/; HttpOnly", n)));
+        }
+        let expected = format!("sid={}", current.load(Ordering::SeqCst));
+        (req.header("Cookie") != Some(expected.as_str())).then(|| Response::json(401, json!({ "error": "login" })))
+    });
+    session
+}
+
+#[tokio::test]
+async fn an_expired_session_is_opened_again() {
+    let backend = FakeBackend::start();
+    let session = with_sessions(&backend);
+    let api = backend.api().with_credentials(Some("ada".into()), Some("secret".into()));
+    api.login("ada", "secret").await.unwrap();
+    api.ls("/").await.unwrap();
+    assert_eq!(backend.server.count("POST /login"), 1);
+
+    // The backend drops the session: the 401 logs in again and the request is repeated with it
+    session.fetch_add(1, Ordering::SeqCst);
+    backend.server.clear();
+    api.ls("/").await.unwrap();
+    let routes: Vec<String> = backend.server.requests().iter().map(|r| r.route()).collect();
+    assert_eq!(routes, ["GET /list", "POST /login", "GET /list"]);
+    assert_eq!(backend.server.requests()[2].header("Cookie"), Some("sid=3"));
+    assert_eq!(api.handshake_headers(), vec![("Cookie".to_string(), "sid=3".to_string())]);
+
+    // Without credentials the 401 is the answer
+    let anonymous = backend.api();
+    assert_eq!(anonymous.ls("/").await.unwrap_err().status(), Some(401));
+}
+
+#[tokio::test]
+async fn the_session_cookie_stays_with_the_backend_origin() {
+    for status in [301, 302, 307, 308] {
+        let backend = FakeBackend::start();
+        let other = FakeBackend::start();
+        let api = backend.api();
+        let target = format!("{}/list?relPath=.", other.url());
+        backend.set_hook(move |req, _| match req.route().as_str() {
+            "POST /login" => Some(Response::empty(200).with_header("Set-Cookie", "sid=1")),
+            "GET /list" if req.query("moved").is_none() => {
+                Some(Response::empty(status).with_header("Location", "/list?relPath=.&moved=1"))
+            }
+            "GET /list" => Some(Response::empty(status).with_header("Location", &target)),
+            _ => None,
+        });
+        api.login("ada", "secret").await.unwrap();
+        api.ls("/").await.unwrap();
+
+        // Sent along the redirect within the backend, not to the other port
+        let requests = backend.server.requests();
+        assert_eq!(requests.len(), 3, "{}", status);
+        assert!(requests[1..].iter().all(|r| r.header("Cookie") == Some("sid=1")), "{}", status);
+        let followed = other.server.requests();
+        assert_eq!(followed.len(), 1, "{}", status);
+        assert_eq!(followed[0].header("Cookie"), None, "{}", status);
+    }
+}
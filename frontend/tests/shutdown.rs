@@ -0,0 +1,51 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::sync::Arc;
+
+// One test: the shutdown flag is shared by the whole process
+#[test]
+fn backend_calls_around_the_runtime_lifecycle() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"x"));
+    let api = backend.api();
+    let rt = Arc::new(frontend::io_runtime(Some(2)).unwrap());
+
+    // From a thread that drives another runtime the call runs instead of panicking
+    for current_thread in [false, true] {
+        let outer = if current_thread {
+            tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+        } else {
+            tokio::runtime::Runtime::new().unwrap()
+        };
+        let listed = outer.block_on(async {
+            let (rt, api) = (rt.clone(), api.clone());
+            tokio::spawn(async move { frontend::block_on(&rt, api.ls("/")) }).await.unwrap()
+        });
+        assert_eq!(listed.unwrap().len(), 1);
+    }
+
+    // Once the shutdown began a call fails with an error
+    frontend::shutdown_runtime(rt.clone());
+    assert!(frontend::shutting_down());
+    let err = frontend::block_on(&rt, api.ls("/")).unwrap_err();
+    assert!(err.to_string().contains("runtime shutting down"), "{}", err);
+
+    // A new runtime ends it
+    let rt = frontend::io_runtime(None).unwrap();
+    assert!(!frontend::shutting_down());
+    assert_eq!(frontend::block_on(&rt, api.ls("/")).unwrap().len(), 1);
+    drop(rt);
+
+    // Unmounting stops the runtime, and a second mount in the same process gets a working one
+    #[cfg(target_os = "linux")]
+    for _ in 0..2 {
+        let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+            return;
+        };
+        assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"x");
+        mnt.unmount();
+        assert!(frontend::shutting_down());
+    }
+}
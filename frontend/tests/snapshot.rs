@@ -0,0 +1,62 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[tokio::test]
+async fn snapshot_selector_goes_with_reads_and_writes_are_refused() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"hello"));
+    let api = backend.api().with_snapshot(Some("v3".to_string()));
+
+    api.ls("/").await.unwrap();
+    assert_eq!(api.read_range("/a.txt", 0, 4).await.unwrap(), b"hello");
+    let reads = backend.server.requests();
+    assert!(!reads.is_empty());
+    for req in &reads {
+        assert_eq!(req.query("snapshot"), Some("v3"), "{}", req.route());
+    }
+
+    backend.server.clear();
+    let tmp = common::TempDir::new("snapshot");
+    let local = tmp.path().join("data");
+    std::fs::write(&local, b"new").unwrap();
+    assert!(api.write_file("/a.txt", &local).await.is_err());
+    assert!(api.mkdir("/dir").await.is_err());
+    assert!(api.delete("/a.txt").await.is_err());
+    let err = api.rename("/a.txt", "/b.txt").await.unwrap_err();
+    assert!(err.to_string().contains("Read-only snapshot view (v3)"), "{}", err);
+    assert_eq!(backend.server.total(), 0);
+    assert_eq!(backend.data("./a.txt").unwrap(), b"hello");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_snapshot_mount_is_read_only() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"hello"));
+    let opts = MountOptions {
+        snapshot: Some("2024-01-01T00:00:00Z".to_string()),
+        ..MountOptions::default()
+    };
+    let api = backend.api().with_snapshot(opts.snapshot.clone());
+    let Some(mnt) = Mounted::start(api, &backend.url(), opts) else {
+        return;
+    };
+
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"hello");
+    let err = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EROFS));
+    let err = std::fs::write(mnt.path("b.txt"), b"x").unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EROFS));
+    let err = std::fs::remove_file(mnt.path("a.txt")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EROFS));
+    assert!(backend
+        .server
+        .requests()
+        .iter()
+        .filter(|r| !r.route().starts_with("GET /socket.io"))
+        .all(|r| r.query("snapshot") == Some("2024-01-01T00:00:00Z")));
+    assert_eq!(backend.data("./a.txt").unwrap(), b"hello");
+    mnt.unmount();
+}
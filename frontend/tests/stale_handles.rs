@@ -0,0 +1,53 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::os::unix::fs::FileExt;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_handle_on_a_file_removed_or_moved_remotely_goes_stale() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./doc.txt", Node::file(b"v1"));
+    backend.insert("./moved.txt", Node::file(b"m1"));
+    backend.insert("./kept.txt", Node::file(b"k1"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+
+    let doc = std::fs::File::open(mnt.path("doc.txt")).unwrap();
+    let moved = std::fs::File::open(mnt.path("moved.txt")).unwrap();
+    let kept = std::fs::File::open(mnt.path("kept.txt")).unwrap();
+    let mut buf = [0u8; 2];
+    doc.read_exact_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf, b"v1");
+
+    // Another client deletes one file and renames another
+    backend.remove("./doc.txt");
+    backend.emit_change("unlink", "./doc.txt");
+    let node = backend.remove("./moved.txt").unwrap();
+    backend.insert("./elsewhere.txt", node);
+    let mut rename = common::entry_json("./elsewhere.txt", &backend.get("./elsewhere.txt").unwrap());
+    rename["op"] = "rename".into();
+    rename["oldPath"] = "./moved.txt".into();
+    rename["newPath"] = "./elsewhere.txt".into();
+    rename["relPath"] = "./elsewhere.txt".into();
+    rename["parent"] = ".".into();
+    backend.emit("fs_change", rename);
+
+    // The next read fails with ESTALE instead of serving what was there, the other handle is untouched
+    let err = wait_for(|| doc.read_at(&mut buf, 0).err());
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+    let err = wait_for(|| moved.read_at(&mut buf, 0).err());
+    assert_eq!(err.raw_os_error(), Some(libc::ESTALE));
+    kept.read_exact_at(&mut buf, 0).unwrap();
+    assert_eq!(&buf, b"k1");
+    drop((doc, moved, kept));
+
+    // Reopening shows the backend state
+    assert!(std::fs::metadata(mnt.path("doc.txt")).is_err());
+    assert_eq!(std::fs::read(mnt.path("elsewhere.txt")).unwrap(), b"m1");
+    mnt.unmount();
+}
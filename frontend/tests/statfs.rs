@@ -0,0 +1,80 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Response};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+fn statvfs(path: &std::path::Path) -> libc::statvfs {
+    let c = std::ffi::CString::new(path.as_os_str().as_encoded_bytes()).unwrap();
+    let mut out: libc::statvfs = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::statvfs(c.as_ptr(), &mut out) }, 0);
+    out
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn free_space_comes_from_the_backend() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let st = statvfs(mnt.dir.path());
+    assert_eq!((st.f_blocks, st.f_bfree, st.f_bavail, st.f_bsize), (1000, 500, 500, 4096));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn an_unreachable_stats_reports_no_free_space() {
+    let backend = FakeBackend::start();
+    backend.set_hook(|req, _| (req.route() == "GET /stats").then(|| Response::empty(503)));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let st = statvfs(mnt.dir.path());
+    assert_eq!((st.f_blocks, st.f_bfree, st.f_bavail), (0, 0, 0));
+    assert!(backend.server.count("GET /stats") > 0);
+    mnt.unmount();
+}
+
+#[tokio::test]
+async fn a_missing_stats_is_an_unknown_capacity_asked_once() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+    assert_eq!(api.capacity().await.unwrap().map(|s| s.blocks), Some(1000));
+
+    // A failing backend is asked again on the next call
+    backend.set_hook(|req, _| (req.route() == "GET /stats").then(|| Response::empty(503)));
+    assert_eq!(api.capacity().await.unwrap_err().status(), Some(503));
+    assert!(api.capacity().await.is_err());
+    assert_eq!(backend.server.count("GET /stats"), 3);
+
+    // One that does not implement /stats is not
+    for status in [404, 405, 501] {
+        let backend = FakeBackend::start();
+        backend.set_hook(move |req, _| (req.route() == "GET /stats").then(|| Response::empty(status)));
+        let api = backend.api();
+        for _ in 0..3 {
+            assert!(api.capacity().await.unwrap().is_none());
+        }
+        // Clones share what was learnt
+        assert!(api.clone().capacity().await.unwrap().is_none());
+        assert_eq!(backend.server.count("GET /stats"), 1, "{}", status);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_backend_without_stats_reports_no_free_space_without_erroring() {
+    let backend = FakeBackend::start();
+    backend.set_hook(|req, _| (req.route() == "GET /stats").then(|| Response::empty(404)));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    for _ in 0..3 {
+        let st = statvfs(mnt.dir.path());
+        assert_eq!((st.f_blocks, st.f_bfree, st.f_bavail, st.f_bsize, st.f_namemax), (0, 0, 0, 4096, 255));
+    }
+    assert_eq!(backend.server.count("GET /stats"), 1);
+    mnt.unmount();
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use serde_json::Value;
+use std::io::Write;
+
+#[cfg(target_os = "linux")]
+fn status(mnt: &Mounted) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(mnt.path(".remotefs/status")).unwrap()).unwrap()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_status_file_reflects_the_mount() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./a.txt", Node::file(b"one"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+
+    let idle = status(&mnt);
+    assert_eq!(idle["backend_url"], backend.url().as_str());
+    assert_eq!(idle["state"], "connected");
+    assert_eq!(idle["websocket"]["connected"], true);
+    assert_eq!(idle["websocket"]["failures"], 0);
+    assert_eq!(idle["open_writes"], 0);
+    assert_eq!(idle["pending_writes"], 0);
+    assert!(idle["last_error"].is_null(), "{}", idle);
+
+    // A file written and not closed yet is an open and pending write, its listing is cached
+    let mut file = std::fs::OpenOptions::new().write(true).open(mnt.path("a.txt")).unwrap();
+    file.write_all(b"two").unwrap();
+    let busy = status(&mnt);
+    assert_eq!(busy["open_writes"], 1, "{}", busy);
+    assert_eq!(busy["pending_writes"], 1, "{}", busy);
+    assert!(busy["cache"]["dir_entries"].as_u64().unwrap() >= 1, "{}", busy);
+    assert!(busy["cache"]["attr_entries"].as_u64().unwrap() >= 1, "{}", busy);
+    drop(file);
+    let closed = status(&mnt);
+    assert_eq!((closed["open_writes"].as_u64(), closed["pending_writes"].as_u64()), (Some(0), Some(0)), "{}", closed);
+
+    // It is read-only and never reaches the backend
+    assert!(std::fs::write(mnt.path(".remotefs/status"), b"{}").is_err());
+    assert!(backend.server.requests().iter().all(|r| !r.query.iter().any(|(_, v)| v.contains(".remotefs"))));
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_status_file_reports_a_failing_websocket_as_degraded() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    let degraded = wait_for(|| Some(status(&mnt)).filter(|s| s["websocket"]["failures"] != 0));
+    assert_eq!(degraded["state"], "degraded");
+    assert_eq!(degraded["websocket"]["connected"], false);
+    assert!(degraded["last_error"].is_string(), "{}", degraded);
+    mnt.unmount();
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::acl::sticky_denies;
+use frontend::options::MountOptions;
+use std::os::unix::fs::PermissionsExt;
+
+#[test]
+fn only_root_and_the_owners_remove_from_a_sticky_directory() {
+    // uid 1001 removing an entry of uid 1002 from a directory of uid 1000
+    assert!(sticky_denies(1001, 0o1777, 1000, 1002));
+    assert!(!sticky_denies(1001, 0o777, 1000, 1002));
+    assert!(!sticky_denies(0, 0o1777, 1000, 1002));
+    assert!(!sticky_denies(1000, 0o1777, 1000, 1002));
+    assert!(!sticky_denies(1002, 0o1777, 1000, 1002));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_sticky_bit_is_shown_in_the_mode() {
+    let backend = FakeBackend::start();
+    let mut shared = Node::dir();
+    shared.mode = 0o1777;
+    backend.insert("./shared", shared);
+    backend.insert("./shared/a.txt", Node::file(b"a"));
+    backend.insert("./plain", Node::dir());
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mode = |rel: &str| std::fs::metadata(mnt.path(rel)).unwrap().permissions().mode() & 0o7777;
+    assert_eq!(mode("shared"), 0o1777);
+    std::fs::set_permissions(mnt.path("plain"), std::fs::Permissions::from_mode(0o1755)).unwrap();
+    assert_eq!(mode("plain"), 0o1755);
+
+    // The mounting user is root here: the sticky bit does not stop it
+    std::fs::remove_file(mnt.path("shared/a.txt")).unwrap();
+    assert!(backend.get("./shared/a.txt").is_none());
+    mnt.unmount();
+}
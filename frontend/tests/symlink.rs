@@ -0,0 +1,106 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, TempDir};
+use frontend::file_api::EntryKind;
+use frontend::options::MountOptions;
+use frontend::overlay::Overlay;
+
+#[tokio::test]
+async fn symlink_and_readlink_go_through_the_backend() {
+    let backend = FakeBackend::start();
+    let api = backend.api();
+
+    api.symlink("../target.txt", "/link").await.unwrap();
+    let sent = backend.server.requests();
+    let req = sent.iter().find(|r| r.route() == "POST /files/symlink").unwrap();
+    assert_eq!(req.query("relPath"), Some("./link"));
+    assert_eq!(req.query("target"), Some("../target.txt"));
+
+    assert_eq!(api.readlink("/link").await.unwrap(), "../target.txt");
+
+    let listed = api.ls("/").await.unwrap();
+    let link = listed.iter().find(|e| e.name == "link").unwrap();
+    assert_eq!(link.entry_kind(), EntryKind::Symlink);
+    assert_eq!(link.symlink_target.as_deref(), Some("../target.txt"));
+}
+
+#[tokio::test]
+async fn symlink_over_an_existing_path_is_refused() {
+    let backend = FakeBackend::start();
+    backend.insert("./taken", Node::file(b"x"));
+    let err = backend.api().symlink("t", "/taken").await.unwrap_err();
+    assert_eq!(err.status(), Some(409));
+}
+
+#[tokio::test]
+async fn readlink_of_a_regular_file_fails() {
+    let backend = FakeBackend::start();
+    backend.insert("./plain", Node::file(b"x"));
+    assert!(backend.api().readlink("/plain").await.is_err());
+}
+
+#[test]
+fn kind_symlink_is_parsed() {
+    assert_eq!(EntryKind::parse(Some("symlink"), false), EntryKind::Symlink);
+    assert_eq!(EntryKind::parse(Some("file"), false), EntryKind::File);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn overlay_keeps_symlinks_local() {
+    let backend = FakeBackend::start();
+    let upper = TempDir::new("upper");
+    let api = backend
+        .api()
+        .with_overlay(Some(Overlay::new(upper.path()).unwrap()));
+
+    api.symlink("/etc/hosts", "/ov-link").await.unwrap();
+    assert_eq!(backend.server.count("POST /files/symlink"), 0);
+    assert_eq!(api.readlink("/ov-link").await.unwrap(), "/etc/hosts");
+
+    let listed = api.ls("/").await.unwrap();
+    let link = listed.iter().find(|e| e.name == "ov-link").unwrap();
+    assert_eq!(link.entry_kind(), EntryKind::Symlink);
+    assert_eq!(link.symlink_target.as_deref(), Some("/etc/hosts"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn symlink_round_trip_through_the_mount() {
+    let backend = FakeBackend::start();
+    backend.insert("./target.txt", Node::file(b"hello"));
+    // A link created on the backend side
+    backend.insert(
+        "./other",
+        Node {
+            symlink_target: Some("target.txt".into()),
+            ..Node::file(b"target.txt")
+        },
+    );
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    std::os::unix::fs::symlink("target.txt", mnt.path("link")).unwrap();
+    assert_eq!(
+        backend.get("./link").unwrap().symlink_target.as_deref(),
+        Some("target.txt")
+    );
+    assert_eq!(
+        std::fs::read_link(mnt.path("link")).unwrap().to_str(),
+        Some("target.txt")
+    );
+    let meta = std::fs::symlink_metadata(mnt.path("link")).unwrap();
+    assert!(meta.file_type().is_symlink());
+
+    let names: Vec<String> = std::fs::read_dir(mnt.dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert!(names.contains(&"other".to_string()));
+    assert_eq!(
+        std::fs::read_link(mnt.path("other")).unwrap().to_str(),
+        Some("target.txt")
+    );
+    mnt.unmount();
+}
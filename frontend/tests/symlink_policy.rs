@@ -0,0 +1,77 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::{MountOptions, SymlinkPolicy};
+use std::path::Path;
+
+fn link(target: &str) -> Node {
+    Node {
+        symlink_target: Some(target.into()),
+        ..Node::file(target.as_bytes())
+    }
+}
+
+#[test]
+fn strict_refuses_targets_out_of_the_root() {
+    let strict = SymlinkPolicy::Strict;
+    assert!(strict.allows(Path::new("sub"), Path::new("../target.txt")));
+    assert!(strict.allows(Path::new("."), Path::new("sub/../target.txt")));
+    assert!(!strict.allows(Path::new("sub"), Path::new("../../etc/passwd")));
+    assert!(!strict.allows(Path::new("."), Path::new("/etc/hosts")));
+    assert!(SymlinkPolicy::Passthrough.allows(Path::new("."), Path::new("/etc/hosts")));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn escaping_symlinks_under_both_policies() {
+    let backend = FakeBackend::start();
+    backend.insert("./target.txt", Node::file(b"hello"));
+    backend.insert("./sub", Node::dir());
+    // Links created on the backend side, one stays inside the root and one leaves it
+    backend.insert("./sub/inside", link("../target.txt"));
+    backend.insert("./sub/escape", link("../../etc/passwd"));
+
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    for (name, target) in [("abs", "/etc/hosts"), ("up", "../outside")] {
+        let err = std::os::unix::fs::symlink(target, mnt.path(name)).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EPERM), "{}", target);
+    }
+    assert_eq!(backend.server.count("POST /files/symlink"), 0);
+    std::os::unix::fs::symlink("sub/../target.txt", mnt.path("ok")).unwrap();
+    assert_eq!(backend.get("./ok").unwrap().symlink_target.as_deref(), Some("sub/../target.txt"));
+
+    let err = std::fs::read_link(mnt.path("sub/escape")).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EPERM));
+    assert_eq!(std::fs::read_link(mnt.path("sub/inside")).unwrap(), Path::new("../target.txt"));
+    assert_eq!(std::fs::read(mnt.path("sub/inside")).unwrap(), b"hello");
+    mnt.unmount();
+
+    let passthrough = MountOptions {
+        symlink_policy: SymlinkPolicy::Passthrough,
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), passthrough) else {
+        return;
+    };
+    std::os::unix::fs::symlink("/etc/hosts", mnt.path("abs")).unwrap();
+    assert_eq!(backend.get("./abs").unwrap().symlink_target.as_deref(), Some("/etc/hosts"));
+    assert_eq!(std::fs::read_link(mnt.path("sub/escape")).unwrap(), Path::new("../../etc/passwd"));
+    mnt.unmount();
+}
+
+#[test]
+fn unknown_options_are_refused() {
+    let parse = |args: &[&str]| {
+        let args: Vec<String> = std::iter::once("frontend").chain(args.iter().copied()).map(String::from).collect();
+        MountOptions::from_args(&args)
+    };
+    for typo in [&["--symlink-polcy", "passthrough"][..], &["--read-onyl"], &["--chek"], &["--max-wrte=4096"]] {
+        let err = parse(typo).unwrap_err().to_string();
+        assert_eq!(err, format!("Opzione sconosciuta: {}", typo[0].split('=').next().unwrap()));
+    }
+    // The backend address and the daemon flag are positional
+    let opts = parse(&["192.168.1.10", "deamon", "--symlink-policy", "passthrough"]).unwrap();
+    assert_eq!(opts.symlink_policy, SymlinkPolicy::Passthrough);
+}
@@ -0,0 +1,40 @@
+use frontend::temp_dir::{choose, ensure_room, pick, MIN_FREE_SPACE};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn the_first_disk_backed_candidate_with_room_is_picked() {
+    let candidates: Vec<PathBuf> = ["/tmp", "/var/tmp", "/home/u/.cache/remote-fs"].iter().map(PathBuf::from).collect();
+    // (RAM-backed, free space) of each candidate, None when it cannot be created
+    let probe = |space: [Option<(bool, Option<u64>)>; 3]| {
+        move |dir: &Path| space[["/tmp", "/var/tmp", "/home/u/.cache/remote-fs"].iter().position(|c| Path::new(c) == dir).unwrap()]
+    };
+    let roomy = Some(MIN_FREE_SPACE * 100);
+
+    // The system temp dir is a tmpfs: the disk-backed /var/tmp takes over
+    let picked = pick(&candidates, probe([Some((true, roomy)), Some((false, roomy)), Some((false, roomy))]));
+    assert_eq!(picked, Some(Path::new("/var/tmp")));
+    // /var/tmp is almost full
+    let picked = pick(&candidates, probe([Some((true, roomy)), Some((false, Some(MIN_FREE_SPACE - 1))), Some((false, roomy))]));
+    assert_eq!(picked, Some(Path::new("/home/u/.cache/remote-fs")));
+    // A disk-backed system temp dir with room is kept, an unknown free space does not rule one out
+    let picked = pick(&candidates, probe([Some((false, roomy)), Some((false, roomy)), None]));
+    assert_eq!(picked, Some(Path::new("/tmp")));
+    let picked = pick(&candidates, probe([Some((true, roomy)), None, Some((false, None))]));
+    assert_eq!(picked, Some(Path::new("/home/u/.cache/remote-fs")));
+    // Nothing fits
+    assert_eq!(pick(&candidates, probe([Some((true, roomy)), Some((false, Some(0))), None])), None);
+}
+
+#[test]
+fn temp_dir_overrides_the_choice() {
+    assert_eq!(choose(Some(Path::new("/srv/buffers"))), PathBuf::from("/srv/buffers"));
+    assert!(choose(None).is_dir());
+}
+
+#[test]
+fn a_write_larger_than_the_free_space_is_refused() {
+    let dir = std::env::temp_dir();
+    ensure_room(&dir, 0).unwrap();
+    let err = ensure_room(&dir, u64::MAX).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, TempDir};
+use frontend::options::MountOptions;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+// A temp directory under `base` whose name has a space, a non-ASCII character and a byte that is not UTF-8
+fn awkward_dir(base: &TempDir) -> PathBuf {
+    let dir = base.path().join("buffer è").join(OsStr::from_bytes(b"raw \xff"));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn write_file_uploads_from_a_path_with_a_space_unicode_and_non_utf8() {
+    let backend = FakeBackend::start();
+    let base = TempDir::new("temp-path");
+    let local = awkward_dir(&base).join("caffè latte.bin");
+    std::fs::write(&local, b"from an awkward path").unwrap();
+
+    backend.api().write_file("./a.txt", &local).await.unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"from an awkward path");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_mount_commits_from_a_temp_dir_with_an_awkward_name() {
+    let backend = FakeBackend::start();
+    let base = TempDir::new("temp-path");
+    let opts = MountOptions {
+        temp_dir: Some(awkward_dir(&base)),
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    std::fs::write(mnt.path("new.txt"), b"written through the mount").unwrap();
+    // The upload runs on release, which the kernel sends after close returns
+    wait_for(|| (backend.data("./new.txt")? == b"written through the mount").then_some(()));
+    mnt.unmount();
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{FakeBackend, Node};
+use frontend::options::MountOptions;
+use std::time::{Duration, Instant};
+
+const RATE: u64 = 256 * 1024;
+const SIZE: usize = 512 * 1024;
+
+fn args(list: &[&str]) -> Vec<String> {
+    std::iter::once("frontend").chain(list.iter().copied()).map(String::from).collect()
+}
+
+#[test]
+fn the_rates_are_parsed_with_their_unit() {
+    let opts = MountOptions::from_args(&args(&["--max-upload-rate", "512K", "--max-download-rate=2m"])).unwrap();
+    assert_eq!(opts.max_upload_rate, Some(512 * 1024));
+    assert_eq!(opts.max_download_rate, Some(2 * 1024 * 1024));
+    assert_eq!(MountOptions::from_args(&args(&["--max-upload-rate", "100"])).unwrap().max_upload_rate, Some(100));
+    for value in ["0", "fast", "10X", "K"] {
+        assert!(MountOptions::from_args(&args(&["--max-download-rate", value])).is_err(), "{}", value);
+    }
+}
+
+#[tokio::test]
+async fn an_upload_takes_as_long_as_the_rate_allows() {
+    let backend = FakeBackend::start();
+    let data = vec![b'u'; SIZE];
+    let api = backend.api().with_rate_limits(Some(RATE), None);
+
+    let started = Instant::now();
+    api.write_bytes("./up.bin", &data).await.unwrap();
+    let elapsed = started.elapsed();
+    // 512 KiB at 256 KiB/s
+    assert!(elapsed >= Duration::from_millis(1800), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    assert_eq!(backend.data("./up.bin").unwrap(), data);
+    // Sent in pieces of about one second of transfer
+    assert!(backend.server.count("PUT /files") >= 2);
+}
+
+#[tokio::test]
+async fn a_download_takes_as_long_as_the_rate_allows() {
+    let backend = FakeBackend::start();
+    backend.insert("./down.bin", Node::file(&vec![b'd'; SIZE]));
+
+    let started = Instant::now();
+    let fast = backend.api().read_all("./down.bin", SIZE as u64).await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(1000), "{:?}", started.elapsed());
+
+    let api = backend.api().with_rate_limits(None, Some(RATE));
+    let started = Instant::now();
+    let slow = api.read_all("./down.bin", SIZE as u64).await.unwrap();
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(1800), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    assert_eq!(slow, fast);
+}
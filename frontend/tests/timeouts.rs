@@ -0,0 +1,49 @@
+mod common;
+
+use common::{FakeBackend, Node};
+use frontend::api_error::ApiError;
+use frontend::options::MountOptions;
+use std::time::{Duration, Instant};
+
+#[test]
+fn the_two_timeouts_are_parsed_in_seconds() {
+    let args: Vec<String> = ["frontend", "--metadata-timeout", "2", "--data-timeout=90"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let opts = MountOptions::from_args(&args).unwrap();
+    assert_eq!(opts.metadata_timeout, Some(Duration::from_secs(2)));
+    assert_eq!(opts.data_timeout, Some(Duration::from_secs(90)));
+    let args: Vec<String> = ["frontend", "--data-timeout", "0"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+}
+
+#[tokio::test]
+async fn a_slow_listing_times_out_while_a_slow_read_completes() {
+    let backend = FakeBackend::start();
+    backend.insert("./big.bin", Node::file(&[7u8; 64 * 1024]));
+    backend.set_delay("GET /list", Duration::from_millis(1500));
+    backend.set_delay("GET /files", Duration::from_millis(1500));
+    let api = backend.api().with_timeouts(Duration::from_millis(300), Duration::from_secs(10));
+
+    let started = Instant::now();
+    let err = api.ls(".").await.unwrap_err();
+    assert!(matches!(&err, ApiError::Network(e) if e.is_timeout()), "{:?}", err);
+    assert!(started.elapsed() < Duration::from_millis(1500), "{:?}", started.elapsed());
+
+    let data = api.read_all("./big.bin", 64 * 1024).await.unwrap();
+    assert_eq!(data, vec![7u8; 64 * 1024]);
+}
+
+#[tokio::test]
+async fn a_read_longer_than_the_data_timeout_fails() {
+    let backend = FakeBackend::start();
+    backend.insert("./big.bin", Node::file(b"data"));
+    backend.set_delay("GET /files", Duration::from_millis(1500));
+    let api = backend.api().with_timeouts(Duration::from_secs(10), Duration::from_millis(300));
+
+    let mut buf = [0u8; 4];
+    let err = api.read_into("./big.bin", 0, &mut buf).await.unwrap_err();
+    assert!(matches!(&err, ApiError::Network(e) if e.is_timeout()), "{:?}", err);
+    assert!(api.ls(".").await.is_ok());
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn recent(mtime: (i64, i64)) -> bool {
+    let mtime = UNIX_EPOCH + Duration::new(mtime.0 as u64, mtime.1 as u32);
+    SystemTime::now().duration_since(mtime).is_ok_and(|age| age < Duration::from_secs(30))
+}
+
+#[tokio::test]
+async fn touch_creates_a_missing_file_and_updates_an_existing_one() {
+    let backend = FakeBackend::start();
+    let mut old = Node::file(b"kept");
+    old.mtime = (1_000_000, 0);
+    backend.insert("./old.txt", old);
+    let api = backend.api();
+
+    api.touch("./new.txt").await.unwrap();
+    api.touch("./old.txt").await.unwrap();
+    assert_eq!(backend.server.count("POST /files/touch"), 2);
+    assert_eq!(backend.server.total(), 2);
+    let new = backend.get("./new.txt").unwrap();
+    assert!(new.data.is_empty() && recent(new.mtime));
+    let old = backend.get("./old.txt").unwrap();
+    assert!(old.data == b"kept" && recent(old.mtime));
+    assert!(api.touch("./missing/new.txt").await.is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn touching_a_new_path_on_the_mount_sends_one_touch() {
+    let backend = FakeBackend::start();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+    backend.server.clear();
+
+    let status = std::process::Command::new("touch").arg(mnt.path("new.txt")).status().unwrap();
+    assert!(status.success());
+    assert_eq!(std::fs::metadata(mnt.path("new.txt")).unwrap().len(), 0);
+    // After the unmount no release is left to upload anything
+    mnt.unmount();
+    let node = backend.get("./new.txt").unwrap();
+    assert!(node.data.is_empty() && recent(node.mtime));
+    let writes: Vec<String> = backend
+        .server
+        .requests()
+        .into_iter()
+        .map(|r| r.route())
+        .filter(|r| !r.starts_with("GET") && !r.starts_with("HEAD"))
+        .collect();
+    assert_eq!(writes, ["POST /files/touch"]);
+}
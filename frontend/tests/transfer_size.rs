@@ -0,0 +1,46 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+fn args(list: &[&str]) -> Vec<String> {
+    std::iter::once("frontend").chain(list.iter().copied()).map(String::from).collect()
+}
+
+#[test]
+fn the_transfer_sizes_are_parsed_within_the_kernel_limits() {
+    let opts = MountOptions::from_args(&args(&["--max-read", "131072", "--max-write=1048576"])).unwrap();
+    assert_eq!((opts.max_read, opts.max_write), (Some(131072), Some(1048576)));
+    for value in ["0", "100", "1073741824", "big"] {
+        assert!(MountOptions::from_args(&args(&["--max-read", value])).is_err(), "{}", value);
+        assert!(MountOptions::from_args(&args(&["--max-write", value])).is_err(), "{}", value);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn ranged_reads(max_read: u32) -> Option<usize> {
+    let backend = FakeBackend::start();
+    let data: Vec<u8> = (0..1024 * 1024).map(|i| (i % 253) as u8).collect();
+    backend.insert("./big.bin", Node::file(&data));
+    let opts = MountOptions {
+        max_read: Some(max_read),
+        ..MountOptions::default()
+    };
+    let mnt = Mounted::start(backend.api(), &backend.url(), opts)?;
+    backend.server.clear();
+    assert_eq!(std::fs::read(mnt.path("big.bin")).unwrap(), data);
+    let reads = backend.server.count("GET /files");
+    mnt.unmount();
+    Some(reads)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_larger_max_read_needs_fewer_backend_reads() {
+    let (Some(small), Some(large)) = (ranged_reads(4096), ranged_reads(128 * 1024)) else {
+        return;
+    };
+    // 1 MiB in pieces of at most 4 KiB
+    assert!(small >= 256, "{}", small);
+    assert!(large <= small / 8, "{} vs {}", large, small);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{FakeBackend, Mounted, Node, Response};
+use frontend::options::MountOptions;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+type Trash = Arc<Mutex<BTreeMap<String, (String, Node)>>>;
+
+// Function that gives the backend the trash endpoints: a trashed entry keeps its original path and
+// gets the id "<n>-<name>"
+fn with_trash(backend: &FakeBackend) -> Trash {
+    let trash: Trash = Arc::default();
+    let store = trash.clone();
+    backend.set_hook(move |req, tree| {
+        let mut trash = store.lock().unwrap();
+        match req.route().as_str() {
+            "GET /trash" => {
+                let entries: Vec<_> = trash
+                    .iter()
+                    .map(|(id, (rel, node))| {
+                        json!({ "id": id, "relPath": rel, "is_dir": node.is_dir, "size": node.data.len(), "deletedAt": 1_700_000_000 })
+                    })
+                    .collect();
+                Some(Response::json(200, json!(entries)))
+            }
+            "POST /trash" => {
+                let rel = req.query("relPath").unwrap().to_string();
+                let Some(node) = tree.remove(&rel) else {
+                    return Some(Response::json(404, json!({ "error": "not found" })));
+                };
+                let id = format!("{}-{}", trash.len() + 1, rel.rsplit('/').next().unwrap());
+                trash.insert(id, (rel, node));
+                Some(Response::json(200, json!({ "ok": true })))
+            }
+            "POST /trash/restore" => {
+                let (rel, node) = trash.remove(req.query("id").unwrap())?;
+                tree.insert(req.query("relPath").map_or(rel, str::to_string), node);
+                Some(Response::json(200, json!({ "ok": true })))
+            }
+            "DELETE /trash" => {
+                trash.remove(req.query("id").unwrap())?;
+                Some(Response::json(200, json!({ "ok": true })))
+            }
+            _ => None,
+        }
+    });
+    trash
+}
+
+#[tokio::test]
+async fn trash_calls_reach_the_trash_endpoints() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let api = backend.api();
+    assert!(!api.trash_supported().await);
+
+    let trash = with_trash(&backend);
+    assert!(api.trash_supported().await);
+    api.trash("./a.txt").await.unwrap();
+    assert!(backend.get("./a.txt").is_none());
+    let listed = api.trash_list().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!((listed[0].id.as_str(), listed[0].rel_path.as_str(), listed[0].size), ("1-a.txt", "./a.txt", 1));
+    api.trash_restore("1-a.txt", Some("./b.txt")).await.unwrap();
+    assert_eq!(backend.data("./b.txt").unwrap(), b"a");
+    assert!(trash.lock().unwrap().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn with_trash_a_delete_lands_in_the_trash_listing() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"keep me"));
+    backend.insert("./b.txt", Node::file(b"drop me"));
+    let trash = with_trash(&backend);
+    let opts = MountOptions { trash: true, ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    std::fs::remove_file(mnt.path("a.txt")).unwrap();
+    std::fs::remove_file(mnt.path("b.txt")).unwrap();
+    assert_eq!(backend.server.count("POST /trash"), 2);
+    assert_eq!(backend.server.count("DELETE /files"), 0);
+    assert!(!mnt.path("a.txt").exists());
+
+    let mut names: Vec<String> = std::fs::read_dir(mnt.path(".remotefs/trash"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["1-a.txt", "2-b.txt"]);
+
+    // Moving an entry out restores it, removing it purges it
+    std::fs::rename(mnt.path(".remotefs/trash/1-a.txt"), mnt.path("a.txt")).unwrap();
+    assert_eq!(backend.data("./a.txt").unwrap(), b"keep me");
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"keep me");
+    std::fs::remove_file(mnt.path(".remotefs/trash/2-b.txt")).unwrap();
+    assert_eq!(backend.server.count("DELETE /trash"), 1);
+    assert!(trash.lock().unwrap().is_empty());
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn without_backend_support_deletes_stay_permanent() {
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let opts = MountOptions { trash: true, ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    std::fs::remove_file(mnt.path("a.txt")).unwrap();
+    assert!(backend.get("./a.txt").is_none());
+    assert_eq!(backend.server.count("DELETE /files"), 1);
+    assert_eq!(backend.server.count("POST /trash"), 0);
+    assert!(!mnt.path(".remotefs/trash").exists());
+    mnt.unmount();
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::{MountOptions, UnlinkPolicy};
+use std::io::Read;
+
+#[test]
+fn the_unlink_policy_is_parsed() {
+    let args = |v: &str| -> Vec<String> { ["frontend", "--unlink-policy", v].iter().map(|s| s.to_string()).collect() };
+    assert_eq!(MountOptions::from_args(&args("immediate")).unwrap().unlink_policy, UnlinkPolicy::Immediate);
+    assert_eq!(MountOptions::from_args(&args("deferred")).unwrap().unlink_policy, UnlinkPolicy::Deferred);
+    assert!(MountOptions::from_args(&args("later")).is_err());
+    assert_eq!(MountOptions::default().unlink_policy, UnlinkPolicy::Deferred);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn an_open_file_stays_readable_after_unlink_until_closed() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"still here"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let mut file = std::fs::File::open(mnt.path("doc.txt")).unwrap();
+    std::fs::remove_file(mnt.path("doc.txt")).unwrap();
+    assert!(std::fs::metadata(mnt.path("doc.txt")).is_err());
+    let names: Vec<String> = std::fs::read_dir(mnt.path(""))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert!(names.iter().all(|n| !n.contains("doc") && !n.contains("unlinked")), "{:?}", names);
+
+    // The handle still reads the content, the backend has not deleted anything yet
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "still here");
+    assert_eq!(backend.server.count("DELETE /files"), 0);
+
+    // The last close deletes it for good
+    drop(file);
+    wait_for(|| (backend.server.count("DELETE /files") == 1).then_some(()));
+    let delete = backend.server.requests().into_iter().find(|r| r.route() == "DELETE /files").unwrap();
+    assert!(delete.query("relPath").unwrap().starts_with("./.remotefs_unlinked_"), "{:?}", delete.query);
+    assert!(backend.get("./doc.txt").is_none());
+    assert!(backend.get(delete.query("relPath").unwrap()).is_none());
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn the_immediate_policy_deletes_on_unlink() {
+    let backend = FakeBackend::start();
+    backend.insert("./doc.txt", Node::file(b"gone"));
+    let opts = MountOptions {
+        unlink_policy: UnlinkPolicy::Immediate,
+        ..MountOptions::default()
+    };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+
+    let file = std::fs::File::open(mnt.path("doc.txt")).unwrap();
+    std::fs::remove_file(mnt.path("doc.txt")).unwrap();
+    assert_eq!(backend.server.count("DELETE /files"), 1);
+    assert!(backend.get("./doc.txt").is_none());
+    drop(file);
+    mnt.unmount();
+}
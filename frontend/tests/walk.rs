@@ -0,0 +1,134 @@
+mod common;
+
+use common::{FakeBackend, Node, TempDir};
+use frontend::audit::{audit_backend, checkpoint_path};
+use frontend::options::MountOptions;
+use frontend::walk::{Checkpoint, Walk, WalkEnd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn tree() -> FakeBackend {
+    let backend = FakeBackend::start();
+    for dir in ["./a", "./a/x", "./a/y", "./b", "./b/z", "./b/z/deep", "./c"] {
+        backend.insert(dir, Node::dir());
+    }
+    backend.insert("./a/x/one.txt", Node::file(b"1"));
+    backend.insert("./b/z/deep/two.txt", Node::file(b"22"));
+    backend.insert("./top.txt", Node::file(b"333"));
+    backend
+}
+
+const ORDER: [&str; 8] = [".", "./a", "./a/x", "./a/y", "./b", "./b/z", "./b/z/deep", "./c"];
+
+#[tokio::test]
+async fn an_interrupted_walk_resumes_after_the_checkpoint() {
+    let backend = tree();
+    let api = backend.api();
+    let state = TempDir::new("walk");
+    let checkpoint = state.path().join("walk.json");
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let mut first = Vec::new();
+    let mut walk = Walk::new(&api, &checkpoint, stop.clone());
+    let end = walk
+        .run(|rel, _, _| {
+            first.push(rel.to_string());
+            // Ctrl-C while the fourth directory is being handled
+            if first.len() == 4 {
+                stop.store(true, Ordering::SeqCst);
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!(end, WalkEnd::Interrupted);
+    assert_eq!(first, ORDER[..4]);
+    let saved: Checkpoint = serde_json::from_slice(&std::fs::read(&checkpoint).unwrap()).unwrap();
+    assert_eq!(saved.cursor.as_deref(), Some("./a/y"));
+
+    // The next run visits none of them again, it only lists the cursor and its ancestors to find where to go on
+    stop.store(false, Ordering::SeqCst);
+    backend.server.clear();
+    let mut second = Vec::new();
+    let mut walk = Walk::new(&api, &checkpoint, stop.clone());
+    let end = walk.run(|rel, _, _| second.push(rel.to_string())).await.unwrap();
+    assert_eq!(end, WalkEnd::Done);
+    assert_eq!(second, ORDER[4..]);
+    assert_eq!(backend.server.count("GET /list"), 3 + ORDER[4..].len());
+    assert!(!checkpoint.exists());
+}
+
+#[tokio::test]
+async fn a_walk_stops_while_a_listing_hangs() {
+    let backend = tree();
+    backend.set_delay("GET /list", Duration::from_secs(5));
+    let api = backend.api();
+    let state = TempDir::new("walk");
+    let checkpoint = state.path().join("walk.json");
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        flag.store(true, Ordering::SeqCst);
+    });
+
+    let started = Instant::now();
+    let mut walk = Walk::new(&api, &checkpoint, stop);
+    let end = walk.run(|_, _, _| panic!("nothing was listed")).await.unwrap();
+    assert_eq!(end, WalkEnd::Interrupted);
+    assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+    // Nothing listed yet: the next run starts from the root
+    let saved: Checkpoint = serde_json::from_slice(&std::fs::read(&checkpoint).unwrap()).unwrap();
+    assert_eq!(saved.cursor, None);
+}
+
+#[test]
+fn the_audit_counts_the_tree_and_reports_what_cannot_be_shown() {
+    let backend = tree();
+    backend.insert(&format!("./c/{}", "n".repeat(300)), Node::file(b""));
+    let state = TempDir::new("audit");
+    let checkpoint = checkpoint_path(Some(state.path()), &backend.url());
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let audit = audit_backend(&backend.api(), &rt, &checkpoint, stop, 4096).unwrap();
+    assert_eq!(audit.end, WalkEnd::Done);
+    assert_eq!(audit.totals.dirs, 8);
+    assert_eq!(audit.totals.files, 4);
+    assert_eq!(audit.totals.bytes, 6);
+    assert_eq!(audit.totals.problems, 1);
+}
+
+#[test]
+fn audit_options_are_parsed() {
+    let args: Vec<String> = ["frontend", "--audit", "--audit-checkpoint", "/var/tmp/audit"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let opts = MountOptions::from_args(&args).unwrap();
+    assert!(opts.audit);
+    assert_eq!(
+        checkpoint_path(opts.audit_checkpoint.as_deref(), "http://10.0.0.1:3000"),
+        std::path::Path::new("/var/tmp/audit/audit-http___10_0_0_1_3000.json")
+    );
+}
+
+#[test]
+fn the_audit_command_reads_the_snapshot_the_mount_would_show() {
+    let backend = tree();
+    let state = TempDir::new("audit");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_frontend"))
+        .arg(backend.url())
+        .args(["--audit", "--snapshot", "nightly"])
+        .arg("--audit-checkpoint")
+        .arg(state.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let lists: Vec<_> = backend.server.requests().into_iter().filter(|r| r.route() == "GET /list").collect();
+    assert!(!lists.is_empty());
+    for req in &lists {
+        assert_eq!(req.query("snapshot"), Some("nightly"), "{:?}", req.query);
+    }
+}
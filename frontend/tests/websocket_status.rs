@@ -0,0 +1,49 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+
+#[cfg(target_os = "linux")]
+fn field(status: &str, name: &str) -> String {
+    let line = status.lines().find(|l| l.starts_with(&format!("{}: ", name))).unwrap();
+    line[name.len() + 2..].to_string()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_failed_handshake_is_reported_as_disconnected() {
+    // The fake backend answers the Socket.IO handshake with 404 unless enabled
+    let backend = FakeBackend::start();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let status = wait_for(|| {
+        let status = std::fs::read_to_string(mnt.path(".remotefs/refresh")).unwrap();
+        (field(&status, "websocket_failures") != "0").then_some(status)
+    });
+    assert_eq!(field(&status, "websocket"), "disconnected", "{}", status);
+    assert_ne!(field(&status, "websocket_error"), "-", "{}", status);
+    // The mount works anyway, on the TTL alone
+    assert_eq!(std::fs::read(mnt.path("a.txt")).unwrap(), b"a");
+    mnt.unmount();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_working_handshake_is_reported_as_connected() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), MountOptions::default()) else {
+        return;
+    };
+
+    let status = wait_for(|| {
+        let status = std::fs::read_to_string(mnt.path(".remotefs/refresh")).unwrap();
+        (field(&status, "websocket") == "connected").then_some(status)
+    });
+    assert_eq!(field(&status, "websocket_failures"), "0", "{}", status);
+    assert_eq!(field(&status, "websocket_error"), "-", "{}", status);
+    mnt.unmount();
+}
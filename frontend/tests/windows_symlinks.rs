@@ -0,0 +1,107 @@
+mod common;
+
+use common::{FakeBackend, Node};
+use frontend::file_api::{DirectoryEntry, EntryKind};
+use frontend::options::{MountOptions, WindowsSymlinks};
+use frontend::win_link;
+
+const TARGET: &str = "../docs/target.txt";
+
+fn utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(data[at..at + 2].try_into().unwrap())
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+// The root listing of a backend holding a file and a symlink, as the Windows layer gets it
+async fn listing() -> Vec<DirectoryEntry> {
+    let backend = FakeBackend::start();
+    backend.insert("./plain.txt", Node::file(b"x"));
+    let api = backend.api();
+    api.symlink(TARGET, "/link").await.unwrap();
+    api.ls("/").await.unwrap()
+}
+
+async fn presented(mode: WindowsSymlinks) -> Vec<DirectoryEntry> {
+    listing().await.into_iter().filter_map(|de| win_link::present(mode, de)).collect()
+}
+
+#[test]
+fn windows_symlinks_defaults_to_hide() {
+    assert_eq!(MountOptions::default().windows_symlinks, WindowsSymlinks::Hide);
+    for (value, mode) in [
+        ("reparse", WindowsSymlinks::Reparse),
+        ("shortcut", WindowsSymlinks::Shortcut),
+        ("hide", WindowsSymlinks::Hide),
+    ] {
+        let args: Vec<String> = ["frontend", "--windows-symlinks", value].iter().map(|s| s.to_string()).collect();
+        assert_eq!(MountOptions::from_args(&args).unwrap().windows_symlinks, mode);
+    }
+    let args: Vec<String> = ["frontend", "--windows-symlinks", "junction"].iter().map(|s| s.to_string()).collect();
+    assert!(MountOptions::from_args(&args).is_err());
+}
+
+#[tokio::test]
+async fn hide_leaves_the_link_out_of_the_listing() {
+    let names: Vec<String> = presented(WindowsSymlinks::Hide).await.into_iter().map(|de| de.name).collect();
+    assert_eq!(names, ["plain.txt"]);
+}
+
+#[tokio::test]
+async fn reparse_keeps_the_link_with_its_target() {
+    let entries = presented(WindowsSymlinks::Reparse).await;
+    let link = entries.iter().find(|de| de.name == "link").unwrap();
+    assert_eq!(link.entry_kind(), EntryKind::Symlink);
+    assert_eq!(link.symlink_target.as_deref(), Some(TARGET));
+    assert!(!win_link::is_shortcut(link));
+
+    let data = win_link::reparse_data(TARGET);
+    let path = utf16("..\\docs\\target.txt");
+    assert_eq!(u32_at(&data, 0), win_link::IO_REPARSE_TAG_SYMLINK);
+    assert_eq!(u16_at(&data, 4) as usize, data.len() - 8);
+    // Substitute name, then print name, both the Windows spelling of the target
+    let n = path.len() as u16;
+    assert_eq!([u16_at(&data, 8), u16_at(&data, 10), u16_at(&data, 12), u16_at(&data, 14)], [0, n, n, n]);
+    assert_eq!(u32_at(&data, 16), 1, "SYMLINK_FLAG_RELATIVE");
+    assert_eq!(&data[20..], [path.clone(), path].concat());
+}
+
+#[tokio::test]
+async fn shortcut_lists_a_lnk_file_with_the_link_target() {
+    let entries = presented(WindowsSymlinks::Shortcut).await;
+    let mut names: Vec<&str> = entries.iter().map(|de| de.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["link.lnk", "plain.txt"]);
+    let lnk = entries.iter().find(|de| de.name == "link.lnk").unwrap();
+    assert_eq!(lnk.entry_kind(), EntryKind::File);
+    assert!(win_link::is_shortcut(lnk));
+    assert!(!win_link::is_shortcut(entries.iter().find(|de| de.name == "plain.txt").unwrap()));
+
+    // The size listed is the one of the content reads return
+    let data = win_link::shortcut(TARGET);
+    assert_eq!(lnk.size, data.len() as i64);
+    assert_eq!(u32_at(&data, 0), 0x4C);
+    assert_eq!(data[4..20], [0x01, 0x14, 0x02, 0, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46]);
+    assert_eq!(u32_at(&data, 20), 0x88, "HasRelativePath | IsUnicode");
+    assert_eq!(u32_at(&data, 60), 1, "SW_SHOWNORMAL");
+    let path = utf16("..\\docs\\target.txt");
+    assert_eq!(u16_at(&data, 76) as usize, path.len() / 2);
+    assert_eq!(data[78..78 + path.len()], path[..]);
+    assert_eq!(&data[78 + path.len()..], [0, 0, 0, 0], "terminal block");
+}
+
+#[test]
+fn a_link_without_its_target_is_hidden_in_every_mode() {
+    let mut raw = common::entry_json("./link", &Node::file(b""));
+    raw["kind"] = "symlink".into();
+    let de: DirectoryEntry = serde_json::from_value(raw).unwrap();
+    for mode in [WindowsSymlinks::Reparse, WindowsSymlinks::Shortcut, WindowsSymlinks::Hide] {
+        assert!(win_link::present(mode, de.clone()).is_none(), "{:?}", mode);
+    }
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{FakeBackend, Node};
+
+#[tokio::test]
+async fn write_bytes_uploads_exactly_the_buffer() {
+    let backend = FakeBackend::start();
+    backend.insert("./old.txt", Node::file(b"previous content"));
+    let api = backend.api();
+
+    api.write_bytes("./note.txt", b"hello\0world").await.unwrap();
+    assert_eq!(backend.data("./note.txt").unwrap(), b"hello\0world");
+    let puts: Vec<_> = backend.server.requests().into_iter().filter(|r| r.route() == "PUT /files").collect();
+    assert_eq!(puts.len(), 1);
+    assert_eq!(puts[0].body, b"hello\0world");
+    assert_eq!(puts[0].query("offset"), Some("0"));
+
+    // An empty buffer truncates
+    api.write_bytes("./old.txt", b"").await.unwrap();
+    assert_eq!(backend.data("./old.txt").unwrap(), b"");
+}
+
+#[tokio::test]
+async fn a_buffer_larger_than_a_chunk_is_sent_in_order() {
+    let backend = FakeBackend::start();
+    let data: Vec<u8> = (0..(2 * 1024 * 1024 + 10)).map(|i| (i % 251) as u8).collect();
+    backend.api().write_bytes("./big.bin", &data).await.unwrap();
+
+    let offsets: Vec<String> = backend
+        .server
+        .requests()
+        .into_iter()
+        .filter(|r| r.route() == "PUT /files")
+        .map(|r| r.query("offset").unwrap().to_string())
+        .collect();
+    assert_eq!(offsets, ["0", "1048576", "2097152"]);
+    assert_eq!(backend.data("./big.bin").unwrap(), data);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{wait_for, FakeBackend, Mounted, Node};
+use frontend::options::MountOptions;
+use std::time::Duration;
+
+#[test]
+fn ws_idle_timeout_is_parsed_in_seconds() {
+    let parse = |extra: &[&str]| {
+        let args: Vec<String> = ["frontend"].iter().chain(extra).map(|s| s.to_string()).collect();
+        MountOptions::from_args(&args)
+    };
+    assert_eq!(parse(&["--ws-idle-timeout", "30"]).unwrap().ws_idle_timeout, Some(Duration::from_secs(30)));
+    assert_eq!(parse(&[]).unwrap().ws_idle_timeout, None);
+    assert!(parse(&["--ws-idle-timeout", "0"]).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn an_idle_mount_closes_the_websocket_and_the_next_operation_reopens_it() {
+    let backend = FakeBackend::start();
+    backend.enable_socket();
+    backend.insert("./a.txt", Node::file(b"a"));
+    let opts = MountOptions { ws_idle_timeout: Some(Duration::from_secs(1)), ..MountOptions::default() };
+    let Some(mnt) = Mounted::start(backend.api(), &backend.url(), opts) else {
+        return;
+    };
+    wait_for(|| backend.socket_connected().then_some(()));
+    let names = || -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(mnt.dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|n| n != ".remotefs")
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(), ["a.txt"]);
+
+    // Nothing touches the mount: the socket is closed
+    wait_for(|| (!backend.socket_connected()).then_some(()));
+    assert_eq!(backend.socket_sessions(), 1);
+
+    // A file added while no event could tell the mount; the next operation reconnects, and the
+    // listing cached before the gap is not served
+    backend.insert("./b.txt", Node::file(b"b"));
+    assert_eq!(names(), ["a.txt", "b.txt"]);
+    wait_for(|| (backend.socket_sessions() == 2 && backend.socket_connected()).then_some(()));
+    mnt.unmount();
+}